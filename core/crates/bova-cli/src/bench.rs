@@ -0,0 +1,293 @@
+//! `bova-cli bench`: decode throughput and real-time headroom.
+//!
+//! 帧一到就取走、不按节奏播放：FFmpeg 引擎测得的是解码速度，mpv 引擎自己按实时节奏渲染，
+//! 测得的是实际渲染帧率。每帧的应显示时刻为“首帧到达时间 + (pts - 首帧 pts) / 速度”，
+//! 超过该时刻（再加一帧的余量）才到达的帧就是实时播放时会被丢掉的帧。
+//! 引擎每秒发送的诊断快照（`PlaybackEvent::Stats`）汇总为解码/转换耗时、队列深度与音画偏差。
+
+use crate::{engine_name, start_engine, CliError, Exit};
+use bova_playback::{MpvCommand, PlaybackConfig, PlaybackEngine, PlaybackEvent, PlaybackStats, VideoFrame};
+use crossbeam_channel::select;
+use serde::Serialize;
+use std::sync::atomic::Ordering;
+use std::time::{Duration, Instant};
+
+/// 到达时间允许晚于应显示时刻的余量（25 fps 的一帧）
+const LATE_TOLERANCE: Duration = Duration::from_millis(40);
+
+pub struct BenchOptions {
+    pub engine: PlaybackEngine,
+    pub hwaccel: bool,
+    pub start_ms: Option<i64>,
+    pub duration_ms: Option<i64>,
+    pub speed: f64,
+    /// 丢帧比例（百分比）超过该值时以 `Exit::BenchFailed` 退出
+    pub max_dropped_pct: Option<f64>,
+    pub json: bool,
+}
+
+#[derive(Debug, Serialize)]
+struct BenchReport {
+    url: String,
+    engine: &'static str,
+    hwaccel: bool,
+    speed: f64,
+    width: u32,
+    height: u32,
+    frames: u64,
+    audio_frames: u64,
+    /// 解码出的媒体时长
+    media_ms: i64,
+    /// 首帧到最后一帧的实际耗时
+    wall_ms: u64,
+    /// 打开到首帧的耗时
+    first_frame_ms: u64,
+    fps: f64,
+    /// 媒体时长 / 实际耗时；小于播放速度时无法实时播放
+    realtime_factor: f64,
+    dropped: u64,
+    dropped_pct: f64,
+    video_codec: Option<String>,
+    /// 实际使用的硬件解码方式；请求了硬件解码但回退到软件解码时为 None
+    hwdec: Option<String>,
+    hw_transfers_ok: u64,
+    hw_transfers_failed: u64,
+    /// 每帧解码耗时（各统计周期平均值的平均 / 最大值），mpv 引擎不提供
+    decode_ms_avg: Option<f64>,
+    decode_ms_max: Option<f64>,
+    /// 每帧转换为 RGBA 的耗时：swscale（FFmpeg）或软件渲染（mpv）
+    scale_ms_avg: Option<f64>,
+    scale_ms_max: Option<f64>,
+    video_queue_max: usize,
+    audio_queue_max: usize,
+    /// mpv 报告的最大音画偏差（绝对值）
+    av_drift_ms_max: Option<f64>,
+}
+
+#[derive(Default)]
+struct Stats {
+    frames: u64,
+    audio_frames: u64,
+    dropped: u64,
+    size: (u32, u32),
+    /// 首帧的到达时间与时间戳
+    first: Option<(Instant, i64)>,
+    last: Option<(Instant, i64)>,
+}
+
+impl Stats {
+    /// 记录一帧，返回它是否晚于应显示时刻
+    fn frame(&mut self, frame: &VideoFrame, speed: f64) -> bool {
+        let now = Instant::now();
+        self.frames += 1;
+        self.size = (frame.width, frame.height);
+        let Some(pts) = frame.pts_ms else { return false };
+        let (t0, pts0) = *self.first.get_or_insert((now, pts));
+        let due = t0 + Duration::from_secs_f64((pts - pts0).max(0) as f64 / speed / 1000.0) + LATE_TOLERANCE;
+        let late = now > due;
+        if late {
+            self.dropped += 1;
+        }
+        self.last = Some((now, pts));
+        late
+    }
+}
+
+/// 各统计周期的耗时：平均值取各周期平均值的平均
+#[derive(Default)]
+struct Timing {
+    sum_avg: f64,
+    intervals: u32,
+    max: Option<f64>,
+}
+
+impl Timing {
+    fn add(&mut self, avg: Option<f64>, max: Option<f64>) {
+        if let Some(avg) = avg {
+            self.sum_avg += avg;
+            self.intervals += 1;
+        }
+        if let Some(max) = max {
+            self.max = Some(self.max.map_or(max, |m| m.max(max)));
+        }
+    }
+
+    fn avg(&self) -> Option<f64> {
+        (self.intervals > 0).then(|| self.sum_avg / self.intervals as f64)
+    }
+}
+
+/// `PlaybackEvent::Stats` 快照的汇总
+#[derive(Default)]
+struct Diagnostics {
+    decode: Timing,
+    scale: Timing,
+    video_queue_max: usize,
+    audio_queue_max: usize,
+    drift_max: Option<f64>,
+}
+
+impl Diagnostics {
+    fn add(&mut self, stats: &PlaybackStats) {
+        self.decode.add(stats.decode_ms_avg, stats.decode_ms_max);
+        self.scale.add(stats.scale_ms_avg, stats.scale_ms_max);
+        self.video_queue_max = self.video_queue_max.max(stats.video_queue);
+        self.audio_queue_max = self.audio_queue_max.max(stats.audio_queue);
+        if let Some(drift) = stats.av_drift_ms {
+            self.drift_max = Some(self.drift_max.map_or(drift.abs(), |d| d.max(drift.abs())));
+        }
+    }
+}
+
+pub fn run(url: &str, opts: &BenchOptions) -> Result<(), CliError> {
+    let cfg = PlaybackConfig {
+        hwaccel: opts.hwaccel,
+        subtitle_enabled: false,
+        engine: Some(opts.engine),
+        start_ms: opts.start_ms.filter(|&ms| ms > 0),
+        ..Default::default()
+    };
+    let opened_at = Instant::now();
+    let handles = start_engine(url, cfg)?;
+    if opts.engine == PlaybackEngine::MPV {
+        // 按原始尺寸渲染（mpv 不会放大），不出声
+        handles.target_render_w.store(7680, Ordering::Relaxed);
+        handles.target_render_h.store(4320, Ordering::Relaxed);
+        if let Some(cmd_tx) = &handles.cmd_tx {
+            let _ = cmd_tx.try_send(MpvCommand::SetVolume(0.0));
+        }
+    }
+    let event_rx = handles.event_rx.clone().unwrap_or_else(crossbeam_channel::never);
+    let end_pts = opts.duration_ms.map(|d| opts.start_ms.unwrap_or(0) + d);
+
+    let mut stats = Stats::default();
+    let mut diagnostics = Diagnostics::default();
+    let mut error: Option<String> = None;
+    loop {
+        while handles.audio_rx.try_recv().is_ok() {
+            stats.audio_frames += 1;
+        }
+        while handles.subtitle_rx.try_recv().is_ok() {}
+        select! {
+            recv(handles.video_rx) -> frame => match frame {
+                Ok(frame) => {
+                    handles.stats.record_presented(stats.frame(&frame, opts.speed));
+                    if end_pts.zip(frame.pts_ms).is_some_and(|(end, pts)| pts >= end) {
+                        break;
+                    }
+                }
+                Err(_) => break,
+            },
+            recv(event_rx) -> event => match event {
+                Ok(PlaybackEvent::Error(e)) => error = Some(e),
+                Ok(PlaybackEvent::Stats(s)) => diagnostics.add(&s),
+                Ok(PlaybackEvent::Finished) => break,
+                _ => {}
+            },
+            recv(handles.eos_rx) -> _ => {
+                while let Ok(frame) = handles.video_rx.try_recv() {
+                    handles.stats.record_presented(stats.frame(&frame, opts.speed));
+                }
+                break;
+            }
+            default(Duration::from_millis(20)) => {}
+        }
+    }
+    let _ = handles.stop_tx.try_send(());
+    let engine_stats = handles.stats.snapshot();
+
+    let (Some((t0, pts0)), Some((t1, pts1))) = (stats.first, stats.last) else {
+        let message = match error {
+            Some(e) => format!("Cannot decode {url}: {e}"),
+            None => format!("No video frames in {url}"),
+        };
+        return Err(CliError::new(Exit::OpenFailed, message));
+    };
+    let wall = t1.duration_since(t0);
+    let wall_secs = wall.as_secs_f64().max(0.001);
+    let media_ms = pts1 - pts0;
+    let report = BenchReport {
+        url: url.to_string(),
+        engine: engine_name(opts.engine),
+        hwaccel: opts.hwaccel,
+        speed: opts.speed,
+        width: stats.size.0,
+        height: stats.size.1,
+        frames: stats.frames,
+        audio_frames: stats.audio_frames,
+        media_ms,
+        wall_ms: wall.as_millis() as u64,
+        first_frame_ms: t0.duration_since(opened_at).as_millis() as u64,
+        fps: stats.frames as f64 / wall_secs,
+        realtime_factor: media_ms as f64 / 1000.0 / wall_secs,
+        dropped: stats.dropped,
+        dropped_pct: stats.dropped as f64 * 100.0 / stats.frames as f64,
+        video_codec: engine_stats.video_codec,
+        hwdec: engine_stats.hwaccel,
+        hw_transfers_ok: engine_stats.hw_transfers_ok,
+        hw_transfers_failed: engine_stats.hw_transfers_failed,
+        decode_ms_avg: diagnostics.decode.avg(),
+        decode_ms_max: diagnostics.decode.max,
+        scale_ms_avg: diagnostics.scale.avg(),
+        scale_ms_max: diagnostics.scale.max,
+        video_queue_max: diagnostics.video_queue_max,
+        audio_queue_max: diagnostics.audio_queue_max,
+        av_drift_ms_max: diagnostics.drift_max,
+    };
+    print_report(&report, opts.json);
+
+    if let Some(e) = error {
+        return Err(CliError::new(Exit::PlaybackError, format!("Decoding stopped early: {e}")));
+    }
+    if let Some(max) = opts.max_dropped_pct.filter(|&max| report.dropped_pct > max) {
+        return Err(CliError::new(
+            Exit::BenchFailed,
+            format!("{:.1}% of frames would be dropped (limit {max}%)", report.dropped_pct),
+        ));
+    }
+    Ok(())
+}
+
+fn print_report(report: &BenchReport, json: bool) {
+    if json {
+        println!("{}", serde_json::to_string_pretty(report).unwrap());
+        return;
+    }
+    println!("{} [{}{}]", report.url, report.engine, if report.hwaccel { ", hwaccel" } else { "" });
+    println!(
+        "  video        {}x{}, {} frames, {:.1} s of media",
+        report.width,
+        report.height,
+        report.frames,
+        report.media_ms as f64 / 1000.0
+    );
+    println!("  first frame  {} ms", report.first_frame_ms);
+    println!(
+        "  decode       {:.1} fps, {:.2}x real time (target {:.2}x)",
+        report.fps, report.realtime_factor, report.speed
+    );
+    println!("  dropped      {} ({:.1}%)", report.dropped, report.dropped_pct);
+    let mut decoder = report.video_codec.clone().unwrap_or_else(|| "?".to_string());
+    match &report.hwdec {
+        Some(api) => decoder.push_str(&format!(", {api}")),
+        None if report.hwaccel => decoder.push_str(", software fallback"),
+        None => {}
+    }
+    if report.hw_transfers_ok + report.hw_transfers_failed > 0 {
+        decoder.push_str(&format!(" ({} transfers, {} failed)", report.hw_transfers_ok, report.hw_transfers_failed));
+    }
+    println!("  decoder      {decoder}");
+    println!("  decode time  {}", timing(report.decode_ms_avg, report.decode_ms_max));
+    println!("  to RGBA      {}", timing(report.scale_ms_avg, report.scale_ms_max));
+    println!("  queues       video max {}, audio max {}", report.video_queue_max, report.audio_queue_max);
+    if let Some(drift) = report.av_drift_ms_max {
+        println!("  A/V drift    max {drift:.0} ms");
+    }
+}
+
+fn timing(avg: Option<f64>, max: Option<f64>) -> String {
+    match (avg, max) {
+        (Some(avg), Some(max)) => format!("{avg:.2} ms avg, {max:.2} ms max"),
+        _ => "n/a".to_string(),
+    }
+}
@@ -0,0 +1,158 @@
+//! `bova-cli screenshot` / `bova-cli thumbnails`: decode the frame at a position and save it as an image.
+//!
+//! 每个位置单独打开一次（FFmpeg 从之前的关键帧解码到目标位置，mpv 用 `start` 精确跳转），
+//! 取第一帧时间戳不早于目标位置的画面。
+
+use crate::{engine_name, format_time, start_engine, CliError, Exit};
+use bova_playback::{MpvCommand, PlaybackConfig, PlaybackEngine, PlaybackEvent, VideoFrame};
+use crossbeam_channel::{after, select};
+use image::imageops::FilterType;
+use image::{DynamicImage, RgbaImage};
+use std::path::Path;
+use std::sync::atomic::Ordering;
+use std::time::Duration;
+
+/// 单个位置的最长等待时间（mpv 需要实时播放到目标帧）
+const GRAB_TIMEOUT: Duration = Duration::from_secs(30);
+/// mpv 的帧时间戳取自 `time-pos`，允许略早于目标位置
+const MPV_PTS_TOLERANCE_MS: i64 = 100;
+
+pub struct GrabOptions {
+    pub engine: PlaybackEngine,
+    pub hwaccel: bool,
+}
+
+pub enum Spacing {
+    /// 均匀分布在整个时长内（不含开头与结尾）
+    Count(u32),
+    Every(i64),
+}
+
+pub fn screenshot(url: &str, at_ms: i64, output: &Path, width: Option<u32>, opts: &GrabOptions) -> Result<(), CliError> {
+    let frame = grab_frame(url, at_ms, opts)?;
+    let (w, h) = save_frame(&frame, output, width)?;
+    println!(
+        "{} ({}x{}, {})",
+        output.display(),
+        w,
+        h,
+        frame.pts_ms.map_or_else(|| "no timestamp".to_string(), format_time)
+    );
+    Ok(())
+}
+
+pub fn thumbnails(url: &str, dir: &Path, spacing: Spacing, width: u32, format: &str, opts: &GrabOptions) -> Result<(), CliError> {
+    // 先打开一次拿到时长
+    let first = grab_frame(url, 0, opts)?;
+    let duration = first
+        .duration_ms
+        .filter(|&d| d > 0)
+        .ok_or_else(|| CliError::new(Exit::OpenFailed, format!("{url} reports no duration, use `screenshot --at` instead")))?;
+    let positions: Vec<i64> = match spacing {
+        Spacing::Count(n) => (1..=n as i64).map(|i| duration * i / (n as i64 + 1)).collect(),
+        Spacing::Every(step) => (1..).map(|i| i * step).take_while(|&t| t < duration).collect(),
+    };
+    if positions.is_empty() {
+        return Err(CliError::new(Exit::Usage, "--interval is longer than the media"));
+    }
+    std::fs::create_dir_all(dir)
+        .map_err(|e| CliError::new(Exit::Failed, format!("Cannot create {}: {e}", dir.display())))?;
+
+    let format = format.trim_start_matches('.').to_lowercase();
+    for (i, &at) in positions.iter().enumerate() {
+        let frame = grab_frame(url, at, opts)?;
+        let path = dir.join(format!("thumb-{:03}.{format}", i + 1));
+        save_frame(&frame, &path, Some(width))?;
+        println!("{}\t{}", path.display(), format_time(at));
+    }
+    Ok(())
+}
+
+/// 打开媒体并取 `at_ms` 处（或其后第一帧）的画面；目标超出结尾时取最后一帧
+pub fn grab_frame(url: &str, at_ms: i64, opts: &GrabOptions) -> Result<VideoFrame, CliError> {
+    let cfg = PlaybackConfig {
+        hwaccel: opts.hwaccel,
+        subtitle_enabled: false,
+        engine: Some(opts.engine),
+        start_ms: Some(at_ms).filter(|&ms| ms > 0),
+        ..Default::default()
+    };
+    let handles = start_engine(url, cfg)?;
+    let tolerance = match opts.engine {
+        PlaybackEngine::MPV => {
+            // 不放大，按原始尺寸渲染；截图时静音
+            handles.target_render_w.store(7680, Ordering::Relaxed);
+            handles.target_render_h.store(4320, Ordering::Relaxed);
+            if let Some(cmd_tx) = &handles.cmd_tx {
+                let _ = cmd_tx.try_send(MpvCommand::SetVolume(0.0));
+            }
+            MPV_PTS_TOLERANCE_MS
+        }
+        PlaybackEngine::FFmpeg => 0,
+    };
+    let event_rx = handles.event_rx.clone().unwrap_or_else(crossbeam_channel::never);
+    let timeout = after(GRAB_TIMEOUT);
+
+    let mut last: Option<VideoFrame> = None;
+    let mut error: Option<String> = None;
+    let result = loop {
+        // FFmpeg 引擎的音频、字幕通道不读会阻塞解码
+        while handles.audio_rx.try_recv().is_ok() {}
+        while handles.subtitle_rx.try_recv().is_ok() {}
+        select! {
+            recv(handles.video_rx) -> frame => match frame {
+                Ok(frame) => match frame.pts_ms {
+                    Some(pts) if pts + tolerance >= at_ms => break Ok(frame),
+                    // mpv 开始播放前先渲染的空白帧没有时间戳
+                    None if opts.engine == PlaybackEngine::FFmpeg => break Ok(frame),
+                    _ => last = Some(frame),
+                },
+                Err(_) => break last.take().ok_or(()),
+            },
+            recv(event_rx) -> event => match event {
+                Ok(PlaybackEvent::Error(e)) => error = Some(e),
+                // mpv 播到结尾后停在最后一帧，不会结束线程
+                Ok(PlaybackEvent::Finished) => break last.take().ok_or(()),
+                _ => {}
+            },
+            recv(handles.eos_rx) -> _ => {
+                // 解码结束：可能还有帧在通道里
+                while let Ok(frame) = handles.video_rx.try_recv() {
+                    last = Some(frame);
+                }
+                break last.take().ok_or(());
+            }
+            recv(timeout) -> _ => break last.take().ok_or(()),
+            default(Duration::from_millis(20)) => {}
+        }
+    };
+    let _ = handles.stop_tx.try_send(());
+
+    result.map_err(|_| match error {
+        Some(e) => CliError::new(Exit::OpenFailed, format!("Cannot decode {url}: {e}")),
+        None => CliError::new(
+            Exit::OpenFailed,
+            format!("No video frame at {} in {url} ({} engine)", format_time(at_ms), engine_name(opts.engine)),
+        ),
+    })
+}
+
+/// 写入图片（格式由扩展名决定），可按宽度等比缩放；返回写入的尺寸
+pub fn save_frame(frame: &VideoFrame, path: &Path, width: Option<u32>) -> Result<(u32, u32), CliError> {
+    let image = RgbaImage::from_raw(frame.width, frame.height, frame.rgba.clone())
+        .ok_or_else(|| CliError::new(Exit::Failed, format!("Invalid frame {}x{}", frame.width, frame.height)))?;
+    let image = match width.filter(|&w| w > 0 && w != frame.width) {
+        Some(w) => {
+            let h = ((frame.height as u64 * w as u64) / frame.width.max(1) as u64).max(1) as u32;
+            image::imageops::resize(&image, w, h, FilterType::Triangle)
+        }
+        None => image,
+    };
+    let (w, h) = image.dimensions();
+    // JPEG 不支持透明通道
+    DynamicImage::ImageRgba8(image)
+        .to_rgb8()
+        .save(path)
+        .map_err(|e| CliError::new(Exit::Failed, format!("Cannot write {}: {e}", path.display())))?;
+    Ok((w, h))
+}
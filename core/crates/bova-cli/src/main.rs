@@ -1,84 +1,534 @@
-use bova_playback::{start_playback_with, PlaybackConfig};
-use bova_probe::probe;
-use clap::Parser;
-use std::time::Duration;
+use bova_core::logging::{self, LogFilter};
+use bova_core::playlist::{Playlist, PlaylistFormat};
+use bova_playback::{start_mpv_playback_handles, start_playback_with, PlaybackConfig, PlaybackEngine, PlaybackHandles};
+use bova_probe::{probe_with, ProbeError, ProbeOptions};
+use bova_settings::{EnginePreference, Preferences, SettingsStore, StateStore};
+use clap::{CommandFactory, Parser, Subcommand, ValueEnum};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
 
-/// Minimal CLI to exercise BovaPlayer core API stubs.
+mod bench;
+mod grab;
+mod play;
+mod scan;
+mod term;
+
+const EXIT_CODES_HELP: &str = "\
+Exit codes:
+  0    success
+  1    failure (I/O, cannot write output)
+  2    invalid arguments
+  3    media could not be opened
+  4    playback error after the media was opened
+  5    not available in this build (e.g. FFmpeg engine without `--features ffmpeg`)
+  6    bench: dropped frames above --max-dropped
+  130  interrupted (Ctrl+C)";
+
+/// Headless player and media tools on top of the BovaPlayer core.
 #[derive(Parser, Debug)]
-#[command(author="BovaPlayer", version="0.0.1", about="Bova CLI Demo", long_about=None)]
+#[command(author="BovaPlayer", version="0.0.1", about="BovaPlayer headless player and media tools", long_about=None, args_conflicts_with_subcommands = true, after_help = EXIT_CODES_HELP)]
 struct Args {
-    /// URL or file path
-    url: String,
+    #[command(subcommand)]
+    command: Option<Command>,
 
-    /// Probe only (print media info)
+    /// URL, file path or playlist to play (same as `play URL`)
+    url: Option<String>,
+
+    /// Print saved preferences and the remembered state of URL
     #[arg(long)]
-    probe: bool,
+    state: bool,
+
+    /// Log filter, e.g. `debug` or `warn,bova_playback=debug,mpv=info`
+    /// [default: $BOVA_LOG, else "info,mpv=warn,ffmpeg=error"]
+    #[arg(long, global = true, value_name = "FILTER")]
+    log: Option<LogFilter>,
 
-    /// Use hardware acceleration
+    #[command(flatten)]
+    play: PlayArgs,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Play without a window: audio output, keyboard controls and a status line
+    #[command(after_help = play::KEYS_HELP)]
+    Play {
+        /// URLs, file paths or playlists (.m3u/.m3u8/.pls/.xspf), played in order
+        #[arg(required = true)]
+        urls: Vec<String>,
+        #[command(flatten)]
+        args: PlayArgs,
+    },
+    /// Print media info (streams, chapters, tags) as JSON
+    Probe {
+        #[arg(required = true)]
+        urls: Vec<String>,
+    },
+    /// Save the frame at a position as an image (format from the file extension)
+    Screenshot {
+        url: String,
+        /// Position (`90`, `1:30`, `1:02:03.5`)
+        #[arg(long, value_parser = parse_time, default_value = "0")]
+        at: i64,
+        #[arg(short, long, default_value = "screenshot.png")]
+        output: PathBuf,
+        /// Scale to this width, keeping the aspect ratio (default: video size)
+        #[arg(long)]
+        width: Option<u32>,
+        #[command(flatten)]
+        engine: EngineArgs,
+    },
+    /// Save evenly spaced frames as images
+    Thumbnails {
+        url: String,
+        /// Output directory
+        #[arg(short, long, default_value = "thumbnails")]
+        output: PathBuf,
+        /// Number of thumbnails, spread over the whole duration
+        #[arg(short = 'n', long, default_value_t = 9)]
+        count: u32,
+        /// One thumbnail every INTERVAL instead of a fixed count
+        #[arg(long, value_parser = parse_time, conflicts_with = "count")]
+        interval: Option<i64>,
+        #[arg(long, default_value_t = 320)]
+        width: u32,
+        /// Image format (file extension): jpg, png, webp
+        #[arg(long, default_value = "jpg")]
+        format: String,
+        #[command(flatten)]
+        engine: EngineArgs,
+    },
+    /// Probe every media file under DIR and write a catalog with codecs, durations and problems
+    Scan {
+        dir: PathBuf,
+        /// Catalog file; `.ndjson` / `.jsonl` writes one JSON object per line.
+        /// Files unchanged since the catalog was written are not probed again.
+        #[arg(short, long, default_value = "bova-catalog.json")]
+        output: PathBuf,
+        /// Parallel probes (0 = number of CPUs)
+        #[arg(short, long, default_value_t = 0)]
+        jobs: usize,
+        /// Probe every file again, ignoring the existing catalog
+        #[arg(long)]
+        full: bool,
+        /// Video codecs the target devices decode (FFmpeg names, comma separated); defaults to this build
+        #[arg(long, value_delimiter = ',')]
+        video_codecs: Option<Vec<String>>,
+        /// Audio codecs the target devices decode
+        #[arg(long, value_delimiter = ',')]
+        audio_codecs: Option<Vec<String>>,
+    },
+    /// Decode as fast as possible and report decode fps and the frames real-time playback would drop
+    Bench {
+        url: String,
+        /// Start position
+        #[arg(long, value_parser = parse_time)]
+        start: Option<i64>,
+        /// Only decode this much media (default: to the end)
+        #[arg(long, value_parser = parse_time)]
+        duration: Option<i64>,
+        /// Real-time speed the dropped frames are counted against
+        #[arg(long, default_value_t = 1.0, value_parser = parse_speed)]
+        speed: f64,
+        /// Exit with code 6 when more than this percentage of frames would be dropped
+        #[arg(long)]
+        max_dropped: Option<f64>,
+        /// Print the report as JSON
+        #[arg(long)]
+        json: bool,
+        #[command(flatten)]
+        engine: EngineArgs,
+    },
+}
+
+/// Engine selection shared by all commands that decode media
+#[derive(clap::Args, Debug, Clone)]
+struct EngineArgs {
+    /// Playback engine; `auto` uses the saved preference for `play` and FFmpeg (when built in) for the tools
+    #[arg(long, value_enum, default_value_t = EngineArg::Auto)]
+    engine: EngineArg,
+    /// Use hardware decoding (also enabled by the saved `hwaccel` preference)
     #[arg(short = 'H', long)]
     hardware: bool,
+    /// Decode in software even if the saved preference enables hardware decoding
+    #[arg(long, conflicts_with = "hardware")]
+    software: bool,
+}
+
+#[derive(clap::Args, Debug, Clone)]
+struct PlayArgs {
+    #[command(flatten)]
+    engine: EngineArgs,
+    /// Start position (`90`, `1:30`, `1:02:03.5`), or `resume` for the remembered position
+    #[arg(long, value_parser = parse_start)]
+    start: Option<StartAt>,
+    /// Stop at this position
+    #[arg(long, value_parser = parse_time)]
+    end: Option<i64>,
+    /// Playback speed, 0.25 to 4
+    #[arg(long, default_value_t = 1.0, value_parser = parse_speed)]
+    speed: f64,
+    /// Subtitles: track number (1-based), a subtitle file, or `off`
+    #[arg(long, value_parser = parse_sub)]
+    sub: Option<SubArg>,
+    /// Audio track (1-based)
+    #[arg(long)]
+    audio_track: Option<u32>,
+    /// Volume 0-100 (default: the saved volume)
+    #[arg(long, value_parser = clap::value_parser!(u8).range(0..=100))]
+    volume: Option<u8>,
+    /// No sound: the FFmpeg engine opens no audio device, mpv plays muted
+    #[arg(long)]
+    no_audio: bool,
+    /// Don't draw the status line (it is only drawn when stdout is a terminal)
+    #[arg(long)]
+    no_status: bool,
 }
 
-fn main() {
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+enum EngineArg {
+    Auto,
+    Mpv,
+    Ffmpeg,
+}
+
+/// `--start`：固定位置或上次记录的续播位置
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StartAt {
+    Position(i64),
+    Resume,
+}
+
+/// `--sub`：轨道序号（从 1 开始）、外挂字幕文件或关闭
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SubArg {
+    Off,
+    Track(u32),
+    File(PathBuf),
+}
+
+/// 进程退出码；脚本和 CI 依赖这些值，只能新增不能改动
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Exit {
+    Ok = 0,
+    Failed = 1,
+    Usage = 2,
+    OpenFailed = 3,
+    PlaybackError = 4,
+    Unsupported = 5,
+    BenchFailed = 6,
+    Interrupted = 130,
+}
+
+impl From<Exit> for ExitCode {
+    fn from(exit: Exit) -> Self {
+        ExitCode::from(exit as u8)
+    }
+}
+
+/// 以某个退出码结束的错误；`message` 为空时不打印
+#[derive(Debug)]
+pub struct CliError {
+    pub exit: Exit,
+    pub message: String,
+}
+
+impl CliError {
+    pub fn new(exit: Exit, message: impl Into<String>) -> Self {
+        Self { exit, message: message.into() }
+    }
+}
+
+fn main() -> ExitCode {
     let args = Args::parse();
+    logging::init();
+    if let Some(filter) = args.log.clone() {
+        logging::set_filter(filter);
+    }
+    // 终端模式等资源在 run 返回前已恢复，之后才退出进程
+    match run(args) {
+        Ok(()) => Exit::Ok.into(),
+        Err(e) => {
+            if !e.message.is_empty() {
+                eprintln!("{}", e.message);
+            }
+            e.exit.into()
+        }
+    }
+}
+
+fn run(args: Args) -> Result<(), CliError> {
+    let command = match (args.command, args.url) {
+        (Some(command), _) => command,
+        (None, Some(url)) if args.state => return print_state(&url),
+        (None, Some(url)) => Command::Play { urls: vec![url], args: args.play },
+        (None, None) => {
+            let _ = Args::command().print_help();
+            return Err(CliError::new(Exit::Usage, ""));
+        }
+    };
 
-    if args.probe {
-        let info = probe(&args.url);
-        println!("{}", serde_json::to_string_pretty(&info).unwrap());
-        return;
+    match command {
+        Command::Play { urls, args } => run_play(&urls, args),
+        Command::Probe { urls } => run_probe(&urls),
+        Command::Screenshot { url, at, output, width, engine } => {
+            let opts = grab_options(&engine)?;
+            grab::screenshot(&url, at, &output, width, &opts)
+        }
+        Command::Thumbnails { url, output, count, interval, width, format, engine } => {
+            let opts = grab_options(&engine)?;
+            let spacing = match interval {
+                Some(ms) if ms > 0 => grab::Spacing::Every(ms),
+                Some(_) => return Err(CliError::new(Exit::Usage, "--interval must be positive")),
+                None => grab::Spacing::Count(count.max(1)),
+            };
+            grab::thumbnails(&url, &output, spacing, width, &format, &opts)
+        }
+        Command::Scan { dir, output, jobs, full, video_codecs, audio_codecs } => {
+            if !cfg!(feature = "ffmpeg") {
+                return Err(CliError::new(Exit::Unsupported, "Scanning needs FFmpeg: rebuild bova-cli with `--features ffmpeg`"));
+            }
+            let format = scan::CatalogFormat::from_path(&output);
+            let opts = scan::ScanOptions { root: dir, output, format, jobs, full, video_codecs, audio_codecs };
+            scan::run(&opts).map_err(|e| CliError::new(Exit::Failed, format!("Scan failed: {e}")))
+        }
+        Command::Bench { url, start, duration, speed, max_dropped, json, engine } => {
+            let grab = grab_options(&engine)?;
+            let opts = bench::BenchOptions {
+                engine: grab.engine,
+                hwaccel: grab.hwaccel,
+                start_ms: start,
+                duration_ms: duration,
+                speed,
+                max_dropped_pct: max_dropped,
+                json,
+            };
+            bench::run(&url, &opts)
+        }
     }
+}
+
+fn run_play(urls: &[String], args: PlayArgs) -> Result<(), CliError> {
+    let urls = expand_playlists(urls)?;
+    let mut settings = SettingsStore::open();
+    let prefs = settings.get().clone();
 
-    println!("Opening: {}", args.url);
-    
-    let config = PlaybackConfig {
-        hwaccel: args.hardware,
-        subtitle_enabled: false,
-        subtitle_index: None,
-        engine: Some(bova_playback::PlaybackEngine::FFmpeg),
+    let engine = resolve_engine(args.engine.engine, Some(prefs.engine))?;
+    let hwaccel = resolve_hwaccel(&args.engine, &prefs);
+    if engine == PlaybackEngine::FFmpeg && args.sub.as_ref().is_some_and(|s| matches!(s, SubArg::File(_))) {
+        return Err(CliError::new(Exit::Unsupported, "External subtitle files need the mpv engine (--engine mpv)"));
+    }
+    let mut opts = play::PlayOptions {
+        engine,
+        hwaccel,
+        start: args.start,
+        end_ms: args.end,
+        speed: args.speed,
+        sub: args.sub,
+        subtitle_enabled: prefs.subtitle_enabled,
+        audio_track: args.audio_track,
+        volume: args.volume.map_or(prefs.volume, |v| v as f32 / 100.0),
+        audio_output: !args.no_audio,
+        status: !args.no_status,
     };
-    
-    if args.hardware {
+    if hwaccel {
         println!("Using hardware acceleration");
     }
-    
-    let handles = match start_playback_with(&args.url, config) {
-        Ok(h) => h,
-        Err(e) => {
-            eprintln!("Playback failed to start: {e}");
-            std::process::exit(1);
+    if urls.len() > 1 {
+        println!("Playlist: {} entries", urls.len());
+    }
+
+    let keys = term::Keys::open();
+    // 播放列表中个别条目失败时继续下一项，全部失败才以错误退出
+    let mut failed = 0;
+    let mut failure = Exit::OpenFailed;
+    let mut index = 0;
+    while index < urls.len() {
+        let url = &urls[index];
+        match play::play(url, &mut opts, &keys) {
+            Ok(play::Outcome::Finished) | Ok(play::Outcome::Next) => index += 1,
+            Ok(play::Outcome::Previous) => index = index.saturating_sub(1),
+            Ok(play::Outcome::Quit) => break,
+            Ok(play::Outcome::Interrupted) => return Err(CliError::new(Exit::Interrupted, "")),
+            Err(e) => {
+                eprintln!("{}", e.message);
+                failed += 1;
+                failure = e.exit;
+                index += 1;
+            }
         }
-    };
+        // 只有第一项从指定位置开始
+        opts.start = None;
+    }
+    drop(keys);
 
-    println!("Playing... Press Ctrl+C to stop.");
-
-    // Simple playback loop that shows we're receiving frames
-    let mut frame_count = 0;
-    let start_time = std::time::Instant::now();
-    
-    while frame_count < 100 { // Limit to 100 frames for demo
-        match handles.video_rx.recv_timeout(Duration::from_millis(100)) {
-            Ok(frame) => {
-                frame_count += 1;
-                if frame_count % 10 == 0 {
-                    println!("Received frame {}: {}x{}", frame_count, frame.width, frame.height);
-                }
+    if failed > 0 && failed >= urls.len() {
+        return Err(CliError::new(failure, ""));
+    }
+
+    if let Some(first) = urls.first() {
+        settings.get_mut().push_recent(first);
+        if let Err(e) = settings.save() {
+            eprintln!("Cannot save settings: {e}");
+        }
+    }
+    Ok(())
+}
+
+fn run_probe(urls: &[String]) -> Result<(), CliError> {
+    let opts = ProbeOptions::interactive();
+    let mut failure: Option<CliError> = None;
+    for url in expand_playlists(urls)? {
+        match probe_with(&url, &opts) {
+            Ok(info) => println!("{}", serde_json::to_string_pretty(&info).unwrap()),
+            Err(ProbeError::FeatureDisabled) => {
+                return Err(CliError::new(Exit::Unsupported, "Probing needs FFmpeg: rebuild bova-cli with `--features ffmpeg`"));
             }
-            Err(_) => {
-                // Timeout or channel closed
-                if handles.eos_rx.try_recv().is_ok() {
-                    println!("End of stream reached");
-                    break;
-                }
+            Err(e) => {
+                eprintln!("Probe failed for {url}: {e}");
+                failure.get_or_insert(CliError::new(Exit::OpenFailed, ""));
             }
         }
-        
-        // Check for stop signal
-        if std::thread::panicking() {
-            break;
+    }
+    failure.map_or(Ok(()), Err)
+}
+
+fn print_state(url: &str) -> Result<(), CliError> {
+    let settings = SettingsStore::open();
+    let state = StateStore::open();
+    let out = serde_json::json!({
+        "settings": settings.get(),
+        "file": state.get().file(url),
+    });
+    println!("{}", serde_json::to_string_pretty(&out).unwrap());
+    Ok(())
+}
+
+/// 本地播放列表文件展开为其中的条目；网络地址和 HLS 列表原样交给引擎
+fn expand_playlists(urls: &[String]) -> Result<Vec<String>, CliError> {
+    let mut out = Vec::new();
+    for url in urls {
+        if is_local_playlist(url) {
+            let playlist = Playlist::load_file(Path::new(url))
+                .map_err(|e| CliError::new(Exit::OpenFailed, format!("Cannot read playlist {url}: {e}")))?;
+            out.extend(playlist.entries.into_iter().map(|e| e.url));
+        } else {
+            out.push(url.clone());
         }
     }
+    if out.is_empty() {
+        return Err(CliError::new(Exit::OpenFailed, "Nothing to play: the playlist is empty"));
+    }
+    Ok(out)
+}
+
+fn is_local_playlist(url: &str) -> bool {
+    let path = Path::new(url);
+    match PlaylistFormat::from_path(path) {
+        _ if url.contains("://") || !path.is_file() => false,
+        Some(PlaylistFormat::M3u) => !std::fs::read_to_string(path).is_ok_and(|text| PlaylistFormat::is_hls(&text)),
+        Some(_) => true,
+        None => false,
+    }
+}
+
+/// `auto`：`play` 用保存的偏好，工具类命令优先 FFmpeg（逐帧解码、不按实时节奏）
+fn resolve_engine(arg: EngineArg, preference: Option<EnginePreference>) -> Result<PlaybackEngine, CliError> {
+    let ffmpeg_built = cfg!(feature = "ffmpeg");
+    match arg {
+        EngineArg::Mpv => Ok(PlaybackEngine::MPV),
+        EngineArg::Ffmpeg if ffmpeg_built => Ok(PlaybackEngine::FFmpeg),
+        EngineArg::Ffmpeg => Err(CliError::new(Exit::Unsupported, "The FFmpeg engine needs FFmpeg: rebuild bova-cli with `--features ffmpeg`")),
+        EngineArg::Auto => Ok(match preference {
+            Some(EnginePreference::Mpv) => PlaybackEngine::MPV,
+            _ if ffmpeg_built => PlaybackEngine::FFmpeg,
+            _ => PlaybackEngine::MPV,
+        }),
+    }
+}
 
-    // Send stop signal
-    let _ = handles.stop_tx.send(());
-    println!("Playback stopped after {} frames ({:.2}s)", frame_count, start_time.elapsed().as_secs_f32());
+fn resolve_hwaccel(args: &EngineArgs, prefs: &Preferences) -> bool {
+    !args.software && (args.hardware || prefs.hwaccel)
+}
+
+fn grab_options(args: &EngineArgs) -> Result<grab::GrabOptions, CliError> {
+    let settings = SettingsStore::open();
+    Ok(grab::GrabOptions {
+        engine: resolve_engine(args.engine, None)?,
+        hwaccel: resolve_hwaccel(args, settings.get()),
+    })
+}
+
+/// 按所选引擎启动播放（`start_playback_with` 在同时编入两个引擎时总是用 FFmpeg）
+pub fn start_engine(url: &str, cfg: PlaybackConfig) -> Result<PlaybackHandles, CliError> {
+    let result = match cfg.engine {
+        Some(PlaybackEngine::MPV) => start_mpv_playback_handles(url, &cfg),
+        _ => start_playback_with(url, cfg),
+    };
+    result.map_err(|e| CliError::new(Exit::OpenFailed, format!("Cannot open {url}: {e:#}")))
+}
+
+pub fn engine_name(engine: PlaybackEngine) -> &'static str {
+    match engine {
+        PlaybackEngine::FFmpeg => "ffmpeg",
+        PlaybackEngine::MPV => "mpv",
+    }
+}
+
+/// `90`、`90.5`、`1:30`、`1:02:03.5` → 毫秒
+fn parse_time(s: &str) -> Result<i64, String> {
+    let s = s.trim().trim_end_matches('s');
+    let mut total = 0.0;
+    let parts: Vec<&str> = s.split(':').collect();
+    if parts.len() > 3 {
+        return Err(format!("invalid time `{s}`, expected SECONDS, MM:SS or HH:MM:SS"));
+    }
+    for (i, part) in parts.iter().enumerate() {
+        let value: f64 = part.parse().map_err(|_| format!("invalid time `{s}`, expected SECONDS, MM:SS or HH:MM:SS"))?;
+        // 只有最后一段可以带小数
+        if value < 0.0 || (i + 1 < parts.len() && value.fract() != 0.0) {
+            return Err(format!("invalid time `{s}`"));
+        }
+        total = total * 60.0 + value;
+    }
+    Ok((total * 1000.0).round() as i64)
+}
+
+fn parse_start(s: &str) -> Result<StartAt, String> {
+    if s.eq_ignore_ascii_case("resume") {
+        return Ok(StartAt::Resume);
+    }
+    parse_time(s).map(StartAt::Position)
+}
+
+fn parse_speed(s: &str) -> Result<f64, String> {
+    let speed: f64 = s.parse().map_err(|_| format!("invalid speed `{s}`"))?;
+    if !(play::MIN_SPEED..=play::MAX_SPEED).contains(&speed) {
+        return Err(format!("speed must be between {} and {}", play::MIN_SPEED, play::MAX_SPEED));
+    }
+    Ok(speed)
+}
+
+fn parse_sub(s: &str) -> Result<SubArg, String> {
+    if s.eq_ignore_ascii_case("off") || s.eq_ignore_ascii_case("no") {
+        return Ok(SubArg::Off);
+    }
+    if let Ok(track) = s.parse::<u32>() {
+        return if track > 0 { Ok(SubArg::Track(track)) } else { Err("subtitle tracks start at 1".to_string()) };
+    }
+    let path = PathBuf::from(s);
+    if !path.is_file() {
+        return Err(format!("subtitle file not found: {s}"));
+    }
+    Ok(SubArg::File(path))
+}
+
+/// 毫秒 → `M:SS` / `H:MM:SS`
+pub fn format_time(ms: i64) -> String {
+    let secs = ms.max(0) / 1000;
+    let (h, m, s) = (secs / 3600, secs / 60 % 60, secs % 60);
+    if h > 0 {
+        format!("{h}:{m:02}:{s:02}")
+    } else {
+        format!("{m}:{s:02}")
+    }
 }
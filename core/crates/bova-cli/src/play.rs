@@ -0,0 +1,723 @@
+//! `bova-cli play`: headless playback with keyboard controls and a status line.
+//!
+//! mpv 引擎自己输出音频并做音画同步，这里只转发按键命令、读取进度；FFmpeg 引擎只交出
+//! 解码后的帧，由本模块用 rodio 播放音频，并按音频时钟（没有音频输出时按墙钟）消费视频帧，
+//! 来不及显示而被跳过的帧计为丢帧。FFmpeg 引擎没有命令通道，跳转通过从新位置重新打开实现。
+
+use crate::term::{Key, Keys, StatusLine};
+use crate::{engine_name, format_time, start_engine, CliError, Exit, StartAt, SubArg};
+use bova_core::resume::{media_key, ResumeStore};
+use bova_playback::{AudioFrame, Chapter, MpvCommand, PlaybackConfig, PlaybackEngine, PlaybackEvent, PlaybackHandles, VideoFrame};
+use bova_probe::{probe_with, ProbeOptions};
+use rodio::{buffer::SamplesBuffer, OutputStream, OutputStreamHandle, Sink};
+use std::collections::VecDeque;
+use std::sync::atomic::Ordering;
+use std::time::{Duration, Instant};
+
+pub const MIN_SPEED: f64 = 0.25;
+pub const MAX_SPEED: f64 = 4.0;
+const SPEED_STEP: f64 = 0.25;
+const VOLUME_STEP: f32 = 0.05;
+const SEEK_STEP_MS: i64 = 10_000;
+const SEEK_LONG_STEP_MS: i64 = 60_000;
+/// 排入声卡的音频帧上限（约 1 秒）
+const AUDIO_QUEUE_FRAMES: usize = 48;
+const STATUS_INTERVAL: Duration = Duration::from_millis(250);
+/// 显示时已落后时钟超过该值的帧计为迟到帧
+const LATE_TOLERANCE_MS: i64 = 40;
+/// 打开后这么久没有任何输出即视为打开失败
+const OPEN_TIMEOUT: Duration = Duration::from_secs(30);
+
+pub const KEYS_HELP: &str = "\
+Keys:
+  space, p     pause / resume
+  left, right  seek -10 s / +10 s
+  down, up     seek -60 s / +60 s
+  PgUp, PgDn   previous / next chapter
+  [ ]          slower / faster (Backspace: normal speed)
+  9 0          volume down / up (m: mute)
+  s            subtitles on / off
+  < >          previous / next playlist entry
+  q, Esc       quit
+stdin may also be a pipe: scripts can write the same keys, e.g. `q` to stop.";
+
+pub struct PlayOptions {
+    pub engine: PlaybackEngine,
+    pub hwaccel: bool,
+    pub start: Option<StartAt>,
+    pub end_ms: Option<i64>,
+    pub speed: f64,
+    pub sub: Option<SubArg>,
+    /// 未指定 `--sub` 时是否显示字幕（保存的偏好）
+    pub subtitle_enabled: bool,
+    pub audio_track: Option<u32>,
+    /// 0.0 ..= 1.0
+    pub volume: f32,
+    /// false：FFmpeg 引擎不打开声卡，mpv 静音
+    pub audio_output: bool,
+    pub status: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    Finished,
+    Quit,
+    Next,
+    Previous,
+    Interrupted,
+}
+
+/// 播放一项直到结束、出错或用户操作；结束时记录续播位置
+pub fn play(url: &str, opts: &mut PlayOptions, keys: &Keys) -> Result<Outcome, CliError> {
+    let resume_key = media_key(url);
+    let mut resume = ResumeStore::default();
+    let start_ms = match opts.start {
+        Some(StartAt::Position(ms)) => Some(ms),
+        Some(StartAt::Resume) => resume_key.as_deref().and_then(|key| resume.position(key)),
+        None => None,
+    };
+
+    println!("Opening: {url} [{}]", engine_name(opts.engine));
+    if let Some(ms) = start_ms.filter(|&ms| ms > 0) {
+        println!("Starting at {}", format_time(ms));
+    }
+
+    let mut player = Player::open(url, opts, start_ms)?;
+    let mut status = StatusLine::new(opts.status);
+    let result = player.run(opts, keys, &mut status);
+    player.stop();
+    status.clear();
+
+    let position = player.position_ms.map_or_else(|| "--:--".to_string(), format_time);
+    match &result {
+        Ok(Outcome::Finished) => println!("Finished at {position} ({} frames, {} dropped)", player.shown, player.dropped),
+        Ok(_) => println!("Stopped at {position}"),
+        Err(_) => {}
+    }
+    if let (Ok(outcome), Some(key), Some(pos)) = (&result, &resume_key, player.position_ms) {
+        let duration = player.duration_ms.unwrap_or(0);
+        // 播到结尾时按已看完记录
+        let pos = if *outcome == Outcome::Finished && opts.end_ms.is_none() && duration > 0 { duration } else { pos };
+        resume.record(key, pos, duration);
+    }
+    result
+}
+
+struct Player {
+    url: String,
+    engine: PlaybackEngine,
+    handles: PlaybackHandles,
+    /// FFmpeg 引擎 + 可用声卡时的音频输出
+    device: Option<(OutputStream, OutputStreamHandle)>,
+    audio: Option<AudioOut>,
+    pending_audio: Option<AudioFrame>,
+    pending_video: Option<VideoFrame>,
+    clock: Clock,
+    /// FFmpeg 引擎是否解码字幕、选中的字幕流（全局流索引），重新打开时沿用
+    subtitle_decode: bool,
+    subtitle_index: Option<u32>,
+    /// 字幕是否显示（`s` 切换）
+    subtitle_enabled: bool,
+    subtitles: VecDeque<(i64, String)>,
+    paused: bool,
+    muted: bool,
+    position_ms: Option<i64>,
+    duration_ms: Option<i64>,
+    chapters: Vec<Chapter>,
+    buffering: Option<u8>,
+    shown: u64,
+    dropped: u64,
+    opened: bool,
+    eos: bool,
+    finished: bool,
+    error: Option<String>,
+}
+
+impl Player {
+    fn open(url: &str, opts: &PlayOptions, start_ms: Option<i64>) -> Result<Self, CliError> {
+        let subtitle_enabled = match &opts.sub {
+            Some(SubArg::Off) => false,
+            Some(_) => true,
+            None => opts.subtitle_enabled,
+        };
+        let subtitle_index = match (&opts.sub, opts.engine) {
+            (Some(SubArg::Track(n)), PlaybackEngine::FFmpeg) => {
+                let index = subtitle_stream_index(url, *n);
+                if index.is_none() {
+                    log::warn!("subtitle track {n} not found, using the default track");
+                }
+                index
+            }
+            _ => None,
+        };
+        let device = if opts.engine == PlaybackEngine::FFmpeg && opts.audio_output {
+            match OutputStream::try_default() {
+                Ok(device) => Some(device),
+                Err(e) => {
+                    log::warn!("no audio device ({e}), playing without sound");
+                    None
+                }
+            }
+        } else {
+            None
+        };
+
+        let mut player = Player {
+            url: url.to_string(),
+            engine: opts.engine,
+            handles: start_engine(url, config(opts, start_ms, subtitle_enabled, subtitle_index))?,
+            device,
+            audio: None,
+            pending_audio: None,
+            pending_video: None,
+            clock: Clock::new(opts.speed),
+            subtitle_decode: subtitle_enabled,
+            subtitle_index,
+            subtitle_enabled,
+            subtitles: VecDeque::new(),
+            paused: false,
+            muted: !opts.audio_output,
+            position_ms: start_ms,
+            duration_ms: None,
+            chapters: Vec::new(),
+            buffering: None,
+            shown: 0,
+            dropped: 0,
+            opened: false,
+            eos: false,
+            finished: false,
+            error: None,
+        };
+        player.after_start(opts);
+        Ok(player)
+    }
+
+    /// 打开后应用速度、音量与字幕选择
+    fn after_start(&mut self, opts: &PlayOptions) {
+        match self.engine {
+            PlaybackEngine::MPV => {
+                // 不显示画面，只需要进度：按最小尺寸渲染
+                self.handles.target_render_w.store(160, Ordering::Relaxed);
+                self.handles.target_render_h.store(90, Ordering::Relaxed);
+                if (opts.speed - 1.0).abs() > f64::EPSILON {
+                    self.send(MpvCommand::SetSpeed(opts.speed));
+                }
+                self.apply_volume(opts);
+                match &opts.sub {
+                    Some(SubArg::Off) => self.send(MpvCommand::DisableSubtitle),
+                    Some(SubArg::Track(n)) => self.send(MpvCommand::SelectSubtitle(*n as i64)),
+                    Some(SubArg::File(path)) => self.send(MpvCommand::LoadExternalSub(path.display().to_string())),
+                    None if !self.subtitle_enabled => self.send(MpvCommand::SetSubVisibility(false)),
+                    None => {}
+                }
+            }
+            PlaybackEngine::FFmpeg => {
+                let volume = self.volume(opts);
+                self.audio = self.device.as_ref().and_then(|(_, handle)| AudioOut::new(handle, volume, opts.speed, self.paused));
+            }
+        }
+    }
+
+    fn run(&mut self, opts: &mut PlayOptions, keys: &Keys, status: &mut StatusLine) -> Result<Outcome, CliError> {
+        let opened_at = Instant::now();
+        let mut last_status: Option<Instant> = None;
+        loop {
+            while let Some(key) = keys.try_key() {
+                if let Some(outcome) = self.handle_key(key, opts) {
+                    return Ok(outcome);
+                }
+            }
+            self.poll_events(status);
+            match self.engine {
+                PlaybackEngine::MPV => self.drain_mpv(),
+                PlaybackEngine::FFmpeg => self.feed_ffmpeg(),
+            }
+            self.show_subtitles(status);
+            if self.handles.eos_rx.try_recv().is_ok() {
+                self.eos = true;
+            }
+
+            if let Some(error) = self.error.take() {
+                let exit = if self.opened { Exit::PlaybackError } else { Exit::OpenFailed };
+                return Err(CliError::new(exit, format!("Playback failed for {}: {error}", self.url)));
+            }
+            if self.eos && !self.opened {
+                return Err(CliError::new(Exit::OpenFailed, format!("Nothing to play in {}", self.url)));
+            }
+            if self.is_finished() || opts.end_ms.zip(self.position_ms).is_some_and(|(end, pos)| pos >= end) {
+                return Ok(Outcome::Finished);
+            }
+            if !self.opened && opened_at.elapsed() > OPEN_TIMEOUT {
+                return Err(CliError::new(Exit::OpenFailed, format!("Timed out opening {}", self.url)));
+            }
+
+            if last_status.is_none_or(|t| t.elapsed() >= STATUS_INTERVAL) {
+                status.draw(&self.status_text(opts));
+                last_status = Some(Instant::now());
+            }
+            std::thread::sleep(Duration::from_millis(5));
+        }
+    }
+
+    fn handle_key(&mut self, key: Key, opts: &mut PlayOptions) -> Option<Outcome> {
+        let position = self.position_ms.unwrap_or(0);
+        match key {
+            Key::Char('q') | Key::Esc => return Some(Outcome::Quit),
+            Key::CtrlC => return Some(Outcome::Interrupted),
+            Key::Char('<') => return Some(Outcome::Previous),
+            Key::Char('>') => return Some(Outcome::Next),
+            Key::Char(' ') | Key::Char('p') => self.set_paused(!self.paused),
+            Key::Left => self.seek_to(position - SEEK_STEP_MS, opts),
+            Key::Right => self.seek_to(position + SEEK_STEP_MS, opts),
+            Key::Down => self.seek_to(position - SEEK_LONG_STEP_MS, opts),
+            Key::Up => self.seek_to(position + SEEK_LONG_STEP_MS, opts),
+            Key::PageUp => self.chapter(false, opts),
+            Key::PageDown => self.chapter(true, opts),
+            Key::Char('[') => self.set_speed(opts.speed - SPEED_STEP, opts),
+            Key::Char(']') => self.set_speed(opts.speed + SPEED_STEP, opts),
+            Key::Backspace => self.set_speed(1.0, opts),
+            Key::Char('9') => {
+                opts.volume = (opts.volume - VOLUME_STEP).max(0.0);
+                self.apply_volume(opts);
+            }
+            Key::Char('0') => {
+                opts.volume = (opts.volume + VOLUME_STEP).min(1.0);
+                self.apply_volume(opts);
+            }
+            Key::Char('m') => {
+                self.muted = !self.muted;
+                self.apply_volume(opts);
+            }
+            Key::Char('s') => {
+                self.subtitle_enabled = !self.subtitle_enabled;
+                if self.engine == PlaybackEngine::MPV {
+                    self.send(MpvCommand::SetSubVisibility(self.subtitle_enabled));
+                }
+            }
+            _ => {}
+        }
+        None
+    }
+
+    fn poll_events(&mut self, status: &mut StatusLine) {
+        if let Some(event_rx) = self.handles.event_rx.clone() {
+            while let Ok(event) = event_rx.try_recv() {
+                match event {
+                    PlaybackEvent::Metadata { tags, .. } => {
+                        // FFmpeg 引擎每次重新打开都会再发一次
+                        if !self.opened {
+                            if let Some(title) = tags.display_title() {
+                                status.message(&format!("Title: {title}"));
+                            }
+                        }
+                        self.opened = true;
+                    }
+                    PlaybackEvent::Chapters(chapters) => {
+                        if self.chapters.is_empty() {
+                            status.message(&format!("{} chapters", chapters.len()));
+                        }
+                        self.chapters = chapters;
+                    }
+                    PlaybackEvent::PositionChanged(secs) => {
+                        self.opened = true;
+                        self.position_ms = Some((secs * 1000.0) as i64);
+                    }
+                    PlaybackEvent::Buffering(pct) => self.buffering = (pct < 100).then_some(pct),
+                    PlaybackEvent::Finished => self.finished = true,
+                    PlaybackEvent::Error(e) => self.error = Some(e),
+                    _ => {}
+                }
+            }
+        }
+        if let Some(tracks) = self.handles.track_info_rx.as_ref().and_then(|rx| rx.try_recv().ok()) {
+            status.message("Subtitle tracks:");
+            for track in &tracks {
+                status.message(&format!("  {track}"));
+            }
+        }
+    }
+
+    /// mpv 自己按节奏渲染，这里只取进度与时长
+    fn drain_mpv(&mut self) {
+        while let Ok(frame) = self.handles.video_rx.try_recv() {
+            if let Some(pts) = frame.pts_ms {
+                self.opened = true;
+                self.position_ms = Some(pts);
+            }
+            if frame.duration_ms.is_some() {
+                self.duration_ms = frame.duration_ms;
+            }
+            self.shown += 1;
+        }
+    }
+
+    fn feed_ffmpeg(&mut self) {
+        if self.paused {
+            return;
+        }
+        // 音频：声卡里保持约 1 秒；没有声卡时按时钟丢弃
+        while let Some(frame) = self.pending_audio.take().or_else(|| self.handles.audio_rx.try_recv().ok()) {
+            self.opened = true;
+            if frame.duration_ms.is_some() {
+                self.duration_ms = frame.duration_ms;
+            }
+            match &mut self.audio {
+                Some(audio) => {
+                    if audio.sink.len() >= AUDIO_QUEUE_FRAMES {
+                        self.pending_audio = Some(frame);
+                        break;
+                    }
+                    if let Some(pts) = frame.pts_ms {
+                        self.clock.start(pts);
+                    }
+                    audio.push(frame);
+                }
+                None => {
+                    if let Some(pts) = frame.pts_ms {
+                        self.clock.start(pts);
+                        if pts > self.clock.now_ms().unwrap_or(pts) {
+                            self.pending_audio = Some(frame);
+                            break;
+                        }
+                    }
+                }
+            }
+        }
+        if let Some(pts) = self.audio.as_mut().and_then(AudioOut::advance) {
+            self.clock.sync(pts);
+        }
+
+        // 视频：取出所有已到时间的帧，只有最新一帧算作显示，其余计为丢帧
+        let mut due = 0;
+        let mut shown_pts = None;
+        while let Some(frame) = self.pending_video.take().or_else(|| self.handles.video_rx.try_recv().ok()) {
+            self.opened = true;
+            if frame.duration_ms.is_some() {
+                self.duration_ms = frame.duration_ms;
+            }
+            if let Some(pts) = frame.pts_ms {
+                self.clock.start(pts);
+                if pts > self.clock.now_ms().unwrap_or(pts) {
+                    self.pending_video = Some(frame);
+                    break;
+                }
+            }
+            shown_pts = frame.pts_ms;
+            due += 1;
+        }
+        if due > 0 {
+            self.shown += 1;
+            self.dropped += due - 1;
+            let stats = &self.handles.stats;
+            stats.record_dropped(due - 1);
+            let drift = shown_pts.zip(self.clock.now_ms()).map(|(pts, now)| now - pts);
+            if self.audio.is_some() {
+                stats.set_av_drift(drift.map(|d| d as f64));
+            }
+            stats.record_presented(drift.is_some_and(|d| d > LATE_TOLERANCE_MS));
+        }
+
+        if let Some(now) = self.clock.now_ms() {
+            self.position_ms = Some(self.duration_ms.filter(|&d| d > 0).map_or(now, |d| now.min(d)));
+        }
+    }
+
+    /// FFmpeg 引擎的字幕按时间输出到状态行上方；字幕通道必须持续读取，否则解码线程会阻塞
+    fn show_subtitles(&mut self, status: &mut StatusLine) {
+        while let Ok(sub) = self.handles.subtitle_rx.try_recv() {
+            let text = sub.text.trim();
+            if !text.is_empty() {
+                self.subtitles.push_back((sub.start_ms, text.replace('\n', " / ")));
+            }
+        }
+        let now = self.position_ms.unwrap_or(0);
+        while let Some((start, text)) = self.subtitles.pop_front() {
+            if start > now {
+                self.subtitles.push_front((start, text));
+                break;
+            }
+            if self.subtitle_enabled {
+                status.message(&format!("[{}] {}", format_time(start), text));
+            }
+        }
+    }
+
+    fn is_finished(&self) -> bool {
+        match self.engine {
+            PlaybackEngine::MPV => self.finished || self.eos,
+            // 解码结束后还要等缓冲的帧和声卡里的音频播完
+            PlaybackEngine::FFmpeg => {
+                self.eos
+                    && self.pending_video.is_none()
+                    && self.pending_audio.is_none()
+                    && self.handles.video_rx.is_empty()
+                    && self.handles.audio_rx.is_empty()
+                    && self.audio.as_ref().is_none_or(|audio| audio.sink.empty())
+            }
+        }
+    }
+
+    fn set_paused(&mut self, paused: bool) {
+        self.paused = paused;
+        match self.engine {
+            PlaybackEngine::MPV => self.send(if paused { MpvCommand::Pause } else { MpvCommand::Resume }),
+            PlaybackEngine::FFmpeg => {
+                if paused {
+                    self.clock.pause();
+                } else {
+                    self.clock.resume();
+                }
+                if let Some(audio) = &self.audio {
+                    if paused {
+                        audio.sink.pause();
+                    } else {
+                        audio.sink.play();
+                    }
+                }
+            }
+        }
+    }
+
+    fn seek_to(&mut self, target_ms: i64, opts: &PlayOptions) {
+        let mut target = target_ms.max(0);
+        if let Some(duration) = self.duration_ms.filter(|&d| d > 0) {
+            target = target.min((duration - 1000).max(0));
+        }
+        match self.engine {
+            PlaybackEngine::MPV => self.send(MpvCommand::SeekAbsolute(target as f64 / 1000.0)),
+            PlaybackEngine::FFmpeg => self.restart(target, opts),
+        }
+        self.position_ms = Some(target);
+    }
+
+    fn chapter(&mut self, forward: bool, opts: &PlayOptions) {
+        match self.engine {
+            PlaybackEngine::MPV => self.send(if forward { MpvCommand::NextChapter } else { MpvCommand::PreviousChapter }),
+            PlaybackEngine::FFmpeg => {
+                let position = self.position_ms.unwrap_or(0);
+                let target = if forward {
+                    Chapter::next_start(&self.chapters, position)
+                } else {
+                    Chapter::previous_start(&self.chapters, position)
+                };
+                if let Some(target) = target {
+                    self.seek_to(target, opts);
+                }
+            }
+        }
+    }
+
+    /// FFmpeg 引擎没有命令通道：从新位置重新打开，丢弃已缓冲的帧与音频
+    fn restart(&mut self, start_ms: i64, opts: &PlayOptions) {
+        let _ = self.handles.stop_tx.try_send(());
+        match start_engine(&self.url, config(opts, Some(start_ms), self.subtitle_decode, self.subtitle_index)) {
+            Ok(handles) => self.handles = handles,
+            Err(e) => {
+                self.error = Some(e.message);
+                return;
+            }
+        }
+        self.pending_audio = None;
+        self.pending_video = None;
+        self.subtitles.clear();
+        self.eos = false;
+        self.clock = Clock::new(opts.speed);
+        self.after_start(opts);
+    }
+
+    fn set_speed(&mut self, speed: f64, opts: &mut PlayOptions) {
+        opts.speed = speed.clamp(MIN_SPEED, MAX_SPEED);
+        match self.engine {
+            PlaybackEngine::MPV => self.send(MpvCommand::SetSpeed(opts.speed)),
+            PlaybackEngine::FFmpeg => {
+                // rodio 通过重采样变速，音调随之改变
+                self.clock.set_speed(opts.speed);
+                if let Some(audio) = &self.audio {
+                    audio.sink.set_speed(opts.speed as f32);
+                }
+            }
+        }
+    }
+
+    fn volume(&self, opts: &PlayOptions) -> f32 {
+        if self.muted { 0.0 } else { opts.volume }
+    }
+
+    fn apply_volume(&self, opts: &PlayOptions) {
+        let volume = self.volume(opts);
+        match self.engine {
+            PlaybackEngine::MPV => self.send(MpvCommand::SetVolume(volume as f64 * 100.0)),
+            PlaybackEngine::FFmpeg => {
+                if let Some(audio) = &self.audio {
+                    audio.sink.set_volume(volume);
+                }
+            }
+        }
+    }
+
+    fn send(&self, cmd: MpvCommand) {
+        if let Some(cmd_tx) = &self.handles.cmd_tx {
+            let _ = cmd_tx.try_send(cmd);
+        }
+    }
+
+    fn stop(&mut self) {
+        let _ = self.handles.stop_tx.try_send(());
+        self.audio = None;
+    }
+
+    fn status_text(&self, opts: &PlayOptions) -> String {
+        let icon = if self.paused {
+            "⏸"
+        } else if self.buffering.is_some() {
+            "⏳"
+        } else {
+            "▶"
+        };
+        let position = self.position_ms.unwrap_or(0);
+        let mut text = match self.duration_ms.filter(|&d| d > 0) {
+            Some(duration) => format!(
+                "{icon} {} / {} ({:.0}%)",
+                format_time(position),
+                format_time(duration),
+                position as f64 * 100.0 / duration as f64
+            ),
+            None => format!("{icon} {}", format_time(position)),
+        };
+        if (opts.speed - 1.0).abs() > f64::EPSILON {
+            text.push_str(&format!("  {:.2}x", opts.speed));
+        }
+        if self.muted {
+            text.push_str("  muted");
+        } else {
+            text.push_str(&format!("  vol {:.0}%", opts.volume * 100.0));
+        }
+        if let Some(pct) = self.buffering {
+            text.push_str(&format!("  buffering {pct}%"));
+        }
+        if let Some(index) = Chapter::index_at(&self.chapters, position) {
+            text.push_str(&format!("  ch {}/{}", index + 1, self.chapters.len()));
+        }
+        if self.dropped > 0 {
+            text.push_str(&format!("  dropped {}", self.dropped));
+        }
+        text
+    }
+}
+
+fn config(opts: &PlayOptions, start_ms: Option<i64>, subtitle_enabled: bool, subtitle_index: Option<u32>) -> PlaybackConfig {
+    PlaybackConfig {
+        hwaccel: opts.hwaccel,
+        subtitle_enabled,
+        subtitle_index,
+        engine: Some(opts.engine),
+        start_ms,
+        audio_track: opts.audio_track,
+        ..Default::default()
+    }
+}
+
+/// FFmpeg 引擎的播放时钟：墙钟 × 速度；声卡开始播放新的音频帧时对齐到该帧的时间戳
+struct Clock {
+    base: Option<(i64, Instant)>,
+    speed: f64,
+    paused_at: Option<Instant>,
+}
+
+impl Clock {
+    fn new(speed: f64) -> Self {
+        Clock { base: None, speed, paused_at: None }
+    }
+
+    /// 第一帧到达时开始计时
+    fn start(&mut self, pts: i64) {
+        if self.base.is_none() {
+            self.base = Some((pts, Instant::now()));
+        }
+    }
+
+    fn sync(&mut self, pts: i64) {
+        if self.paused_at.is_none() {
+            self.base = Some((pts, Instant::now()));
+        }
+    }
+
+    fn now_ms(&self) -> Option<i64> {
+        let (pts, at) = self.base?;
+        let now = self.paused_at.unwrap_or_else(Instant::now);
+        Some(pts + (now.saturating_duration_since(at).as_secs_f64() * 1000.0 * self.speed) as i64)
+    }
+
+    fn pause(&mut self) {
+        self.paused_at.get_or_insert_with(Instant::now);
+    }
+
+    fn resume(&mut self) {
+        if let Some(paused_at) = self.paused_at.take() {
+            if let Some((_, at)) = &mut self.base {
+                *at += paused_at.elapsed();
+            }
+        }
+    }
+
+    fn set_speed(&mut self, speed: f64) {
+        if let Some(now_ms) = self.now_ms() {
+            self.base = Some((now_ms, self.paused_at.unwrap_or_else(Instant::now)));
+        }
+        self.speed = speed;
+    }
+}
+
+/// rodio 输出；记下排入声卡的各帧时间戳，从而知道正在播放哪一帧
+struct AudioOut {
+    sink: Sink,
+    queued: VecDeque<i64>,
+    next_pts: i64,
+}
+
+impl AudioOut {
+    fn new(handle: &OutputStreamHandle, volume: f32, speed: f64, paused: bool) -> Option<Self> {
+        let sink = match Sink::try_new(handle) {
+            Ok(sink) => sink,
+            Err(e) => {
+                log::warn!("cannot open audio output: {e}");
+                return None;
+            }
+        };
+        sink.set_volume(volume);
+        sink.set_speed(speed as f32);
+        if paused {
+            sink.pause();
+        }
+        Some(AudioOut { sink, queued: VecDeque::new(), next_pts: 0 })
+    }
+
+    fn push(&mut self, frame: AudioFrame) {
+        let channels = frame.channels.max(1);
+        let pts = frame.pts_ms.unwrap_or(self.next_pts);
+        let samples_per_channel = (frame.samples.len() / channels as usize) as i64;
+        self.next_pts = pts + samples_per_channel * 1000 / frame.sample_rate.max(1) as i64;
+        self.queued.push_back(pts);
+        self.sink.append(SamplesBuffer::new(channels, frame.sample_rate, frame.samples));
+    }
+
+    /// 声卡开始播放新的一帧时返回该帧的时间戳
+    fn advance(&mut self) -> Option<i64> {
+        let mut advanced = false;
+        while self.queued.len() > self.sink.len() {
+            self.queued.pop_front();
+            advanced = true;
+        }
+        if advanced { self.queued.front().copied() } else { None }
+    }
+}
+
+/// `--sub N` 对应的全局流索引（FFmpeg 引擎按流索引选择字幕）
+fn subtitle_stream_index(url: &str, track: u32) -> Option<u32> {
+    let info = probe_with(url, &ProbeOptions::interactive()).ok()?;
+    info.streams
+        .iter()
+        .filter(|s| s.kind == "subtitle")
+        .nth(track.saturating_sub(1) as usize)
+        .map(|s| s.index)
+}
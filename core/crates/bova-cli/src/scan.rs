@@ -0,0 +1,363 @@
+//! `bova-cli scan <dir>`: probe every media file under a directory and write a catalog.
+//!
+//! 目录树遍历后用 rayon 并行探测；输出 JSON 数组或 NDJSON（每行一个文件）。
+//! 再次扫描时读取已有目录文件，大小与修改时间未变的文件直接沿用上次结果。
+
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Instant, UNIX_EPOCH};
+
+use bova_probe::{probe_with, DecoderCapabilities, HdrFormat, MediaInfo, ProbeOptions};
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+
+const VIDEO_EXTS: &[&str] = &[
+    "mkv", "mp4", "m4v", "avi", "mov", "wmv", "flv", "webm", "ts", "m2ts", "mts", "mpg", "mpeg",
+    "rmvb", "rm", "3gp", "ogv", "vob",
+];
+const AUDIO_EXTS: &[&str] = &[
+    "mp3", "flac", "m4a", "aac", "ogg", "opus", "wav", "ape", "wma", "wv", "aiff", "aif", "dsf", "mka",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CatalogFormat {
+    Json,
+    Ndjson,
+}
+
+impl CatalogFormat {
+    /// `.ndjson` / `.jsonl` 输出 NDJSON，其余为 JSON 数组
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|e| e.to_str()).map(|e| e.to_ascii_lowercase()).as_deref() {
+            Some("ndjson") | Some("jsonl") => Self::Ndjson,
+            _ => Self::Json,
+        }
+    }
+}
+
+pub struct ScanOptions {
+    pub root: PathBuf,
+    pub output: PathBuf,
+    pub format: CatalogFormat,
+    /// 并行探测的线程数，0 为 CPU 核数
+    pub jobs: usize,
+    /// 忽略上次的结果，全部重新探测
+    pub full: bool,
+    /// 目标设备能解码的编码；None 时使用本机解码能力
+    pub video_codecs: Option<Vec<String>>,
+    pub audio_codecs: Option<Vec<String>>,
+}
+
+/// 播放前值得注意的问题
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Problem {
+    ProbeFailed { error: String },
+    NoStreams,
+    /// 视频文件没有音轨
+    MissingAudio,
+    /// 视频扩展名的文件没有视频轨
+    MissingVideo,
+    NoDuration,
+    UnsupportedVideo { codec: String },
+    UnsupportedAudio { codec: String },
+}
+
+impl Problem {
+    fn label(&self) -> &'static str {
+        match self {
+            Problem::ProbeFailed { .. } => "probe_failed",
+            Problem::NoStreams => "no_streams",
+            Problem::MissingAudio => "missing_audio",
+            Problem::MissingVideo => "missing_video",
+            Problem::NoDuration => "no_duration",
+            Problem::UnsupportedVideo { .. } => "unsupported_video",
+            Problem::UnsupportedAudio { .. } => "unsupported_audio",
+        }
+    }
+}
+
+/// 目录中的一个文件
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CatalogEntry {
+    pub path: String,
+    pub size: u64,
+    /// 修改时间（Unix 秒），用于判断文件是否变化
+    pub modified: u64,
+    pub container: Option<String>,
+    pub duration_ms: Option<u64>,
+    pub bit_rate: Option<u64>,
+    pub video_codec: Option<String>,
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    pub hdr: Option<HdrFormat>,
+    pub audio_codecs: Vec<String>,
+    pub audio_languages: Vec<String>,
+    pub subtitle_codecs: Vec<String>,
+    pub subtitle_languages: Vec<String>,
+    pub problems: Vec<Problem>,
+}
+
+impl CatalogEntry {
+    fn from_info(path: String, size: u64, modified: u64, info: &MediaInfo) -> Self {
+        let mut entry = Self { path, size, modified, ..Default::default() };
+        entry.container = info.format.clone();
+        entry.duration_ms = info.duration_ms;
+        entry.bit_rate = info.bit_rate;
+        // 封面图不算视频轨
+        let video = info.streams.iter().find(|s| s.kind == "video" && !s.has_disposition("attached_pic"));
+        if let Some(v) = video {
+            entry.video_codec = Some(v.codec.clone());
+            entry.width = v.width;
+            entry.height = v.height;
+            entry.hdr = v.hdr.as_ref().map(|h| h.format);
+        }
+        for s in &info.streams {
+            let lang = || s.language.clone().unwrap_or_else(|| "und".to_string());
+            match s.kind.as_str() {
+                "audio" => {
+                    entry.audio_codecs.push(s.codec.clone());
+                    entry.audio_languages.push(lang());
+                }
+                "subtitle" => {
+                    entry.subtitle_codecs.push(s.codec.clone());
+                    entry.subtitle_languages.push(lang());
+                }
+                _ => {}
+            }
+        }
+        entry
+    }
+
+    fn check(&mut self, is_video_file: bool, caps: &DecoderCapabilities) {
+        let has_audio = !self.audio_codecs.is_empty();
+        if self.video_codec.is_none() && !has_audio {
+            self.problems.push(Problem::NoStreams);
+            return;
+        }
+        if is_video_file && self.video_codec.is_none() {
+            self.problems.push(Problem::MissingVideo);
+        }
+        if self.video_codec.is_some() && !has_audio {
+            self.problems.push(Problem::MissingAudio);
+        }
+        if self.duration_ms.is_none() {
+            self.problems.push(Problem::NoDuration);
+        }
+        if let Some(codec) = self.video_codec.as_ref().filter(|c| !caps.can_decode_video(c)) {
+            self.problems.push(Problem::UnsupportedVideo { codec: codec.clone() });
+        }
+        // 有一条能解码的音轨即可播放
+        if has_audio && !self.audio_codecs.iter().any(|c| caps.can_decode_audio(c)) {
+            self.problems.push(Problem::UnsupportedAudio { codec: self.audio_codecs[0].clone() });
+        }
+    }
+}
+
+#[derive(Default)]
+struct Summary {
+    probed: usize,
+    unchanged: usize,
+    failed: usize,
+    removed: usize,
+}
+
+pub fn run(opts: &ScanOptions) -> Result<(), String> {
+    if !opts.root.is_dir() {
+        return Err(format!("not a directory: {}", opts.root.display()));
+    }
+    let started = Instant::now();
+
+    let mut files = Vec::new();
+    walk(&opts.root, &mut files);
+    files.sort();
+    println!("Found {} media files under {}", files.len(), opts.root.display());
+
+    let mut previous = if opts.full { HashMap::new() } else { load_catalog(&opts.output) };
+    let caps = {
+        let local = bova_probe::decoder_capabilities();
+        DecoderCapabilities {
+            video: opts.video_codecs.clone().unwrap_or(local.video),
+            audio: opts.audio_codecs.clone().unwrap_or(local.audio),
+            subtitle: local.subtitle,
+        }
+    };
+
+    // 未变化的文件沿用上次结果，其余并行探测
+    let mut summary = Summary::default();
+    let mut entries = Vec::with_capacity(files.len());
+    let mut pending = Vec::new();
+    for path in files {
+        let Some((size, modified)) = file_stamp(&path) else { continue };
+        let key = path.display().to_string();
+        match previous.remove(&key) {
+            Some(old) if old.size == size && old.modified == modified => {
+                summary.unchanged += 1;
+                entries.push(old);
+            }
+            _ => pending.push((path, key, size, modified)),
+        }
+    }
+    summary.removed = previous.len();
+
+    let pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(opts.jobs)
+        .build()
+        .map_err(|e| e.to_string())?;
+    let done = AtomicUsize::new(0);
+    let total = pending.len();
+    let probe_opts = ProbeOptions { timeout_ms: Some(30_000), ..Default::default() };
+    let probed: Vec<CatalogEntry> = pool.install(|| {
+        pending.into_par_iter()
+            .map(|(path, key, size, modified)| {
+                let is_video_file = has_ext(&path, VIDEO_EXTS);
+                let entry = match probe_with(&key, &probe_opts) {
+                    Ok(info) => {
+                        let mut entry = CatalogEntry::from_info(key, size, modified, &info);
+                        entry.check(is_video_file, &caps);
+                        entry
+                    }
+                    Err(e) => CatalogEntry {
+                        path: key,
+                        size,
+                        modified,
+                        problems: vec![Problem::ProbeFailed { error: e.to_string() }],
+                        ..Default::default()
+                    },
+                };
+                let n = done.fetch_add(1, Ordering::Relaxed) + 1;
+                if n.is_multiple_of(100) || n == total {
+                    log::info!("probed {n}/{total}");
+                }
+                entry
+            })
+            .collect()
+    });
+    summary.probed = probed.len();
+    summary.failed = probed.iter()
+        .filter(|e| e.problems.iter().any(|p| matches!(p, Problem::ProbeFailed { .. })))
+        .count();
+    entries.extend(probed);
+    entries.sort_by(|a, b| a.path.cmp(&b.path));
+
+    write_catalog(&opts.output, opts.format, &entries).map_err(|e| format!("cannot write {}: {e}", opts.output.display()))?;
+    print_summary(&entries, &summary, started, &opts.output);
+    Ok(())
+}
+
+/// 递归收集媒体文件；跳过隐藏目录，不跟随目录符号链接（避免循环）
+fn walk(dir: &Path, out: &mut Vec<PathBuf>) {
+    let Ok(read) = fs::read_dir(dir) else {
+        log::warn!("cannot read {}", dir.display());
+        return;
+    };
+    for entry in read.flatten() {
+        let path = entry.path();
+        let Ok(file_type) = entry.file_type() else { continue };
+        if file_type.is_dir() {
+            if !entry.file_name().to_string_lossy().starts_with('.') {
+                walk(&path, out);
+            }
+        } else if has_ext(&path, VIDEO_EXTS) || has_ext(&path, AUDIO_EXTS) {
+            out.push(path);
+        }
+    }
+}
+
+fn has_ext(path: &Path, exts: &[&str]) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .map(|e| exts.contains(&e.to_ascii_lowercase().as_str()))
+        .unwrap_or(false)
+}
+
+fn file_stamp(path: &Path) -> Option<(u64, u64)> {
+    let meta = fs::metadata(path).ok()?;
+    let modified = meta.modified().ok()?.duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+    Some((meta.len(), modified))
+}
+
+/// 读取上次的结果；不存在或无法解析时视为首次扫描
+fn load_catalog(path: &Path) -> HashMap<String, CatalogEntry> {
+    let Ok(file) = fs::File::open(path) else { return HashMap::new() };
+    let entries: Vec<CatalogEntry> = match CatalogFormat::from_path(path) {
+        CatalogFormat::Json => serde_json::from_reader(BufReader::new(file)).unwrap_or_else(|e| {
+            log::warn!("ignoring unreadable catalog {}: {e}", path.display());
+            Vec::new()
+        }),
+        CatalogFormat::Ndjson => BufReader::new(file)
+            .lines()
+            .map_while(Result::ok)
+            .filter_map(|line| serde_json::from_str(&line).ok())
+            .collect(),
+    };
+    entries.into_iter().map(|e| (e.path.clone(), e)).collect()
+}
+
+/// 先写临时文件再替换，中途失败不破坏上次的结果
+fn write_catalog(path: &Path, format: CatalogFormat, entries: &[CatalogEntry]) -> std::io::Result<()> {
+    let tmp = path.with_extension("tmp");
+    {
+        let mut out = BufWriter::new(fs::File::create(&tmp)?);
+        match format {
+            CatalogFormat::Json => serde_json::to_writer_pretty(&mut out, entries)?,
+            CatalogFormat::Ndjson => {
+                for entry in entries {
+                    serde_json::to_writer(&mut out, entry)?;
+                    out.write_all(b"\n")?;
+                }
+            }
+        }
+        out.flush()?;
+    }
+    fs::rename(&tmp, path)
+}
+
+fn print_summary(entries: &[CatalogEntry], summary: &Summary, started: Instant, output: &Path) {
+    println!(
+        "Scanned {} files in {:.1}s: {} probed, {} unchanged, {} failed, {} removed",
+        entries.len(), started.elapsed().as_secs_f32(), summary.probed, summary.unchanged, summary.failed, summary.removed
+    );
+
+    let total_ms: u64 = entries.iter().filter_map(|e| e.duration_ms).sum();
+    let total_bytes: u64 = entries.iter().map(|e| e.size).sum();
+    println!("Total: {:.1} h, {:.1} GiB", total_ms as f64 / 3_600_000.0, total_bytes as f64 / (1u64 << 30) as f64);
+
+    let mut video: BTreeMap<&str, usize> = BTreeMap::new();
+    let mut audio: BTreeMap<&str, usize> = BTreeMap::new();
+    for e in entries {
+        if let Some(c) = &e.video_codec {
+            *video.entry(c).or_default() += 1;
+        }
+        if let Some(c) = e.audio_codecs.first() {
+            *audio.entry(c).or_default() += 1;
+        }
+    }
+    println!("Video codecs: {}", format_counts(&video));
+    println!("Audio codecs: {}", format_counts(&audio));
+
+    let with_problems: Vec<&CatalogEntry> = entries.iter().filter(|e| !e.problems.is_empty()).collect();
+    if with_problems.is_empty() {
+        println!("No problems found");
+    } else {
+        let mut kinds: BTreeMap<&str, usize> = BTreeMap::new();
+        for p in with_problems.iter().flat_map(|e| &e.problems) {
+            *kinds.entry(p.label()).or_default() += 1;
+        }
+        println!("{} files with problems: {}", with_problems.len(), format_counts(&kinds));
+    }
+    println!("Catalog written to {}", output.display());
+}
+
+fn format_counts(counts: &BTreeMap<&str, usize>) -> String {
+    if counts.is_empty() {
+        return "-".to_string();
+    }
+    let mut sorted: Vec<_> = counts.iter().collect();
+    sorted.sort_by(|a, b| b.1.cmp(a.1).then(a.0.cmp(b.0)));
+    sorted.iter().map(|(k, n)| format!("{k} {n}")).collect::<Vec<_>>().join(", ")
+}
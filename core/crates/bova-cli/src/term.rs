@@ -0,0 +1,177 @@
+//! Terminal handling for `play`: single-key input and the status line.
+//!
+//! stdin 是终端时切换到非规范模式逐键读取（Ctrl+C 也作为按键处理，保证退出前恢复
+//! 终端设置）；stdin 是管道时按字节读取，脚本可以写入同样的按键控制播放。
+
+use crossbeam_channel::{unbounded, Receiver};
+use std::io::{IsTerminal, Read, Write};
+use std::thread;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Key {
+    Char(char),
+    Left,
+    Right,
+    Up,
+    Down,
+    PageUp,
+    PageDown,
+    Backspace,
+    Esc,
+    CtrlC,
+}
+
+pub struct Keys {
+    rx: Receiver<Key>,
+    #[cfg(unix)]
+    _raw: Option<RawMode>,
+}
+
+impl Keys {
+    pub fn open() -> Self {
+        #[cfg(unix)]
+        let raw = RawMode::enable();
+        let (tx, rx) = unbounded();
+        thread::spawn(move || {
+            let mut stdin = std::io::stdin();
+            let mut buf = [0u8; 32];
+            loop {
+                // 转义序列由终端一次写入，一次 read 即可拿到完整序列
+                let n = match stdin.read(&mut buf) {
+                    Ok(0) | Err(_) => break,
+                    Ok(n) => n,
+                };
+                for key in parse_keys(&buf[..n]) {
+                    if tx.send(key).is_err() {
+                        return;
+                    }
+                }
+            }
+        });
+        Keys {
+            rx,
+            #[cfg(unix)]
+            _raw: raw,
+        }
+    }
+
+    pub fn try_key(&self) -> Option<Key> {
+        self.rx.try_recv().ok()
+    }
+}
+
+fn parse_keys(bytes: &[u8]) -> Vec<Key> {
+    let mut keys = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            0x1b if matches!(bytes.get(i + 1), Some(b'[') | Some(b'O')) => {
+                // CSI：可选的数字参数后跟结束字符
+                let mut j = i + 2;
+                while j < bytes.len() && (bytes[j].is_ascii_digit() || bytes[j] == b';') {
+                    j += 1;
+                }
+                let key = match (bytes.get(j), &bytes[i + 2..j.min(bytes.len())]) {
+                    (Some(b'A'), _) => Some(Key::Up),
+                    (Some(b'B'), _) => Some(Key::Down),
+                    (Some(b'C'), _) => Some(Key::Right),
+                    (Some(b'D'), _) => Some(Key::Left),
+                    (Some(b'~'), b"5") => Some(Key::PageUp),
+                    (Some(b'~'), b"6") => Some(Key::PageDown),
+                    _ => None,
+                };
+                keys.extend(key);
+                i = j + 1;
+                continue;
+            }
+            0x1b => keys.push(Key::Esc),
+            0x03 => keys.push(Key::CtrlC),
+            0x7f | 0x08 => keys.push(Key::Backspace),
+            b'\r' | b'\n' => {}
+            b if b.is_ascii_graphic() || b == b' ' => keys.push(Key::Char(b as char)),
+            _ => {}
+        }
+        i += 1;
+    }
+    keys
+}
+
+/// 关闭回显、行缓冲与信号键；Drop 时恢复原设置
+#[cfg(unix)]
+struct RawMode {
+    original: libc::termios,
+}
+
+#[cfg(unix)]
+impl RawMode {
+    fn enable() -> Option<Self> {
+        if !std::io::stdin().is_terminal() {
+            return None;
+        }
+        unsafe {
+            let mut original: libc::termios = std::mem::zeroed();
+            if libc::tcgetattr(libc::STDIN_FILENO, &mut original) != 0 {
+                return None;
+            }
+            let mut raw = original;
+            raw.c_lflag &= !(libc::ICANON | libc::ECHO | libc::ISIG);
+            raw.c_cc[libc::VMIN] = 1;
+            raw.c_cc[libc::VTIME] = 0;
+            if libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &raw) != 0 {
+                return None;
+            }
+            Some(RawMode { original })
+        }
+    }
+}
+
+#[cfg(unix)]
+impl Drop for RawMode {
+    fn drop(&mut self) {
+        unsafe {
+            libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &self.original);
+        }
+    }
+}
+
+/// stdout 上的单行状态；stdout 不是终端（重定向到日志）时不绘制
+pub struct StatusLine {
+    enabled: bool,
+    drawn: bool,
+}
+
+impl StatusLine {
+    pub fn new(enabled: bool) -> Self {
+        StatusLine { enabled: enabled && std::io::stdout().is_terminal(), drawn: false }
+    }
+
+    pub fn draw(&mut self, text: &str) {
+        if !self.enabled {
+            return;
+        }
+        let mut out = std::io::stdout().lock();
+        let _ = write!(out, "\r{text}\x1b[K");
+        let _ = out.flush();
+        self.drawn = true;
+    }
+
+    /// 在状态行上方输出一行消息，状态行随后重绘
+    pub fn message(&mut self, text: &str) {
+        let mut out = std::io::stdout().lock();
+        if self.drawn {
+            let _ = write!(out, "\r\x1b[K");
+            self.drawn = false;
+        }
+        let _ = writeln!(out, "{text}");
+        let _ = out.flush();
+    }
+
+    pub fn clear(&mut self) {
+        if self.drawn {
+            let mut out = std::io::stdout().lock();
+            let _ = write!(out, "\r\x1b[K");
+            let _ = out.flush();
+            self.drawn = false;
+        }
+    }
+}
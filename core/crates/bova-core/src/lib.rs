@@ -3,10 +3,21 @@
 
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 use parking_lot::Mutex;
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
+pub mod library;
+pub mod logging;
+pub mod playlist;
+pub mod resume;
+
+use resume::ResumeStore;
+
+/// 播放中续播位置的写盘间隔（暂停、停止、切换文件时立即写入）
+const RESUME_SAVE_INTERVAL: Duration = Duration::from_secs(10);
+
 #[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 pub enum HwAccelPolicy {
     Auto,
@@ -36,6 +47,14 @@ pub struct MediaOptions {
     pub tone_map: ToneMapMode,
     pub scaler: ScalerKind,
     pub network_cache_ms: u32,
+    /// Socket timeout for network inputs; engine default when unset
+    #[serde(default)]
+    pub network_timeout_ms: Option<u32>,
+    #[serde(default)]
+    pub user_agent: Option<String>,
+    /// Extra HTTP headers sent with network requests
+    #[serde(default)]
+    pub http_headers: Vec<(String, String)>,
     pub extra: serde_json::Value,
 }
 
@@ -49,6 +68,9 @@ impl Default for MediaOptions {
             tone_map: ToneMapMode::Auto,
             scaler: ScalerKind::Lanczos,
             network_cache_ms: 1000,
+            network_timeout_ms: None,
+            user_agent: None,
+            http_headers: Vec::new(),
             extra: serde_json::Value::Null,
         }
     }
@@ -67,6 +89,8 @@ pub enum PlayerError {
 #[derive(Debug, Clone)]
 pub struct MediaHandle {
     pub url: String,
+    /// 本地文件上次播放到的位置，前端可提示“从 X 继续”
+    pub resume_ms: Option<i64>,
 }
 
 #[derive(Debug, Clone)]
@@ -94,6 +118,12 @@ pub trait Player: Send + Sync {
     fn select_track(&mut self, sel: TrackSelector) -> Result<(), PlayerError>;
     fn set_property(&mut self, _key: &str, _val: PropertyValue) -> Result<(), PlayerError> { Ok(()) }
     fn get_property(&self, _key: &str) -> Option<PropertyValue> { None }
+    /// 当前媒体上次记录的播放位置（仅本地文件）
+    fn resume_position(&self) -> Option<i64> { None }
+    /// 前端上报实际播放进度，用于续播记忆
+    fn update_position(&mut self, _position_ms: i64, _duration_ms: i64) {}
+    /// 用户选择从头播放时清除记录的位置
+    fn clear_resume(&mut self) {}
 }
 
 #[derive(Default)]
@@ -101,6 +131,7 @@ pub struct BovaPlayer {
     state: Arc<Mutex<State>>,
     playing: Arc<AtomicBool>,
     listeners: Arc<Mutex<Vec<EventCallback>>>,
+    resume: Arc<Mutex<ResumeStore>>,
 }
 
 #[derive(Debug, Default)]
@@ -108,8 +139,12 @@ struct State {
     opened: bool,
     current: Option<MediaHandle>,
     position_ms: i64,
+    duration_ms: i64,
     subtitle_enabled: bool,
     current_subtitle_index: Option<u32>,
+    /// 续播记忆的键（本地文件）及上次写盘时间
+    media_key: Option<String>,
+    resume_saved_at: Option<Instant>,
 }
 
 impl BovaPlayer {
@@ -119,6 +154,17 @@ impl BovaPlayer {
         self.listeners.lock().push(cb);
     }
 
+    /// 把当前进度写入续播记忆（前端上报过进度时才写）
+    fn save_resume(&self) {
+        let mut st = self.state.lock();
+        let Some(key) = st.media_key.clone() else { return };
+        if st.resume_saved_at.is_none() && st.position_ms == 0 { return; }
+        st.resume_saved_at = Some(Instant::now());
+        let (position_ms, duration_ms) = (st.position_ms, st.duration_ms);
+        drop(st);
+        self.resume.lock().record(&key, position_ms, duration_ms);
+    }
+
     fn emit(&self, kind: EventKind, payload: serde_json::Value) {
         let evt = Event { kind, payload };
         let json = serde_json::to_string(&evt).unwrap_or_else(|_| "{}".to_string());
@@ -130,13 +176,19 @@ impl BovaPlayer {
 
 impl Player for BovaPlayer {
     fn open(&mut self, url: &str, _opts: MediaOptions) -> Result<MediaHandle, PlayerError> {
-        let handle = MediaHandle { url: url.to_string() };
+        self.save_resume();
+        let media_key = resume::media_key(url);
+        let resume_ms = media_key.as_deref().and_then(|key| self.resume.lock().position(key));
+        let handle = MediaHandle { url: url.to_string(), resume_ms };
         let mut st = self.state.lock();
         st.opened = true;
         st.current = Some(handle.clone());
         st.position_ms = 0;
+        st.duration_ms = 0;
+        st.media_key = media_key;
+        st.resume_saved_at = None;
         drop(st);
-        self.emit(EventKind::Opened, serde_json::json!({"url": url}));
+        self.emit(EventKind::Opened, serde_json::json!({"url": url, "resume_ms": resume_ms}));
         Ok(handle)
     }
 
@@ -150,16 +202,20 @@ impl Player for BovaPlayer {
     fn pause(&mut self) -> Result<(), PlayerError> {
         if !self.state.lock().opened { return Err(PlayerError::InvalidState("not opened")); }
         self.playing.store(false, Ordering::SeqCst);
+        self.save_resume();
         self.emit(EventKind::Pause, serde_json::json!({}));
         Ok(())
     }
 
     fn stop(&mut self) -> Result<(), PlayerError> {
         self.playing.store(false, Ordering::SeqCst);
+        self.save_resume();
         let mut st = self.state.lock();
         st.opened = false;
         st.current = None;
         st.position_ms = 0;
+        st.duration_ms = 0;
+        st.media_key = None;
         drop(st);
         self.emit(EventKind::Stop, serde_json::json!({}));
         Ok(())
@@ -192,6 +248,34 @@ impl Player for BovaPlayer {
         }
         Ok(())
     }
+
+    fn resume_position(&self) -> Option<i64> {
+        self.state.lock().current.as_ref().and_then(|h| h.resume_ms)
+    }
+
+    fn update_position(&mut self, position_ms: i64, duration_ms: i64) {
+        let mut st = self.state.lock();
+        if !st.opened { return; }
+        let was_watched = resume::is_watched(st.position_ms, st.duration_ms);
+        st.position_ms = position_ms.max(0);
+        if duration_ms > 0 { st.duration_ms = duration_ms; }
+        // 首次进入结尾区域时立即写入（随后可能直接退出），其余按间隔写入
+        let due = st.resume_saved_at.is_none_or(|t| t.elapsed() >= RESUME_SAVE_INTERVAL)
+            || (!was_watched && resume::is_watched(st.position_ms, st.duration_ms));
+        drop(st);
+        if due { self.save_resume(); }
+    }
+
+    fn clear_resume(&mut self) {
+        let mut st = self.state.lock();
+        if let Some(h) = st.current.as_mut() { h.resume_ms = None; }
+        // 之后只记录新上报的进度
+        st.position_ms = 0;
+        st.resume_saved_at = None;
+        let key = st.media_key.clone();
+        drop(st);
+        if let Some(key) = key { self.resume.lock().clear(&key); }
+    }
 }
 
 /// Convenience constructor for consumers that don't want the trait object yet.
@@ -0,0 +1,391 @@
+//! Local media library (library.json).
+//!
+//! 记录用户添加的文件夹，扫描其中的视频文件，从文件名（缺少时结合所在目录名，如
+//! `Show/Season 01/05.mkv`）识别剧名、季、集或片名与年份；剧集按剧名归入剧集与季。
+//! 观看状态沿用续播记忆：state.json 中以 `resume::media_key` 为键的记录，与直接
+//! 打开文件时共用，因此在媒体库外播放同一文件也会更新进度。
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use bova_settings::{MediaState, Store, Versioned};
+
+use crate::resume;
+
+mod parse;
+
+pub use parse::{parse_name, parse_season_dir, ParsedName};
+
+pub const VIDEO_EXTENSIONS: &[&str] = &[
+    "mkv", "mp4", "m4v", "avi", "mov", "wmv", "flv", "webm", "ts", "m2ts", "mts", "mpg", "mpeg",
+    "rmvb", "rm", "3gp", "ogv", "vob",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MediaKind {
+    Movie,
+    Episode,
+}
+
+/// 媒体库中的一个视频文件
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LibraryFile {
+    /// 规范化后的绝对路径
+    pub path: String,
+    pub size: u64,
+    /// 修改时间（Unix 秒）
+    pub modified: u64,
+    /// 第一次被扫描到的时间（Unix 秒），用于“最近添加”
+    pub added_at: u64,
+    pub kind: MediaKind,
+    /// 片名或剧名
+    pub title: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub year: Option<i32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub season: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub episode: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub episode_end: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub group: Option<String>,
+}
+
+impl LibraryFile {
+    /// 续播 / 观看状态的记忆键（与 `resume::media_key` 一致）
+    pub fn media_key(&self) -> String {
+        resume::file_key(&self.path, self.size, self.modified)
+    }
+
+    /// 所属剧集的分组键：忽略大小写与标点的剧名
+    pub fn series_key(&self) -> Option<String> {
+        (self.kind == MediaKind::Episode).then(|| normalize_key(&self.title))
+    }
+
+    pub fn file_name(&self) -> &str {
+        Path::new(&self.path).file_name().and_then(|n| n.to_str()).unwrap_or(&self.path)
+    }
+
+    /// 集的显示名：`S01E02`、`S01E01-E02`
+    pub fn episode_label(&self) -> Option<String> {
+        let episode = self.episode?;
+        let mut label = format!("S{:02}E{:02}", self.season.unwrap_or(1), episode);
+        if let Some(end) = self.episode_end {
+            label.push_str(&format!("-E{:02}", end));
+        }
+        Some(label)
+    }
+}
+
+/// 一部剧：同名剧集文件按季分组
+#[derive(Debug, Clone, PartialEq)]
+pub struct Series {
+    pub key: String,
+    pub title: String,
+    pub year: Option<i32>,
+    /// 按季数排序
+    pub seasons: Vec<Season>,
+    /// 最近一集加入的时间
+    pub added_at: u64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Season {
+    pub number: u32,
+    /// 按集数排序
+    pub episodes: Vec<LibraryFile>,
+}
+
+impl Series {
+    pub fn episodes(&self) -> impl Iterator<Item = &LibraryFile> {
+        self.seasons.iter().flat_map(|s| s.episodes.iter())
+    }
+
+    pub fn episode_count(&self) -> usize {
+        self.seasons.iter().map(|s| s.episodes.len()).sum()
+    }
+}
+
+/// 某个文件的观看状态
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct WatchState {
+    pub watched: bool,
+    /// 未看完时的续播位置
+    pub position_ms: Option<i64>,
+    pub duration_ms: Option<i64>,
+    /// 最后一次播放或标记的时间（Unix 秒），0 为从未播放
+    pub updated_at: u64,
+}
+
+impl WatchState {
+    pub fn of(file: &LibraryFile, state: &MediaState) -> Self {
+        match state.file(&file.media_key()) {
+            Some(s) => Self {
+                watched: s.watched,
+                position_ms: s.position_ms,
+                duration_ms: s.duration_ms,
+                updated_at: s.updated_at,
+            },
+            None => Self::default(),
+        }
+    }
+
+    /// 已播放的百分比（0..=100），未知时长时为 None
+    pub fn progress_pct(&self) -> Option<f64> {
+        let position = self.position_ms?;
+        let duration = self.duration_ms.filter(|&d| d > 0)?;
+        Some((position as f64 / duration as f64 * 100.0).clamp(0.0, 100.0))
+    }
+
+    pub fn in_progress(&self) -> bool {
+        !self.watched && self.position_ms.is_some()
+    }
+}
+
+/// 手动标记已看 / 未看；两种情况都清除续播位置
+pub fn set_watched(state: &mut MediaState, file: &LibraryFile, watched: bool) {
+    state.update_file(&file.media_key(), |s| {
+        s.watched = watched;
+        s.position_ms = None;
+    });
+}
+
+/// 一次扫描的结果统计
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ScanStats {
+    pub total: usize,
+    pub added: usize,
+    pub removed: usize,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LibraryIndex {
+    /// 用户添加的文件夹
+    pub folders: Vec<String>,
+    pub files: Vec<LibraryFile>,
+    /// 上次扫描完成的时间（Unix 秒）
+    pub scanned_at: u64,
+}
+
+impl LibraryIndex {
+    /// 添加文件夹（已存在时返回 false）；需要再调用 `rescan` 才会索引其中的文件
+    pub fn add_folder(&mut self, folder: &str) -> bool {
+        let folder = canonical(Path::new(folder)).unwrap_or_else(|| folder.to_string());
+        if self.folders.contains(&folder) {
+            return false;
+        }
+        self.folders.push(folder);
+        true
+    }
+
+    /// 移除文件夹及其中已索引的文件
+    pub fn remove_folder(&mut self, folder: &str) -> bool {
+        let before = self.folders.len();
+        self.folders.retain(|f| f != folder);
+        if self.folders.len() == before {
+            return false;
+        }
+        let folders = self.folders.clone();
+        self.files.retain(|f| folders.iter().any(|root| Path::new(&f.path).starts_with(root)));
+        true
+    }
+
+    /// 重新扫描全部文件夹
+    pub fn rescan(&mut self) -> ScanStats {
+        let (files, stats) = scan_folders(&self.folders, &self.files);
+        self.files = files;
+        self.scanned_at = unix_now();
+        stats
+    }
+
+    pub fn file(&self, path: &str) -> Option<&LibraryFile> {
+        self.files.iter().find(|f| f.path == path)
+    }
+
+    /// 电影，按片名排序
+    pub fn movies(&self) -> Vec<&LibraryFile> {
+        let mut movies: Vec<&LibraryFile> = self.files.iter().filter(|f| f.kind == MediaKind::Movie).collect();
+        movies.sort_by_cached_key(|f| (f.title.to_lowercase(), f.year));
+        movies
+    }
+
+    /// 剧集按剧名分组，按剧名排序
+    pub fn series(&self) -> Vec<Series> {
+        let mut groups: BTreeMap<String, Vec<&LibraryFile>> = BTreeMap::new();
+        for file in &self.files {
+            if let Some(key) = file.series_key() {
+                groups.entry(key).or_default().push(file);
+            }
+        }
+        groups.into_iter()
+            .map(|(key, files)| {
+                let mut seasons: BTreeMap<u32, Vec<LibraryFile>> = BTreeMap::new();
+                for file in &files {
+                    seasons.entry(file.season.unwrap_or(1)).or_default().push((*file).clone());
+                }
+                let seasons = seasons.into_iter()
+                    .map(|(number, mut episodes)| {
+                        episodes.sort_by(|a, b| a.episode.cmp(&b.episode).then_with(|| a.path.cmp(&b.path)));
+                        Season { number, episodes }
+                    })
+                    .collect();
+                Series {
+                    key,
+                    // 同一剧名写法不一时取出现最多的
+                    title: most_common(files.iter().map(|f| f.title.as_str())).to_string(),
+                    year: files.iter().filter_map(|f| f.year).min(),
+                    seasons,
+                    added_at: files.iter().map(|f| f.added_at).max().unwrap_or(0),
+                }
+            })
+            .collect()
+    }
+}
+
+impl Versioned for LibraryIndex {
+    const VERSION: u32 = 1;
+    const FILE: &'static str = "library.json";
+}
+
+pub type LibraryStore = Store<LibraryIndex>;
+
+/// 扫描文件夹；`previous` 中大小与修改时间未变的文件沿用原记录（保留加入时间）
+pub fn scan_folders(folders: &[String], previous: &[LibraryFile]) -> (Vec<LibraryFile>, ScanStats) {
+    let previous: HashMap<&str, &LibraryFile> = previous.iter().map(|f| (f.path.as_str(), f)).collect();
+    let now = unix_now();
+    let mut paths = Vec::new();
+    for folder in folders {
+        walk(Path::new(folder), &mut paths);
+    }
+    paths.sort();
+    paths.dedup();
+
+    let mut stats = ScanStats::default();
+    let mut files = Vec::with_capacity(paths.len());
+    for path in paths {
+        let Some(canonical_path) = canonical(&path) else { continue };
+        let Ok(meta) = std::fs::metadata(&path) else { continue };
+        let size = meta.len();
+        let modified = meta.modified().ok()
+            .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        let added_at = match previous.get(canonical_path.as_str()) {
+            Some(old) if old.size == size && old.modified == modified => {
+                files.push((*old).clone());
+                continue;
+            }
+            Some(old) => old.added_at,
+            None => {
+                stats.added += 1;
+                now
+            }
+        };
+        files.push(describe(&path, canonical_path, size, modified, added_at));
+    }
+    stats.total = files.len();
+    let current: HashSet<&str> = files.iter().map(|f| f.path.as_str()).collect();
+    stats.removed = previous.keys().filter(|p| !current.contains(**p)).count();
+    (files, stats)
+}
+
+/// 由文件名与目录名得到条目信息
+fn describe(path: &Path, canonical_path: String, size: u64, modified: u64, added_at: u64) -> LibraryFile {
+    let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or_default();
+    let mut parsed = parse_name(stem);
+    let parent = path.parent();
+    let dir_name = |dir: Option<&Path>| dir.and_then(|d| d.file_name()).and_then(|n| n.to_str()).map(str::to_string);
+    let season_dir = dir_name(parent).as_deref().and_then(parse_season_dir);
+
+    if parsed.is_episode() {
+        parsed.season = parsed.season.or(season_dir);
+        // `Show/Season 01/05.mkv`：剧名来自季目录的上一级
+        let show_dir = if season_dir.is_some() { parent.and_then(Path::parent) } else { parent };
+        if parsed.title.is_empty() || parsed.year.is_none() {
+            if let Some(show) = dir_name(show_dir).map(|name| parse_name(&name)) {
+                if parsed.title.is_empty() {
+                    parsed.title = show.title;
+                }
+                parsed.year = parsed.year.or(show.year);
+            }
+        }
+    } else if let Some(dir) = dir_name(parent).map(|name| parse_name(&name)).filter(|d| !d.is_episode()) {
+        // `Movie (2019)/movie.mkv`：带年份的目录视为电影目录，文件名没有年份时以目录为准；
+        // 同名时也取目录的写法（文件名常是全小写）
+        let same_title = normalize_key(&dir.title) == normalize_key(&parsed.title);
+        if parsed.title.is_empty() || (dir.year.is_some() && (parsed.year.is_none() || same_title)) {
+            parsed.title = dir.title;
+            parsed.year = parsed.year.or(dir.year);
+        }
+    }
+    if parsed.title.is_empty() {
+        parsed.title = stem.to_string();
+    }
+
+    let is_episode = parsed.is_episode();
+    LibraryFile {
+        path: canonical_path,
+        size,
+        modified,
+        added_at,
+        kind: if is_episode { MediaKind::Episode } else { MediaKind::Movie },
+        title: parsed.title,
+        year: parsed.year,
+        season: is_episode.then(|| parsed.season.unwrap_or(1)),
+        episode: parsed.episode,
+        episode_end: parsed.episode_end,
+        group: parsed.group,
+    }
+}
+
+/// 递归收集视频文件；跳过隐藏目录，不跟随目录符号链接（避免循环）
+fn walk(dir: &Path, out: &mut Vec<PathBuf>) {
+    let Ok(read) = std::fs::read_dir(dir) else {
+        log::warn!("cannot read {}", dir.display());
+        return;
+    };
+    for entry in read.flatten() {
+        let path = entry.path();
+        let Ok(file_type) = entry.file_type() else { continue };
+        if file_type.is_dir() {
+            if !entry.file_name().to_string_lossy().starts_with('.') {
+                walk(&path, out);
+            }
+        } else if is_video(&path) {
+            out.push(path);
+        }
+    }
+}
+
+pub fn is_video(path: &Path) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .map(|e| VIDEO_EXTENSIONS.contains(&e.to_ascii_lowercase().as_str()))
+        .unwrap_or(false)
+}
+
+fn canonical(path: &Path) -> Option<String> {
+    std::fs::canonicalize(path).ok().map(|p| p.display().to_string())
+}
+
+fn normalize_key(title: &str) -> String {
+    title.chars().filter(|c| c.is_alphanumeric()).flat_map(char::to_lowercase).collect()
+}
+
+fn most_common<'a>(values: impl Iterator<Item = &'a str>) -> &'a str {
+    let mut counts: HashMap<&str, usize> = HashMap::new();
+    for v in values {
+        *counts.entry(v).or_default() += 1;
+    }
+    counts.into_iter().max_by(|a, b| a.1.cmp(&b.1).then_with(|| b.0.cmp(a.0))).map(|(v, _)| v).unwrap_or_default()
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)
+}
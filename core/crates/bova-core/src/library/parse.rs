@@ -0,0 +1,328 @@
+//! Filename parsing for the local library.
+//!
+//! 识别剧集（`Show.Name.S01E02.1080p`、`Show 1x02`、`[Group] Anime - 05`、
+//! `[Group][Anime][05][1080p]`、`某剧 第05集`）与电影（`Movie.Name.2019.1080p`、
+//! `Movie Name (2019)`）。文件名里缺少的剧名或季数由调用方从目录名补充。
+//!
+//! 片名可以含年份或纯数字（`1917`、`Blade Runner 2049`）：括号中的年份优先，
+//! 否则取画质等标记之前的最后一个年份。
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ParsedName {
+    /// 剧名或片名；只有集数的文件名（`05.mkv`、`E05.mkv`）为空
+    pub title: String,
+    pub year: Option<i32>,
+    pub season: Option<u32>,
+    pub episode: Option<u32>,
+    /// 多集合并的文件（`S01E01-E02`）的最后一集
+    pub episode_end: Option<u32>,
+    /// 开头 `[...]` 中的字幕组 / 发布组
+    pub group: Option<String>,
+}
+
+impl ParsedName {
+    pub fn is_episode(&self) -> bool {
+        self.episode.is_some()
+    }
+}
+
+/// 片名之后常见的画质、来源、编码标记，遇到即视为标题结束
+const RELEASE_TAGS: &[&str] = &[
+    "480p", "576p", "720p", "1080p", "1080i", "2160p", "4k", "uhd", "x264", "x265", "h264", "h265",
+    "h.264", "h.265", "hevc", "avc", "av1", "bluray", "blu-ray", "bdrip", "brrip", "bdremux", "web-dl",
+    "webdl", "webrip", "hdtv", "dvdrip", "remux", "hdr", "hdr10", "10bit", "8bit", "aac", "ac3", "dts",
+    "flac", "proper", "repack", "extended", "unrated", "complete", "chs", "cht", "big5",
+];
+
+enum Marker {
+    Episode { season: Option<u32>, episode: u32, end: Option<u32> },
+    Season(u32),
+    Year(i32),
+    Release,
+}
+
+/// 解析不带扩展名的文件名（或目录名）
+pub fn parse_name(stem: &str) -> ParsedName {
+    let mut parsed = ParsedName::default();
+    let (outside, brackets) = split_brackets(stem);
+
+    // 开头的方括号是发布组：`[Group] Title - 05`
+    let starts_with_bracket = stem.trim_start().starts_with(['[', '【']);
+    let mut brackets = brackets.into_iter();
+    let mut rest: Vec<String> = Vec::new();
+    if starts_with_bracket {
+        parsed.group = brackets.next().filter(|g| !g.is_empty());
+    }
+    rest.extend(brackets);
+
+    let text = normalize_separators(&outside);
+    let tokens: Vec<&str> = text.split_whitespace().collect();
+
+    // 年份不直接结束标题：片名里也可能有年份（`Blade Runner 2049 (2017)`），最后再决定
+    let mut years: Vec<(usize, i32)> = Vec::new();
+    let mut title_end = tokens.len();
+    let mut i = 0;
+    while i < tokens.len() {
+        let token = tokens[i];
+        // 动画常见的 `Title - 05`
+        if is_dash(token) {
+            if let Some((ep, end)) = tokens.get(i + 1).and_then(|t| bare_episode(t)) {
+                if parsed.episode.is_none() {
+                    parsed.episode = Some(ep);
+                    parsed.episode_end = end;
+                }
+                title_end = title_end.min(i);
+                i += 2;
+                continue;
+            }
+        }
+        match classify(token, i) {
+            Some(Marker::Episode { season, episode, end }) => {
+                if parsed.episode.is_none() {
+                    parsed.season = season.or(parsed.season);
+                    parsed.episode = Some(episode);
+                    parsed.episode_end = end;
+                }
+                title_end = title_end.min(i);
+            }
+            Some(Marker::Season(season)) => {
+                // `Show S01 E05` / `Show S01 - 05`
+                parsed.season.get_or_insert(season);
+                title_end = title_end.min(i);
+            }
+            Some(Marker::Year(year)) => years.push((i, year)),
+            Some(Marker::Release) => title_end = title_end.min(i),
+            None => {}
+        }
+        i += 1;
+    }
+
+    // 优先括号中的年份；否则取标题结束前的最后一个年份，之前的留在标题里。
+    // 标题结束前没有年份时取之后的第一个（`Show.S01E02.2019`）
+    let bracket_year = rest.iter().find_map(|s| parse_year(s.trim()));
+    let before_end: Vec<(usize, i32)> = years.iter().copied().filter(|&(i, _)| i < title_end).collect();
+    let title_year = match bracket_year {
+        Some(year) => before_end.iter().rev().find(|&&(_, y)| y == year),
+        None => before_end.last(),
+    };
+    parsed.year = bracket_year
+        .or(title_year.map(|&(_, y)| y))
+        .or_else(|| years.iter().find(|&&(i, _)| i >= title_end).map(|&(_, y)| y));
+    if let Some(&(i, _)) = title_year {
+        title_end = i;
+    }
+    let mut title_tokens: Vec<&str> = tokens[..title_end].to_vec();
+
+    // 其余方括号：`[05]` 是集数，`(2019)` 是年份，全部是方括号时取第一段文字作标题
+    for segment in &rest {
+        let segment = segment.trim();
+        if let Some(year) = parse_year(segment) {
+            parsed.year.get_or_insert(year);
+        } else if let Some((ep, end)) = bare_episode(segment) {
+            if parsed.episode.is_none() {
+                parsed.episode = Some(ep);
+                parsed.episode_end = end;
+            }
+        } else if title_tokens.is_empty() && parsed.title.is_empty() && !is_release_segment(segment) {
+            parsed.title = clean_title(&normalize_separators(segment));
+        }
+    }
+
+    // 有发布组但没有分隔符：`[Group] Title 05 [1080p]`
+    if parsed.episode.is_none() && parsed.group.is_some() && title_tokens.len() > 1 {
+        if let Some((ep, end)) = title_tokens.last().and_then(|t| bare_episode(t)) {
+            parsed.episode = Some(ep);
+            parsed.episode_end = end;
+            title_tokens.pop();
+        }
+    }
+    // 整个文件名只有集数：`05`
+    if parsed.episode.is_none() && title_tokens.len() == 1 && tokens.len() == 1 {
+        if let Some((ep, end)) = bare_episode(title_tokens[0]) {
+            parsed.episode = Some(ep);
+            parsed.episode_end = end;
+            title_tokens.clear();
+        }
+    }
+
+    if !title_tokens.is_empty() {
+        parsed.title = clean_title(&title_tokens.join(" "));
+    }
+    parsed
+}
+
+/// 季目录：`Season 1`、`Season 01`、`S01`、`第1季`；`Specials` 为第 0 季
+pub fn parse_season_dir(name: &str) -> Option<u32> {
+    let lower = name.trim().to_lowercase();
+    if lower == "specials" || lower == "special" || lower == "sp" {
+        return Some(0);
+    }
+    if let Some(rest) = lower.strip_prefix("season") {
+        return rest.trim_start_matches([' ', '.', '_', '-']).parse().ok();
+    }
+    if let Some(rest) = lower.strip_prefix('s') {
+        if !rest.is_empty() && rest.len() <= 2 && rest.bytes().all(|b| b.is_ascii_digit()) {
+            return rest.parse().ok();
+        }
+    }
+    let rest = lower.strip_prefix('第')?.strip_suffix('季')?;
+    rest.trim().parse().ok()
+}
+
+/// 拆出括号内容；返回括号外的文字与各括号段（按出现顺序）
+fn split_brackets(stem: &str) -> (String, Vec<String>) {
+    let mut outside = String::new();
+    let mut segments = Vec::new();
+    let mut current: Option<(char, String)> = None;
+    for c in stem.chars() {
+        match &mut current {
+            Some((close, segment)) => {
+                if c == *close {
+                    segments.push(segment.trim().to_string());
+                    current = None;
+                    outside.push(' ');
+                } else {
+                    segment.push(c);
+                }
+            }
+            None => match c {
+                '[' => current = Some((']', String::new())),
+                '【' => current = Some(('】', String::new())),
+                '(' => current = Some((')', String::new())),
+                '（' => current = Some(('）', String::new())),
+                _ => outside.push(c),
+            },
+        }
+    }
+    // 未闭合的括号按普通文字处理
+    if let Some((_, segment)) = current {
+        outside.push(' ');
+        outside.push_str(&segment);
+    }
+    (outside, segments)
+}
+
+/// 没有空格的文件名用 `.` / `_` 分词；`第05集` 与前面的剧名拆开
+fn normalize_separators(text: &str) -> String {
+    let mut text = if text.trim().contains(' ') {
+        text.replace('_', " ")
+    } else {
+        text.replace(['.', '_'], " ")
+    };
+    if let Some(pos) = text.find('第') {
+        if pos > 0 && text[pos + '第'.len_utf8()..].starts_with(|c: char| c.is_ascii_digit()) {
+            text.insert(pos, ' ');
+        }
+    }
+    text
+}
+
+fn classify(token: &str, index: usize) -> Option<Marker> {
+    let lower = token.to_lowercase();
+    if let Some(marker) = parse_season_episode(&lower)
+        .or_else(|| parse_cross_episode(&lower))
+        .or_else(|| parse_chinese(&lower))
+    {
+        return Some(marker);
+    }
+    if let Some((episode, end)) = lower.strip_prefix("ep").or_else(|| lower.strip_prefix('e')).and_then(bare_episode) {
+        return Some(Marker::Episode { season: None, episode, end });
+    }
+    // 片名本身可能是年份或类似标记（`1917`、`4K`），第一个词不作为标记
+    if index == 0 {
+        return None;
+    }
+    if let Some(year) = parse_year(&lower) {
+        return Some(Marker::Year(year));
+    }
+    RELEASE_TAGS.contains(&lower.as_str()).then_some(Marker::Release)
+}
+
+/// `s01e02`、`s01e01e02`、`s01e01-e02`、`s01e01-02`，以及单独的 `s01`
+fn parse_season_episode(token: &str) -> Option<Marker> {
+    let rest = token.strip_prefix('s')?;
+    let (season, rest) = take_number(rest, 2)?;
+    if rest.is_empty() {
+        return Some(Marker::Season(season));
+    }
+    let rest = rest.strip_prefix('e')?;
+    let (episode, rest) = take_number(rest, 4)?;
+    let end = match strip_version(rest).trim_start_matches('-').trim_start_matches('e') {
+        "" => None,
+        tail => Some(take_number(tail, 4).filter(|(_, r)| r.is_empty())?.0),
+    };
+    Some(Marker::Episode { season: Some(season), episode, end: end.filter(|&e| e > episode) })
+}
+
+/// `1x02`（限制位数以免把 `1920x1080` 当成集数）
+fn parse_cross_episode(token: &str) -> Option<Marker> {
+    let (season, rest) = take_number(token, 2)?;
+    let (episode, rest) = take_number(rest.strip_prefix('x')?, 3)?;
+    rest.is_empty().then_some(Marker::Episode { season: Some(season), episode, end: None })
+}
+
+/// `第05集` / `第5话` / `第2季`
+fn parse_chinese(token: &str) -> Option<Marker> {
+    let rest = token.strip_prefix('第')?;
+    let (number, rest) = take_number(rest, 4)?;
+    match rest {
+        "集" | "话" | "話" => Some(Marker::Episode { season: None, episode: number, end: None }),
+        "季" => Some(Marker::Season(number)),
+        _ => None,
+    }
+}
+
+/// 纯数字的集数，可带版本后缀（`05`、`05v2`、`12-13`）；年份不算
+fn bare_episode(token: &str) -> Option<(u32, Option<u32>)> {
+    let lower = token.to_lowercase();
+    let (episode, rest) = take_number(&lower, 4)?;
+    if parse_year(&lower).is_some() {
+        return None;
+    }
+    let rest = strip_version(rest);
+    if rest.is_empty() {
+        return Some((episode, None));
+    }
+    let (end, tail) = take_number(rest.strip_prefix('-')?, 4)?;
+    (strip_version(tail).is_empty() && end > episode).then_some((episode, Some(end)))
+}
+
+fn strip_version(s: &str) -> &str {
+    match s.strip_prefix('v') {
+        Some(rest) if !rest.is_empty() && rest.bytes().all(|b| b.is_ascii_digit()) => "",
+        _ => s,
+    }
+}
+
+fn parse_year(token: &str) -> Option<i32> {
+    if token.len() != 4 || !token.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let year: i32 = token.parse().ok()?;
+    (1900..=2099).contains(&year).then_some(year)
+}
+
+/// 开头最多 `max_digits` 位数字及其后的剩余部分
+fn take_number(s: &str, max_digits: usize) -> Option<(u32, &str)> {
+    let digits = s.bytes().take_while(|b| b.is_ascii_digit()).count();
+    if digits == 0 || digits > max_digits {
+        return None;
+    }
+    Some((s[..digits].parse().ok()?, &s[digits..]))
+}
+
+fn is_dash(token: &str) -> bool {
+    matches!(token, "-" | "–" | "—")
+}
+
+fn is_release_segment(segment: &str) -> bool {
+    segment.split_whitespace().all(|t| RELEASE_TAGS.contains(&t.to_lowercase().as_str()))
+}
+
+fn clean_title(title: &str) -> String {
+    title.split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .trim_matches(|c: char| matches!(c, '-' | '–' | '—' | ',' | ' '))
+        .to_string()
+}
@@ -0,0 +1,209 @@
+//! Process-wide logger for the `log` facade.
+//!
+//! 各 crate 直接使用 `log` 宏，target 默认为模块路径（如 `bova_playback::mpv_player`）；
+//! mpv 与 FFmpeg 自己的日志由播放引擎以 `mpv::<模块>`、`ffmpeg` 为 target 转发进来。
+//! 通过过滤的记录写到 stderr，并交给 `add_sink` 注册的回调（GUI 日志面板、FFI 回调）。
+//!
+//! 过滤规则沿用 `RUST_LOG` 的常见写法：`warn,bova_playback=debug,mpv=info`，
+//! 启动时取 `BOVA_LOG` 环境变量，未设置时为 `DEFAULT_FILTER`。
+
+use std::fmt;
+use std::io::Write;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Once};
+
+use log::{Level, LevelFilter, Log, Metadata, Record};
+use parking_lot::{const_mutex, const_rwlock, Mutex, RwLock};
+
+/// 默认过滤规则：mpv 与 FFmpeg 只保留警告/错误（AAC 时间戳告警等噪声很多）
+pub const DEFAULT_FILTER: &str = "info,mpv=warn,ffmpeg=error";
+
+/// 环境变量名，取值为过滤规则
+pub const LOG_ENV: &str = "BOVA_LOG";
+
+/// A log record as handed to sinks.
+#[derive(Debug, Clone)]
+pub struct LogRecord {
+    pub level: Level,
+    pub target: String,
+    pub message: String,
+}
+
+impl fmt::Display for LogRecord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:<5} [{}] {}", self.level, self.target, self.message)
+    }
+}
+
+/// Per-target levels: a default level plus `target=level` rules; the longest
+/// matching target prefix wins (`bova_playback` also covers `bova_playback::demux`).
+#[derive(Debug, Clone, PartialEq)]
+pub struct LogFilter {
+    default: LevelFilter,
+    rules: Vec<(String, LevelFilter)>,
+}
+
+impl LogFilter {
+    pub const fn new(default: LevelFilter) -> Self {
+        Self { default, rules: Vec::new() }
+    }
+
+    /// `BOVA_LOG`, or `DEFAULT_FILTER` when unset or invalid.
+    pub fn from_env() -> Self {
+        let default = || DEFAULT_FILTER.parse().expect("valid default filter");
+        match std::env::var(LOG_ENV) {
+            Ok(spec) if !spec.trim().is_empty() => spec.parse().unwrap_or_else(|e| {
+                eprintln!("{LOG_ENV}: {e}, using \"{DEFAULT_FILTER}\"");
+                default()
+            }),
+            _ => default(),
+        }
+    }
+
+    /// Add or replace the rule for `target`.
+    pub fn with(mut self, target: &str, level: LevelFilter) -> Self {
+        self.rules.retain(|(t, _)| t != target);
+        self.rules.push((target.to_string(), level));
+        self
+    }
+
+    pub fn level_for(&self, target: &str) -> LevelFilter {
+        self.rules
+            .iter()
+            .filter(|(prefix, _)| {
+                target.strip_prefix(prefix.as_str()).is_some_and(|rest| rest.is_empty() || rest.starts_with("::"))
+            })
+            .max_by_key(|(prefix, _)| prefix.len())
+            .map_or(self.default, |&(_, level)| level)
+    }
+
+    /// Most verbose level of any rule, for `log::set_max_level`.
+    pub fn max_level(&self) -> LevelFilter {
+        self.rules.iter().map(|&(_, level)| level).fold(self.default, Ord::max)
+    }
+}
+
+impl Default for LogFilter {
+    fn default() -> Self {
+        DEFAULT_FILTER.parse().expect("valid default filter")
+    }
+}
+
+impl FromStr for LogFilter {
+    type Err = String;
+
+    fn from_str(spec: &str) -> Result<Self, Self::Err> {
+        let mut filter = LogFilter::new(LevelFilter::Info);
+        for part in spec.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+            let parse_level = |s: &str| s.trim().parse::<LevelFilter>().map_err(|_| format!("invalid log level `{s}`"));
+            match part.split_once('=') {
+                Some((target, level)) => {
+                    let level = parse_level(level)?;
+                    filter = filter.with(target.trim(), level);
+                }
+                // 单独的级别是默认级别；单独的 target 表示该 target 输出全部级别
+                None => match parse_level(part) {
+                    Ok(level) => filter.default = level,
+                    Err(_) => filter = filter.with(part, LevelFilter::Trace),
+                },
+            }
+        }
+        Ok(filter)
+    }
+}
+
+impl fmt::Display for LogFilter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.default.as_str().to_lowercase())?;
+        for (target, level) in &self.rules {
+            write!(f, ",{}={}", target, level.as_str().to_lowercase())?;
+        }
+        Ok(())
+    }
+}
+
+pub type SinkId = u64;
+type Sink = Arc<dyn Fn(&LogRecord) + Send + Sync>;
+
+struct Logger {
+    filter: RwLock<LogFilter>,
+    sinks: Mutex<Vec<(SinkId, Sink)>>,
+    stderr: AtomicBool,
+    next_sink: AtomicU64,
+}
+
+static LOGGER: Logger = Logger {
+    filter: const_rwlock(LogFilter::new(LevelFilter::Info)),
+    sinks: const_mutex(Vec::new()),
+    stderr: AtomicBool::new(true),
+    next_sink: AtomicU64::new(1),
+};
+
+impl Log for Logger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= self.filter.read().level_for(metadata.target())
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        let record = LogRecord {
+            level: record.level(),
+            target: record.target().to_string(),
+            message: record.args().to_string(),
+        };
+        if self.stderr.load(Ordering::Relaxed) {
+            let _ = writeln!(std::io::stderr().lock(), "{record}");
+        }
+        // 回调里可能再次输出日志，先复制一份再调用，避免持锁重入
+        let sinks: Vec<Sink> = self.sinks.lock().iter().map(|(_, sink)| sink.clone()).collect();
+        for sink in sinks {
+            sink(&record);
+        }
+    }
+
+    fn flush(&self) {
+        let _ = std::io::stderr().flush();
+    }
+}
+
+/// Install the logger with the `BOVA_LOG` filter. Safe to call more than once;
+/// later calls keep the filter that is already in effect.
+pub fn init() {
+    static INIT: Once = Once::new();
+    INIT.call_once(|| {
+        if log::set_logger(&LOGGER).is_err() {
+            // 宿主程序已经装了自己的 logger，日志交给它处理
+            return;
+        }
+        set_filter(LogFilter::from_env());
+    });
+}
+
+pub fn set_filter(filter: LogFilter) {
+    log::set_max_level(filter.max_level());
+    *LOGGER.filter.write() = filter;
+}
+
+pub fn filter() -> LogFilter {
+    LOGGER.filter.read().clone()
+}
+
+/// Write records to stderr (default on); hosts that show logs themselves can turn it off.
+pub fn set_stderr(enabled: bool) {
+    LOGGER.stderr.store(enabled, Ordering::Relaxed);
+}
+
+/// Receive every record that passes the filter. The callback runs on the thread that
+/// logged, so it should only hand the record off (channel, queue).
+pub fn add_sink(sink: impl Fn(&LogRecord) + Send + Sync + 'static) -> SinkId {
+    let id = LOGGER.next_sink.fetch_add(1, Ordering::Relaxed);
+    LOGGER.sinks.lock().push((id, Arc::new(sink)));
+    id
+}
+
+pub fn remove_sink(id: SinkId) {
+    LOGGER.sinks.lock().retain(|(sink_id, _)| *sink_id != id);
+}
@@ -0,0 +1,228 @@
+//! M3U / M3U8, PLS and XSPF import and export.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use quick_xml::events::Event;
+use quick_xml::Reader;
+
+use super::{PlaylistEntry, PlaylistError};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlaylistFormat {
+    /// M3U / M3U8（扩展 M3U，UTF-8）
+    M3u,
+    Pls,
+    Xspf,
+}
+
+impl PlaylistFormat {
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let ext = path.extension()?.to_str()?.to_ascii_lowercase();
+        match ext.as_str() {
+            "m3u" | "m3u8" => Some(Self::M3u),
+            "pls" => Some(Self::Pls),
+            "xspf" => Some(Self::Xspf),
+            _ => None,
+        }
+    }
+
+    /// 是否为可识别的播放列表文件
+    pub fn is_playlist(path: &str) -> bool {
+        Self::from_path(Path::new(path)).is_some()
+    }
+
+    /// M3U8 文本是否为 HLS 媒体 / 主播放列表（应交给播放引擎，而不是当作文件列表展开）
+    pub fn is_hls(text: &str) -> bool {
+        text.lines().map(str::trim).any(|l| l.starts_with("#EXT-X-TARGETDURATION") || l.starts_with("#EXT-X-STREAM-INF"))
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::M3u => "m3u8",
+            Self::Pls => "pls",
+            Self::Xspf => "xspf",
+        }
+    }
+
+    /// 解析播放列表文本；`base_dir` 用于解析相对路径
+    pub fn parse(self, text: &str, base_dir: Option<&Path>) -> Result<Vec<PlaylistEntry>, PlaylistError> {
+        let text = text.trim_start_matches('\u{feff}');
+        match self {
+            Self::M3u => Ok(parse_m3u(text, base_dir)),
+            Self::Pls => Ok(parse_pls(text, base_dir)),
+            Self::Xspf => parse_xspf(text, base_dir),
+        }
+    }
+
+    pub fn write(self, entries: &[PlaylistEntry]) -> String {
+        match self {
+            Self::M3u => write_m3u(entries),
+            Self::Pls => write_pls(entries),
+            Self::Xspf => write_xspf(entries),
+        }
+    }
+}
+
+/// 相对路径以播放列表所在目录为准，file:// 转为本地路径，其他 URL 原样保留
+fn resolve(location: &str, base_dir: Option<&Path>) -> String {
+    if location.starts_with("file://") {
+        if let Some(path) = url::Url::parse(location).ok().and_then(|u| u.to_file_path().ok()) {
+            return path.display().to_string();
+        }
+    }
+    if location.contains("://") || Path::new(location).is_absolute() {
+        return location.to_string();
+    }
+    match base_dir {
+        Some(dir) => dir.join(location).display().to_string(),
+        None => location.to_string(),
+    }
+}
+
+fn seconds_to_ms(secs: &str) -> Option<i64> {
+    secs.trim().parse::<f64>().ok().filter(|s| *s >= 0.0).map(|s| (s * 1000.0) as i64)
+}
+
+fn duration_secs(entry: &PlaylistEntry) -> i64 {
+    entry.duration_ms.map_or(-1, |ms| ms / 1000)
+}
+
+fn parse_m3u(text: &str, base_dir: Option<&Path>) -> Vec<PlaylistEntry> {
+    let mut entries = Vec::new();
+    let mut info: Option<(Option<i64>, Option<String>)> = None;
+    for line in text.lines().map(str::trim).filter(|l| !l.is_empty()) {
+        // #EXTINF:<秒数> [属性...],<标题>
+        if let Some(rest) = line.strip_prefix("#EXTINF:") {
+            let (head, title) = rest.split_once(',').unwrap_or((rest, ""));
+            let secs = head.split_whitespace().next().unwrap_or("");
+            let title = Some(title.trim().to_string()).filter(|t| !t.is_empty());
+            info = Some((seconds_to_ms(secs), title));
+            continue;
+        }
+        if line.starts_with('#') { continue; }
+        let (duration_ms, title) = info.take().unwrap_or((None, None));
+        entries.push(PlaylistEntry { url: resolve(line, base_dir), title, duration_ms });
+    }
+    entries
+}
+
+fn write_m3u(entries: &[PlaylistEntry]) -> String {
+    let mut out = String::from("#EXTM3U\n");
+    for e in entries {
+        // 没有标题时留空，导入后不会把文件名当成标题
+        out.push_str(&format!("#EXTINF:{},{}\n{}\n", duration_secs(e), e.title.as_deref().unwrap_or(""), e.url));
+    }
+    out
+}
+
+/// PLS 中编号相同的一组条目
+#[derive(Default)]
+struct PlsItem {
+    url: Option<String>,
+    title: Option<String>,
+    duration_ms: Option<i64>,
+}
+
+fn parse_pls(text: &str, base_dir: Option<&Path>) -> Vec<PlaylistEntry> {
+    // FileN / TitleN / LengthN，按 N 排序
+    let mut items: BTreeMap<u32, PlsItem> = BTreeMap::new();
+    for line in text.lines().map(str::trim) {
+        let Some((key, value)) = line.split_once('=') else { continue };
+        let key = key.trim().to_ascii_lowercase();
+        let value = value.trim();
+        let (field, n) = key.split_at(key.find(|c: char| c.is_ascii_digit()).unwrap_or(key.len()));
+        let Ok(n) = n.parse::<u32>() else { continue };
+        let item = items.entry(n).or_default();
+        match field {
+            "file" => item.url = Some(resolve(value, base_dir)),
+            "title" => item.title = Some(value.to_string()).filter(|t| !t.is_empty()),
+            "length" => item.duration_ms = seconds_to_ms(value),
+            _ => {}
+        }
+    }
+    items.into_values()
+        .filter_map(|item| Some(PlaylistEntry { url: item.url?, title: item.title, duration_ms: item.duration_ms }))
+        .collect()
+}
+
+fn write_pls(entries: &[PlaylistEntry]) -> String {
+    let mut out = String::from("[playlist]\n");
+    for (i, e) in entries.iter().enumerate() {
+        let n = i + 1;
+        out.push_str(&format!("File{n}={}\n", e.url));
+        if let Some(title) = &e.title {
+            out.push_str(&format!("Title{n}={title}\n"));
+        }
+        out.push_str(&format!("Length{n}={}\n", duration_secs(e)));
+    }
+    out.push_str(&format!("NumberOfEntries={}\nVersion=2\n", entries.len()));
+    out
+}
+
+fn parse_xspf(text: &str, base_dir: Option<&Path>) -> Result<Vec<PlaylistEntry>, PlaylistError> {
+    let mut reader = Reader::from_str(text);
+    reader.trim_text(true);
+    let mut entries = Vec::new();
+    let mut track: Option<PlaylistEntry> = None;
+    let mut field: Option<String> = None;
+    loop {
+        match reader.read_event() {
+            Ok(Event::Start(e)) => {
+                let name = String::from_utf8_lossy(e.local_name().as_ref()).to_string();
+                if name == "track" {
+                    track = Some(PlaylistEntry::new(String::new()));
+                } else if track.is_some() {
+                    field = Some(name);
+                }
+            }
+            Ok(Event::Text(t)) => {
+                if let (Some(track), Some(field)) = (track.as_mut(), field.as_deref()) {
+                    let text = t.unescape().map_err(|e| PlaylistError::Parse(e.to_string()))?;
+                    match field {
+                        "location" if track.url.is_empty() => track.url = resolve(text.trim(), base_dir),
+                        "title" => track.title = Some(text.trim().to_string()),
+                        "duration" => track.duration_ms = text.trim().parse().ok(),
+                        _ => {}
+                    }
+                }
+            }
+            Ok(Event::End(e)) => {
+                if e.local_name().as_ref() == b"track" {
+                    if let Some(track) = track.take().filter(|t| !t.url.is_empty()) {
+                        entries.push(track);
+                    }
+                }
+                field = None;
+            }
+            Ok(Event::Eof) => break,
+            Ok(_) => {}
+            Err(e) => return Err(PlaylistError::Parse(format!("xspf at byte {}: {}", reader.buffer_position(), e))),
+        }
+    }
+    Ok(entries)
+}
+
+fn write_xspf(entries: &[PlaylistEntry]) -> String {
+    use quick_xml::escape::escape;
+    let mut out = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<playlist version=\"1\" xmlns=\"http://xspf.org/ns/0/\">\n  <trackList>\n");
+    for e in entries {
+        // location 必须是 URI，本地路径转为 file://
+        let location = if Path::new(&e.url).is_absolute() {
+            url::Url::from_file_path(&e.url).map(|u| u.to_string()).unwrap_or_else(|_| e.url.clone())
+        } else {
+            e.url.clone()
+        };
+        out.push_str("    <track>\n");
+        out.push_str(&format!("      <location>{}</location>\n", escape(location.as_str())));
+        if let Some(title) = &e.title {
+            out.push_str(&format!("      <title>{}</title>\n", escape(title.as_str())));
+        }
+        if let Some(ms) = e.duration_ms {
+            out.push_str(&format!("      <duration>{}</duration>\n", ms));
+        }
+        out.push_str("    </track>\n");
+    }
+    out.push_str("  </trackList>\n</playlist>\n");
+    out
+}
@@ -0,0 +1,281 @@
+//! Playlist model shared by the frontends.
+//!
+//! `order` 是播放顺序（entries 的下标排列）：关闭随机时为顺序排列，开启随机时为
+//! 打乱后的排列，上一首 / 下一首 / 自动前进都沿着它移动。播放列表保存在配置目录的
+//! playlist.json 中，可导入导出 M3U8 / PLS / XSPF（见 `formats`）。
+
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use bova_settings::{Store, Versioned};
+
+mod formats;
+
+pub use formats::PlaylistFormat;
+
+#[derive(Debug, Error)]
+pub enum PlaylistError {
+    #[error("io error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("unknown playlist format: {0}")]
+    UnknownFormat(String),
+    #[error("invalid playlist: {0}")]
+    Parse(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PlaylistEntry {
+    /// 本地路径或 URL
+    pub url: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub duration_ms: Option<i64>,
+}
+
+impl PlaylistEntry {
+    pub fn new(url: impl Into<String>) -> Self {
+        Self { url: url.into(), title: None, duration_ms: None }
+    }
+
+    /// 标题，没有时使用文件名
+    pub fn display_title(&self) -> String {
+        if let Some(title) = self.title.as_deref().filter(|t| !t.is_empty()) {
+            return title.to_string();
+        }
+        let name = self.url.trim_end_matches('/').rsplit(['/', '\\']).next().unwrap_or(&self.url);
+        let name = name.split('?').next().unwrap_or(name);
+        if name.is_empty() { self.url.clone() } else { name.to_string() }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RepeatMode {
+    #[default]
+    Off,
+    One,
+    All,
+}
+
+impl RepeatMode {
+    /// 依次切换 Off → All → One
+    pub fn cycle(self) -> Self {
+        match self {
+            RepeatMode::Off => RepeatMode::All,
+            RepeatMode::All => RepeatMode::One,
+            RepeatMode::One => RepeatMode::Off,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Playlist {
+    pub entries: Vec<PlaylistEntry>,
+    /// 当前项在 entries 中的下标
+    pub current: Option<usize>,
+    pub repeat: RepeatMode,
+    shuffle: bool,
+    order: Vec<usize>,
+}
+
+impl Playlist {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn current_entry(&self) -> Option<&PlaylistEntry> {
+        self.current.and_then(|i| self.entries.get(i))
+    }
+
+    pub fn position_of(&self, url: &str) -> Option<usize> {
+        self.entries.iter().position(|e| e.url == url)
+    }
+
+    /// 追加一项并返回其下标；随机模式下插入到剩余播放顺序中的随机位置
+    pub fn push(&mut self, entry: PlaylistEntry) -> usize {
+        let index = self.entries.len();
+        self.entries.push(entry);
+        if self.shuffle {
+            let start = self.order_pos().map_or(0, |p| p + 1);
+            let at = fastrand::usize(start..=self.order.len());
+            self.order.insert(at, index);
+        } else {
+            self.order.push(index);
+        }
+        index
+    }
+
+    pub fn extend(&mut self, entries: impl IntoIterator<Item = PlaylistEntry>) {
+        for entry in entries {
+            self.push(entry);
+        }
+    }
+
+    pub fn remove(&mut self, index: usize) -> Option<PlaylistEntry> {
+        if index >= self.entries.len() { return None; }
+        let entry = self.entries.remove(index);
+        self.order.retain(|&i| i != index);
+        for i in &mut self.order {
+            if *i > index { *i -= 1; }
+        }
+        self.current = match self.current {
+            Some(c) if c == index => None,
+            Some(c) if c > index => Some(c - 1),
+            other => other,
+        };
+        Some(entry)
+    }
+
+    /// 把 `from` 项移动到 `to` 位置
+    pub fn move_entry(&mut self, from: usize, to: usize) {
+        let len = self.entries.len();
+        if from >= len || to >= len || from == to { return; }
+        let entry = self.entries.remove(from);
+        self.entries.insert(to, entry);
+        let remap = |i: usize| {
+            if i == from {
+                to
+            } else if from < to && i > from && i <= to {
+                i - 1
+            } else if to < from && i >= to && i < from {
+                i + 1
+            } else {
+                i
+            }
+        };
+        for i in &mut self.order {
+            *i = remap(*i);
+        }
+        self.current = self.current.map(remap);
+        if !self.shuffle {
+            self.order = (0..len).collect();
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.order.clear();
+        self.current = None;
+    }
+
+    pub fn select(&mut self, index: usize) -> Option<&PlaylistEntry> {
+        if index >= self.entries.len() { return None; }
+        self.current = Some(index);
+        self.entries.get(index)
+    }
+
+    pub fn shuffle(&self) -> bool {
+        self.shuffle
+    }
+
+    /// 开关随机播放：开启时打乱顺序并把当前项放在最前
+    pub fn set_shuffle(&mut self, shuffle: bool) {
+        self.shuffle = shuffle;
+        self.order = (0..self.entries.len()).collect();
+        if shuffle {
+            fastrand::shuffle(&mut self.order);
+            if let Some(pos) = self.order_pos() {
+                let current = self.order.remove(pos);
+                self.order.insert(0, current);
+            }
+        }
+    }
+
+    /// 用户切到下一首：到末尾时回到开头
+    pub fn next_entry(&mut self) -> Option<&PlaylistEntry> {
+        self.step(1, true)
+    }
+
+    /// 用户切到上一首：到开头时回到末尾
+    pub fn previous(&mut self) -> Option<&PlaylistEntry> {
+        self.step(-1, true)
+    }
+
+    /// 当前项播放完毕后的下一项：单曲重复返回当前项，列表重复时循环，否则到末尾结束
+    pub fn advance(&mut self) -> Option<&PlaylistEntry> {
+        match self.repeat {
+            RepeatMode::One if self.current.is_some() => self.current_entry(),
+            RepeatMode::All => self.step(1, true),
+            _ => self.step(1, false),
+        }
+    }
+
+    /// `advance` 将返回的项，不改变当前项；供引擎提前打开下一项。
+    /// 播放顺序需要修复时返回 None，由 `advance` 处理
+    pub fn upcoming(&self) -> Option<&PlaylistEntry> {
+        if self.repeat == RepeatMode::One && self.current.is_some() {
+            return self.current_entry();
+        }
+        let len = self.order.len();
+        if len == 0 || len != self.entries.len() { return None; }
+        let pos = self.order_pos().map_or(0, |p| p + 1);
+        let pos = if pos < len { pos } else if self.repeat == RepeatMode::All { 0 } else { return None };
+        self.entries.get(self.order[pos])
+    }
+
+    fn order_pos(&self) -> Option<usize> {
+        let current = self.current?;
+        self.order.iter().position(|&i| i == current)
+    }
+
+    fn step(&mut self, delta: isize, wrap: bool) -> Option<&PlaylistEntry> {
+        self.repair_order();
+        let len = self.order.len() as isize;
+        if len == 0 { return None; }
+        let pos = match self.order_pos() {
+            Some(pos) => pos as isize + delta,
+            None if delta > 0 => 0,
+            None => len - 1,
+        };
+        let pos = if wrap { pos.rem_euclid(len) } else if (0..len).contains(&pos) { pos } else { return None };
+        self.current = Some(self.order[pos as usize]);
+        self.current_entry()
+    }
+
+    /// 手工编辑过的 playlist.json 里 order 可能与 entries 不一致
+    fn repair_order(&mut self) {
+        let mut sorted = self.order.clone();
+        sorted.sort_unstable();
+        if sorted != (0..self.entries.len()).collect::<Vec<_>>() {
+            self.set_shuffle(self.shuffle);
+        }
+    }
+
+    /// 读取播放列表文件（格式由扩展名决定），相对路径以文件所在目录为准
+    pub fn load_file(path: &Path) -> Result<Self, PlaylistError> {
+        let format = PlaylistFormat::from_path(path)
+            .ok_or_else(|| PlaylistError::UnknownFormat(path.display().to_string()))?;
+        let text = std::fs::read_to_string(path)?;
+        let mut playlist = Self::new();
+        playlist.extend(format.parse(&text, path.parent())?);
+        Ok(playlist)
+    }
+
+    /// 导出为播放列表文件（格式由扩展名决定）
+    pub fn save_file(&self, path: &Path) -> Result<(), PlaylistError> {
+        let format = PlaylistFormat::from_path(path)
+            .ok_or_else(|| PlaylistError::UnknownFormat(path.display().to_string()))?;
+        std::fs::write(path, format.write(&self.entries))?;
+        Ok(())
+    }
+}
+
+impl Versioned for Playlist {
+    const VERSION: u32 = 1;
+    const FILE: &'static str = "playlist.json";
+}
+
+/// 上次会话的播放列表（playlist.json）
+pub type PlaylistStore = Store<Playlist>;
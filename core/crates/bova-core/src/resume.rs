@@ -0,0 +1,95 @@
+//! Resume-position memory for local files.
+//!
+//! 键为 “规范化路径 + 文件大小 + 修改时间”，文件被替换后不会误续播；位置保存在
+//! bova-settings 的 state.json 中，与 GUI / CLI / FFI 共用。播放到接近结尾时视为
+//! 已看完并清除位置。
+
+use std::time::UNIX_EPOCH;
+
+use bova_settings::StateStore;
+
+/// 小于该位置时不值得续播
+pub const MIN_RESUME_MS: i64 = 10_000;
+/// 播放超过时长的该比例即视为看完
+pub const WATCHED_RATIO: f64 = 0.95;
+
+/// 本地文件的记忆键；网络地址及无法读取的文件返回 None
+pub fn media_key(url: &str) -> Option<String> {
+    let path = url.strip_prefix("file://").unwrap_or(url);
+    if path.contains("://") {
+        return None;
+    }
+    let path = std::fs::canonicalize(path).ok()?;
+    let meta = std::fs::metadata(&path).ok()?;
+    if !meta.is_file() {
+        return None;
+    }
+    let mtime = meta.modified().ok()
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    Some(file_key(&path.display().to_string(), meta.len(), mtime))
+}
+
+/// 已知规范化路径、大小与修改时间（Unix 秒）时的记忆键，媒体库用它避免逐个读取文件信息
+pub fn file_key(canonical_path: &str, size: u64, mtime: u64) -> String {
+    format!("file:{}:{}:{}", canonical_path, size, mtime)
+}
+
+/// 按比例判断是否看完；不用固定的剩余时长，否则几秒的短片一开始就算看完
+pub fn is_watched(position_ms: i64, duration_ms: i64) -> bool {
+    duration_ms > 0 && position_ms > 0 && position_ms as f64 >= duration_ms as f64 * WATCHED_RATIO
+}
+
+/// 续播位置的读写；状态文件在第一次使用时打开
+#[derive(Default)]
+pub struct ResumeStore {
+    store: Option<StateStore>,
+}
+
+impl ResumeStore {
+    fn store(&mut self) -> &mut StateStore {
+        self.store.get_or_insert_with(StateStore::open)
+    }
+
+    /// 上次记录的位置（读取最新的文件内容，其他进程可能刚写入）
+    pub fn position(&mut self, key: &str) -> Option<i64> {
+        let store = self.store();
+        store.reload();
+        store.get().file(key).and_then(|s| s.position_ms)
+    }
+
+    /// 记录播放进度：接近结尾时标记为已看完并清除位置，过于靠前的位置不覆盖已有记录
+    pub fn record(&mut self, key: &str, position_ms: i64, duration_ms: i64) {
+        if !is_watched(position_ms, duration_ms) && position_ms < MIN_RESUME_MS {
+            return;
+        }
+        let result = self.store().update(|state| {
+            state.update_file(key, |s| {
+                if is_watched(position_ms, duration_ms) {
+                    s.position_ms = None;
+                    s.watched = true;
+                } else {
+                    s.position_ms = Some(position_ms);
+                }
+                if duration_ms > 0 {
+                    s.duration_ms = Some(duration_ms);
+                }
+            })
+        });
+        if let Err(e) = result {
+            log::warn!("cannot save resume position: {}", e);
+        }
+    }
+
+    pub fn clear(&mut self, key: &str) {
+        let result = self.store().update(|state| {
+            if let Some(s) = state.files.get_mut(key) {
+                s.position_ms = None;
+            }
+        });
+        if let Err(e) = result {
+            log::warn!("cannot clear resume position: {}", e);
+        }
+    }
+}
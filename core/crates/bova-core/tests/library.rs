@@ -0,0 +1,68 @@
+//! Local library filename parsing.
+
+use bova_core::library::{parse_name, parse_season_dir, ParsedName};
+
+fn episode(title: &str, season: Option<u32>, episode: u32) -> ParsedName {
+    ParsedName { title: title.to_string(), season, episode: Some(episode), ..ParsedName::default() }
+}
+
+fn movie(title: &str, year: Option<i32>) -> ParsedName {
+    ParsedName { title: title.to_string(), year, ..ParsedName::default() }
+}
+
+#[test]
+fn scene_style_episodes() {
+    assert_eq!(parse_name("Show.S01E02.1080p"), episode("Show", Some(1), 2));
+    assert_eq!(parse_name("The.Show.Name.s02e10.720p.WEB-DL"), episode("The Show Name", Some(2), 10));
+    assert_eq!(parse_name("Show 1x02"), episode("Show", Some(1), 2));
+    assert_eq!(
+        parse_name("Show.S01E01-E02.1080p"),
+        ParsedName { episode_end: Some(2), ..episode("Show", Some(1), 1) }
+    );
+    assert_eq!(parse_name("Show.S01E01E02").episode_end, Some(2));
+    assert_eq!(parse_name("Show.2019.S01E02").year, Some(2019));
+    assert_eq!(parse_name("Show.S01E02.2019").year, Some(2019));
+    assert_eq!(parse_name("Movie.1920x1080").episode, None, "resolution is not 1920x1080");
+}
+
+#[test]
+fn anime_and_chinese_episodes() {
+    let group = Some("Group".to_string());
+    assert_eq!(parse_name("[Group] Anime - 05"), ParsedName { group: group.clone(), ..episode("Anime", None, 5) });
+    assert_eq!(
+        parse_name("[Group][Anime][05][1080p]"),
+        ParsedName { group: group.clone(), ..episode("Anime", None, 5) }
+    );
+    assert_eq!(parse_name("[Group] Anime 05v2 [1080p]"), ParsedName { group, ..episode("Anime", None, 5) });
+    assert_eq!(parse_name("某剧 第05集"), episode("某剧", None, 5));
+    assert_eq!(parse_name("某剧第12话"), episode("某剧", None, 12));
+    assert_eq!(parse_name("05"), episode("", None, 5));
+    assert_eq!(parse_name("E05"), episode("", None, 5));
+}
+
+#[test]
+fn movies_with_years() {
+    assert_eq!(parse_name("Movie.Name.2019.1080p.BluRay.x264"), movie("Movie Name", Some(2019)));
+    assert_eq!(parse_name("Movie Name (2019)"), movie("Movie Name", Some(2019)));
+    assert_eq!(parse_name("Movie Name"), movie("Movie Name", None));
+}
+
+#[test]
+fn numeric_titles() {
+    assert_eq!(parse_name("1917"), movie("1917", None));
+    assert_eq!(parse_name("1917 (2019)"), movie("1917", Some(2019)));
+    assert_eq!(parse_name("1917.2019.2160p"), movie("1917", Some(2019)));
+    assert_eq!(parse_name("Blade Runner 2049 (2017)"), movie("Blade Runner 2049", Some(2017)));
+    assert_eq!(parse_name("Blade.Runner.2049.2017.1080p"), movie("Blade Runner 2049", Some(2017)));
+    assert_eq!(parse_name("Blade Runner 2049"), movie("Blade Runner", Some(2049)), "a lone year is read as the year");
+    assert_eq!(parse_name("Movie 2019 1080p (2019)"), movie("Movie", Some(2019)));
+}
+
+#[test]
+fn season_directories() {
+    assert_eq!(parse_season_dir("Season 1"), Some(1));
+    assert_eq!(parse_season_dir("S02"), Some(2));
+    assert_eq!(parse_season_dir("第3季"), Some(3));
+    assert_eq!(parse_season_dir("Specials"), Some(0));
+    assert_eq!(parse_season_dir("Extras"), None);
+}
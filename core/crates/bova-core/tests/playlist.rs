@@ -0,0 +1,91 @@
+//! Playlist file formats.
+
+use std::path::Path;
+
+use bova_core::playlist::{PlaylistEntry, PlaylistFormat};
+
+#[test]
+fn hls_playlists_are_detected() {
+    let media = "#EXTM3U\n#EXT-X-VERSION:3\n#EXT-X-TARGETDURATION:6\n#EXTINF:6.0,\nseg0.ts\n";
+    let master = "#EXTM3U\n#EXT-X-STREAM-INF:BANDWIDTH=800000\nlow/index.m3u8\n";
+    let local = "#EXTM3U\n#EXTINF:215,Artist - Title\n/music/track.flac\n";
+    assert!(PlaylistFormat::is_hls(media));
+    assert!(PlaylistFormat::is_hls(master));
+    assert!(!PlaylistFormat::is_hls(local));
+}
+
+#[test]
+fn pls_entries_follow_their_number() {
+    let pls = "[playlist]\nFile2=http://radio/b\nTitle2=B\nFile1=a.mp3\nLength1=61\nTitle3=no file\nNumberOfEntries=3\n";
+    let entries = PlaylistFormat::Pls.parse(pls, Some(Path::new("/music"))).unwrap();
+    assert_eq!(entries.len(), 2, "items without FileN are dropped");
+    assert_eq!(entries[0].url, Path::new("/music").join("a.mp3").display().to_string());
+    assert_eq!((entries[0].title.as_deref(), entries[0].duration_ms), (None, Some(61_000)));
+    assert_eq!((entries[1].url.as_str(), entries[1].title.as_deref()), ("http://radio/b", Some("B")));
+}
+
+fn local(path: &str) -> String {
+    Path::new(path).display().to_string()
+}
+
+#[test]
+fn m3u_import() {
+    let m3u = "\u{feff}#EXTM3U\n\
+               #EXTINF:215 tvg-id=\"x\",Artist - Title\n\
+               sub/track.flac\n\
+               \n\
+               #EXTINF:-1,\n\
+               http://radio.example/stream\n\
+               file:///music/a%20b.mp3\n";
+    let entries = PlaylistFormat::M3u.parse(m3u, Some(Path::new("/lists"))).unwrap();
+    assert_eq!(entries, [
+        PlaylistEntry {
+            url: Path::new("/lists").join("sub/track.flac").display().to_string(),
+            title: Some("Artist - Title".into()),
+            duration_ms: Some(215_000),
+        },
+        PlaylistEntry::new("http://radio.example/stream"),
+        PlaylistEntry::new(local("/music/a b.mp3")),
+    ]);
+}
+
+#[test]
+fn xspf_import() {
+    let xspf = r#"<?xml version="1.0" encoding="UTF-8"?>
+<playlist version="1" xmlns="http://xspf.org/ns/0/">
+  <trackList>
+    <track>
+      <location>file:///music/Tom%20%26%20Jerry.mp3</location>
+      <title>Tom &amp; Jerry &lt;live&gt;</title>
+      <duration>61500</duration>
+    </track>
+    <track><location>sub/b.ogg</location></track>
+    <track><title>no location</title></track>
+    <track><location>https://example.com/c.mp3?a=1&amp;b=2</location></track>
+  </trackList>
+</playlist>"#;
+    let entries = PlaylistFormat::Xspf.parse(xspf, Some(Path::new("/lists"))).unwrap();
+    assert_eq!(entries, [
+        PlaylistEntry {
+            url: local("/music/Tom & Jerry.mp3"),
+            title: Some("Tom & Jerry <live>".into()),
+            duration_ms: Some(61_500),
+        },
+        PlaylistEntry::new(Path::new("/lists").join("sub/b.ogg").display().to_string()),
+        PlaylistEntry::new("https://example.com/c.mp3?a=1&b=2"),
+    ]);
+}
+
+#[test]
+fn export_import_round_trips() {
+    let entries = vec![
+        PlaylistEntry { url: local("/music/Tom & Jerry.mp3"), title: Some("Tom & Jerry <live>".into()), duration_ms: Some(61_000) },
+        PlaylistEntry::new(local("/music/untitled.flac")),
+        PlaylistEntry { url: "http://radio.example/stream?a=1&b=2".into(), title: None, duration_ms: Some(5_000) },
+    ];
+    for format in [PlaylistFormat::M3u, PlaylistFormat::Pls, PlaylistFormat::Xspf] {
+        let text = format.write(&entries);
+        let back = format.parse(&text, Some(Path::new("/elsewhere"))).unwrap();
+        assert_eq!(back, entries, "{format:?}:\n{text}");
+    }
+}
@@ -0,0 +1,27 @@
+//! Watched detection for resume positions.
+
+use bova_core::resume::is_watched;
+
+#[test]
+fn short_clips_are_not_watched_at_the_start() {
+    assert!(!is_watched(0, 8_000));
+    assert!(!is_watched(1_000, 8_000));
+    assert!(!is_watched(5_000, 12_000));
+    assert!(is_watched(7_700, 8_000));
+}
+
+#[test]
+fn long_media_is_watched_near_the_end() {
+    let two_hours = 2 * 3_600_000;
+    assert!(!is_watched(two_hours / 2, two_hours));
+    // credits: the last 5 % count as watched
+    assert!(is_watched(two_hours - 300_000, two_hours));
+    assert!(is_watched(two_hours, two_hours));
+}
+
+#[test]
+fn unknown_duration_or_position_is_not_watched() {
+    assert!(!is_watched(60_000, 0));
+    assert!(!is_watched(0, 0));
+    assert!(!is_watched(-1, 10_000));
+}
@@ -0,0 +1,390 @@
+//! Async Emby / Jellyfin API.
+//!
+//! 所有请求共用一个带连接池的 reqwest::Client（连接 / 请求超时），
+//! GET 请求在超时、连接失败和 5xx 时按指数退避重试；POST（登录、播放协商、
+//! 播放上报）会改变服务器状态，只发送一次。每个方法只负责一次 API 调用并返回类型化结果，
+//! 调度（去重、取消、事件）由调用方决定。
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+
+use crate::playback::{device_profile, select_source, PlaybackInfoResponse};
+use crate::{
+    EmbyDashboard, EmbyError, EmbyItem, EmbyServer, ItemPage, ItemQuery, MediaSource, PlaySession,
+    ServerInfo, StreamSelection, TICKS_PER_MS,
+};
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+const MAX_RETRIES: u32 = 3;
+const RETRY_BASE_DELAY: Duration = Duration::from_millis(400);
+
+/// 详情页需要的额外字段
+const DETAIL_FIELDS: &str = "Overview,Genres,People,MediaSources,Chapters,PrimaryImageAspectRatio,ProductionYear,CommunityRating,OfficialRating,BackdropImageTags,ChildCount,RecursiveItemCount";
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct ItemsResp {
+    items: Vec<EmbyItem>,
+    total_record_count: Option<usize>,
+}
+
+/// 播放状态上报（/Sessions/Playing, /Progress, /Stopped）
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ReportEvent {
+    Start { position_ms: i64, paused: bool },
+    /// `event` 为 "TimeUpdate" / "Pause" / "Unpause" 等
+    Progress { position_ms: i64, paused: bool, event: &'static str },
+    Stop { position_ms: i64 },
+}
+
+#[derive(Clone)]
+pub struct EmbyApi {
+    http: reqwest::Client,
+}
+
+impl EmbyApi {
+    pub fn new() -> Result<Self, EmbyError> {
+        let http = reqwest::Client::builder()
+            .connect_timeout(CONNECT_TIMEOUT)
+            .timeout(REQUEST_TIMEOUT)
+            .pool_max_idle_per_host(8)
+            .build()?;
+        Ok(Self { http })
+    }
+
+    /// 识别服务器类型（无需登录）
+    pub async fn server_info(&self, base_url: &str) -> Result<ServerInfo, EmbyError> {
+        let url = format!("{}/System/Info/Public", base_url.trim_end_matches('/'));
+        match get_json::<serde_json::Value>(self.http.get(&url)).await {
+            Ok(info) => Ok(ServerInfo::from_public_info(&info)),
+            Err(e) if e.is_retryable() => Err(e),
+            Err(_) => Err(EmbyError::NotMediaServer),
+        }
+    }
+
+    /// 识别服务器后用户名密码登录，返回带 token 与 user_id 的服务器
+    pub async fn authenticate(&self, mut server: EmbyServer, password: &str) -> Result<EmbyServer, EmbyError> {
+        // 先识别 Emby / Jellyfin，决定认证头与接口路径
+        let info = self.server_info(&server.url).await?;
+        server.kind = info.kind;
+        server.version = info.version;
+        if let Some(name) = info.name.filter(|n| !n.is_empty()) {
+            server.name = name;
+        }
+
+        let auth_url = format!("{}/Users/AuthenticateByName", server.base_url());
+        let body = serde_json::json!({
+            "Username": server.username,
+            "Pw": password
+        });
+        let json: serde_json::Value = get_json(server.authed(self.http.post(&auth_url)).json(&body)).await?;
+        match (
+            json.get("AccessToken").and_then(|v| v.as_str()),
+            json.get("User").and_then(|u| u.get("Id").and_then(|v| v.as_str())),
+        ) {
+            (Some(token), Some(user)) => {
+                server.access_token = Some(token.to_string());
+                server.user_id = Some(user.to_string());
+                Ok(server)
+            }
+            _ => Err(EmbyError::Parse("missing AccessToken or User.Id".to_string())),
+        }
+    }
+
+    /// 用户的媒体库（My Media）
+    pub async fn views(&self, server: &EmbyServer) -> Result<Vec<EmbyItem>, EmbyError> {
+        require_login(server)?;
+        let url = server.views_url("Fields=PrimaryImageAspectRatio,Overview,ProductionYear,CommunityRating,OfficialRating");
+        Ok(get_json::<ItemsResp>(server.authed(self.http.get(&url))).await?.items)
+    }
+
+    /// 继续观看
+    pub async fn resume_items(&self, server: &EmbyServer, limit: usize) -> Result<Vec<EmbyItem>, EmbyError> {
+        require_login(server)?;
+        let url = server.resume_url(&format!(
+            "Limit={}&Recursive=true&Fields=PrimaryImageAspectRatio,Overview,ProductionYear,CommunityRating,OfficialRating",
+            limit
+        ));
+        Ok(get_json::<ItemsResp>(server.authed(self.http.get(&url))).await?.items)
+    }
+
+    /// 首页：媒体库与继续观看并发获取，继续观看失败不影响首页
+    pub async fn dashboard(&self, server: &EmbyServer) -> Result<EmbyDashboard, EmbyError> {
+        let (views, resume) = tokio::join!(self.views(server), self.resume_items(server, 12));
+        let resume_items = resume.unwrap_or_else(|e| {
+            log::warn!("resume items failed: {}", e);
+            Vec::new()
+        });
+        Ok(EmbyDashboard { views: views?, resume_items })
+    }
+
+    /// 按条件查询一页媒体项
+    pub async fn items(&self, server: &EmbyServer, query: &ItemQuery) -> Result<ItemPage, EmbyError> {
+        require_login(server)?;
+        let url = server.items_url(&query.to_query_string());
+        let page = get_json::<ItemsResp>(server.authed(self.http.get(&url))).await?;
+        let total = page.total_record_count.unwrap_or(query.start_index + page.items.len());
+        Ok(ItemPage { items: page.items, total, query: query.clone() })
+    }
+
+    /// 全库快速搜索（/Search/Hints），只使用 query 的 search_term / include_item_types / 分页
+    pub async fn search_hints(&self, server: &EmbyServer, query: &ItemQuery) -> Result<ItemPage, EmbyError> {
+        let user_id = require_login(server)?;
+        let term = query.search_term.clone().unwrap_or_default();
+        let url = format!(
+            "{}/Search/Hints?UserId={}&SearchTerm={}&StartIndex={}&Limit={}&IncludeItemTypes={}",
+            server.base_url(),
+            user_id,
+            percent_encoding::utf8_percent_encode(&term, percent_encoding::NON_ALPHANUMERIC),
+            query.start_index,
+            query.limit,
+            query.include_item_types.as_deref().unwrap_or("Movie,Series,Episode"),
+        );
+
+        // 搜索提示只带有少量字段，转换为 EmbyItem 以复用卡片渲染
+        #[derive(Deserialize)]
+        #[serde(rename_all = "PascalCase")]
+        struct Hint {
+            item_id: Option<String>,
+            id: Option<String>,
+            name: String,
+            #[serde(rename = "Type")]
+            field_type: Option<String>,
+            media_type: Option<String>,
+            production_year: Option<i32>,
+            primary_image_tag: Option<String>,
+            series: Option<String>,
+            index_number: Option<i32>,
+            parent_index_number: Option<i32>,
+            run_time_ticks: Option<i64>,
+        }
+        #[derive(Deserialize)]
+        #[serde(rename_all = "PascalCase")]
+        struct HintsResp {
+            search_hints: Vec<Hint>,
+            total_record_count: Option<usize>,
+        }
+
+        let resp = get_json::<HintsResp>(server.authed(self.http.get(&url))).await?;
+        let items: Vec<EmbyItem> = resp.search_hints.into_iter()
+            .filter_map(|h| {
+                let id = h.item_id.or(h.id)?;
+                Some(EmbyItem {
+                    id,
+                    name: h.name,
+                    series_name: h.series,
+                    field_type: h.field_type,
+                    media_type: h.media_type,
+                    index_number: h.index_number,
+                    parent_index_number: h.parent_index_number,
+                    run_time_ticks: h.run_time_ticks,
+                    image_tags: h.primary_image_tag.map(|t| HashMap::from([("Primary".to_string(), t)])),
+                    production_year: h.production_year,
+                    ..Default::default()
+                })
+            })
+            .collect();
+        let total = resp.total_record_count.unwrap_or(query.start_index + items.len());
+        Ok(ItemPage { items, total, query: query.clone() })
+    }
+
+    /// 类型列表（用于筛选），parent_id 为 None 时为全库
+    pub async fn genres(&self, server: &EmbyServer, parent_id: Option<&str>) -> Result<Vec<String>, EmbyError> {
+        let user_id = require_login(server)?;
+        let mut url = format!("{}/Genres?UserId={}&SortBy=SortName&Recursive=true", server.base_url(), user_id);
+        if let Some(pid) = parent_id {
+            url.push_str(&format!("&ParentId={}", pid));
+        }
+        #[derive(Deserialize)]
+        #[serde(rename_all = "PascalCase")]
+        struct Genre {
+            name: String,
+        }
+        #[derive(Deserialize)]
+        #[serde(rename_all = "PascalCase")]
+        struct GenresResp {
+            items: Vec<Genre>,
+        }
+        let data = get_json::<GenresResp>(server.authed(self.http.get(&url))).await?;
+        Ok(data.items.into_iter().map(|g| g.name).collect())
+    }
+
+    /// 目录内容；parent_id 为 None 时返回媒体库列表
+    pub async fn children(&self, server: &EmbyServer, parent_id: Option<&str>, recursive: bool) -> Result<Vec<EmbyItem>, EmbyError> {
+        require_login(server)?;
+        let fields = "Fields=Overview,PrimaryImageAspectRatio,ProductionYear,CommunityRating,OfficialRating,BackdropImageTags,ChildCount,RecursiveItemCount";
+        let url = match parent_id {
+            // 递归查询，用于浏览器模式（跳过空文件夹，直接找到影片）
+            Some(pid) if recursive => server.items_url(&format!(
+                "ParentId={}&Recursive=true&IncludeItemTypes=Movie,Series&SortBy=DateCreated,SortName&SortOrder=Descending&{}",
+                pid, fields
+            )),
+            // 非递归查询，用于 Series -> Season 或 Season -> Episode
+            Some(pid) => server.items_url(&format!("ParentId={}&SortBy=SortName&{}", pid, fields)),
+            None => server.views_url(fields),
+        };
+        Ok(get_json::<ItemsResp>(server.authed(self.http.get(&url))).await?.items)
+    }
+
+    /// 媒体库的最新内容预览（递归查找影片、剧集、音乐和照片）
+    pub async fn latest_in_view(&self, server: &EmbyServer, view_id: &str, limit: usize) -> Result<Vec<EmbyItem>, EmbyError> {
+        require_login(server)?;
+        let url = server.items_url(&format!(
+            "ParentId={}&Recursive=true&IncludeItemTypes=Movie,Series,Audio,Photo,MusicAlbum&Limit={}&SortBy=DateCreated,SortName&SortOrder=Descending&Fields=PrimaryImageAspectRatio,Overview,ProductionYear,CommunityRating,OfficialRating,ChildCount,RecursiveItemCount",
+            view_id, limit
+        ));
+        Ok(get_json::<ItemsResp>(server.authed(self.http.get(&url))).await?.items)
+    }
+
+    /// 某一季的剧集
+    pub async fn season_episodes(&self, server: &EmbyServer, season_id: &str) -> Result<Vec<EmbyItem>, EmbyError> {
+        require_login(server)?;
+        let url = server.items_url(&format!("ParentId={}&Fields=PrimaryImageAspectRatio,Overview", season_id));
+        Ok(get_json::<ItemsResp>(server.authed(self.http.get(&url))).await?.items)
+    }
+
+    /// Series 的总集数（只取 TotalRecordCount，不拉取条目）
+    pub async fn episode_count(&self, server: &EmbyServer, series_id: &str) -> Result<usize, EmbyError> {
+        require_login(server)?;
+        let url = server.items_url(&format!("ParentId={}&Recursive=true&IncludeItemTypes=Episode&Limit=0", series_id));
+        let data = get_json::<ItemsResp>(server.authed(self.http.get(&url))).await?;
+        Ok(data.total_record_count.unwrap_or(data.items.len()))
+    }
+
+    /// 单个条目的完整信息（含演职人员、类型与媒体源）
+    pub async fn item(&self, server: &EmbyServer, item_id: &str) -> Result<EmbyItem, EmbyError> {
+        let user_id = require_login(server)?;
+        let url = if server.uses_new_user_endpoints() {
+            format!("{}/Items/{}?UserId={}&Fields={}", server.base_url(), item_id, user_id, DETAIL_FIELDS)
+        } else {
+            format!("{}/Users/{}/Items/{}?Fields={}", server.base_url(), user_id, item_id, DETAIL_FIELDS)
+        };
+        get_json(server.authed(self.http.get(&url))).await
+    }
+
+    /// 下载图片（见 `EmbyServer::image_url`）
+    pub async fn image(&self, url: &str) -> Result<Vec<u8>, EmbyError> {
+        Ok(send(self.http.get(url)).await?.bytes().await?.to_vec())
+    }
+
+    /// 通过 /Items/{id}/PlaybackInfo 协商播放方式；max_bitrate 为 None 时使用服务器设定
+    pub async fn playback_info(&self, server: &EmbyServer, item_id: &str, max_bitrate: Option<u64>) -> Result<StreamSelection, EmbyError> {
+        let user_id = require_login(server)?;
+        let max_bitrate = max_bitrate.or(server.max_bitrate).unwrap_or(crate::UNLIMITED_BITRATE);
+        let url = format!(
+            "{}/Items/{}/PlaybackInfo?UserId={}&MaxStreamingBitrate={}&AutoOpenLiveStream=true",
+            server.base_url(), item_id, user_id, max_bitrate
+        );
+        let body = serde_json::json!({ "DeviceProfile": device_profile(max_bitrate) });
+        let info = get_json::<PlaybackInfoResponse>(server.authed(self.http.post(&url)).json(&body)).await?;
+        select_source(server, item_id, info, max_bitrate)
+    }
+
+    /// 下载服务器上的字幕流到 `dir`，返回本地路径
+    pub async fn download_subtitle(&self, server: &EmbyServer, item_id: &str, source: &MediaSource, index: i64, dir: &Path) -> Result<PathBuf, EmbyError> {
+        let stream = source.media_streams.iter().find(|st| st.index == index).ok_or(EmbyError::NotFound)?;
+        let format = stream.subtitle_format();
+        // 服务器给出的 DeliveryUrl 优先（外挂字幕通常带有）
+        let url = match stream.delivery_url.as_deref().filter(|u| u.starts_with('/')) {
+            Some(path) => format!("{}{}", server.base_url(), path),
+            None => format!(
+                "{}/Videos/{}/{}/Subtitles/{}/Stream.{}",
+                server.base_url(), item_id, source.id, index, format
+            ),
+        };
+        let data = send(server.authed(self.http.get(&url))).await?.bytes().await?;
+        tokio::fs::create_dir_all(dir).await?;
+        let path = dir.join(format!("{}_{}_{}.{}", item_id, source.id, index, format));
+        tokio::fs::write(&path, &data).await?;
+        Ok(path)
+    }
+
+    /// 上报播放状态；服务器据此更新续播位置和已看状态
+    pub async fn report(&self, server: &EmbyServer, item_id: &str, session: &PlaySession, event: ReportEvent) -> Result<(), EmbyError> {
+        let mut body = serde_json::json!({
+            "ItemId": item_id,
+            "MediaSourceId": session.media_source_id,
+            "PlaySessionId": session.play_session_id,
+        });
+        let endpoint = match event {
+            ReportEvent::Start { position_ms, paused } => {
+                body["PositionTicks"] = (position_ms * TICKS_PER_MS).into();
+                body["IsPaused"] = paused.into();
+                body["CanSeek"] = true.into();
+                body["PlayMethod"] = session.method.as_str().into();
+                "/Sessions/Playing"
+            }
+            ReportEvent::Progress { position_ms, paused, event } => {
+                body["PositionTicks"] = (position_ms * TICKS_PER_MS).into();
+                body["IsPaused"] = paused.into();
+                body["CanSeek"] = true.into();
+                body["PlayMethod"] = session.method.as_str().into();
+                body["EventName"] = event.into();
+                "/Sessions/Playing/Progress"
+            }
+            ReportEvent::Stop { position_ms } => {
+                body["PositionTicks"] = (position_ms * TICKS_PER_MS).into();
+                "/Sessions/Playing/Stopped"
+            }
+        };
+        let url = format!("{}{}", server.base_url(), endpoint);
+        send(server.authed(self.http.post(&url)).json(&body)).await?;
+        Ok(())
+    }
+}
+
+fn require_login(server: &EmbyServer) -> Result<&str, EmbyError> {
+    match (&server.access_token, &server.user_id) {
+        (Some(_), Some(user_id)) => Ok(user_id),
+        _ => Err(EmbyError::NotAuthenticated),
+    }
+}
+
+/// 发送请求；GET / HEAD 在超时、连接失败和 5xx 时按指数退避重试，
+/// 其他方法不是幂等的（重复的 POST 可能重复登录、打开直播流或上报），只发送一次
+async fn send(req: reqwest::RequestBuilder) -> Result<reqwest::Response, EmbyError> {
+    let idempotent = req
+        .try_clone()
+        .and_then(|r| r.build().ok())
+        .is_some_and(|r| matches!(*r.method(), reqwest::Method::GET | reqwest::Method::HEAD));
+    if !idempotent {
+        return checked(req.send().await);
+    }
+    let mut attempt = 0;
+    loop {
+        // 流式 body 无法复制，只能发送一次
+        let Some(this_try) = req.try_clone() else {
+            return checked(req.send().await);
+        };
+        let err = match checked(this_try.send().await) {
+            Ok(resp) => return Ok(resp),
+            Err(e) => e,
+        };
+        if !err.is_retryable() || attempt >= MAX_RETRIES {
+            return Err(err);
+        }
+        let delay = RETRY_BASE_DELAY * 2u32.pow(attempt);
+        log::warn!("{}，{}ms 后重试 ({}/{})", err, delay.as_millis(), attempt + 1, MAX_RETRIES);
+        tokio::time::sleep(delay).await;
+        attempt += 1;
+    }
+}
+
+fn checked(result: reqwest::Result<reqwest::Response>) -> Result<reqwest::Response, EmbyError> {
+    let resp = result?;
+    if resp.status().is_success() {
+        Ok(resp)
+    } else {
+        Err(EmbyError::from_status(resp.status()))
+    }
+}
+
+async fn get_json<T: DeserializeOwned>(req: reqwest::RequestBuilder) -> Result<T, EmbyError> {
+    let bytes = send(req).await?.bytes().await?;
+    Ok(serde_json::from_slice(&bytes)?)
+}
@@ -0,0 +1,33 @@
+//! Synchronous wrapper for callers without an async runtime (FFI, CLI).
+
+use std::future::Future;
+
+use tokio::runtime::Runtime;
+
+use crate::{EmbyApi, EmbyError};
+
+/// 自带运行时的同步 API：`api.block_on(api.inner().views(&server))`
+pub struct BlockingEmbyApi {
+    runtime: Runtime,
+    api: EmbyApi,
+}
+
+impl BlockingEmbyApi {
+    pub fn new() -> Result<Self, EmbyError> {
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(1)
+            .thread_name("bova-emby")
+            .enable_all()
+            .build()?;
+        Ok(Self { runtime, api: EmbyApi::new()? })
+    }
+
+    pub fn inner(&self) -> &EmbyApi {
+        &self.api
+    }
+
+    /// 在内部运行时上执行一个请求并等待结果（可从多个线程同时调用）
+    pub fn block_on<F: Future>(&self, fut: F) -> F::Output {
+        self.runtime.block_on(fut)
+    }
+}
@@ -0,0 +1,81 @@
+//! Typed errors for Emby / Jellyfin requests, shown to the user as-is.
+
+use thiserror::Error;
+
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum EmbyError {
+    #[error("未登录或登录已失效")]
+    NotAuthenticated,
+    #[error("用户名或密码错误")]
+    Unauthorized,
+    #[error("没有访问权限")]
+    Forbidden,
+    #[error("请求的内容不存在")]
+    NotFound,
+    #[error("请求超时")]
+    Timeout,
+    #[error("无法连接服务器: {0}")]
+    Connect(String),
+    #[error("网络错误: {0}")]
+    Network(String),
+    #[error("服务器错误 (HTTP {0})")]
+    Server(u16),
+    #[error("请求失败 (HTTP {0})")]
+    Http(u16),
+    #[error("响应解析失败: {0}")]
+    Parse(String),
+    #[error("不是 Emby/Jellyfin 服务器")]
+    NotMediaServer,
+    #[error("服务器拒绝播放: {0}")]
+    PlaybackRefused(String),
+    #[error("没有可播放的媒体源")]
+    NoPlayableSource,
+    #[error("本地文件错误: {0}")]
+    Io(String),
+}
+
+impl EmbyError {
+    /// 暂时性错误才值得重试（超时、连接失败、5xx）
+    pub fn is_retryable(&self) -> bool {
+        matches!(self, EmbyError::Timeout | EmbyError::Connect(_) | EmbyError::Network(_) | EmbyError::Server(_))
+    }
+
+    pub(crate) fn from_status(status: reqwest::StatusCode) -> Self {
+        match status.as_u16() {
+            401 => EmbyError::Unauthorized,
+            403 => EmbyError::Forbidden,
+            404 => EmbyError::NotFound,
+            408 | 429 => EmbyError::Timeout,
+            code @ 500..=599 => EmbyError::Server(code),
+            code => EmbyError::Http(code),
+        }
+    }
+}
+
+impl From<reqwest::Error> for EmbyError {
+    fn from(e: reqwest::Error) -> Self {
+        if e.is_timeout() {
+            EmbyError::Timeout
+        } else if e.is_connect() {
+            EmbyError::Connect(e.to_string())
+        } else if e.is_decode() {
+            EmbyError::Parse(e.to_string())
+        } else if let Some(status) = e.status() {
+            EmbyError::from_status(status)
+        } else {
+            EmbyError::Network(e.to_string())
+        }
+    }
+}
+
+impl From<serde_json::Error> for EmbyError {
+    fn from(e: serde_json::Error) -> Self {
+        EmbyError::Parse(e.to_string())
+    }
+}
+
+impl From<std::io::Error> for EmbyError {
+    fn from(e: std::io::Error) -> Self {
+        EmbyError::Io(e.to_string())
+    }
+}
@@ -0,0 +1,22 @@
+//! bova-emby: Emby / Jellyfin client shared by the GUI, CLI and FFI.
+//!
+//! `models` mirrors the server JSON (`EmbyItem`, `UserData`, `MediaSource`,
+//! `MediaStream`, `Person`); `EmbyApi` performs the requests asynchronously and
+//! returns typed results. `blocking::BlockingEmbyApi` wraps it for callers
+//! without a tokio runtime.
+
+mod api;
+pub mod blocking;
+mod error;
+mod models;
+mod playback;
+mod query;
+
+pub use api::{EmbyApi, ReportEvent};
+pub use error::EmbyError;
+pub use models::{
+    ChapterInfo, EmbyDashboard, EmbyItem, EmbyServer, MediaSource, MediaStream, Person, PlayMethod, PlaySession,
+    ServerInfo, ServerKind, UserData, TICKS_PER_MS,
+};
+pub use playback::{device_profile, format_bitrate, StreamSelection, BITRATE_PRESETS, UNLIMITED_BITRATE};
+pub use query::{parse_years, ItemPage, ItemQuery, SortField};
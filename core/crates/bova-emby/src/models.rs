@@ -0,0 +1,407 @@
+//! Server, item and media-source models. Field names follow the Emby JSON
+//! (PascalCase) so they deserialize straight from API responses.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+pub(crate) const CLIENT_NAME: &str = "BovaPlayer";
+const DEVICE_NAME: &str = "BovaPlayer Desktop";
+const DEVICE_ID: &str = "bova-player-id";
+const CLIENT_VERSION: &str = "0.0.1";
+
+/// 服务器类型，通过 /System/Info/Public 自动识别
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum ServerKind {
+    #[default]
+    Emby,
+    Jellyfin,
+}
+
+impl std::fmt::Display for ServerKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ServerKind::Emby => write!(f, "Emby"),
+            ServerKind::Jellyfin => write!(f, "Jellyfin"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EmbyServer {
+    pub name: String,
+    pub url: String,
+    pub username: String, // Store username for display/re-auth if needed
+    pub user_id: Option<String>,
+    pub access_token: Option<String>,
+    #[serde(default)]
+    pub kind: ServerKind,
+    #[serde(default)]
+    pub version: Option<String>,
+    /// 用户设定的最大串流码率 (bps)，None 为不限
+    #[serde(default)]
+    pub max_bitrate: Option<u64>,
+}
+
+impl EmbyServer {
+    pub fn base_url(&self) -> &str {
+        self.url.trim_end_matches('/')
+    }
+
+    /// MediaBrowser 授权串：Emby 放在 X-Emby-Authorization，Jellyfin 放在 Authorization
+    pub fn authorization(&self) -> String {
+        let mut auth = format!(
+            "MediaBrowser Client=\"{}\", Device=\"{}\", DeviceId=\"{}\", Version=\"{}\"",
+            CLIENT_NAME, DEVICE_NAME, DEVICE_ID, CLIENT_VERSION
+        );
+        if let Some(token) = &self.access_token {
+            auth.push_str(&format!(", Token=\"{}\"", token));
+        }
+        auth
+    }
+
+    /// 为请求附加该服务器类型对应的认证头
+    pub fn authed(&self, req: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
+        match self.kind {
+            ServerKind::Jellyfin => req.header("Authorization", self.authorization()),
+            ServerKind::Emby => {
+                let req = req.header("X-Emby-Authorization", self.authorization());
+                match &self.access_token {
+                    Some(token) => req.header("X-Emby-Token", token),
+                    None => req,
+                }
+            }
+        }
+    }
+
+    /// 交给 FFmpeg / mpv 的请求头，与 `authed` 一致（串流地址本身另带 api_key）
+    pub fn stream_headers(&self) -> Vec<(String, String)> {
+        let mut headers = Vec::new();
+        match self.kind {
+            ServerKind::Jellyfin => headers.push(("Authorization".to_string(), self.authorization())),
+            ServerKind::Emby => {
+                headers.push(("X-Emby-Authorization".to_string(), self.authorization()));
+                if let Some(token) = &self.access_token {
+                    headers.push(("X-Emby-Token".to_string(), token.clone()));
+                }
+            }
+        }
+        headers
+    }
+
+    /// Jellyfin 10.9 起用户相关接口改为 /Items?userId= 等形式，旧路径已弃用
+    pub(crate) fn uses_new_user_endpoints(&self) -> bool {
+        if self.kind != ServerKind::Jellyfin {
+            return false;
+        }
+        let mut parts = self.version.as_deref().unwrap_or("").split('.').map(|p| p.parse::<u32>().unwrap_or(0));
+        let major = parts.next().unwrap_or(0);
+        let minor = parts.next().unwrap_or(0);
+        (major, minor) >= (10, 9)
+    }
+
+    fn user_id_or_empty(&self) -> &str {
+        self.user_id.as_deref().unwrap_or("")
+    }
+
+    /// 用户媒体项查询，`query` 为不带 `?` 的查询串
+    pub fn items_url(&self, query: &str) -> String {
+        if self.uses_new_user_endpoints() {
+            format!("{}/Items?UserId={}&{}", self.base_url(), self.user_id_or_empty(), query)
+        } else {
+            format!("{}/Users/{}/Items?{}", self.base_url(), self.user_id_or_empty(), query)
+        }
+    }
+
+    pub fn views_url(&self, query: &str) -> String {
+        if self.uses_new_user_endpoints() {
+            format!("{}/UserViews?UserId={}&{}", self.base_url(), self.user_id_or_empty(), query)
+        } else {
+            format!("{}/Users/{}/Views?{}", self.base_url(), self.user_id_or_empty(), query)
+        }
+    }
+
+    /// 封面 / 背景图地址（图片接口无需认证）
+    pub fn image_url(&self, item_id: &str, tag: &str, is_backdrop: bool) -> String {
+        let endpoint = if is_backdrop { "Backdrop" } else { "Primary" };
+        format!("{}/Items/{}/Images/{}?tag={}&quality=90", self.base_url(), item_id, endpoint, tag)
+    }
+
+    /// HLS 转码流（master playlist，含多个码率档位）；未登录时为空串
+    pub fn hls_url(&self, item_id: &str) -> String {
+        match &self.access_token {
+            Some(token) => format!("{}/Videos/{}/master.m3u8?MediaSourceId={}&api_key={}",
+                self.base_url(), item_id, item_id, token),
+            None => String::new(),
+        }
+    }
+
+    pub fn resume_url(&self, query: &str) -> String {
+        if self.uses_new_user_endpoints() {
+            format!("{}/UserItems/Resume?UserId={}&{}", self.base_url(), self.user_id_or_empty(), query)
+        } else {
+            format!("{}/Users/{}/Items/Resume?{}", self.base_url(), self.user_id_or_empty(), query)
+        }
+    }
+}
+
+
+/// /System/Info/Public 的结果
+#[derive(Debug, Clone, Serialize)]
+pub struct ServerInfo {
+    pub kind: ServerKind,
+    pub name: Option<String>,
+    pub version: Option<String>,
+}
+
+impl ServerInfo {
+    /// 由 /System/Info/Public 的响应识别服务器类型
+    pub fn from_public_info(info: &serde_json::Value) -> Self {
+        let product = info.get("ProductName").and_then(|v| v.as_str()).unwrap_or("");
+        let version = info.get("Version").and_then(|v| v.as_str()).map(|s| s.to_string());
+        // Jellyfin 版本号为 10.x；较旧的 Jellyfin 不返回 ProductName
+        let major: u32 = version.as_deref().and_then(|v| v.split('.').next()).and_then(|m| m.parse().ok()).unwrap_or(0);
+        let kind = if product.to_ascii_lowercase().contains("jellyfin") || (product.is_empty() && major >= 10) {
+            ServerKind::Jellyfin
+        } else {
+            ServerKind::Emby
+        };
+        Self {
+            kind,
+            name: info.get("ServerName").and_then(|v| v.as_str()).map(|s| s.to_string()),
+            version,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "PascalCase")] // Emby uses PascalCase for JSON fields
+pub struct EmbyItem {
+    pub id: String,
+    pub name: String,
+    pub original_title: Option<String>,
+    /// 单集 / 季所属剧集的名称
+    pub series_name: Option<String>,
+    #[serde(rename = "Type")]
+    pub field_type: Option<String>, // "Movie", "Episode", "Series", "Folder", "CollectionFolder"
+    pub media_type: Option<String>, // "Video", "Audio"
+    pub parent_id: Option<String>,
+    pub index_number: Option<i32>,      // Episode number
+    pub parent_index_number: Option<i32>, // Season number
+    pub run_time_ticks: Option<i64>,
+    pub image_tags: Option<HashMap<String, String>>, // Primary image tag
+    pub backdrop_image_tags: Option<Vec<String>>,
+    pub overview: Option<String>,
+    pub production_year: Option<i32>,
+    pub community_rating: Option<f32>,
+    pub official_rating: Option<String>,
+    pub child_count: Option<i32>,  // 子项数量（剧集总数）
+    pub recursive_item_count: Option<i32>,  // 递归子项数量
+    pub user_data: Option<UserData>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub genres: Vec<String>,
+    /// 演职人员，仅在请求 Fields=People 时返回
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub people: Vec<Person>,
+    /// 仅在请求 Fields=MediaSources 时返回
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub media_sources: Vec<MediaSource>,
+    /// 章节与片头 / 片尾标记，仅在请求 Fields=Chapters 时返回
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub chapters: Vec<ChapterInfo>,
+}
+
+impl EmbyItem {
+    /// 服务器记录的续播位置（毫秒），没有则为 None
+    pub fn resume_position_ms(&self) -> Option<i64> {
+        self.user_data.as_ref()
+            .and_then(|d| d.playback_position_ticks)
+            .filter(|&t| t > 0)
+            .map(|t| t / TICKS_PER_MS)
+    }
+
+    /// 普通章节（不含片头 / 片尾标记），结束时间为下一章节的开始或片长
+    pub fn playback_chapters(&self) -> Vec<bova_probe::Chapter> {
+        let starts: Vec<(i64, Option<String>)> = self.chapters.iter()
+            .filter(|c| c.is_chapter())
+            .map(|c| (c.start_ms(), c.name.clone().filter(|n| !n.is_empty())))
+            .collect();
+        let runtime_ms = self.run_time_ticks.map_or(0, |t| t / TICKS_PER_MS);
+        starts.iter().enumerate()
+            .map(|(i, (start_ms, title))| bova_probe::Chapter {
+                id: i as i64,
+                start_ms: *start_ms,
+                end_ms: starts.get(i + 1).map_or(runtime_ms, |next| next.0),
+                title: title.clone(),
+            })
+            .collect()
+    }
+
+    /// 服务器标记的片头区间（毫秒），缺少 IntroEnd 时为 None
+    pub fn intro_range_ms(&self) -> Option<(i64, i64)> {
+        let marker = |kind: &str| self.chapters.iter().find(|c| c.marker_type.as_deref() == Some(kind)).map(ChapterInfo::start_ms);
+        let (start, end) = (marker("IntroStart")?, marker("IntroEnd")?);
+        (end > start).then_some((start, end))
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "PascalCase")]
+pub struct UserData {
+    pub playback_position_ticks: Option<i64>,
+    pub played_percentage: Option<f64>,
+    pub play_count: Option<i32>,
+    pub played: Option<bool>,
+    pub is_favorite: Option<bool>,
+}
+
+/// 章节；Emby 4.8 起片头 / 片尾检测结果也以带 MarkerType 的章节返回
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "PascalCase")]
+pub struct ChapterInfo {
+    pub start_position_ticks: i64,
+    pub name: Option<String>,
+    pub marker_type: Option<String>, // "Chapter", "IntroStart", "IntroEnd", "CreditsStart"
+    pub image_tag: Option<String>,
+}
+
+impl ChapterInfo {
+    pub fn start_ms(&self) -> i64 {
+        self.start_position_ticks / TICKS_PER_MS
+    }
+
+    /// 普通章节（旧版服务器不返回 MarkerType）
+    pub fn is_chapter(&self) -> bool {
+        matches!(self.marker_type.as_deref(), None | Some("Chapter"))
+    }
+}
+
+/// 演员 / 导演 / 编剧等
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "PascalCase")]
+pub struct Person {
+    pub name: String,
+    pub id: Option<String>,
+    pub role: Option<String>,
+    #[serde(rename = "Type")]
+    pub person_type: Option<String>, // "Actor", "Director", "Writer", ...
+    pub primary_image_tag: Option<String>,
+}
+
+/// Emby 时间单位：1 tick = 100ns
+pub const TICKS_PER_MS: i64 = 10_000;
+
+/// 服务器端的播放方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PlayMethod {
+    DirectPlay,
+    DirectStream,
+    Transcode,
+}
+
+impl PlayMethod {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PlayMethod::DirectPlay => "DirectPlay",
+            PlayMethod::DirectStream => "DirectStream",
+            PlayMethod::Transcode => "Transcode",
+        }
+    }
+}
+
+/// 一次播放会话的标识，上报进度时需要与 PlaybackInfo 返回的一致
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlaySession {
+    pub media_source_id: String,
+    pub play_session_id: String,
+    pub method: PlayMethod,
+}
+
+impl PlaySession {
+    /// 未经 PlaybackInfo 协商时使用（本地生成 PlaySessionId）
+    pub fn new(media_source_id: &str, method: PlayMethod) -> Self {
+        let nanos = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_nanos()).unwrap_or(0);
+        Self {
+            media_source_id: media_source_id.to_string(),
+            play_session_id: format!("bova-{:x}", nanos),
+            method,
+        }
+    }
+}
+
+
+/// PlaybackInfo 返回的一个 MediaSource（只取协商需要的字段）
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct MediaSource {
+    pub id: String,
+    pub name: Option<String>,
+    pub container: Option<String>,
+    pub bitrate: Option<u64>,
+    #[serde(default)]
+    pub supports_direct_play: bool,
+    #[serde(default)]
+    pub supports_direct_stream: bool,
+    #[serde(default)]
+    pub supports_transcoding: bool,
+    pub direct_stream_url: Option<String>,
+    pub transcoding_url: Option<String>,
+    #[serde(default)]
+    pub media_streams: Vec<MediaStream>,
+}
+
+/// MediaSource 中的一条流（视频 / 音频 / 字幕）
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct MediaStream {
+    pub index: i64,
+    #[serde(rename = "Type")]
+    pub stream_type: String, // "Video", "Audio", "Subtitle"
+    pub codec: Option<String>,
+    pub language: Option<String>,
+    pub title: Option<String>,
+    pub display_title: Option<String>,
+    #[serde(default)]
+    pub is_external: bool,
+    #[serde(default)]
+    pub is_default: bool,
+    #[serde(default)]
+    pub is_forced: bool,
+    #[serde(default)]
+    pub is_text_subtitle_stream: bool,
+    pub delivery_url: Option<String>,
+}
+
+impl MediaStream {
+    /// 下载时请求的格式：ASS/SSA 保留样式，其余文本字幕由服务器转为 SRT
+    pub(crate) fn subtitle_format(&self) -> &'static str {
+        match self.codec.as_deref().map(|c| c.to_ascii_lowercase()).as_deref() {
+            Some("ass") => "ass",
+            Some("ssa") => "ssa",
+            Some("vtt") | Some("webvtt") => "vtt",
+            _ => "srt",
+        }
+    }
+}
+
+impl MediaSource {
+    /// 服务器可提供下载的字幕流（外挂字幕，以及可转换为文本的内封字幕）
+    pub fn subtitle_streams(&self) -> impl Iterator<Item = &MediaStream> {
+        self.media_streams.iter()
+            .filter(|st| st.stream_type == "Subtitle" && (st.is_external || st.is_text_subtitle_stream))
+    }
+
+    /// 默认或强制的外挂字幕，播放开始时自动加载
+    pub fn default_external_subtitle(&self) -> Option<i64> {
+        self.media_streams.iter()
+            .find(|st| st.stream_type == "Subtitle" && st.is_external && (st.is_default || st.is_forced))
+            .map(|st| st.index)
+    }
+}
+
+/// 首页：媒体库与继续观看
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct EmbyDashboard {
+    pub views: Vec<EmbyItem>,
+    pub resume_items: Vec<EmbyItem>,
+}
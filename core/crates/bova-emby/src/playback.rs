@@ -0,0 +1,145 @@
+//! Playback negotiation: the DeviceProfile sent to PlaybackInfo and the
+//! choice between direct play, direct stream and transcoding.
+
+use serde::{Deserialize, Serialize};
+use std::sync::OnceLock;
+
+use crate::models::CLIENT_NAME;
+use crate::{EmbyError, EmbyServer, MediaSource, PlayMethod, PlaySession};
+
+/// /Items/{id}/PlaybackInfo 的响应
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub(crate) struct PlaybackInfoResponse {
+    #[serde(default)]
+    media_sources: Vec<MediaSource>,
+    play_session_id: Option<String>,
+    error_code: Option<String>,
+}
+
+/// 协商结果：实际播放地址与会话信息
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StreamSelection {
+    pub url: String,
+    pub session: PlaySession,
+    pub source: MediaSource,
+}
+
+/// 码率上限选项（bps），None 表示不限
+pub const BITRATE_PRESETS: &[Option<u64>] = &[
+    None,
+    Some(60_000_000),
+    Some(40_000_000),
+    Some(20_000_000),
+    Some(10_000_000),
+    Some(8_000_000),
+    Some(4_000_000),
+    Some(2_000_000),
+    Some(1_000_000),
+];
+
+/// 未设定上限时告诉服务器的码率（足够直接播放任何本地文件）
+pub const UNLIMITED_BITRATE: u64 = 200_000_000;
+
+pub fn format_bitrate(bitrate: Option<u64>) -> String {
+    match bitrate {
+        None => "不限".to_string(),
+        Some(b) if b >= 1_000_000 => format!("{} Mbps", b / 1_000_000),
+        Some(b) => format!("{} kbps", b / 1000),
+    }
+}
+
+fn decoder_capabilities() -> &'static bova_probe::DecoderCapabilities {
+    static CAPS: OnceLock<bova_probe::DecoderCapabilities> = OnceLock::new();
+    CAPS.get_or_init(bova_probe::decoder_capabilities)
+}
+
+/// 根据本地解码能力生成 DeviceProfile：能解的编码直接播放，否则转码为 HLS h264/aac
+pub fn device_profile(max_bitrate: u64) -> serde_json::Value {
+    let caps = decoder_capabilities();
+    let video_codecs = caps.video.join(",");
+    let audio_codecs = caps.audio.join(",");
+    let containers = "mkv,mp4,m4v,mov,avi,webm,ts,m2ts,mpegts,flv,wmv,asf,mpeg,mpg,ogg,3gp";
+    let audio_containers = "mp3,flac,m4a,aac,ogg,oga,opus,wav,wma,ape,webma";
+
+    let mut subtitle_profiles = Vec::new();
+    for format in ["srt", "subrip", "ass", "ssa", "vtt", "webvtt", "sub"] {
+        subtitle_profiles.push(serde_json::json!({ "Format": format, "Method": "External" }));
+        subtitle_profiles.push(serde_json::json!({ "Format": format, "Method": "Embed" }));
+    }
+    for format in ["pgssub", "pgs", "dvdsub", "dvbsub", "mov_text"] {
+        subtitle_profiles.push(serde_json::json!({ "Format": format, "Method": "Embed" }));
+    }
+
+    serde_json::json!({
+        "Name": CLIENT_NAME,
+        "MaxStreamingBitrate": max_bitrate,
+        "MaxStaticBitrate": max_bitrate,
+        "MusicStreamingTranscodingBitrate": 320_000,
+        "DirectPlayProfiles": [
+            { "Type": "Video", "Container": containers, "VideoCodec": video_codecs, "AudioCodec": audio_codecs },
+            { "Type": "Audio", "Container": audio_containers, "AudioCodec": audio_codecs },
+        ],
+        "TranscodingProfiles": [
+            {
+                "Type": "Video", "Container": "ts", "Protocol": "hls", "Context": "Streaming",
+                "VideoCodec": "h264", "AudioCodec": "aac,mp3,ac3",
+                "MaxAudioChannels": "6", "BreakOnNonKeyFrames": true,
+            },
+            { "Type": "Audio", "Container": "mp3", "Protocol": "http", "Context": "Streaming", "AudioCodec": "mp3" },
+        ],
+        "ContainerProfiles": [],
+        "CodecProfiles": [],
+        "SubtitleProfiles": subtitle_profiles,
+    })
+}
+
+/// 在服务器返回的 MediaSources 中选择：直接播放 > 直接串流 > 转码
+pub(crate) fn select_source(server: &EmbyServer, item_id: &str, info: PlaybackInfoResponse, max_bitrate: u64) -> Result<StreamSelection, EmbyError> {
+    let token = server.access_token.clone().unwrap_or_default();
+    let play_session_id = info.play_session_id.clone()
+        .unwrap_or_else(|| PlaySession::new(item_id, PlayMethod::DirectPlay).play_session_id);
+    let within_cap = |s: &MediaSource| s.bitrate.is_none_or(|b| b <= max_bitrate);
+    let with_key = |url: String| {
+        if url.contains("api_key=") || url.contains("ApiKey=") {
+            url
+        } else {
+            format!("{}{}api_key={}", url, if url.contains('?') { "&" } else { "?" }, token)
+        }
+    };
+
+    let direct = info.media_sources.iter()
+        .find(|s| s.supports_direct_play && within_cap(s))
+        .map(|s| (s, PlayMethod::DirectPlay))
+        .or_else(|| info.media_sources.iter()
+            .find(|s| s.supports_direct_stream && within_cap(s))
+            .map(|s| (s, PlayMethod::DirectStream)));
+    if let Some((source, method)) = direct {
+        let url = match source.direct_stream_url.as_deref().filter(|_| method == PlayMethod::DirectStream) {
+            Some(path) => with_key(format!("{}{}", server.base_url(), path)),
+            None => format!(
+                "{}/Videos/{}/stream?static=true&MediaSourceId={}&PlaySessionId={}&api_key={}",
+                server.base_url(), item_id, source.id, play_session_id, token
+            ),
+        };
+        return Ok(StreamSelection {
+            url,
+            session: PlaySession { media_source_id: source.id.clone(), play_session_id, method },
+            source: source.clone(),
+        });
+    }
+
+    if let Some(source) = info.media_sources.iter().find(|s| s.supports_transcoding && s.transcoding_url.is_some()) {
+        let path = source.transcoding_url.clone().unwrap_or_default();
+        return Ok(StreamSelection {
+            url: with_key(format!("{}{}", server.base_url(), path)),
+            session: PlaySession { media_source_id: source.id.clone(), play_session_id, method: PlayMethod::Transcode },
+            source: source.clone(),
+        });
+    }
+
+    match info.error_code {
+        Some(code) => Err(EmbyError::PlaybackRefused(code)),
+        None => Err(EmbyError::NoPlayableSource),
+    }
+}
@@ -0,0 +1,199 @@
+//! Library queries: sorting, filters and server-side paging.
+
+use serde::{Deserialize, Serialize};
+
+use crate::EmbyItem;
+
+/// 排序字段（对应 SortBy 参数）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum SortField {
+    #[default]
+    DateCreated,
+    SortName,
+    PremiereDate,
+    CommunityRating,
+    DatePlayed,
+    Runtime,
+}
+
+impl SortField {
+    pub const ALL: [SortField; 6] = [
+        SortField::DateCreated,
+        SortField::SortName,
+        SortField::PremiereDate,
+        SortField::CommunityRating,
+        SortField::DatePlayed,
+        SortField::Runtime,
+    ];
+
+    fn as_param(&self) -> &'static str {
+        match self {
+            SortField::DateCreated => "DateCreated,SortName",
+            SortField::SortName => "SortName",
+            SortField::PremiereDate => "PremiereDate,ProductionYear,SortName",
+            SortField::CommunityRating => "CommunityRating,SortName",
+            SortField::DatePlayed => "DatePlayed,SortName",
+            SortField::Runtime => "Runtime,SortName",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            SortField::DateCreated => "加入日期",
+            SortField::SortName => "名称",
+            SortField::PremiereDate => "上映日期",
+            SortField::CommunityRating => "评分",
+            SortField::DatePlayed => "播放日期",
+            SortField::Runtime => "时长",
+        }
+    }
+}
+
+/// 媒体库查询条件；分页由服务器完成（StartIndex / Limit）。
+/// 反序列化时缺省字段取默认值，便于 FFI 只传需要的条件
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ItemQuery {
+    pub parent_id: Option<String>,
+    pub recursive: bool,
+    pub include_item_types: Option<String>,
+    pub search_term: Option<String>,
+    pub genres: Vec<String>,
+    pub years: Vec<i32>,
+    pub min_community_rating: Option<f32>,
+    /// Some(true) 只看已播放，Some(false) 只看未播放
+    pub played: Option<bool>,
+    pub favorites_only: bool,
+    pub sort_by: SortField,
+    pub descending: bool,
+    pub start_index: usize,
+    pub limit: usize,
+}
+
+impl Default for ItemQuery {
+    fn default() -> Self {
+        Self {
+            parent_id: None,
+            recursive: true,
+            include_item_types: None,
+            search_term: None,
+            genres: Vec::new(),
+            years: Vec::new(),
+            min_community_rating: None,
+            played: None,
+            favorites_only: false,
+            sort_by: SortField::default(),
+            descending: true,
+            start_index: 0,
+            limit: 20,
+        }
+    }
+}
+
+impl ItemQuery {
+    /// 浏览某个目录：递归时只列出影片和剧集（跳过空文件夹）
+    pub fn browse(parent_id: &str, recursive: bool) -> Self {
+        Self {
+            parent_id: Some(parent_id.to_string()),
+            recursive,
+            include_item_types: recursive.then(|| "Movie,Series".to_string()),
+            ..Default::default()
+        }
+    }
+
+    /// 是否设置了筛选条件（不含排序与分页）
+    pub fn has_filters(&self) -> bool {
+        !self.genres.is_empty()
+            || !self.years.is_empty()
+            || self.min_community_rating.is_some()
+            || self.played.is_some()
+            || self.favorites_only
+    }
+
+    pub fn clear_filters(&mut self) {
+        self.genres.clear();
+        self.years.clear();
+        self.min_community_rating = None;
+        self.played = None;
+        self.favorites_only = false;
+    }
+
+    pub fn to_query_string(&self) -> String {
+        let enc = |v: &str| percent_encoding::utf8_percent_encode(v, percent_encoding::NON_ALPHANUMERIC).to_string();
+        let mut q = vec![
+            format!("SortBy={}", self.sort_by.as_param()),
+            format!("SortOrder={}", if self.descending { "Descending" } else { "Ascending" }),
+            format!("StartIndex={}", self.start_index),
+            format!("Limit={}", self.limit),
+            format!("Recursive={}", self.recursive),
+            format!("Fields={}", ITEM_FIELDS),
+            "EnableTotalRecordCount=true".to_string(),
+        ];
+        if let Some(pid) = &self.parent_id {
+            q.push(format!("ParentId={}", pid));
+        }
+        if let Some(types) = &self.include_item_types {
+            q.push(format!("IncludeItemTypes={}", types));
+        }
+        if let Some(term) = self.search_term.as_deref().filter(|t| !t.is_empty()) {
+            q.push(format!("SearchTerm={}", enc(term)));
+        }
+        if !self.genres.is_empty() {
+            q.push(format!("Genres={}", enc(&self.genres.join("|"))));
+        }
+        if !self.years.is_empty() {
+            let years: Vec<String> = self.years.iter().map(|y| y.to_string()).collect();
+            q.push(format!("Years={}", years.join(",")));
+        }
+        if let Some(rating) = self.min_community_rating {
+            q.push(format!("MinCommunityRating={}", rating));
+        }
+        let mut filters = Vec::new();
+        match self.played {
+            Some(true) => filters.push("IsPlayed"),
+            Some(false) => filters.push("IsUnplayed"),
+            None => {}
+        }
+        if self.favorites_only {
+            filters.push("IsFavorite");
+        }
+        if !filters.is_empty() {
+            q.push(format!("Filters={}", filters.join(",")));
+        }
+        q.join("&")
+    }
+}
+
+/// 一页查询结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ItemPage {
+    pub items: Vec<EmbyItem>,
+    pub total: usize,
+    pub query: ItemQuery,
+}
+
+/// 年份输入的有效范围；范围输入被截到此区间内，展开后最多 301 个年份
+const YEAR_RANGE: std::ops::RangeInclusive<i32> = 1800..=2100;
+
+/// 解析 "2020" / "2018,2020" / "2010-2019" 形式的年份输入。
+/// 超出 `YEAR_RANGE` 的年份被忽略，范围的端点截到 `YEAR_RANGE` 内；结果去重
+pub fn parse_years(input: &str) -> Vec<i32> {
+    let (min, max) = (*YEAR_RANGE.start(), *YEAR_RANGE.end());
+    let mut years = Vec::new();
+    for part in input.split([',', '，', ' ']).map(str::trim).filter(|p| !p.is_empty()) {
+        match part.split_once('-') {
+            Some((a, b)) => {
+                if let (Ok(a), Ok(b)) = (a.trim().parse::<i32>(), b.trim().parse::<i32>()) {
+                    let (from, to) = (a.min(b).max(min), a.max(b).min(max));
+                    years.extend(from..=to);
+                }
+            }
+            None => years.extend(part.parse::<i32>().ok().filter(|y| YEAR_RANGE.contains(y))),
+        }
+    }
+    let mut seen = std::collections::HashSet::new();
+    years.retain(|y| seen.insert(*y));
+    years
+}
+
+const ITEM_FIELDS: &str = "Overview,PrimaryImageAspectRatio,ProductionYear,CommunityRating,OfficialRating,BackdropImageTags,ChildCount,RecursiveItemCount";
@@ -0,0 +1,84 @@
+//! `EmbyApi` against a mock HTTP server: login, typed responses and which
+//! requests are retried.
+
+mod common;
+
+use bova_emby::blocking::BlockingEmbyApi;
+use bova_emby::{EmbyError, EmbyServer, PlayMethod, PlaySession, ReportEvent, ServerKind};
+use common::{count, server, start};
+
+const ITEMS: &str = r#"{"Items":[{"Id":"i1","Name":"Movie","Type":"Movie"}],"TotalRecordCount":1}"#;
+
+#[test]
+fn authenticate_detects_jellyfin_and_stores_token() {
+    let (url, mock) = start(&[
+        ("GET /System/Info/Public", &[(200, r#"{"ProductName":"Jellyfin Server","ServerName":"home","Version":"10.9.6"}"#)]),
+        ("POST /Users/AuthenticateByName", &[(200, r#"{"AccessToken":"secret","User":{"Id":"u42"}}"#)]),
+    ]);
+    let api = BlockingEmbyApi::new().unwrap();
+    let login = EmbyServer { access_token: None, user_id: None, ..server(&url) };
+    let server = api.block_on(api.inner().authenticate(login, "pw")).unwrap();
+
+    assert_eq!(server.kind, ServerKind::Jellyfin);
+    assert_eq!(server.name, "home");
+    assert_eq!(server.access_token.as_deref(), Some("secret"));
+    assert_eq!(server.user_id.as_deref(), Some("u42"));
+    let log = mock.lock().unwrap().log.clone();
+    let (_, headers) = log.iter().find(|(r, _)| r.starts_with("POST")).unwrap();
+    assert!(headers["authorization"].starts_with("MediaBrowser Client=\"BovaPlayer\""));
+}
+
+#[test]
+fn get_requests_retry_server_errors() {
+    let (url, mock) = start(&[("GET /Users/u1/Items", &[(503, ""), (502, ""), (200, ITEMS)])]);
+    let api = BlockingEmbyApi::new().unwrap();
+    let items = api.block_on(api.inner().season_episodes(&server(&url), "s1")).unwrap();
+
+    assert_eq!(items.len(), 1);
+    assert_eq!(items[0].id, "i1");
+    assert_eq!(count(&mock, "GET /Users/u1/Items"), 3);
+    let log = mock.lock().unwrap().log.clone();
+    assert_eq!(log[0].1["x-emby-token"], "tok");
+}
+
+#[test]
+fn client_errors_are_not_retried() {
+    let (url, mock) = start(&[("GET /Users/u1/Items", &[(401, "")])]);
+    let api = BlockingEmbyApi::new().unwrap();
+    let err = api.block_on(api.inner().season_episodes(&server(&url), "s1")).unwrap_err();
+
+    assert_eq!(err, EmbyError::Unauthorized);
+    assert_eq!(count(&mock, "GET /Users/u1/Items"), 1);
+}
+
+#[test]
+fn posts_are_sent_once() {
+    let (url, mock) = start(&[
+        ("POST /Sessions/Playing", &[(503, "")]),
+        ("POST /Items/i1/PlaybackInfo", &[(500, "")]),
+        ("POST /Users/AuthenticateByName", &[(502, "")]),
+        ("GET /System/Info/Public", &[(200, r#"{"ProductName":"Emby Server","Version":"4.8.0"}"#)]),
+    ]);
+    let api = BlockingEmbyApi::new().unwrap();
+    let server = server(&url);
+
+    let session = PlaySession::new("src1", PlayMethod::DirectPlay);
+    let report = api.block_on(api.inner().report(&server, "i1", &session, ReportEvent::Start { position_ms: 0, paused: false }));
+    assert_eq!(report, Err(EmbyError::Server(503)));
+    assert_eq!(count(&mock, "POST /Sessions/Playing"), 1);
+
+    let info = api.block_on(api.inner().playback_info(&server, "i1", None));
+    assert_eq!(info.err(), Some(EmbyError::Server(500)));
+    assert_eq!(count(&mock, "POST /Items/i1/PlaybackInfo"), 1);
+
+    let login = api.block_on(api.inner().authenticate(server.clone(), "pw"));
+    assert_eq!(login.err(), Some(EmbyError::Server(502)));
+    assert_eq!(count(&mock, "POST /Users/AuthenticateByName"), 1);
+}
+
+#[test]
+fn unknown_server_is_reported() {
+    let (url, _) = start(&[("GET /System/Info/Public", &[(200, "<html>router login</html>")])]);
+    let api = BlockingEmbyApi::new().unwrap();
+    assert_eq!(api.block_on(api.inner().server_info(&url)).err(), Some(EmbyError::NotMediaServer));
+}
@@ -0,0 +1,98 @@
+//! Mock Emby / Jellyfin HTTP server for the API tests.
+
+#![allow(dead_code)]
+
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use bova_emby::{EmbyServer, ServerKind};
+
+/// Canned responses per `METHOD /path` (query ignored). Responses are used in
+/// order; the last one repeats.
+#[derive(Default)]
+pub struct Mock {
+    routes: HashMap<String, Vec<(u16, String)>>,
+    /// `METHOD /path?query` and headers of every request
+    pub log: Vec<(String, HashMap<String, String>)>,
+}
+
+pub fn start(routes: &[(&str, &[(u16, &str)])]) -> (String, Arc<Mutex<Mock>>) {
+    let mock = Mock {
+        routes: routes
+            .iter()
+            .map(|(route, resps)| (route.to_string(), resps.iter().map(|&(s, b)| (s, b.to_string())).collect()))
+            .collect(),
+        log: Vec::new(),
+    };
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    let mock = Arc::new(Mutex::new(mock));
+    let shared = mock.clone();
+    thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            let mock = shared.clone();
+            thread::spawn(move || serve(stream, &mock));
+        }
+    });
+    (url, mock)
+}
+
+fn serve(mut stream: TcpStream, mock: &Mutex<Mock>) {
+    let mut reader = BufReader::new(stream.try_clone().unwrap());
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line).unwrap_or(0) == 0 {
+            return;
+        }
+        let mut parts = line.split_whitespace();
+        let request = format!("{} {}", parts.next().unwrap_or(""), parts.next().unwrap_or(""));
+        let mut headers = HashMap::new();
+        loop {
+            let mut header = String::new();
+            if reader.read_line(&mut header).unwrap_or(0) == 0 {
+                return;
+            }
+            let Some((name, value)) = header.trim().split_once(':') else { break };
+            headers.insert(name.to_ascii_lowercase(), value.trim().to_string());
+        }
+        let body_len = headers.get("content-length").map_or(0, |v| v.parse().unwrap());
+        reader.read_exact(&mut vec![0; body_len]).unwrap();
+
+        let (status, body) = {
+            let mut mock = mock.lock().unwrap();
+            mock.log.push((request.clone(), headers));
+            let route = request.split('?').next().unwrap();
+            match mock.routes.get_mut(route) {
+                Some(resps) if resps.len() > 1 => resps.remove(0),
+                Some(resps) => resps[0].clone(),
+                None => (404, String::new()),
+            }
+        };
+        let head = format!(
+            "HTTP/1.1 {status} X\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n",
+            body.len()
+        );
+        let _ = stream.write_all(head.as_bytes());
+        let _ = stream.write_all(body.as_bytes());
+    }
+}
+
+pub fn count(mock: &Mutex<Mock>, route: &str) -> usize {
+    mock.lock().unwrap().log.iter().filter(|(r, _)| r.split('?').next() == Some(route)).count()
+}
+
+pub fn server(url: &str) -> EmbyServer {
+    EmbyServer {
+        name: "test".into(),
+        url: url.into(),
+        username: "alice".into(),
+        user_id: Some("u1".into()),
+        access_token: Some("tok".into()),
+        kind: ServerKind::Emby,
+        version: None,
+        max_bitrate: None,
+    }
+}
@@ -0,0 +1,127 @@
+//! Captured server responses (`tests/fixtures`) deserialized into the models,
+//! served through the mock server so the real request path is exercised.
+
+mod common;
+
+use bova_emby::blocking::BlockingEmbyApi;
+use bova_emby::{ItemQuery, PlayMethod, ServerInfo, ServerKind, TICKS_PER_MS};
+use common::{server, start};
+
+const ITEMS: &str = include_str!("fixtures/items.json");
+const RESUME: &str = include_str!("fixtures/resume.json");
+const PLAYBACK_INFO: &str = include_str!("fixtures/playback_info.json");
+const SYSTEM_INFO: &str = include_str!("fixtures/system_info.json");
+
+#[test]
+fn items_page() {
+    let (url, _) = start(&[("GET /Users/u1/Items", &[(200, ITEMS)])]);
+    let api = BlockingEmbyApi::new().unwrap();
+    let page = api.block_on(api.inner().items(&server(&url), &ItemQuery::browse("40001", true))).unwrap();
+
+    assert_eq!(page.total, 214);
+    assert_eq!(page.items.len(), 2);
+    let movie = &page.items[0];
+    assert_eq!(movie.id, "40113");
+    assert_eq!(movie.field_type.as_deref(), Some("Movie"));
+    assert_eq!(movie.media_type.as_deref(), Some("Video"));
+    assert_eq!(movie.run_time_ticks, Some(98_880_000_000));
+    assert_eq!(movie.production_year, Some(2017));
+    assert_eq!(movie.community_rating, Some(7.5));
+    assert_eq!(movie.genres, ["Science Fiction", "Drama"]);
+    assert_eq!(movie.image_tags.as_ref().unwrap()["Primary"], "8a1b2c3d4e5f");
+    assert_eq!(movie.backdrop_image_tags.as_deref(), Some(&["0f1e2d3c4b5a".to_string()][..]));
+    let user_data = movie.user_data.as_ref().unwrap();
+    assert_eq!((user_data.played, user_data.is_favorite, user_data.play_count), (Some(true), Some(true), Some(1)));
+    assert_eq!(movie.resume_position_ms(), None, "zero position is not resumable");
+
+    let series = &page.items[1];
+    assert_eq!(series.field_type.as_deref(), Some("Series"));
+    assert_eq!((series.child_count, series.recursive_item_count), (Some(3), Some(26)));
+    assert!(series.media_sources.is_empty() && series.people.is_empty());
+}
+
+#[test]
+fn resume_items() {
+    let (url, mock) = start(&[("GET /Users/u1/Items/Resume", &[(200, RESUME)])]);
+    let api = BlockingEmbyApi::new().unwrap();
+    let items = api.block_on(api.inner().resume_items(&server(&url), 12)).unwrap();
+
+    assert_eq!(mock.lock().unwrap().log.len(), 1);
+    let episode = &items[0];
+    assert_eq!(episode.name, "Secrets");
+    assert_eq!(episode.series_name.as_deref(), Some("Dark"));
+    assert_eq!(episode.original_title, None);
+    assert_eq!((episode.parent_index_number, episode.index_number), (Some(1), Some(1)));
+    assert_eq!(episode.parent_id.as_deref(), Some("51873"));
+    assert_eq!(episode.resume_position_ms(), Some(13_797_480_000 / TICKS_PER_MS));
+    let played = episode.user_data.as_ref().unwrap().played_percentage.unwrap();
+    assert!((played - 43.77).abs() < 0.01);
+}
+
+#[test]
+fn playback_info_direct_play() {
+    let (url, _) = start(&[("POST /Items/40113/PlaybackInfo", &[(200, PLAYBACK_INFO)])]);
+    let api = BlockingEmbyApi::new().unwrap();
+    let selection = api.block_on(api.inner().playback_info(&server(&url), "40113", None)).unwrap();
+
+    let source = &selection.source;
+    assert_eq!(selection.session.method, PlayMethod::DirectPlay);
+    assert_eq!(selection.session.play_session_id, "2b8c9f0e1d2a4b3c");
+    assert_eq!(selection.session.media_source_id, "6c1f9d2e8a7b4c3d5e6f7a8b9c0d1e2f");
+    assert_eq!(
+        selection.url,
+        format!("{url}/Videos/40113/stream?static=true&MediaSourceId={}&PlaySessionId=2b8c9f0e1d2a4b3c&api_key=tok", source.id)
+    );
+    assert_eq!(source.container.as_deref(), Some("mkv"));
+    assert_eq!(source.bitrate, Some(49_300_000));
+    assert_eq!(source.media_streams.len(), 4);
+
+    let video = &source.media_streams[0];
+    assert_eq!((video.stream_type.as_str(), video.codec.as_deref()), ("Video", Some("hevc")));
+    let subtitles: Vec<i64> = source.subtitle_streams().map(|st| st.index).collect();
+    assert_eq!(subtitles, [2, 3]);
+    assert_eq!(source.default_external_subtitle(), Some(3));
+    assert!(source.media_streams[3].delivery_url.as_deref().unwrap().starts_with("/Videos/40113/"));
+}
+
+#[test]
+fn playback_info_over_bitrate_cap_transcodes() {
+    let (url, _) = start(&[("POST /Items/40113/PlaybackInfo", &[(200, PLAYBACK_INFO)])]);
+    let api = BlockingEmbyApi::new().unwrap();
+    let selection = api.block_on(api.inner().playback_info(&server(&url), "40113", Some(20_000_000))).unwrap();
+
+    assert_eq!(selection.session.method, PlayMethod::Transcode);
+    assert!(selection.url.starts_with(&format!("{url}/videos/40113/master.m3u8?")), "{}", selection.url);
+    assert_eq!(selection.url.matches("api_key=").count(), 1, "the server's api_key is kept");
+}
+
+#[test]
+fn system_info() {
+    let json: serde_json::Value = serde_json::from_str(SYSTEM_INFO).unwrap();
+    let info = ServerInfo::from_public_info(&json);
+    assert_eq!(info.kind, ServerKind::Jellyfin, "no ProductName, 10.x version");
+    assert_eq!(info.name.as_deref(), Some("living-room"));
+    assert_eq!(info.version.as_deref(), Some("10.9.11"));
+
+    let (url, _) = start(&[("GET /System/Info/Public", &[(200, SYSTEM_INFO)])]);
+    let api = BlockingEmbyApi::new().unwrap();
+    let served = api.block_on(api.inner().server_info(&url)).unwrap();
+    assert_eq!((served.kind, served.version), (ServerKind::Jellyfin, Some("10.9.11".to_string())));
+}
+
+#[test]
+fn search_hints_keep_the_series_apart_from_the_original_title() {
+    let hints = r#"{"SearchHints":[
+        {"ItemId":"e1","Name":"Secrets","Type":"Episode","Series":"Dark","IndexNumber":1,"ParentIndexNumber":1},
+        {"ItemId":"m1","Name":"Arrival","Type":"Movie","ProductionYear":2016}
+    ],"TotalRecordCount":2}"#;
+    let (url, _) = start(&[("GET /Search/Hints", &[(200, hints)])]);
+    let api = BlockingEmbyApi::new().unwrap();
+    let query = ItemQuery { search_term: Some("dark".into()), ..ItemQuery::default() };
+    let page = api.block_on(api.inner().search_hints(&server(&url), &query)).unwrap();
+
+    assert_eq!(page.total, 2);
+    assert_eq!(page.items[0].series_name.as_deref(), Some("Dark"));
+    assert_eq!(page.items[0].original_title, None);
+    assert_eq!(page.items[1].series_name, None);
+}
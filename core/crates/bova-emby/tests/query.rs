@@ -0,0 +1,29 @@
+//! Year filter input parsing.
+
+use bova_emby::parse_years;
+
+#[test]
+fn single_years_lists_and_ranges() {
+    assert_eq!(parse_years("2020"), [2020]);
+    assert_eq!(parse_years("2018, 2020，2022"), [2018, 2020, 2022]);
+    assert_eq!(parse_years("2010-2013"), [2010, 2011, 2012, 2013]);
+    assert_eq!(parse_years("2013-2010"), [2010, 2011, 2012, 2013], "reversed range");
+    assert_eq!(parse_years("2011,2010-2012,2012"), [2011, 2010, 2012], "duplicates dropped");
+}
+
+#[test]
+fn oversized_ranges_are_clamped() {
+    let years = parse_years("1-2000000000");
+    assert_eq!(years.len(), 301);
+    assert_eq!((years[0], years[300]), (1800, 2100));
+    assert_eq!(parse_years("2099-99999"), [2099, 2100]);
+    assert!(parse_years("3000-4000").is_empty(), "entirely outside the window");
+    assert!(parse_years("99999, 1200, -5").is_empty());
+}
+
+#[test]
+fn garbage_is_ignored() {
+    assert!(parse_years("").is_empty());
+    assert!(parse_years("abc, 20x0, 2010-, -, 2010-abc").is_empty());
+    assert_eq!(parse_years("foo 1999 bar"), [1999]);
+}
@@ -0,0 +1,126 @@
+//! Emby / Jellyfin browsing for host apps.
+//!
+//! 所有函数同步执行并返回 JSON 字符串（由 `bova_string_free` 释放）：
+//! 成功为 `{"ok":true,"data":...}`，失败为 `{"ok":false,"error":"...","retryable":bool}`。
+//! `server_json` 为登录返回的服务器对象（含 access_token / user_id），由调用方保存。
+
+use libc::{c_char, c_int};
+use std::ffi::{CStr, CString};
+
+use bova_emby::blocking::BlockingEmbyApi;
+use bova_emby::{EmbyError, EmbyServer, ItemQuery};
+
+lazy_static::lazy_static! {
+    static ref EMBY: Option<BlockingEmbyApi> = BlockingEmbyApi::new()
+        .map_err(|e| log::warn!("failed to start Emby runtime: {}", e))
+        .ok();
+}
+
+pub(crate) fn arg(ptr: *const c_char) -> Option<String> {
+    if ptr.is_null() { return None; }
+    unsafe { CStr::from_ptr(ptr).to_str().ok().map(|s| s.to_string()) }
+}
+
+pub(crate) fn into_c(value: serde_json::Value) -> *mut c_char {
+    CString::new(value.to_string()).unwrap_or_default().into_raw()
+}
+
+pub(crate) fn error_json(message: &str, retryable: bool) -> *mut c_char {
+    into_c(serde_json::json!({ "ok": false, "error": message, "retryable": retryable }))
+}
+
+fn respond<T: serde::Serialize>(result: Result<T, EmbyError>) -> *mut c_char {
+    match result.map(|data| serde_json::to_value(data)) {
+        Ok(Ok(data)) => into_c(serde_json::json!({ "ok": true, "data": data })),
+        Ok(Err(e)) => error_json(&e.to_string(), false),
+        Err(e) => error_json(&e.to_string(), e.is_retryable()),
+    }
+}
+
+/// 解析 server_json 并在内部运行时上执行请求
+fn with_server<T, F>(server_json: *const c_char, f: F) -> *mut c_char
+where
+    T: serde::Serialize,
+    F: FnOnce(&BlockingEmbyApi, EmbyServer) -> Result<T, EmbyError>,
+{
+    let Some(api) = EMBY.as_ref() else { return error_json("Emby runtime unavailable", false) };
+    let server = match arg(server_json).map(|s| serde_json::from_str::<EmbyServer>(&s)) {
+        Some(Ok(server)) => server,
+        Some(Err(e)) => return error_json(&format!("invalid server json: {}", e), false),
+        None => return error_json("server json is null", false),
+    };
+    respond(f(api, server))
+}
+
+/// 登录；data 为需要保存的服务器对象
+#[no_mangle]
+pub extern "C" fn bova_emby_login(url: *const c_char, username: *const c_char, password: *const c_char) -> *mut c_char {
+    let Some(api) = EMBY.as_ref() else { return error_json("Emby runtime unavailable", false) };
+    let (Some(url), Some(username)) = (arg(url), arg(username)) else {
+        return error_json("url and username are required", false);
+    };
+    let server = EmbyServer {
+        name: url.clone(),
+        url,
+        username,
+        user_id: None,
+        access_token: None,
+        kind: Default::default(),
+        version: None,
+        max_bitrate: None,
+    };
+    let password = arg(password).unwrap_or_default();
+    respond(api.block_on(api.inner().authenticate(server, &password)))
+}
+
+/// 首页：媒体库与继续观看
+#[no_mangle]
+pub extern "C" fn bova_emby_dashboard(server_json: *const c_char) -> *mut c_char {
+    with_server(server_json, |api, server| api.block_on(api.inner().dashboard(&server)))
+}
+
+/// 目录内容；parent_id 为 null 时返回媒体库列表
+#[no_mangle]
+pub extern "C" fn bova_emby_children(server_json: *const c_char, parent_id: *const c_char, recursive: c_int) -> *mut c_char {
+    let parent_id = arg(parent_id);
+    with_server(server_json, |api, server| {
+        api.block_on(api.inner().children(&server, parent_id.as_deref(), recursive != 0))
+    })
+}
+
+/// 按条件分页查询；query_json 为 ItemQuery（缺省字段取默认值），data 为 ItemPage
+#[no_mangle]
+pub extern "C" fn bova_emby_items(server_json: *const c_char, query_json: *const c_char) -> *mut c_char {
+    let query = match arg(query_json).map(|s| serde_json::from_str::<ItemQuery>(&s)) {
+        Some(Ok(query)) => query,
+        Some(Err(e)) => return error_json(&format!("invalid query json: {}", e), false),
+        None => ItemQuery::default(),
+    };
+    with_server(server_json, |api, server| api.block_on(api.inner().items(&server, &query)))
+}
+
+/// 全库快速搜索，data 为 ItemPage
+#[no_mangle]
+pub extern "C" fn bova_emby_search(server_json: *const c_char, term: *const c_char, start_index: c_int, limit: c_int) -> *mut c_char {
+    let query = ItemQuery {
+        search_term: arg(term),
+        start_index: start_index.max(0) as usize,
+        limit: if limit > 0 { limit as usize } else { ItemQuery::default().limit },
+        ..Default::default()
+    };
+    with_server(server_json, |api, server| api.block_on(api.inner().search_hints(&server, &query)))
+}
+
+/// 单个条目的完整信息（含演职人员、类型与媒体源）
+#[no_mangle]
+pub extern "C" fn bova_emby_item(server_json: *const c_char, item_id: *const c_char) -> *mut c_char {
+    let Some(item_id) = arg(item_id) else { return error_json("item id is null", false) };
+    with_server(server_json, |api, server| api.block_on(api.inner().item(&server, &item_id)))
+}
+
+/// 协商播放方式；data 为 StreamSelection（url、会话与媒体源）
+#[no_mangle]
+pub extern "C" fn bova_emby_playback_info(server_json: *const c_char, item_id: *const c_char) -> *mut c_char {
+    let Some(item_id) = arg(item_id) else { return error_json("item id is null", false) };
+    with_server(server_json, |api, server| api.block_on(api.inner().playback_info(&server, &item_id, None)))
+}
@@ -7,6 +7,10 @@ use std::collections::HashMap;
 
 use bova_core::{create_player, MediaOptions, Player};
 
+mod emby;
+mod logging;
+mod settings;
+
 #[repr(C)]
 pub struct BovaPlayerHandle(*mut c_void);
 
@@ -20,6 +24,7 @@ struct Holder {
 
 #[no_mangle]
 pub extern "C" fn bova_create() -> BovaPlayerHandle {
+    bova_core::logging::init();
     let holder = Box::new(Holder { player: create_player() });
     BovaPlayerHandle(Box::into_raw(holder) as *mut c_void)
 }
@@ -78,6 +83,31 @@ pub extern "C" fn bova_stop(h: BovaPlayerHandle) -> c_int {
     match holder.player.stop() { Ok(_) => 0, Err(_) => -2 }
 }
 
+/// 当前媒体上次记录的续播位置（毫秒），没有时返回 -1
+#[no_mangle]
+pub extern "C" fn bova_get_resume_position(h: BovaPlayerHandle) -> c_longlong {
+    if h.0.is_null() { return -1; }
+    let holder = unsafe { &*(h.0 as *const Holder) };
+    holder.player.resume_position().unwrap_or(-1)
+}
+
+/// 上报实际播放进度，供续播记忆使用
+#[no_mangle]
+pub extern "C" fn bova_update_position(h: BovaPlayerHandle, pos_ms: c_longlong, duration_ms: c_longlong) -> c_int {
+    if h.0.is_null() { return -1; }
+    let holder = unsafe { &mut *(h.0 as *mut Holder) };
+    holder.player.update_position(pos_ms, duration_ms);
+    0
+}
+
+#[no_mangle]
+pub extern "C" fn bova_clear_resume(h: BovaPlayerHandle) -> c_int {
+    if h.0.is_null() { return -1; }
+    let holder = unsafe { &mut *(h.0 as *mut Holder) };
+    holder.player.clear_resume();
+    0
+}
+
 // Helper to get version string (for quick sanity in host bindings)
 #[no_mangle]
 pub extern "C" fn bova_version_string() -> *mut c_char {
@@ -95,10 +125,10 @@ pub extern "C" fn bova_string_free(p: *mut c_char) {
 // Flutter MPV Player FFI API
 // ============================================================================
 
-use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicI32, AtomicU32, Ordering};
 
 #[cfg(feature = "mpv")]
-use bova_playback::{start_mpv_playback_handles, PlaybackConfig, MpvCommand};
+use bova_playback::{start_mpv_playback_handles, NetworkOptions, PlaybackConfig, PlaybackEvent, MpvCommand, QualityMode, ReplayGainMode};
 
 // 全局播放器管理器
 lazy_static::lazy_static! {
@@ -114,6 +144,14 @@ struct PlayerInstance {
     duration: Arc<Mutex<f64>>,
     video_width: Arc<AtomicU32>,
     video_height: Arc<AtomicU32>,
+    /// Buffering fill level 0-100, or -1 when not buffering
+    buffering_pct: Arc<AtomicI32>,
+    /// HLS/DASH variants as a JSON array, "[]" for non-adaptive media
+    variants_json: Arc<Mutex<String>>,
+    /// Active variant index, or -1
+    current_variant: Arc<AtomicI32>,
+    /// Chapters as a JSON array, "[]" when the media has none
+    chapters_json: Arc<Mutex<String>>,
 }
 
 // Flutter-specific API
@@ -126,6 +164,7 @@ pub extern "C" fn bova_flutter_initialize() -> *mut c_char {
 /// 创建新的播放器实例，返回播放器ID（>0表示成功，<=0表示失败）
 #[no_mangle]
 pub extern "C" fn bova_mpv_create_player() -> c_longlong {
+    bova_core::logging::init();
     let player_id = {
         let mut next_id = NEXT_PLAYER_ID.lock().unwrap();
         let id = *next_id;
@@ -141,6 +180,10 @@ pub extern "C" fn bova_mpv_create_player() -> c_longlong {
         duration: Arc::new(Mutex::new(0.0)),
         video_width: Arc::new(AtomicU32::new(0)),
         video_height: Arc::new(AtomicU32::new(0)),
+        buffering_pct: Arc::new(AtomicI32::new(-1)),
+        variants_json: Arc::new(Mutex::new("[]".to_string())),
+        current_variant: Arc::new(AtomicI32::new(-1)),
+        chapters_json: Arc::new(Mutex::new("[]".to_string())),
     };
     
     PLAYERS.lock().unwrap().insert(player_id, instance);
@@ -150,10 +193,25 @@ pub extern "C" fn bova_mpv_create_player() -> c_longlong {
 /// 打开媒体文件/URL
 #[no_mangle]
 pub extern "C" fn bova_mpv_open_media(player_id: c_longlong, url: *const c_char, hwaccel: c_int) -> c_int {
+    open_media(player_id, url, hwaccel, MediaOptions::default())
+}
+
+/// 打开媒体文件/URL，options_json 为 MediaOptions（网络缓存、超时、UA、HTTP 头等）
+#[no_mangle]
+pub extern "C" fn bova_mpv_open_media_with_options(
+    player_id: c_longlong,
+    url: *const c_char,
+    hwaccel: c_int,
+    options_json: *const c_char,
+) -> c_int {
+    open_media(player_id, url, hwaccel, opt_from_json(options_json))
+}
+
+fn open_media(player_id: c_longlong, url: *const c_char, hwaccel: c_int, opts: MediaOptions) -> c_int {
     #[cfg(not(feature = "mpv"))]
     {
-        let _ = (player_id, url, hwaccel);
-        eprintln!("[bova-ffi] MPV feature not enabled");
+        let _ = (player_id, url, hwaccel, opts);
+        log::error!("MPV feature not enabled");
         return -1;
     }
     
@@ -169,6 +227,12 @@ pub extern "C" fn bova_mpv_open_media(player_id: c_longlong, url: *const c_char,
             subtitle_enabled: false,
             subtitle_index: None,
             engine: None,
+            network: NetworkOptions::from(&opts),
+            quality: QualityMode::Auto,
+            start_ms: None,
+            crossfade_ms: 0,
+            replay_gain: ReplayGainMode::Track,
+            audio_track: None,
         };
         
         match start_mpv_playback_handles(&url_str, &config) {
@@ -190,7 +254,7 @@ pub extern "C" fn bova_mpv_open_media(player_id: c_longlong, url: *const c_char,
                 }
             }
             Err(e) => {
-                eprintln!("[bova-ffi] Failed to open media: {}", e);
+                log::error!("Failed to open media: {}", e);
                 -3
             }
         }
@@ -206,6 +270,41 @@ fn process_video_frames(player_id: i64) {
             let players = PLAYERS.lock().unwrap();
             if let Some(instance) = players.get(&player_id) {
                 if let Some(ref handles) = instance.handles {
+                    if let Some(ref event_rx) = handles.event_rx {
+                        while let Ok(event) = event_rx.try_recv() {
+                            match event {
+                                PlaybackEvent::Buffering(pct) if pct < 100 => {
+                                    instance.buffering_pct.store(pct as i32, Ordering::Release);
+                                }
+                                PlaybackEvent::Buffering(_) => {
+                                    instance.buffering_pct.store(-1, Ordering::Release);
+                                }
+                                PlaybackEvent::VariantsAvailable(variants) => {
+                                    if let Ok(json) = serde_json::to_string(&variants) {
+                                        *instance.variants_json.lock().unwrap() = json;
+                                    }
+                                }
+                                PlaybackEvent::VariantChanged(index) => {
+                                    instance.current_variant.store(index as i32, Ordering::Release);
+                                }
+                                PlaybackEvent::Chapters(chapters) => {
+                                    if let Ok(json) = serde_json::to_string(&chapters) {
+                                        *instance.chapters_json.lock().unwrap() = json;
+                                    }
+                                }
+                                PlaybackEvent::TrackChanged(url) => {
+                                    log::info!("continuing with queued item: {}", url);
+                                    *instance.variants_json.lock().unwrap() = "[]".to_string();
+                                    instance.current_variant.store(-1, Ordering::Release);
+                                    *instance.chapters_json.lock().unwrap() = "[]".to_string();
+                                }
+                                PlaybackEvent::Error(err) => {
+                                    log::error!("playback error: {}", err);
+                                }
+                                _ => {}
+                            }
+                        }
+                    }
                     match handles.video_rx.try_recv() {
                         Ok(frame) => {
                             // 更新视频尺寸
@@ -220,8 +319,10 @@ fn process_video_frames(player_id: i64) {
                                 *instance.duration.lock().unwrap() = dur as f64 / 1000.0;
                             }
                             
-                            // 帧数据存储在全局缓存中供Flutter读取
-                            store_latest_frame(player_id, frame);
+                            // 帧数据存储在全局缓存中供Flutter读取；上一帧还没被取走即为丢帧
+                            if store_latest_frame(player_id, frame) {
+                                handles.stats.record_dropped(1);
+                            }
                             true
                         }
                         Err(TryRecvError::Empty) => {
@@ -251,9 +352,9 @@ lazy_static::lazy_static! {
 }
 
 #[cfg(feature = "mpv")]
-fn store_latest_frame(player_id: i64, frame: bova_playback::VideoFrame) {
+fn store_latest_frame(player_id: i64, frame: bova_playback::VideoFrame) -> bool {
     let mut cache = FRAME_CACHE.lock().unwrap();
-    cache.insert(player_id, frame);
+    cache.insert(player_id, frame).is_some()
 }
 
 #[no_mangle]
@@ -408,6 +509,149 @@ pub extern "C" fn bova_mpv_is_playing(player_id: c_longlong) -> c_int {
     }
 }
 
+/// 获取缓冲进度（0-100），未在缓冲时返回 -1
+#[no_mangle]
+pub extern "C" fn bova_mpv_get_buffering(player_id: c_longlong) -> c_int {
+    let players = PLAYERS.lock().unwrap();
+    if let Some(instance) = players.get(&player_id) {
+        instance.buffering_pct.load(Ordering::Acquire) as c_int
+    } else {
+        -1
+    }
+}
+
+/// 获取 HLS/DASH 画质列表（JSON 数组），需用 bova_string_free 释放
+#[no_mangle]
+pub extern "C" fn bova_mpv_get_variants_json(player_id: c_longlong) -> *mut c_char {
+    let players = PLAYERS.lock().unwrap();
+    let json = match players.get(&player_id) {
+        Some(instance) => instance.variants_json.lock().unwrap().clone(),
+        None => "[]".to_string(),
+    };
+    CString::new(json).unwrap_or_default().into_raw()
+}
+
+/// 获取当前画质索引，非自适应流返回 -1
+#[no_mangle]
+pub extern "C" fn bova_mpv_get_current_variant(player_id: c_longlong) -> c_int {
+    let players = PLAYERS.lock().unwrap();
+    if let Some(instance) = players.get(&player_id) {
+        instance.current_variant.load(Ordering::Acquire) as c_int
+    } else {
+        -1
+    }
+}
+
+/// 设置画质：index >= 0 固定为该画质，-1 为自动
+#[no_mangle]
+pub extern "C" fn bova_mpv_set_quality(player_id: c_longlong, index: c_int) -> c_int {
+    #[cfg(not(feature = "mpv"))]
+    {
+        let _ = (player_id, index);
+        return -1;
+    }
+
+    #[cfg(feature = "mpv")]
+    {
+        let mode = if index < 0 { QualityMode::Auto } else { QualityMode::Fixed(index as usize) };
+        let players = PLAYERS.lock().unwrap();
+        if let Some(instance) = players.get(&player_id) {
+            if let Some(ref handles) = instance.handles {
+                if let Some(ref cmd_tx) = handles.cmd_tx {
+                    let _ = cmd_tx.send(MpvCommand::SetQuality(mode));
+                    return 0;
+                }
+            }
+        }
+        -2
+    }
+}
+
+/// 排入当前媒体之后播放的下一项：立即预先打开，当前项结束后无缝衔接；
+/// 再次调用会替换之前排入的项
+#[no_mangle]
+pub extern "C" fn bova_mpv_queue_next(player_id: c_longlong, url: *const c_char) -> c_int {
+    #[cfg(not(feature = "mpv"))]
+    {
+        let _ = (player_id, url);
+        return -1;
+    }
+
+    #[cfg(feature = "mpv")]
+    {
+        if url.is_null() {
+            return -1;
+        }
+        let url_str = unsafe { CStr::from_ptr(url).to_string_lossy().to_string() };
+        let players = PLAYERS.lock().unwrap();
+        if let Some(instance) = players.get(&player_id) {
+            if let Some(ref handles) = instance.handles {
+                if let Some(ref next_tx) = handles.next_tx {
+                    let _ = next_tx.send(url_str);
+                    return 0;
+                }
+            }
+        }
+        -2
+    }
+}
+
+/// 获取章节列表（JSON 数组，元素为 {id, start_ms, end_ms, title}），需用 bova_string_free 释放
+#[no_mangle]
+pub extern "C" fn bova_mpv_get_chapters_json(player_id: c_longlong) -> *mut c_char {
+    let players = PLAYERS.lock().unwrap();
+    let json = match players.get(&player_id) {
+        Some(instance) => instance.chapters_json.lock().unwrap().clone(),
+        None => "[]".to_string(),
+    };
+    CString::new(json).unwrap_or_default().into_raw()
+}
+
+/// 获取播放诊断（JSON 对象，字段见 bova_playback::PlaybackStats），未打开媒体时返回 "{}"，需用 bova_string_free 释放
+#[no_mangle]
+pub extern "C" fn bova_mpv_get_stats_json(player_id: c_longlong) -> *mut c_char {
+    #[cfg(feature = "mpv")]
+    let json = {
+        let players = PLAYERS.lock().unwrap();
+        players
+            .get(&player_id)
+            .and_then(|instance| instance.handles.as_ref())
+            .and_then(|handles| serde_json::to_string(&handles.stats.snapshot()).ok())
+            .unwrap_or_else(|| "{}".to_string())
+    };
+    #[cfg(not(feature = "mpv"))]
+    let json = {
+        let _ = player_id;
+        "{}".to_string()
+    };
+    CString::new(json).unwrap_or_default().into_raw()
+}
+
+/// 跳到下一章节（direction > 0）或上一章节（direction <= 0）
+#[no_mangle]
+pub extern "C" fn bova_mpv_step_chapter(player_id: c_longlong, direction: c_int) -> c_int {
+    #[cfg(not(feature = "mpv"))]
+    {
+        let _ = (player_id, direction);
+        return -1;
+    }
+
+    #[cfg(feature = "mpv")]
+    {
+        let cmd = if direction > 0 { MpvCommand::NextChapter } else { MpvCommand::PreviousChapter };
+        let players = PLAYERS.lock().unwrap();
+        if let Some(instance) = players.get(&player_id) {
+            if let Some(ref handles) = instance.handles {
+                if let Some(ref cmd_tx) = handles.cmd_tx {
+                    let _ = cmd_tx.send(cmd);
+                    return 0;
+                }
+            }
+        }
+        -2
+    }
+}
+
 /// 获取视频宽度
 #[no_mangle]
 pub extern "C" fn bova_mpv_get_video_width(player_id: c_longlong) -> c_int {
@@ -489,8 +733,12 @@ pub extern "C" fn bova_mpv_get_latest_frame(
     
     #[cfg(feature = "mpv")]
     {
-        let mut cache = FRAME_CACHE.lock().unwrap();
-        if let Some(frame) = cache.remove(&player_id) {
+        // 先释放帧缓存锁：帧处理线程持有 PLAYERS 时会再锁帧缓存
+        let frame = FRAME_CACHE.lock().unwrap().remove(&player_id);
+        if let Some(frame) = frame {
+            if let Some(handles) = PLAYERS.lock().unwrap().get(&player_id).and_then(|i| i.handles.as_ref()) {
+                handles.stats.record_presented(false);
+            }
             let width = frame.width as c_int;
             let height = frame.height as c_int;
             let data_len = frame.rgba.len();
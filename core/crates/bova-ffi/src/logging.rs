@@ -0,0 +1,62 @@
+//! Log forwarding for host apps.
+//!
+//! 所有 crate 的日志（包括 mpv 与 FFmpeg 转发进来的）经 `bova_core::logging` 过滤后，
+//! 交给宿主注册的回调。回调在输出日志的线程上调用（解码线程、mpv 线程等），
+//! 参数字符串只在回调期间有效；宿主应尽快复制并转交到自己的线程。
+//! 级别：1 = error, 2 = warn, 3 = info, 4 = debug, 5 = trace。
+
+use libc::{c_char, c_int, c_void};
+use std::ffi::{CStr, CString};
+use std::sync::Mutex;
+
+use bova_core::logging::{self, LogFilter, SinkId};
+
+pub type BovaLogCallback =
+    Option<extern "C" fn(level: c_int, target: *const c_char, message: *const c_char, user_data: *mut c_void)>;
+
+/// 当前回调对应的 sink
+static SINK: Mutex<Option<SinkId>> = Mutex::new(None);
+
+/// 设置日志回调，替换之前的回调；传 NULL 取消
+#[no_mangle]
+pub extern "C" fn bova_set_log_callback(callback: BovaLogCallback, user_data: *mut c_void) {
+    logging::init();
+    let mut sink = SINK.lock().unwrap();
+    if let Some(id) = sink.take() {
+        logging::remove_sink(id);
+    }
+    let Some(callback) = callback else { return };
+    // 裸指针不是 Send，按地址保存；由宿主保证 user_data 在回调期间有效
+    let user_data = user_data as usize;
+    *sink = Some(logging::add_sink(move |record| {
+        let target = CString::new(record.target.as_str()).unwrap_or_default();
+        let message = CString::new(record.message.replace('\0', "")).unwrap_or_default();
+        callback(record.level as c_int, target.as_ptr(), message.as_ptr(), user_data as *mut c_void);
+    }));
+}
+
+/// 设置日志过滤规则，如 "warn,bova_playback=debug,mpv=info"；NULL 恢复 BOVA_LOG / 默认规则。
+/// 成功返回 0，规则无效返回 -2
+#[no_mangle]
+pub extern "C" fn bova_set_log_filter(spec: *const c_char) -> c_int {
+    logging::init();
+    let filter = if spec.is_null() {
+        LogFilter::from_env()
+    } else {
+        match unsafe { CStr::from_ptr(spec) }.to_string_lossy().parse::<LogFilter>() {
+            Ok(filter) => filter,
+            Err(e) => {
+                log::warn!("invalid log filter: {e}");
+                return -2;
+            }
+        }
+    };
+    logging::set_filter(filter);
+    0
+}
+
+/// 是否同时输出到 stderr（默认输出）；宿主自行显示日志时可关闭
+#[no_mangle]
+pub extern "C" fn bova_set_log_stderr(enabled: c_int) {
+    logging::set_stderr(enabled != 0);
+}
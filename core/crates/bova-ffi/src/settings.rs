@@ -0,0 +1,128 @@
+//! Shared preferences and per-file state for host apps.
+//!
+//! 与 GUI / CLI 使用同一份 settings.json 与 state.json。返回值格式与 Emby 接口相同
+//! （`{"ok":true,"data":...}`，由 `bova_string_free` 释放）；写入时传入的 JSON
+//! 只需包含要修改的字段，其余字段保持不变。
+//!
+//! `bova_resume_*` 按文件路径读写续播位置，供不经过 `BovaPlayer` 的播放器
+//! （Flutter 的 MPV 播放器）使用，与 `bova_get_resume_position` 等共用同一份记录。
+
+use libc::{c_char, c_int, c_longlong};
+use std::sync::Mutex;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use bova_core::resume::{media_key, ResumeStore};
+use bova_settings::{SettingsStore, StateStore};
+
+use crate::emby::{arg, error_json, into_c};
+
+lazy_static::lazy_static! {
+    static ref SETTINGS: Mutex<SettingsStore> = Mutex::new(SettingsStore::open());
+    static ref STATE: Mutex<StateStore> = Mutex::new(StateStore::open());
+    static ref RESUME: Mutex<ResumeStore> = Mutex::new(ResumeStore::default());
+}
+
+fn ok<T: Serialize>(data: &T) -> *mut c_char {
+    into_c(serde_json::json!({ "ok": true, "data": data }))
+}
+
+/// 把 patch 中的顶层字段覆盖到 current 上
+fn merge<T: Serialize + DeserializeOwned>(current: &T, patch: &str) -> Result<T, String> {
+    let patch: serde_json::Value = serde_json::from_str(patch).map_err(|e| format!("invalid json: {}", e))?;
+    let serde_json::Value::Object(patch) = patch else { return Err("expected a json object".to_string()) };
+    let mut value = serde_json::to_value(current).map_err(|e| e.to_string())?;
+    if let Some(map) = value.as_object_mut() {
+        map.extend(patch);
+    }
+    serde_json::from_value(value).map_err(|e| format!("invalid field: {}", e))
+}
+
+/// 全局偏好；每次调用都重新读取，以看到其他进程（GUI / CLI）的修改
+#[no_mangle]
+pub extern "C" fn bova_settings_get() -> *mut c_char {
+    let mut settings = SETTINGS.lock().unwrap();
+    settings.reload();
+    ok(settings.get())
+}
+
+/// 修改全局偏好，data 为修改后的完整偏好
+#[no_mangle]
+pub extern "C" fn bova_settings_set(patch_json: *const c_char) -> *mut c_char {
+    let Some(patch) = arg(patch_json) else { return error_json("settings json is null", false) };
+    let mut settings = SETTINGS.lock().unwrap();
+    settings.reload();
+    let prefs = match merge(settings.get(), &patch) {
+        Ok(prefs) => prefs,
+        Err(e) => return error_json(&e, false),
+    };
+    settings.set(prefs);
+    if let Err(e) = settings.save_if_dirty() {
+        return error_json(&e.to_string(), false);
+    }
+    ok(settings.get())
+}
+
+/// 某个文件的记忆；没有记录时 data 为 null
+#[no_mangle]
+pub extern "C" fn bova_file_state_get(key: *const c_char) -> *mut c_char {
+    let Some(key) = arg(key) else { return error_json("key is null", false) };
+    let mut state = STATE.lock().unwrap();
+    state.reload();
+    ok(&state.get().file(&key))
+}
+
+/// 修改某个文件的记忆（不存在时新建），data 为修改后的记录
+#[no_mangle]
+pub extern "C" fn bova_file_state_set(key: *const c_char, patch_json: *const c_char) -> *mut c_char {
+    let (Some(key), Some(patch)) = (arg(key), arg(patch_json)) else {
+        return error_json("key and state json are required", false);
+    };
+    let mut state = STATE.lock().unwrap();
+    state.reload();
+    let current = state.get().file(&key).cloned().unwrap_or_default();
+    let updated = match merge(&current, &patch) {
+        Ok(updated) => updated,
+        Err(e) => return error_json(&e, false),
+    };
+    state.get_mut().update_file(&key, |s| *s = updated);
+    if let Err(e) = state.save() {
+        return error_json(&e.to_string(), false);
+    }
+    ok(&state.get().file(&key))
+}
+
+/// 删除某个文件的记忆
+#[no_mangle]
+pub extern "C" fn bova_file_state_forget(key: *const c_char) -> *mut c_char {
+    let Some(key) = arg(key) else { return error_json("key is null", false) };
+    let mut state = STATE.lock().unwrap();
+    state.reload();
+    let removed = state.get_mut().forget_file(&key);
+    if let Err(e) = state.save_if_dirty() {
+        return error_json(&e.to_string(), false);
+    }
+    ok(&removed)
+}
+
+/// 本地文件上次的播放位置（毫秒）；没有记录或不是本地文件时返回 -1
+#[no_mangle]
+pub extern "C" fn bova_resume_get(path: *const c_char) -> c_longlong {
+    let Some(key) = arg(path).and_then(|p| media_key(&p)) else { return -1 };
+    RESUME.lock().unwrap().position(&key).unwrap_or(-1)
+}
+
+/// 记录播放进度；接近结尾时视为看完并清除位置。不是本地文件时返回 -1
+#[no_mangle]
+pub extern "C" fn bova_resume_record(path: *const c_char, pos_ms: c_longlong, duration_ms: c_longlong) -> c_int {
+    let Some(key) = arg(path).and_then(|p| media_key(&p)) else { return -1 };
+    RESUME.lock().unwrap().record(&key, pos_ms, duration_ms);
+    0
+}
+
+#[no_mangle]
+pub extern "C" fn bova_resume_clear(path: *const c_char) -> c_int {
+    let Some(key) = arg(path).and_then(|p| media_key(&p)) else { return -1 };
+    RESUME.lock().unwrap().clear(&key);
+    0
+}
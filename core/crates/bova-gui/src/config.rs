@@ -0,0 +1,143 @@
+//! Saved Emby servers and remote shares.
+//!
+//! 列表保存在配置目录的 servers.json 中，不含任何令牌或密码；
+//! Emby access_token 与远程共享密码存入 `bova_settings::Credentials`。
+//! 首次启动时会迁移旧版写在工作目录下的 bova_emby_config.json。
+
+use std::path::PathBuf;
+
+use bova_remote::RemoteConnection;
+use bova_settings::Credentials;
+
+use crate::emby::EmbyServer;
+
+const SERVERS_FILE: &str = "servers.json";
+const LEGACY_FILE: &str = "bova_emby_config.json";
+
+/// servers.json 的内容（与旧版 bova_emby_config.json 相同的结构）
+#[derive(Default, serde::Serialize, serde::Deserialize)]
+struct SavedConfig {
+    #[serde(default)]
+    servers: Vec<EmbyServer>,
+    #[serde(default)]
+    remote_sources: Vec<RemoteConnection>,
+}
+
+pub struct ServerStore {
+    path: Option<PathBuf>,
+    credentials: Option<Credentials>,
+}
+
+fn emby_account(server: &EmbyServer) -> String {
+    format!("emby:{}:{}", server.base_url(), server.username)
+}
+
+fn remote_account(conn: &RemoteConnection) -> String {
+    format!("remote:{}:{}", conn.url, conn.username.as_deref().unwrap_or(""))
+}
+
+impl ServerStore {
+    pub fn open() -> Self {
+        let path = bova_settings::config_file(SERVERS_FILE)
+            .map_err(|e| log::warn!("config dir unavailable: {}", e))
+            .ok();
+        // 凭据存储不可用时仍可使用，只是令牌不会保存（需要重新登录）
+        let credentials = Credentials::open()
+            .map_err(|e| log::warn!("credential store unavailable: {}", e))
+            .ok();
+        Self { path, credentials }
+    }
+
+    /// 供日志显示：配置文件位置与凭据后端
+    pub fn describe(&self) -> String {
+        format!(
+            "{} · 凭据: {}",
+            self.path.as_ref().map(|p| p.display().to_string()).unwrap_or_else(|| "未保存".to_string()),
+            self.credentials.as_ref().map(|c| c.backend().to_string()).unwrap_or_else(|| "不可用".to_string()),
+        )
+    }
+
+    /// 读取服务器与远程共享，并从凭据存储中补全令牌和密码
+    pub fn load(&self) -> (Vec<EmbyServer>, Vec<RemoteConnection>) {
+        let Some(path) = &self.path else { return (Vec::new(), Vec::new()) };
+        let mut config = match bova_settings::load_json::<SavedConfig>(path) {
+            Ok(Some(config)) => config,
+            Ok(None) => match self.migrate_legacy() {
+                Some(config) => config,
+                None => return (Vec::new(), Vec::new()),
+            },
+            Err(e) => {
+                log::warn!("{} unreadable: {}", path.display(), e);
+                return (Vec::new(), Vec::new());
+            }
+        };
+        if let Some(creds) = &self.credentials {
+            for server in &mut config.servers {
+                server.access_token = creds.get(&emby_account(server)).unwrap_or_else(|e| {
+                    log::warn!("token for {} unavailable: {}", server.name, e);
+                    None
+                });
+            }
+            for conn in &mut config.remote_sources {
+                conn.password = creds.get(&remote_account(conn)).unwrap_or(None);
+            }
+        }
+        (config.servers, config.remote_sources)
+    }
+
+    /// 保存列表；令牌和密码只写入凭据存储
+    pub fn save(&self, servers: &[EmbyServer], remotes: &[RemoteConnection]) {
+        let Some(path) = &self.path else { return };
+        let mut config = SavedConfig { servers: servers.to_vec(), remote_sources: remotes.to_vec() };
+        for server in &mut config.servers {
+            if let (Some(token), Some(creds)) = (server.access_token.take(), &self.credentials) {
+                if let Err(e) = creds.set(&emby_account(server), &token) {
+                    log::warn!("cannot store token for {}: {}", server.name, e);
+                }
+            }
+        }
+        for conn in &mut config.remote_sources {
+            if let (Some(password), Some(creds)) = (conn.password.take(), &self.credentials) {
+                if let Err(e) = creds.set(&remote_account(conn), &password) {
+                    log::warn!("cannot store password for {}: {}", conn.name, e);
+                }
+            }
+        }
+        if let Err(e) = bova_settings::save_json(path, &config) {
+            log::warn!("cannot save {}: {}", path.display(), e);
+        }
+    }
+
+    /// 删除服务器时一并清除其令牌
+    pub fn forget_emby(&self, server: &EmbyServer) {
+        if let Some(creds) = &self.credentials {
+            let _ = creds.delete(&emby_account(server));
+        }
+    }
+
+    pub fn forget_remote(&self, conn: &RemoteConnection) {
+        if let Some(creds) = &self.credentials {
+            let _ = creds.delete(&remote_account(conn));
+        }
+    }
+
+    /// 迁移工作目录下的旧配置（兼容仅含服务器数组的更早格式）：
+    /// 写入新位置后删除旧文件，避免明文令牌继续留在磁盘上
+    fn migrate_legacy(&self) -> Option<SavedConfig> {
+        let legacy = std::env::current_dir().ok()?.join(LEGACY_FILE);
+        let text = std::fs::read_to_string(&legacy).ok()?;
+        let config = serde_json::from_str::<SavedConfig>(&text)
+            .or_else(|_| serde_json::from_str::<Vec<EmbyServer>>(&text).map(|servers| SavedConfig { servers, ..Default::default() }))
+            .map_err(|e| log::warn!("cannot migrate {}: {}", legacy.display(), e))
+            .ok()?;
+        self.save(&config.servers, &config.remote_sources);
+        // 凭据存储不可用时令牌无处安放，保留旧文件由用户处理
+        if self.credentials.is_some() {
+            match std::fs::remove_file(&legacy) {
+                Ok(()) => log::info!("migrated {} to {}", legacy.display(), self.describe()),
+                Err(e) => log::warn!("migrated {} but cannot remove it: {}", legacy.display(), e),
+            }
+        }
+        Some(config)
+    }
+}
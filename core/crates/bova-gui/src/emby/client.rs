@@ -0,0 +1,365 @@
+//! Schedules `bova_emby::EmbyApi` requests for the UI.
+//!
+//! 请求跑在独立的 tokio 运行时上，结果通过 `EmbyEvent` 通道回到 UI 线程。
+//! 相同 key 的请求在完成前只会发送一次，导航类请求可以在用户切换页面时整体取消。
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::Sender;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use bova_emby::{EmbyApi, EmbyError, ReportEvent};
+use tokio::runtime::Runtime;
+use tokio::sync::mpsc::{unbounded_channel, UnboundedSender};
+use tokio::task::AbortHandle;
+
+use super::{EmbyEvent, EmbyItem, EmbyRequest, EmbyServer, ItemQuery, MediaSource, PlaySession};
+
+const PROGRESS_INTERVAL: Duration = Duration::from_secs(10);
+const SEEK_THRESHOLD_MS: i64 = 5_000;
+
+/// 去重 key 带上服务器地址与用户，切换服务器后同名请求不会被旧请求挡住
+fn server_key(server: &EmbyServer, key: &str) -> String {
+    format!("{}#{}:{}", server.base_url(), server.user_id.as_deref().unwrap_or(""), key)
+}
+
+/// 请求的生命周期归属
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RequestScope {
+    /// 属于当前页面（列表、详情、首页），离开页面时取消
+    Navigation,
+    /// 与页面无关（登录、图片、播放协商、字幕），不随导航取消
+    Background,
+}
+
+struct InFlight {
+    id: u64,
+    scope: RequestScope,
+    handle: AbortHandle,
+}
+
+pub struct EmbyClient {
+    tx: Sender<EmbyEvent>,
+    runtime: Arc<Runtime>,
+    api: EmbyApi,
+    inflight: Arc<Mutex<HashMap<String, InFlight>>>,
+    next_id: AtomicU64,
+}
+
+impl EmbyClient {
+    pub fn new(tx: Sender<EmbyEvent>) -> Result<Self, EmbyError> {
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(2)
+            .thread_name("bova-emby")
+            .enable_all()
+            .build()?;
+        Ok(Self {
+            tx,
+            runtime: Arc::new(runtime),
+            api: EmbyApi::new()?,
+            inflight: Arc::new(Mutex::new(HashMap::new())),
+            next_id: AtomicU64::new(1),
+        })
+    }
+
+    /// 在运行时上启动请求；同 key 的请求仍在进行时直接忽略
+    fn spawn<F>(&self, key: String, scope: RequestScope, fut: F)
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        // 持锁期间完成 spawn + 登记，任务结束时的清理会等到登记之后
+        let mut inflight = self.inflight.lock().unwrap();
+        if inflight.contains_key(&key) {
+            return;
+        }
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let map = self.inflight.clone();
+        let done_key = key.clone();
+        let handle = self.runtime.spawn(async move {
+            fut.await;
+            let mut inflight = map.lock().unwrap();
+            if inflight.get(&done_key).map(|f| f.id) == Some(id) {
+                inflight.remove(&done_key);
+            }
+        });
+        inflight.insert(key, InFlight { id, scope, handle: handle.abort_handle() });
+    }
+
+    /// 取消某一类仍在进行（或等待重试）的请求
+    pub fn cancel(&self, scope: RequestScope) {
+        let mut inflight = self.inflight.lock().unwrap();
+        inflight.retain(|key, f| {
+            if f.scope == scope {
+                f.handle.abort();
+                log::debug!("cancelled {}", key);
+                false
+            } else {
+                true
+            }
+        });
+    }
+
+    pub fn authenticate(&self, server: EmbyServer, password: String) {
+        let tx = self.tx.clone();
+        let api = self.api.clone();
+        let key = format!("auth:{}:{}", server.base_url(), server.username);
+
+        self.spawn(key, RequestScope::Background, async move {
+            let _ = tx.send(match api.authenticate(server, &password).await {
+                Ok(server) => EmbyEvent::AuthSuccess(server),
+                Err(e) => EmbyEvent::AuthError(e),
+            });
+        });
+    }
+
+    pub fn get_dashboard(&self, server: &EmbyServer) {
+        let tx = self.tx.clone();
+        let api = self.api.clone();
+        let server = server.clone();
+
+        self.spawn(server_key(&server, "dashboard"), RequestScope::Navigation, async move {
+            let _ = tx.send(match api.dashboard(&server).await {
+                Ok(dash) => EmbyEvent::DashboardLoaded(dash),
+                Err(e) => EmbyEvent::ItemsError(e),
+            });
+        });
+    }
+
+    /// 按条件查询一页媒体项，结果以 ItemPageLoaded 返回
+    pub fn query_items(&self, server: &EmbyServer, query: ItemQuery) {
+        let tx = self.tx.clone();
+        let api = self.api.clone();
+        let server = server.clone();
+        let key = server_key(&server, &format!("page?{}", query.to_query_string()));
+
+        self.spawn(key, RequestScope::Navigation, async move {
+            let _ = tx.send(match api.items(&server, &query).await {
+                Ok(page) => EmbyEvent::ItemPageLoaded(page),
+                Err(e) => EmbyEvent::ItemsError(e),
+            });
+        });
+    }
+
+    /// 全库快速搜索（/Search/Hints），结果同样以 ItemPageLoaded 返回
+    pub fn search_hints(&self, server: &EmbyServer, query: ItemQuery) {
+        let tx = self.tx.clone();
+        let api = self.api.clone();
+        let server = server.clone();
+        let key = server_key(&server, &format!("search?{}", query.to_query_string()));
+
+        self.spawn(key, RequestScope::Navigation, async move {
+            let _ = tx.send(match api.search_hints(&server, &query).await {
+                Ok(page) => EmbyEvent::ItemPageLoaded(page),
+                Err(e) => EmbyEvent::ItemsError(e),
+            });
+        });
+    }
+
+    /// 获取类型列表（用于筛选），parent_id 为 None 时为全库
+    pub fn get_genres(&self, server: &EmbyServer, parent_id: Option<String>) {
+        let tx = self.tx.clone();
+        let api = self.api.clone();
+        let server = server.clone();
+        let key = server_key(&server, &format!("genres:{}", parent_id.as_deref().unwrap_or("")));
+
+        self.spawn(key, RequestScope::Background, async move {
+            let _ = tx.send(match api.genres(&server, parent_id.as_deref()).await {
+                Ok(genres) => EmbyEvent::GenresLoaded(genres),
+                Err(e) => EmbyEvent::RequestFailed(EmbyRequest::Genres, e),
+            });
+        });
+    }
+
+    pub fn get_items(&self, server: &EmbyServer, parent_id: Option<String>, recursive: bool) {
+        let tx = self.tx.clone();
+        let api = self.api.clone();
+        let server = server.clone();
+        let key = server_key(&server, &format!("items:{}:{}", parent_id.as_deref().unwrap_or(""), recursive));
+
+        self.spawn(key, RequestScope::Navigation, async move {
+            let _ = tx.send(match api.children(&server, parent_id.as_deref(), recursive).await {
+                Ok(items) => EmbyEvent::ItemsLoaded(items),
+                Err(e) => EmbyEvent::ItemsError(e),
+            });
+        });
+    }
+
+    /// 首页每个媒体库的最新内容预览
+    pub fn get_view_items(&self, server: &EmbyServer, view_id: &str) {
+        let tx = self.tx.clone();
+        let api = self.api.clone();
+        let server = server.clone();
+        let view_id = view_id.to_string();
+
+        self.spawn(server_key(&server, &format!("view:{}", view_id)), RequestScope::Navigation, async move {
+            let _ = tx.send(match api.latest_in_view(&server, &view_id, 12).await {
+                Ok(items) => EmbyEvent::ViewItemsLoaded(view_id, items),
+                Err(e) => EmbyEvent::RequestFailed(EmbyRequest::ViewItems(view_id), e),
+            });
+        });
+    }
+
+    /// Series 详情页中某一季的剧集
+    pub fn get_season_episodes(&self, server: &EmbyServer, season_id: &str) {
+        let tx = self.tx.clone();
+        let api = self.api.clone();
+        let server = server.clone();
+        let season_id = season_id.to_string();
+
+        self.spawn(server_key(&server, &format!("season:{}", season_id)), RequestScope::Navigation, async move {
+            let _ = tx.send(match api.season_episodes(&server, &season_id).await {
+                Ok(items) => EmbyEvent::SeasonEpisodesLoaded(season_id, items),
+                Err(e) => EmbyEvent::RequestFailed(EmbyRequest::SeasonEpisodes(season_id), e),
+            });
+        });
+    }
+
+    /// 获取 Series 的总集数
+    pub fn get_series_episode_count(&self, server: &EmbyServer, series_id: String) {
+        let tx = self.tx.clone();
+        let api = self.api.clone();
+        let server = server.clone();
+
+        self.spawn(server_key(&server, &format!("count:{}", series_id)), RequestScope::Background, async move {
+            let _ = tx.send(match api.episode_count(&server, &series_id).await {
+                Ok(count) => EmbyEvent::SeriesEpisodeCountLoaded(series_id, count as i32),
+                Err(e) => EmbyEvent::RequestFailed(EmbyRequest::EpisodeCount(series_id), e),
+            });
+        });
+    }
+
+    /// 下载封面/背景图，image_key 同时作为去重 key
+    pub fn load_image(&self, image_key: &str, url: &str) {
+        let tx = self.tx.clone();
+        let api = self.api.clone();
+        let image_key = image_key.to_string();
+        let url = url.to_string();
+
+        self.spawn(format!("image:{}", image_key), RequestScope::Background, async move {
+            let _ = tx.send(match api.image(&url).await {
+                Ok(bytes) => EmbyEvent::ImageLoaded(image_key, bytes),
+                Err(e) => EmbyEvent::RequestFailed(EmbyRequest::Image(image_key), e),
+            });
+        });
+    }
+
+    /// 协商播放方式，结果以 PlaybackResolved 事件返回
+    pub fn resolve_playback(&self, server: &EmbyServer, item: EmbyItem, start_ms: Option<i64>) {
+        let tx = self.tx.clone();
+        let api = self.api.clone();
+        let server = server.clone();
+
+        self.spawn(server_key(&server, &format!("playback:{}", item.id)), RequestScope::Background, async move {
+            // 列表中的条目不含章节，播放前补取（失败不影响播放）
+            let mut item = item;
+            if item.chapters.is_empty() {
+                if let Ok(full) = api.item(&server, &item.id).await {
+                    item.chapters = full.chapters;
+                }
+            }
+            let _ = tx.send(match api.playback_info(&server, &item.id, None).await {
+                Ok(selection) => EmbyEvent::PlaybackResolved(Box::new(item), Box::new(selection), start_ms),
+                Err(e) => EmbyEvent::PlaybackError(e),
+            });
+        });
+    }
+
+    /// 下载服务器上的字幕流到临时目录，完成后发送 SubtitleDownloaded
+    pub fn download_subtitle(&self, server: &EmbyServer, item_id: &str, source: &MediaSource, index: i64) {
+        let tx = self.tx.clone();
+        let api = self.api.clone();
+        let server = server.clone();
+        let item_id = item_id.to_string();
+        let source = source.clone();
+        let key = server_key(&server, &format!("subtitle:{}:{}:{}", item_id, source.id, index));
+
+        self.spawn(key, RequestScope::Background, async move {
+            let dir = std::env::temp_dir().join("bova-emby-subs");
+            let _ = tx.send(match api.download_subtitle(&server, &item_id, &source, index, &dir).await {
+                Ok(path) => EmbyEvent::SubtitleDownloaded(index, path.to_string_lossy().to_string()),
+                Err(e) => EmbyEvent::SubtitleError(e),
+            });
+        });
+    }
+
+    /// 创建播放会话并立即上报开始播放
+    pub fn start_reporter(&self, server: &EmbyServer, item_id: &str, session: &PlaySession, position_ms: i64) -> PlaybackReporter {
+        let (report_tx, mut report_rx) = unbounded_channel::<ReportEvent>();
+        let tx = self.tx.clone();
+        let api = self.api.clone();
+        let server = server.clone();
+        let item_id = item_id.to_string();
+        let session = session.clone();
+
+        // 上报按顺序发送，不参与去重与取消；通道关闭后处理完剩余消息再退出
+        self.runtime.spawn(async move {
+            while let Some(event) = report_rx.recv().await {
+                if let Err(e) = api.report(&server, &item_id, &session, event).await {
+                    log::warn!("report {:?} failed: {}", event, e);
+                    let _ = tx.send(EmbyEvent::RequestFailed(EmbyRequest::Report, e));
+                }
+            }
+        });
+
+        let _ = report_tx.send(ReportEvent::Start { position_ms, paused: false });
+        PlaybackReporter {
+            tx: report_tx,
+            last_report: Instant::now(),
+            last_tick: Instant::now(),
+            last_paused: false,
+            last_position_ms: position_ms,
+            stopped: false,
+        }
+    }
+}
+
+/// 向服务器上报播放状态（/Sessions/Playing, /Progress, /Stopped）。
+/// 由 `EmbyClient::start_reporter` 创建，请求在运行时上按顺序发送，不阻塞 UI。
+pub struct PlaybackReporter {
+    tx: UnboundedSender<ReportEvent>,
+    last_report: Instant,
+    last_tick: Instant,
+    last_paused: bool,
+    last_position_ms: i64,
+    stopped: bool,
+}
+
+impl PlaybackReporter {
+    /// 每帧调用：暂停状态变化或跳转时立即上报，否则每 10 秒上报一次进度
+    pub fn tick(&mut self, position_ms: i64, paused: bool) {
+        if self.stopped {
+            return;
+        }
+        let elapsed = if self.last_paused { 0 } else { self.last_tick.elapsed().as_millis() as i64 };
+        let jumped = (position_ms - (self.last_position_ms + elapsed)).abs() > SEEK_THRESHOLD_MS;
+        self.last_position_ms = position_ms;
+        self.last_tick = Instant::now();
+        if paused != self.last_paused {
+            let event = if paused { "Pause" } else { "Unpause" };
+            let _ = self.tx.send(ReportEvent::Progress { position_ms, paused, event });
+            self.last_paused = paused;
+            self.last_report = Instant::now();
+        } else if (jumped && self.last_report.elapsed() >= Duration::from_secs(1))
+            || (!paused && self.last_report.elapsed() >= PROGRESS_INTERVAL)
+        {
+            let _ = self.tx.send(ReportEvent::Progress { position_ms, paused, event: "TimeUpdate" });
+            self.last_report = Instant::now();
+        }
+    }
+
+    /// 结束会话；服务器据此更新续播位置和已看状态
+    pub fn stop(&mut self, position_ms: i64) {
+        if !self.stopped {
+            let _ = self.tx.send(ReportEvent::Stop { position_ms });
+            self.stopped = true;
+        }
+    }
+}
+
+impl Drop for PlaybackReporter {
+    fn drop(&mut self) {
+        self.stop(self.last_position_ms);
+    }
+}
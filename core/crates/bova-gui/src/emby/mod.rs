@@ -0,0 +1,55 @@
+//! Emby / Jellyfin integration for the GUI. Models and requests come from
+//! `bova_emby`; `client` schedules them and reports back as `EmbyEvent`s.
+
+use bova_playback::SubtitleTrackInfo;
+
+mod client;
+
+pub use bova_emby::{
+    format_bitrate, parse_years, EmbyDashboard, EmbyError, EmbyItem, EmbyServer, ItemPage, ItemQuery,
+    MediaSource, PlayMethod, PlaySession, SortField, StreamSelection, BITRATE_PRESETS,
+};
+pub use client::{EmbyClient, PlaybackReporter, RequestScope};
+
+/// 服务器可提供下载的字幕流，转换为播放器的字幕轨道（id 为 Emby 的流索引）
+pub fn subtitle_tracks(source: &MediaSource) -> Vec<SubtitleTrackInfo> {
+    source.subtitle_streams()
+        .map(|st| SubtitleTrackInfo {
+            id: st.index,
+            lang: st.language.clone(),
+            title: st.display_title.clone().or_else(|| st.title.clone()),
+            external: st.is_external,
+        })
+        .collect()
+}
+
+/// 后台请求的类别，失败时随 RequestFailed 一起返回，便于 UI 清理加载状态
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EmbyRequest {
+    Image(String),          // image_key
+    ViewItems(String),      // view_id
+    SeasonEpisodes(String), // season_id
+    EpisodeCount(String),   // series_id
+    Genres,
+    Report,                 // 播放进度上报
+}
+
+#[derive(Debug, Clone)]
+pub enum EmbyEvent {
+    AuthSuccess(EmbyServer),
+    AuthError(EmbyError),
+    ItemsLoaded(Vec<EmbyItem>),
+    DashboardLoaded(EmbyDashboard),
+    ItemsError(EmbyError),
+    ImageLoaded(String, Vec<u8>), // (image_key, image_data)
+    ViewItemsLoaded(String, Vec<EmbyItem>), // (view_id, items) - 用于 Dashboard 预览
+    SeasonEpisodesLoaded(String, Vec<EmbyItem>), // (season_id, episodes) - 用于 Series 详情页
+    SeriesEpisodeCountLoaded(String, i32), // (series_id, total_episode_count) - 用于显示剧集数量徽章
+    PlaybackResolved(Box<EmbyItem>, Box<StreamSelection>, Option<i64>), // (item, selection, start_ms)，大对象装箱，避免撑大所有事件
+    PlaybackError(EmbyError),
+    ItemPageLoaded(ItemPage),
+    GenresLoaded(Vec<String>),
+    SubtitleDownloaded(i64, String), // (stream_index, local_path)
+    SubtitleError(EmbyError),
+    RequestFailed(EmbyRequest, EmbyError),
+}
@@ -0,0 +1,393 @@
+//! Local media library for the GUI.
+//!
+//! 索引与文件名解析在 `bova_core::library`；这里在后台线程扫描，并把电影、剧集、季、
+//! 集转换成 `EmbyItem`，从而直接复用 Emby 的首页、浏览与详情页面。条目 id 以
+//! `local:` 开头，与服务器条目区分。
+
+use std::cmp::Reverse;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::Sender;
+use std::thread;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use bova_core::library::{
+    parse_season_dir, scan_folders, LibraryFile, LibraryIndex, LibraryStore, MediaKind, ScanStats, Series, WatchState,
+};
+use bova_emby::{UserData, TICKS_PER_MS};
+use bova_settings::MediaState;
+
+use crate::emby::{EmbyDashboard, EmbyItem, ItemPage, ItemQuery, SortField};
+
+const MOVIES_VIEW: &str = "local:view:movies";
+const SHOWS_VIEW: &str = "local:view:shows";
+const FILE_PREFIX: &str = "local:file:";
+const SERIES_PREFIX: &str = "local:series:";
+const SEASON_PREFIX: &str = "local:season:";
+
+/// 首页每个分类预览的条目数
+const PREVIEW_ITEMS: usize = 12;
+
+const IMAGE_EXTS: &[&str] = &["jpg", "jpeg", "png"];
+const POSTER_NAMES: &[&str] = &["poster", "folder", "cover"];
+const BACKDROP_NAMES: &[&str] = &["fanart", "backdrop", "background"];
+
+#[derive(Debug)]
+pub enum LibraryEvent {
+    Scanned(Vec<LibraryFile>, ScanStats),
+    /// (image_key, image_data)，与 EmbyEvent::ImageLoaded 相同
+    ImageLoaded(String, Vec<u8>),
+}
+
+pub fn is_local_id(id: &str) -> bool {
+    id.starts_with("local:")
+}
+
+pub struct LocalLibrary {
+    store: LibraryStore,
+    /// 按剧名分组的结果，索引变化时重建
+    series: Vec<Series>,
+    scanning: bool,
+    tx: Sender<LibraryEvent>,
+}
+
+impl LocalLibrary {
+    pub fn new(tx: Sender<LibraryEvent>) -> Self {
+        let store = LibraryStore::open();
+        let series = store.get().series();
+        Self { store, series, scanning: false, tx }
+    }
+
+    pub fn index(&self) -> &LibraryIndex {
+        self.store.get()
+    }
+
+    pub fn scanning(&self) -> bool {
+        self.scanning
+    }
+
+    /// 添加文件夹并立即扫描
+    pub fn add_folder(&mut self, folder: &str) -> bool {
+        if !self.store.get_mut().add_folder(folder) {
+            return false;
+        }
+        self.save();
+        self.rescan();
+        true
+    }
+
+    pub fn remove_folder(&mut self, folder: &str) {
+        if self.store.get_mut().remove_folder(folder) {
+            self.save();
+            self.series = self.store.get().series();
+        }
+    }
+
+    /// 在后台线程重新扫描全部文件夹，结果见 `LibraryEvent::Scanned`
+    pub fn rescan(&mut self) {
+        if self.scanning {
+            return;
+        }
+        self.scanning = true;
+        let index = self.store.get();
+        let (folders, previous) = (index.folders.clone(), index.files.clone());
+        let tx = self.tx.clone();
+        thread::spawn(move || {
+            let (files, stats) = scan_folders(&folders, &previous);
+            let _ = tx.send(LibraryEvent::Scanned(files, stats));
+        });
+    }
+
+    pub fn apply_scan(&mut self, files: Vec<LibraryFile>) {
+        self.scanning = false;
+        let index = self.store.get_mut();
+        // 扫描期间移除的文件夹不再保留其中的文件
+        let folders = index.folders.clone();
+        index.files = files.into_iter()
+            .filter(|f| folders.iter().any(|root| Path::new(&f.path).starts_with(root)))
+            .collect();
+        index.scanned_at = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+        self.save();
+        self.series = self.store.get().series();
+    }
+
+    fn save(&mut self) {
+        if let Err(e) = self.store.save_if_dirty() {
+            log::warn!("cannot save library: {}", e);
+        }
+    }
+
+    // ── 转换为 Emby 页面使用的条目 ──────────────────────────
+
+    pub fn dashboard(&self, state: &MediaState) -> EmbyDashboard {
+        let mut views = Vec::new();
+        if self.index().files.iter().any(|f| f.kind == MediaKind::Movie) {
+            views.push(view_item(MOVIES_VIEW, "电影"));
+        }
+        if !self.series.is_empty() {
+            views.push(view_item(SHOWS_VIEW, "剧集"));
+        }
+        // 继续观看：未看完的文件，最近播放的在前
+        let mut resume: Vec<(&LibraryFile, WatchState)> = self.index().files.iter()
+            .map(|f| (f, WatchState::of(f, state)))
+            .filter(|(_, w)| w.in_progress())
+            .collect();
+        resume.sort_by_key(|(_, w)| Reverse(w.updated_at));
+        EmbyDashboard {
+            views,
+            resume_items: resume.into_iter().take(PREVIEW_ITEMS).map(|(f, w)| file_item(f, w)).collect(),
+        }
+    }
+
+    /// 首页分类的预览：最近添加的在前
+    pub fn view_items(&self, view_id: &str, state: &MediaState) -> Vec<EmbyItem> {
+        let mut query = ItemQuery::browse(view_id, true);
+        query.sort_by = SortField::DateCreated;
+        query.descending = true;
+        query.limit = PREVIEW_ITEMS;
+        self.query(&query, state).items
+    }
+
+    /// 每部剧的总集数（卡片徽章）
+    pub fn episode_counts(&self) -> Vec<(String, i32)> {
+        self.series.iter().map(|s| (series_id(s), s.episode_count() as i32)).collect()
+    }
+
+    pub fn seasons(&self, series_id: &str, state: &MediaState) -> Vec<EmbyItem> {
+        let Some(series) = self.find_series(series_id) else { return Vec::new() };
+        series.seasons.iter()
+            .map(|season| {
+                let episodes: Vec<WatchState> = season.episodes.iter().map(|f| WatchState::of(f, state)).collect();
+                EmbyItem {
+                    id: season_id(series, season.number),
+                    name: if season.number == 0 { "特别篇".to_string() } else { format!("第 {} 季", season.number) },
+                    field_type: Some("Season".to_string()),
+                    parent_id: Some(series_id.to_string()),
+                    index_number: Some(season.number as i32),
+                    child_count: Some(season.episodes.len() as i32),
+                    user_data: Some(UserData {
+                        played: Some(episodes.iter().all(|w| w.watched)),
+                        ..Default::default()
+                    }),
+                    ..Default::default()
+                }
+            })
+            .collect()
+    }
+
+    pub fn season_episodes(&self, season_id: &str, state: &MediaState) -> Vec<EmbyItem> {
+        let Some((key, number)) = season_id.strip_prefix(SEASON_PREFIX).and_then(|rest| rest.rsplit_once(':')) else {
+            return Vec::new();
+        };
+        self.series.iter()
+            .find(|s| s.key == key)
+            .and_then(|s| s.seasons.iter().find(|season| season.number.to_string() == number))
+            .map(|season| season.episodes.iter().map(|f| file_item(f, WatchState::of(f, state))).collect())
+            .unwrap_or_default()
+    }
+
+    /// 按 Emby 的查询条件在本地筛选、排序、分页；本地条目没有类型、评分与收藏，
+    /// 设置了这些条件时不会有结果
+    pub fn query(&self, query: &ItemQuery, state: &MediaState) -> ItemPage {
+        // (条目, 加入时间, 最后播放时间)
+        let mut items: Vec<(EmbyItem, u64, u64)> = Vec::new();
+        let movies = || {
+            self.index().movies().into_iter()
+                .map(|f| {
+                    let watch = WatchState::of(f, state);
+                    (file_item(f, watch), f.added_at, watch.updated_at)
+                })
+                .collect::<Vec<_>>()
+        };
+        let shows = || self.series.iter().map(|s| self.series_entry(s, state)).collect::<Vec<_>>();
+        match query.parent_id.as_deref() {
+            Some(MOVIES_VIEW) => items.extend(movies()),
+            Some(SHOWS_VIEW) => items.extend(shows()),
+            Some(id) if id.starts_with(SEASON_PREFIX) => {
+                items.extend(self.season_episodes(id, state).into_iter().map(|item| (item, 0, 0)));
+            }
+            Some(id) if id.starts_with(SERIES_PREFIX) => {
+                items.extend(self.seasons(id, state).into_iter().map(|item| (item, 0, 0)));
+            }
+            _ => {
+                items.extend(movies());
+                items.extend(shows());
+            }
+        }
+
+        if let Some(term) = query.search_term.as_deref().map(str::to_lowercase).filter(|t| !t.is_empty()) {
+            items.retain(|(item, _, _)| item.name.to_lowercase().contains(&term));
+        }
+        if !query.years.is_empty() {
+            items.retain(|(item, _, _)| item.production_year.is_some_and(|y| query.years.contains(&y)));
+        }
+        if let Some(played) = query.played {
+            items.retain(|(item, _, _)| item.user_data.as_ref().and_then(|d| d.played).unwrap_or(false) == played);
+        }
+        if !query.genres.is_empty() || query.min_community_rating.is_some() || query.favorites_only {
+            items.clear();
+        }
+
+        let name_key = |item: &EmbyItem| item.name.to_lowercase();
+        match query.sort_by {
+            SortField::DateCreated => items.sort_by(|a, b| a.1.cmp(&b.1).then_with(|| name_key(&a.0).cmp(&name_key(&b.0)))),
+            SortField::PremiereDate => items.sort_by(|a, b| a.0.production_year.cmp(&b.0.production_year).then_with(|| name_key(&a.0).cmp(&name_key(&b.0)))),
+            SortField::DatePlayed => items.sort_by(|a, b| a.2.cmp(&b.2).then_with(|| name_key(&a.0).cmp(&name_key(&b.0)))),
+            SortField::Runtime => items.sort_by(|a, b| a.0.run_time_ticks.cmp(&b.0.run_time_ticks).then_with(|| name_key(&a.0).cmp(&name_key(&b.0)))),
+            // 季与集保持原有顺序
+            SortField::SortName | SortField::CommunityRating if query.parent_id.as_deref().is_some_and(|id| id.starts_with(SEASON_PREFIX) || id.starts_with(SERIES_PREFIX)) => {}
+            SortField::SortName | SortField::CommunityRating => items.sort_by_cached_key(|(item, _, _)| name_key(item)),
+        }
+        if query.descending {
+            items.reverse();
+        }
+
+        let total = items.len();
+        let items = items.into_iter().skip(query.start_index).take(query.limit.max(1)).map(|(item, _, _)| item).collect();
+        ItemPage { items, total, query: query.clone() }
+    }
+
+    /// 可直接播放的本地文件
+    pub fn file(&self, item_id: &str) -> Option<&LibraryFile> {
+        self.index().file(item_id.strip_prefix(FILE_PREFIX)?)
+    }
+
+    /// 重新生成条目（观看状态变化后刷新详情页）
+    pub fn item(&self, item_id: &str, state: &MediaState) -> Option<EmbyItem> {
+        if let Some(file) = self.file(item_id) {
+            return Some(file_item(file, WatchState::of(file, state)));
+        }
+        self.find_series(item_id).map(|s| self.series_entry(s, state).0)
+    }
+
+    /// 某个条目的全部文件（剧集为所有集）
+    pub fn files_of(&self, item_id: &str) -> Vec<&LibraryFile> {
+        if let Some(file) = self.file(item_id) {
+            return vec![file];
+        }
+        self.find_series(item_id).map(|s| s.episodes().collect()).unwrap_or_default()
+    }
+
+    /// 在后台读取条目旁的海报 / 背景图（Kodi / Emby 的命名习惯）；没有图片时返回 false
+    pub fn load_image(&self, item_id: &str, image_key: &str, backdrop: bool) -> bool {
+        let Some(path) = self.artwork(item_id, backdrop) else { return false };
+        let (tx, key) = (self.tx.clone(), image_key.to_string());
+        thread::spawn(move || match std::fs::read(&path) {
+            Ok(data) => {
+                let _ = tx.send(LibraryEvent::ImageLoaded(key, data));
+            }
+            Err(e) => log::warn!("cannot read {}: {}", path.display(), e),
+        });
+        true
+    }
+
+    fn artwork(&self, item_id: &str, backdrop: bool) -> Option<PathBuf> {
+        let names = if backdrop { BACKDROP_NAMES } else { POSTER_NAMES };
+        if let Some(file) = self.file(item_id) {
+            let path = Path::new(&file.path);
+            let (dir, stem) = (path.parent()?, path.file_stem()?.to_str()?);
+            // `<文件名>-poster.jpg` / 剧集的 `<文件名>-thumb.jpg`，电影再找目录里的 poster.jpg
+            let own: Vec<String> = if backdrop {
+                vec![format!("{stem}-fanart")]
+            } else {
+                vec![format!("{stem}-poster"), format!("{stem}-thumb"), stem.to_string()]
+            };
+            return find_image(dir, &own.iter().map(String::as_str).collect::<Vec<_>>())
+                .or_else(|| (file.kind == MediaKind::Movie).then(|| find_image(dir, names)).flatten())
+                .or_else(|| backdrop.then(|| file.series_key().and_then(|key| self.series_dir(&key))).flatten()
+                    .and_then(|dir| find_image(&dir, names)));
+        }
+        let key = item_id.strip_prefix(SEASON_PREFIX)
+            .and_then(|rest| rest.rsplit_once(':').map(|(key, _)| key))
+            .or_else(|| item_id.strip_prefix(SERIES_PREFIX))?;
+        find_image(&self.series_dir(key)?, names)
+    }
+
+    /// 剧集所在目录：季目录的上一级
+    fn series_dir(&self, key: &str) -> Option<PathBuf> {
+        let series = self.series.iter().find(|s| s.key == key)?;
+        let dir = Path::new(&series.episodes().next()?.path).parent()?;
+        let is_season_dir = dir.file_name().and_then(|n| n.to_str()).and_then(parse_season_dir).is_some();
+        Some(if is_season_dir { dir.parent()? } else { dir }.to_path_buf())
+    }
+
+    fn find_series(&self, item_id: &str) -> Option<&Series> {
+        let key = item_id.strip_prefix(SERIES_PREFIX)?;
+        self.series.iter().find(|s| s.key == key)
+    }
+
+    /// 剧集条目及其排序用的加入时间、最后播放时间
+    fn series_entry(&self, series: &Series, state: &MediaState) -> (EmbyItem, u64, u64) {
+        let watch: Vec<WatchState> = series.episodes().map(|f| WatchState::of(f, state)).collect();
+        let count = series.episode_count();
+        let item = EmbyItem {
+            id: series_id(series),
+            name: series.title.clone(),
+            field_type: Some("Series".to_string()),
+            media_type: Some("Video".to_string()),
+            production_year: series.year,
+            overview: Some(format!("{} 季 · {} 集", series.seasons.len(), count)),
+            child_count: Some(count as i32),
+            recursive_item_count: Some(count as i32),
+            user_data: Some(UserData {
+                played: Some(watch.iter().all(|w| w.watched)),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        (item, series.added_at, watch.iter().map(|w| w.updated_at).max().unwrap_or(0))
+    }
+}
+
+fn view_item(id: &str, name: &str) -> EmbyItem {
+    EmbyItem {
+        id: id.to_string(),
+        name: name.to_string(),
+        field_type: Some("CollectionFolder".to_string()),
+        ..Default::default()
+    }
+}
+
+fn series_id(series: &Series) -> String {
+    format!("{SERIES_PREFIX}{}", series.key)
+}
+
+fn season_id(series: &Series, number: u32) -> String {
+    format!("{SEASON_PREFIX}{}:{}", series.key, number)
+}
+
+fn file_item(file: &LibraryFile, watch: WatchState) -> EmbyItem {
+    let episode = file.kind == MediaKind::Episode;
+    let name = match file.episode_label().filter(|_| episode) {
+        Some(label) => format!("{} {}", file.title, label),
+        None => file.title.clone(),
+    };
+    let mut overview = file.path.clone();
+    if let Some(group) = &file.group {
+        overview.push_str(&format!("\n发布组: {}", group));
+    }
+    EmbyItem {
+        id: format!("{FILE_PREFIX}{}", file.path),
+        name,
+        field_type: Some(if episode { "Episode" } else { "Movie" }.to_string()),
+        media_type: Some("Video".to_string()),
+        parent_id: file.series_key().map(|key| format!("{SEASON_PREFIX}{}:{}", key, file.season.unwrap_or(1))),
+        index_number: file.episode.map(|e| e as i32),
+        parent_index_number: file.season.filter(|_| episode).map(|s| s as i32),
+        run_time_ticks: watch.duration_ms.map(|ms| ms * TICKS_PER_MS),
+        overview: Some(overview),
+        production_year: file.year,
+        user_data: Some(UserData {
+            playback_position_ticks: watch.position_ms.map(|ms| ms * TICKS_PER_MS),
+            played_percentage: watch.progress_pct(),
+            played: Some(watch.watched),
+            ..Default::default()
+        }),
+        ..Default::default()
+    }
+}
+
+fn find_image(dir: &Path, stems: &[&str]) -> Option<PathBuf> {
+    stems.iter()
+        .flat_map(|stem| IMAGE_EXTS.iter().map(move |ext| dir.join(format!("{stem}.{ext}"))))
+        .find(|p| p.is_file())
+}
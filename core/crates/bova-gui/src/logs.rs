@@ -0,0 +1,83 @@
+//! Entries shown in the log panel.
+//!
+//! 面板同时显示 GUI 自己的事件（`push`，级别按前缀符号推断）和 `log` 记录
+//! （`bova_core::logging` 的 sink 经通道送到 UI 线程）。面板的级别筛选只影响显示；
+//! 哪些记录会被收集由 `BOVA_LOG` 决定。
+
+use std::sync::mpsc::{channel, Receiver};
+use std::sync::Mutex;
+
+use bova_core::logging::{self, LogRecord};
+use log::Level;
+
+/// 面板最多保留的条目数，超出后丢弃最早的
+const MAX_ENTRIES: usize = 2000;
+
+/// 筛选下拉框的选项：显示该级别及更严重的条目
+pub const LEVELS: [Level; 5] = [Level::Error, Level::Warn, Level::Info, Level::Debug, Level::Trace];
+
+pub fn level_label(level: Level) -> &'static str {
+    match level {
+        Level::Error => "错误",
+        Level::Warn => "警告",
+        Level::Info => "信息",
+        Level::Debug => "调试",
+        Level::Trace => "跟踪",
+    }
+}
+
+pub struct LogEntry {
+    pub level: Level,
+    pub text: String,
+}
+
+pub struct LogBuffer {
+    entries: Vec<LogEntry>,
+    /// 显示的最低级别
+    pub min_level: Level,
+    records_rx: Receiver<LogRecord>,
+}
+
+impl LogBuffer {
+    /// 注册 `log` sink；记录在下一帧由 `drain_records` 取出
+    pub fn new(initial: Vec<String>) -> Self {
+        let (tx, records_rx) = channel();
+        let tx = Mutex::new(tx);
+        logging::add_sink(move |record| {
+            let _ = tx.lock().unwrap().send(record.clone());
+        });
+        let mut buffer = Self { entries: Vec::new(), min_level: Level::Info, records_rx };
+        for line in initial {
+            buffer.push(line);
+        }
+        buffer
+    }
+
+    /// GUI 事件：以 ✕ 开头的是错误，其余为信息
+    pub fn push(&mut self, text: String) {
+        let level = if text.starts_with('✕') { Level::Error } else { Level::Info };
+        self.push_entry(LogEntry { level, text });
+    }
+
+    pub fn drain_records(&mut self) {
+        while let Ok(record) = self.records_rx.try_recv() {
+            self.push_entry(LogEntry { level: record.level, text: format!("[{}] {}", record.target, record.message) });
+        }
+    }
+
+    fn push_entry(&mut self, entry: LogEntry) {
+        if self.entries.len() >= MAX_ENTRIES {
+            self.entries.drain(..self.entries.len() + 1 - MAX_ENTRIES);
+        }
+        self.entries.push(entry);
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    /// 通过级别筛选的条目
+    pub fn visible(&self) -> impl Iterator<Item = &LogEntry> {
+        self.entries.iter().filter(|e| e.level <= self.min_level)
+    }
+}
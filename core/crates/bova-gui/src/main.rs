@@ -6,15 +6,25 @@ use std::time::Duration;
 use std::time::Instant;
 
 use bova_core::{create_player, MediaOptions, Player};
+use bova_core::playlist::{Playlist, PlaylistEntry, PlaylistFormat, PlaylistStore, RepeatMode};
 #[cfg(feature = "mpv")]
 use bova_playback::start_mpv_playback_handles;
-use bova_playback::{AudioFrame, PlaybackHandles, PlaybackConfig, PlaybackCommand, PlaybackEngine, PlaybackEvent, MpvCommand, SubtitleTrackInfo, VideoFrame, SubtitleFrame};
+use bova_playback::{AudioFrame, Chapter, NetworkOptions, PlaybackHandles, PlaybackConfig, PlaybackCommand, PlaybackEngine, PlaybackEvent, PlaybackStats, MpvCommand, QualityMode, ReplayGainMode, SubtitleTrackInfo, Variant, VideoFrame, SubtitleFrame};
 use eframe::{egui, App};
 use rodio::{OutputStream, Sink, OutputStreamHandle, buffer::SamplesBuffer};
 use rfd::FileDialog;
 
+mod config;
 mod emby;
-use emby::{EmbyClient, EmbyServer, EmbyItem, EmbyEvent, EmbyDashboard};
+mod library;
+mod logs;
+mod remote;
+use emby::{EmbyClient, EmbyServer, EmbyItem, EmbyEvent, EmbyDashboard, EmbyRequest, ItemQuery, SortField, PlaybackReporter, RequestScope, PlaySession, PlayMethod, MediaSource, BITRATE_PRESETS, format_bitrate};
+use library::{LibraryEvent, LocalLibrary};
+use logs::LogBuffer;
+use remote::{RemoteBrowser, RemoteEvent};
+use bova_remote::{RemoteConnection, RemoteEntry};
+use bova_settings::{EnginePreference, FileState, Preferences, RepeatPreference, SettingsStore, StateStore};
 
 // ━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━
 //  Theme / Colors
@@ -164,6 +174,53 @@ fn render_image_fit(
     );
 }
 
+// 播放诊断浮层（类似 mpv 的 stats），画在视频左上角
+fn render_stats_overlay(ui: &egui::Ui, painter: &egui::Painter, video_rect: egui::Rect, stats: &PlaybackStats) {
+    let ms = |avg: Option<f64>, max: Option<f64>| match (avg, max) {
+        (Some(avg), Some(max)) => format!("{:.2} ms (max {:.2})", avg, max),
+        _ => "-".to_string(),
+    };
+    let mut lines = vec![
+        format!("引擎      {}", stats.engine),
+        format!(
+            "视频      {} {}x{}",
+            stats.video_codec.as_deref().unwrap_or("?"),
+            stats.width,
+            stats.height
+        ),
+        format!("硬件解码  {}", stats.hwaccel.as_deref().unwrap_or("否")),
+    ];
+    if stats.hw_transfers_ok + stats.hw_transfers_failed > 0 {
+        lines.push(format!("硬件帧下载 成功 {} / 失败 {}", stats.hw_transfers_ok, stats.hw_transfers_failed));
+    }
+    lines.push(format!("解码      {:.1} fps, 共 {} 帧", stats.decode_fps, stats.frames_decoded));
+    lines.push(format!("解码耗时  {}", ms(stats.decode_ms_avg, stats.decode_ms_max)));
+    lines.push(format!("转换耗时  {}", ms(stats.scale_ms_avg, stats.scale_ms_max)));
+    lines.push(format!(
+        "队列      视频 {}/{}  音频 {}/{}",
+        stats.video_queue, stats.video_queue_capacity, stats.audio_queue, stats.audio_queue_capacity
+    ));
+    if let Some(pct) = stats.demux_fill_pct {
+        lines.push(format!("预读      {}%", pct));
+    }
+    lines.push(format!(
+        "显示      {} 帧, 丢帧 {}, 迟到 {}",
+        stats.frames_presented, stats.frames_dropped, stats.frames_late
+    ));
+    if let Some(drift) = stats.av_drift_ms {
+        lines.push(format!("音画偏差  {:+.0} ms", drift));
+    }
+
+    let galley = ui.painter().layout_no_wrap(
+        lines.join("\n"),
+        egui::FontId::monospace(12.0),
+        egui::Color32::WHITE,
+    );
+    let pos = video_rect.min + egui::vec2(12.0, 12.0);
+    painter.rect_filled(egui::Rect::from_min_size(pos, galley.size()).expand(8.0), 6.0, egui::Color32::from_black_alpha(170));
+    painter.galley(pos, galley, egui::Color32::WHITE);
+}
+
 fn accent_button(ui: &mut egui::Ui, label: &str) -> egui::Response {
     let btn = egui::Button::new(
         egui::RichText::new(label)
@@ -220,12 +277,14 @@ struct BovaGuiApp {
     opened: bool,
     last_instant: Option<Instant>,
 
-    // 事件日志
-    logs: Vec<String>,
+    // 事件日志（GUI 事件与 `log` 记录）
+    logs: LogBuffer,
     rx: mpsc::Receiver<String>,
 
     // 探测结果
     last_probe_json: Option<String>,
+    /// 后台 Probe 进行中：(url, 结果)
+    probe_rx: Option<Receiver<(String, Result<bova_probe::MediaInfo, bova_probe::ProbeError>)>>,
 
     // 选择文件夹
     current_dir: Option<PathBuf>,
@@ -256,7 +315,13 @@ struct BovaGuiApp {
     position_ms: i64,
     duration_ms: i64,
     volume: f32,
-    loop_play: bool,
+    /// Read-ahead fill level while the engine is (re)buffering
+    buffering_pct: Option<u8>,
+    media_options: MediaOptions,
+    /// HLS/DASH quality levels of the current stream (empty otherwise)
+    variants: Vec<Variant>,
+    current_variant: Option<usize>,
+    quality_mode: QualityMode,
     
     // Engine state
     playback_engine: PlaybackEngine,
@@ -273,10 +338,14 @@ struct BovaGuiApp {
 
     // Emby State
     emby_servers: Vec<EmbyServer>,
+    server_store: config::ServerStore,
+    // 全局偏好与逐文件记忆（音量、字幕选择），定期写盘
+    settings: SettingsStore,
+    media_state: StateStore,
+    settings_synced_at: Instant,
     current_emby_server: Option<EmbyServer>,
     emby_client: Option<EmbyClient>,
     emby_event_rx: Receiver<EmbyEvent>,
-    emby_event_tx: Sender<EmbyEvent>,
     emby_items: Vec<EmbyItem>,
     emby_dashboard: Option<EmbyDashboard>,
     emby_navigation_stack: Vec<(String, String)>, // (id, name)
@@ -301,8 +370,12 @@ struct BovaGuiApp {
     series_count_loading: std::collections::HashSet<String>, // series_id being loaded
     
     // Pagination
-    emby_items_per_page: usize,
-    emby_current_page: usize,
+    // 浏览器查询（搜索 / 筛选 / 排序 / 服务器端分页）
+    emby_query: ItemQuery,
+    emby_total_items: usize,
+    emby_genres: Vec<String>,
+    emby_search_input: String,
+    emby_year_input: String,
     
     // Emby UI State
     show_add_server_window: bool,
@@ -310,16 +383,65 @@ struct BovaGuiApp {
     new_server_user: String,
     new_server_pass: String,
     emby_status_msg: Option<String>,
+    // 当前 Emby 播放会话（进度上报）
+    emby_reporter: Option<PlaybackReporter>,
+    // 下一次启动播放时的起始位置（续播）
+    start_position_ms: Option<i64>,
+    // 本地文件上次的播放位置，等待用户选择是否继续
+    resume_offer: Option<i64>,
+    // 当前 Emby 播放的媒体源及服务器提供的字幕
+    emby_source: Option<(String, MediaSource)>, // (item_id, source)
+    emby_subtitle_tracks: Vec<SubtitleTrackInfo>,
+    selected_emby_subtitle: Option<i64>,
+    // 等待文件加载完成后交给引擎的外挂字幕
+    pending_external_subs: Vec<String>,
+    // 播放列表项之间的淡入淡出（毫秒，0 为无缝衔接）与已交给引擎预先打开的下一项
+    crossfade_ms: u32,
+    queued_next: Option<String>,
+    // 当前媒体没有视频轨（音乐文件），进度按音频时钟计算
+    audio_only: bool,
+    // 章节：引擎读取的容器章节，或 Emby 提供的（engine_chapters 为 false）
+    chapters: Vec<Chapter>,
+    engine_chapters: bool,
+    // Emby 标记的片头区间（毫秒）；本次播放已跳过或已错过时 intro_handled 为 true
+    intro_range: Option<(i64, i64)>,
+    intro_handled: bool,
+    skip_intro: bool,
+
+    // 本地媒体库：以 Emby 页面展示，library_active 时页面的数据来自本地索引
+    library: LocalLibrary,
+    library_event_rx: Receiver<LibraryEvent>,
+    library_active: bool,
+
+    // Remote shares (WebDAV / FTP)
+    remote_connections: Vec<RemoteConnection>,
+    current_remote: Option<RemoteConnection>,
+    remote_path: String,
+    remote_entries: Vec<RemoteEntry>,
+    remote_loading: bool,
+    remote_status_msg: Option<String>,
+    remote_browser: RemoteBrowser,
+    remote_event_rx: Receiver<RemoteEvent>,
+    /// Remote file being played; its source is reopened on every (re)start
+    remote_playing: Option<(RemoteConnection, RemoteEntry)>,
+    show_add_remote_window: bool,
+    new_remote_name: String,
+    new_remote_url: String,
+    new_remote_user: String,
+    new_remote_pass: String,
     
     app_mode: AppMode,
-    // MRU & 播放列表
+    // MRU & 播放列表（播放列表跨会话保存）
     mru: Vec<String>,
-    playlist: Vec<String>,
-    playlist_index: usize,
+    playlist: Playlist,
+    playlist_store: PlaylistStore,
 
     // UI state
     show_logs: bool,
     show_probe: bool,
+    /// 视频上的播放诊断浮层（I 键 / 底栏“统计”按钮）
+    show_stats: bool,
+    playback_stats: Option<PlaybackStats>,
     
     // Track the video display area for dynamic render sizing
     video_display_w: f32,
@@ -339,6 +461,7 @@ enum AppMode {
     Welcome,  // 新增：欢迎页/启动页
     Player,
     Emby,
+    Remote,   // WebDAV / FTP 浏览
 }
 
 // ━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━
@@ -348,6 +471,20 @@ enum AppMode {
 impl BovaGuiApp {
     fn start_playback(&mut self) {
         self.stop_playback();
+
+        // Remote files play through a fresh MediaSource on each start
+        let source = match &self.remote_playing {
+            Some((conn, entry)) => match bova_remote::open_source(conn, &entry.path, entry.size) {
+                Ok(src) => Some(bova_playback::source::register_source(src, &entry.name)),
+                Err(e) => {
+                    self.logs.push(format!("✕ 远程文件打开失败: {e}"));
+                    return;
+                }
+            },
+            None => None,
+        };
+        let play_url = source.as_ref().map_or_else(|| self.url.clone(), |g| g.url().to_string());
+        let start_ms = self.start_position_ms.take();
         
         match self.playback_engine {
             PlaybackEngine::MPV => {
@@ -356,11 +493,17 @@ impl BovaGuiApp {
                     subtitle_enabled: self.subtitle_enabled,
                     subtitle_index: self.current_subtitle_index,
                     engine: Some(PlaybackEngine::MPV),
+                    network: NetworkOptions::from(&self.media_options),
+                    quality: self.quality_mode,
+                    start_ms,
+                    crossfade_ms: self.crossfade_ms,
+                    replay_gain: ReplayGainMode::Track,
+                    audio_track: None,
                 };
                 #[cfg(feature = "mpv")]
-                match start_mpv_playback_handles(&self.url, &cfg) {
+                match start_mpv_playback_handles(&play_url, &cfg) {
                     Ok(h) => {
-                        self.playback = Some(h);
+                        self.playback = Some(PlaybackHandles { source: source.map(Arc::new), ..h });
                         self.playing = true;
                         self.subtitle_tracks.clear();
                         self.selected_subtitle_id = None;
@@ -390,10 +533,16 @@ impl BovaGuiApp {
                     subtitle_enabled: self.subtitle_enabled,
                     subtitle_index: self.current_subtitle_index,
                     engine: Some(PlaybackEngine::FFmpeg),
+                    network: NetworkOptions::from(&self.media_options),
+                    quality: self.quality_mode,
+                    start_ms,
+                    crossfade_ms: self.crossfade_ms,
+                    replay_gain: ReplayGainMode::Track,
+                    audio_track: None,
                 };
-                match bova_playback::start_playback_with(&self.url, cfg) {
+                match bova_playback::start_playback_with(&play_url, cfg) {
                     Ok(h) => {
-                        self.playback = Some(h);
+                        self.playback = Some(PlaybackHandles { source: source.map(Arc::new), ..h });
                         self.playing = true;
                         let accel = if self.hwaccel_enabled { "硬件解码" } else { "软解码" };
                         self.logs.push(format!("▶ FFmpeg引擎已启动 ({})", accel));
@@ -438,9 +587,36 @@ impl BovaGuiApp {
         self.audio_anchor_pts = None;
         self.audio_anchor_time = None;
         self.active_subtitles.clear();
+        self.buffering_pct = None;
+        self.playback_stats = None;
+        self.variants.clear();
+        self.current_variant = None;
+        self.pending_external_subs.clear();
+        self.queued_next = None;
+        self.audio_only = false;
         self.playing = false;
     }
 
+    fn set_quality(&mut self, mode: QualityMode) {
+        if self.quality_mode == mode { return; }
+        self.quality_mode = mode;
+        let label = match mode {
+            QualityMode::Auto => "自动".to_string(),
+            QualityMode::Fixed(i) => self.variants.get(i).map(|v| v.to_string()).unwrap_or_else(|| format!("#{i}")),
+        };
+        self.logs.push(format!("🎚 画质: {}", label));
+        let cmd_tx = self.playback.as_ref().and_then(|pb| pb.cmd_tx.clone());
+        match cmd_tx {
+            Some(cmd_tx) => { let _ = cmd_tx.try_send(MpvCommand::SetQuality(mode)); }
+            // FFmpeg 引擎：固定档位需要从当前位置重新打开（自动档位在分片边界内切换）
+            None if self.playback.is_some() => {
+                self.start_position_ms = Some(self.position_ms);
+                self.start_playback();
+            }
+            None => {}
+        }
+    }
+
     fn current_audio_time_ms(&self) -> Option<i64> {
         if !self.playing { return None; }
         if let (Some(pts), Some(t0)) = (self.audio_anchor_pts, self.audio_anchor_time) {
@@ -453,17 +629,137 @@ impl BovaGuiApp {
         if let Some(pos) = self.mru.iter().position(|p| p == &path) { self.mru.remove(pos); }
         self.mru.insert(0, path.clone());
         if self.mru.len() > 10 { self.mru.pop(); }
-        if !self.playlist.iter().any(|p| p == &path) {
-            self.playlist.push(path);
-            self.playlist_index = self.playlist.len()-1;
+        if is_session_url(&path) { return; }
+        let index = match self.playlist.position_of(&path) {
+            Some(index) => index,
+            None => self.playlist.push(PlaylistEntry::new(path)),
+        };
+        self.playlist.select(index);
+    }
+
+    /// 当前媒体在逐文件记忆中的键；带令牌的 Emby 串流地址不记录
+    fn media_key(&self) -> Option<String> {
+        if self.url.is_empty() || is_session_url(&self.url) { return None; }
+        Some(self.url.clone())
+    }
+
+    fn file_state(&self) -> Option<FileState> {
+        self.media_key().and_then(|key| self.media_state.get().file(&key).cloned())
+    }
+
+    /// 立即写入（state.json 同时由播放核心记录续播位置，不能只改内存副本）
+    fn update_file_state(&mut self, f: impl FnOnce(&mut FileState)) {
+        if let Some(key) = self.media_key() {
+            if let Err(e) = self.media_state.update(|m| m.update_file(&key, f)) {
+                log::warn!("cannot save file state: {}", e);
+            }
+        }
+    }
+
+    fn apply_volume(&mut self) {
+        if let Some(cmd_tx) = self.playback.as_ref().and_then(|pb| pb.cmd_tx.as_ref()) {
+            let _ = cmd_tx.try_send(MpvCommand::SetVolume((self.volume * 100.0) as f64));
         }
     }
 
+    /// 拖动结束后记住该文件的音量
+    fn remember_volume(&mut self) {
+        if self.playback.is_some() {
+            let volume = self.volume;
+            self.update_file_state(|s| s.volume = Some(volume));
+        }
+    }
+
+    /// 跳到指定位置；没有命令通道的引擎（FFmpeg）从该位置重新打开
+    fn seek_to(&mut self, target_ms: i64) {
+        self.position_ms = target_ms;
+        match self.playback.as_ref().and_then(|pb| pb.cmd_tx.as_ref()) {
+            Some(cmd_tx) => { let _ = cmd_tx.try_send(MpvCommand::SeekAbsolute(target_ms as f64 / 1000.0)); }
+            None => {
+                self.start_position_ms = Some(target_ms);
+                self.start_playback();
+            }
+        }
+    }
+
+    /// 字幕轨道就绪后恢复该文件上次的字幕选择
+    fn restore_subtitle_choice(&mut self) {
+        let Some(state) = self.file_state() else { return };
+        let cmd = if state.subtitle_off {
+            self.selected_subtitle_id = None;
+            MpvCommand::DisableSubtitle
+        } else if let Some(id) = state.subtitle_track.filter(|id| self.subtitle_tracks.iter().any(|t| t.id == *id)) {
+            self.selected_subtitle_id = Some(id);
+            MpvCommand::SelectSubtitle(id)
+        } else {
+            return;
+        };
+        if let Some(cmd_tx) = self.playback.as_ref().and_then(|pb| pb.cmd_tx.as_ref()) {
+            let _ = cmd_tx.try_send(cmd);
+            self.logs.push("🎦 已恢复上次的字幕选择".to_string());
+        }
+    }
+
+    /// 把当前偏好写回设置，有改动时保存
+    fn sync_settings(&mut self) {
+        let prefs = Preferences {
+            volume: self.volume,
+            repeat: match self.playlist.repeat {
+                RepeatMode::Off => RepeatPreference::Off,
+                RepeatMode::One => RepeatPreference::One,
+                RepeatMode::All => RepeatPreference::All,
+            },
+            hwaccel: self.hwaccel_enabled,
+            engine: match self.playback_engine {
+                PlaybackEngine::MPV => EnginePreference::Mpv,
+                PlaybackEngine::FFmpeg => EnginePreference::Ffmpeg,
+            },
+            subtitle_enabled: self.subtitle_enabled,
+            crossfade_ms: self.crossfade_ms,
+            skip_intro: self.skip_intro,
+            // Emby 串流地址含令牌，不写入磁盘
+            recent: self.mru.iter().filter(|p| !is_session_url(p)).cloned().collect(),
+        };
+        self.settings.set(prefs);
+        if let Err(e) = self.settings.save_if_dirty() {
+            log::warn!("cannot save settings: {}", e);
+        }
+        // 播放过的条目补上时长
+        if let Some(index) = self.playlist.current.filter(|_| self.duration_ms > 0) {
+            let entry = &mut self.playlist.entries[index];
+            if entry.url == self.url && entry.duration_ms.is_none() {
+                entry.duration_ms = Some(self.duration_ms);
+            }
+        }
+        self.playlist_store.set(self.playlist.clone());
+        if let Err(e) = self.playlist_store.save_if_dirty() {
+            log::warn!("cannot save playlist: {}", e);
+        }
+        self.settings_synced_at = Instant::now();
+    }
+
     fn open_and_play(&mut self, path: String) {
+        self.open_and_play_from(path, None);
+    }
+
+    fn open_and_play_from(&mut self, path: String, start_ms: Option<i64>) {
+        self.finish_emby_session();
         self.url = path.clone();
-        let opts = MediaOptions::default();
+        self.quality_mode = QualityMode::Auto;
+        self.remote_playing = None;
+        self.start_position_ms = start_ms;
+        self.clear_chapters();
+        if let Some(volume) = self.file_state().and_then(|s| s.volume) {
+            self.volume = volume;
+        }
+        let opts = self.media_options.clone();
+        self.resume_offer = None;
         match self.player.open(&self.url, opts) {
-            Ok(_) => {
+            Ok(handle) => {
+                if let (None, Some(ms)) = (start_ms, handle.resume_ms) {
+                    self.logs.push(format!("⏯ 上次播放到 {}", Self::format_time(ms)));
+                    self.resume_offer = Some(ms);
+                }
                 let _ = self.player.play();
                 self.start_playback();
                 self.remember_file(self.url.clone());
@@ -472,6 +768,126 @@ impl BovaGuiApp {
         }
     }
 
+    /// 播放 Emby 项目：先经 PlaybackInfo 协商播放方式（结果见 PlaybackResolved 事件）。
+    /// `resume` 时从服务器记录的位置开始
+    fn play_emby_item(&mut self, item: &EmbyItem, resume: bool) {
+        let start_ms = if resume { item.resume_position_ms() } else { None };
+        if let Some(file) = self.library.file(&item.id) {
+            // 本地文件：不续播时从头开始，不再询问上次的位置
+            let path = file.path.clone();
+            self.logs.push(format!("📚 媒体库播放: {}", item.name));
+            self.app_mode = AppMode::Player;
+            self.open_and_play_from(path, Some(start_ms.unwrap_or(0)));
+            return;
+        }
+        if let (Some(client), Some(srv)) = (&self.emby_client, &self.current_emby_server) {
+            self.emby_status_msg = Some(format!("正在准备播放: {}", item.name));
+            client.resolve_playback(srv, item.clone(), start_ms);
+        }
+    }
+
+    /// 以给定地址开始播放 Emby 项目，并开始向服务器上报进度
+    fn start_emby_stream(&mut self, item: &EmbyItem, url: String, session: PlaySession, source: Option<MediaSource>, start_ms: Option<i64>) {
+        let Some(srv) = self.current_emby_server.clone() else { return };
+        if url.is_empty() { return; }
+        self.app_mode = AppMode::Player;
+        let method = session.method.as_str();
+        match start_ms {
+            Some(ms) => self.logs.push(format!("🌐 Emby播放 [{}]: {} (从 {} 继续)", method, item.name, Self::format_time(ms))),
+            None => self.logs.push(format!("🌐 Emby播放 [{}]: {}", method, item.name)),
+        }
+        self.open_and_play_from(url, start_ms);
+        // 引擎读到容器章节后会替换这里的
+        self.chapters = item.playback_chapters();
+        self.intro_range = item.intro_range_ms();
+        if let Some((start, end)) = self.intro_range {
+            self.logs.push(format!("⏭ 片头: {} - {}", Self::format_time(start), Self::format_time(end)));
+            // 从片头之后续播时不再提示
+            self.intro_handled = start_ms.is_some_and(|ms| ms >= end);
+        }
+        if let (true, Some(client)) = (self.playback.is_some(), &self.emby_client) {
+            self.emby_reporter = Some(client.start_reporter(&srv, &item.id, &session, start_ms.unwrap_or(0)));
+        }
+        if let Some(source) = source {
+            self.emby_subtitle_tracks = emby::subtitle_tracks(&source);
+            if !self.emby_subtitle_tracks.is_empty() {
+                self.logs.push(format!("🎦 服务器提供 {} 条字幕", self.emby_subtitle_tracks.len()));
+            }
+            let default_sub = source.default_external_subtitle();
+            self.emby_source = Some((item.id.clone(), source));
+            if let Some(index) = default_sub.filter(|_| self.subtitle_enabled) {
+                self.select_emby_subtitle(index);
+            }
+        }
+    }
+
+    fn clear_chapters(&mut self) {
+        self.chapters.clear();
+        self.engine_chapters = false;
+        self.intro_range = None;
+        self.intro_handled = false;
+    }
+
+    /// 跳到下一 / 上一章节：容器章节在 mpv 上用其章节命令，其余按章节列表定位
+    fn step_chapter(&mut self, forward: bool) {
+        if self.engine_chapters {
+            if let Some(cmd_tx) = self.playback.as_ref().and_then(|pb| pb.cmd_tx.as_ref()) {
+                let _ = cmd_tx.try_send(if forward { MpvCommand::NextChapter } else { MpvCommand::PreviousChapter });
+                return;
+            }
+        }
+        let target = if forward {
+            Chapter::next_start(&self.chapters, self.position_ms)
+        } else {
+            Chapter::previous_start(&self.chapters, self.position_ms)
+        };
+        if let Some(target_ms) = target {
+            if let Some(title) = Chapter::index_at(&self.chapters, target_ms).and_then(|i| self.chapters[i].title.clone()) {
+                self.logs.push(format!("📑 {}", title));
+            }
+            self.seek_to(target_ms);
+        }
+    }
+
+    fn skip_intro_now(&mut self) {
+        let Some((_, end)) = self.intro_range else { return };
+        self.intro_handled = true;
+        self.logs.push("⏭ 已跳过片头".to_string());
+        self.seek_to(end);
+    }
+
+    /// 播放进入 Emby 标记的片头时按设置自动跳过
+    fn check_intro(&mut self) {
+        let Some((start, end)) = self.intro_range else { return };
+        if self.intro_handled || !self.playing || self.position_ms < start {
+            return;
+        }
+        // 片头只剩不到 1 秒时不再跳
+        if self.position_ms >= end - 1000 {
+            self.intro_handled = true;
+        } else if self.skip_intro {
+            self.skip_intro_now();
+        }
+    }
+
+    /// 下载服务器字幕并作为外挂字幕加载（结果见 SubtitleDownloaded 事件）
+    fn select_emby_subtitle(&mut self, index: i64) {
+        if let (Some(client), Some(srv), Some((item_id, source))) = (&self.emby_client, &self.current_emby_server, &self.emby_source) {
+            self.selected_emby_subtitle = Some(index);
+            client.download_subtitle(srv, item_id, source, index);
+        }
+    }
+
+    /// 结束当前 Emby 播放会话，上报停止位置
+    fn finish_emby_session(&mut self) {
+        if let Some(mut reporter) = self.emby_reporter.take() {
+            reporter.stop(self.position_ms);
+        }
+        self.emby_source = None;
+        self.emby_subtitle_tracks.clear();
+        self.selected_emby_subtitle = None;
+    }
+
     fn add_to_playlist(&mut self) {
         let path = self.url.clone();
         if path.is_empty() { return; }
@@ -479,17 +895,123 @@ impl BovaGuiApp {
     }
 
     fn playlist_prev(&mut self) {
-        if self.playlist.is_empty() { return; }
-        if self.playlist_index == 0 { self.playlist_index = self.playlist.len()-1; } else { self.playlist_index -= 1; }
-        let path = self.playlist[self.playlist_index].clone();
-        self.open_and_play(path);
+        if let Some(entry) = self.playlist.previous() {
+            let path = entry.url.clone();
+            self.open_and_play(path);
+        }
     }
 
     fn playlist_next(&mut self) {
-        if self.playlist.is_empty() { return; }
-        self.playlist_index = (self.playlist_index + 1) % self.playlist.len();
-        let path = self.playlist[self.playlist_index].clone();
-        self.open_and_play(path);
+        if let Some(entry) = self.playlist.next_entry() {
+            let path = entry.url.clone();
+            self.open_and_play(path);
+        }
+    }
+
+    /// 当前媒体播放完毕：单曲重复时重播，否则按播放列表前进，列表结束则停止
+    fn handle_end_of_media(&mut self) {
+        self.player.update_position(self.duration_ms, self.duration_ms);
+        if self.playlist.repeat == RepeatMode::One {
+            self.start_playback();
+            return;
+        }
+        let in_playlist = self.playlist.current_entry().is_some_and(|e| e.url == self.url);
+        if in_playlist {
+            if let Some(entry) = self.playlist.advance() {
+                let path = entry.url.clone();
+                self.logs.push(format!("⏭ 下一项: {}", entry.display_title()));
+                self.open_and_play(path);
+                return;
+            }
+        }
+        self.playing = false;
+        self.finish_emby_session();
+    }
+
+    /// 接近结尾时把播放列表的下一项交给引擎预先打开，结束后无缝衔接（见 TrackChanged）。
+    /// 列表在此之后有变化时重新排入
+    fn queue_next_item(&mut self) {
+        if self.remote_playing.is_some() || self.emby_reporter.is_some() { return; }
+        if self.duration_ms <= 0 || self.duration_ms - self.position_ms > PRELOAD_LEAD_MS { return; }
+        let Some(next_tx) = self.playback.as_ref().and_then(|pb| pb.next_tx.clone()) else { return };
+        let in_playlist = self.playlist.current_entry().is_some_and(|e| e.url == self.url);
+        let Some(url) = self.playlist.upcoming().filter(|_| in_playlist).map(|e| e.url.clone()) else { return };
+        if self.queued_next.as_deref() == Some(url.as_str()) { return; }
+        if next_tx.try_send(url.clone()).is_ok() {
+            self.queued_next = Some(url);
+        }
+    }
+
+    /// 引擎已无缝进入排入的下一项：推进播放列表并切换当前媒体，不重建引擎和音频输出
+    fn continue_with_queued(&mut self, url: String) {
+        self.queued_next = None;
+        self.player.update_position(self.duration_ms, self.duration_ms);
+        let expected = self.playlist.advance().map(|e| (e.url.clone(), e.display_title()));
+        match expected {
+            Some((path, title)) if path == url => self.logs.push(format!("⏭ 下一项: {}", title)),
+            // 排入后列表又有变化：按当前列表重新打开
+            Some((path, _)) => {
+                self.open_and_play(path);
+                return;
+            }
+            None => {
+                self.stop_playback();
+                return;
+            }
+        }
+        self.url = url.clone();
+        self.position_ms = 0;
+        self.duration_ms = 0;
+        self.resume_offer = None;
+        self.subtitle_tracks.clear();
+        self.selected_subtitle_id = None;
+        self.active_subtitles.clear();
+        self.variants.clear();
+        self.current_variant = None;
+        self.audio_only = false;
+        self.clear_chapters();
+        self.audio_anchor_pts = None;
+        self.audio_anchor_time = None;
+        self.video_anchor_pts = None;
+        self.video_anchor_time = None;
+        if let Some(volume) = self.file_state().and_then(|s| s.volume) {
+            self.volume = volume;
+            self.apply_volume();
+        }
+        // 播放核心记录续播位置；引擎已从头播放，忽略上次的位置
+        match self.player.open(&url, self.media_options.clone()) {
+            Ok(_) => { let _ = self.player.play(); }
+            Err(e) => self.logs.push(format!("✕ 打开失败: {e}")),
+        }
+        self.remember_file(url);
+    }
+
+    /// 导入播放列表文件，替换当前列表并播放第一项
+    fn import_playlist(&mut self, path: &std::path::Path) {
+        match Playlist::load_file(path) {
+            Ok(mut imported) => {
+                self.logs.push(format!("📃 导入播放列表: {} ({} 项)", path.display(), imported.len()));
+                imported.repeat = self.playlist.repeat;
+                imported.set_shuffle(self.playlist.shuffle());
+                self.playlist = imported;
+                self.playlist_next();
+            }
+            Err(e) => self.logs.push(format!("✕ 播放列表导入失败: {e}")),
+        }
+    }
+
+    fn export_playlist(&mut self) {
+        let Some(path) = FileDialog::new()
+            .add_filter("M3U8", &["m3u8"])
+            .add_filter("PLS", &["pls"])
+            .add_filter("XSPF", &["xspf"])
+            .set_file_name(format!("playlist.{}", PlaylistFormat::M3u.extension()))
+            .save_file()
+        else { return };
+        match self.playlist.save_file(&path) {
+            Ok(()) => self.logs.push(format!("📃 已导出播放列表: {}", path.display())),
+            Err(e) => self.logs.push(format!("✕ 播放列表导出失败: {e}")),
+        }
     }
     
     fn file_basename(&self) -> String {
@@ -545,6 +1067,19 @@ impl BovaGuiApp {
         }));
 
         let (emby_tx, emby_rx) = channel();
+        let (remote_tx, remote_rx) = channel();
+        let (library_tx, library_rx) = channel();
+        let server_store = config::ServerStore::open();
+        let (emby_servers, remote_connections) = server_store.load();
+        let settings = SettingsStore::open();
+        let prefs = settings.get().clone();
+        let playlist_store = PlaylistStore::open();
+        let mut playlist = playlist_store.get().clone();
+        playlist.repeat = match prefs.repeat {
+            RepeatPreference::Off => RepeatMode::Off,
+            RepeatPreference::One => RepeatMode::One,
+            RepeatPreference::All => RepeatMode::All,
+        };
 
         Self {
             url: String::new(),
@@ -558,12 +1093,19 @@ impl BovaGuiApp {
             playing: false,
             position_ms: 0,
             duration_ms: 0,
-            volume: 1.0,
-            loop_play: false,
+            volume: prefs.volume,
+            buffering_pct: None,
+            media_options: MediaOptions::default(),
+            variants: Vec::new(),
+            current_variant: None,
+            quality_mode: QualityMode::Auto,
             
-            playback_engine: PlaybackEngine::MPV,
-            hwaccel_enabled: true,
-            subtitle_enabled: true,
+            playback_engine: match prefs.engine {
+                EnginePreference::Mpv => PlaybackEngine::MPV,
+                EnginePreference::Ffmpeg => PlaybackEngine::FFmpeg,
+            },
+            hwaccel_enabled: prefs.hwaccel,
+            subtitle_enabled: prefs.subtitle_enabled,
             current_subtitle_index: None,
             mpv_command_tx: None,
             mpv_event_rx: None,
@@ -572,14 +1114,20 @@ impl BovaGuiApp {
             audio_handle: None,
             audio_stream: None,
             
-            logs: Vec::new(),
+            logs: LogBuffer::new(vec![
+                format!("⚙ 配置: {}", server_store.describe()),
+                format!("⚙ 设置: {}", settings.path().map(|p| p.display().to_string()).unwrap_or_else(|| "未保存".to_string())),
+            ]),
             show_logs: false,
             show_probe: false,
+            show_stats: false,
+            playback_stats: None,
             last_probe_json: None,
+            probe_rx: None,
             
-            playlist: Vec::new(),
-            playlist_index: 0,
-            mru: Vec::new(),
+            playlist,
+            playlist_store,
+            mru: prefs.recent,
             current_dir: None,
             
             audio_anchor_pts: None,
@@ -598,11 +1146,16 @@ impl BovaGuiApp {
             selected_subtitle_id: None,
             
             // Emby init
-            emby_servers: Self::load_servers(),
+            emby_servers,
+            server_store,
+            settings,
+            media_state: StateStore::open(),
+            settings_synced_at: Instant::now(),
             current_emby_server: None,
-            emby_client: Some(EmbyClient::new(emby_tx.clone())),
+            emby_client: EmbyClient::new(emby_tx)
+                .map_err(|e| log::warn!("failed to start Emby client: {}", e))
+                .ok(),
             emby_event_rx: emby_rx,
-            emby_event_tx: emby_tx,
             emby_items: Vec::new(),
             emby_dashboard: None,
             emby_navigation_stack: Vec::new(),
@@ -617,13 +1170,48 @@ impl BovaGuiApp {
             new_server_user: String::new(),
             new_server_pass: String::new(),
             emby_status_msg: None,
+            emby_reporter: None,
+            start_position_ms: None,
+            resume_offer: None,
+            emby_source: None,
+            emby_subtitle_tracks: Vec::new(),
+            selected_emby_subtitle: None,
+            pending_external_subs: Vec::new(),
+            crossfade_ms: prefs.crossfade_ms,
+            queued_next: None,
+            audio_only: false,
+            chapters: Vec::new(),
+            engine_chapters: false,
+            intro_range: None,
+            intro_handled: false,
+            skip_intro: prefs.skip_intro,
+            library: LocalLibrary::new(library_tx),
+            library_event_rx: library_rx,
+            library_active: false,
+            remote_connections,
+            current_remote: None,
+            remote_path: String::new(),
+            remote_entries: Vec::new(),
+            remote_loading: false,
+            remote_status_msg: None,
+            remote_browser: RemoteBrowser::new(remote_tx),
+            remote_event_rx: remote_rx,
+            remote_playing: None,
+            show_add_remote_window: false,
+            new_remote_name: String::new(),
+            new_remote_url: String::new(),
+            new_remote_user: String::new(),
+            new_remote_pass: String::new(),
             emby_image_cache: std::collections::HashMap::new(),
             emby_image_loading: std::collections::HashSet::new(),
             pending_images: Vec::new(),
             series_episode_count: std::collections::HashMap::new(),
             series_count_loading: std::collections::HashSet::new(),
-            emby_items_per_page: 20,
-            emby_current_page: 0,
+            emby_query: ItemQuery::default(),
+            emby_total_items: 0,
+            emby_genres: Vec::new(),
+            emby_search_input: String::new(),
+            emby_year_input: String::new(),
             player,
             rx,
             active_subtitles: Vec::new(),
@@ -639,6 +1227,7 @@ impl BovaGuiApp {
 impl App for BovaGuiApp {
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
         // ── Process events ──
+        self.logs.drain_records();
         while let Ok(msg) = self.rx.try_recv() {
             self.logs.push(msg.clone());
             if let Ok(evt) = serde_json::from_str::<serde_json::Value>(&msg) {
@@ -686,7 +1275,8 @@ impl App for BovaGuiApp {
                     PlaybackEvent::Stopped => { self.playing = false; self.opened = false; }
                     PlaybackEvent::Finished => {
                         self.playing = false;
-                        if self.loop_play {
+                        self.player.update_position(self.duration_ms, self.duration_ms);
+                        if self.playlist.repeat == RepeatMode::One {
                             if let Some(cmd_tx) = &self.mpv_command_tx {
                                 let _ = cmd_tx.send(PlaybackCommand::Play);
                             }
@@ -695,6 +1285,12 @@ impl App for BovaGuiApp {
                     PlaybackEvent::PositionChanged(pos) => {
                         self.position_ms = (pos * 1000.0) as i64;
                     }
+                    PlaybackEvent::Buffering(pct) => {
+                        self.buffering_pct = if pct < 100 { Some(pct) } else { None };
+                    }
+                    PlaybackEvent::VariantsAvailable(variants) => { self.variants = variants; }
+                    PlaybackEvent::VariantChanged(idx) => { self.current_variant = Some(idx); }
+                    PlaybackEvent::TrackChanged(_) | PlaybackEvent::Metadata { .. } | PlaybackEvent::Chapters(_) | PlaybackEvent::Stats(_) => {}
                     PlaybackEvent::Error(err) => {
                         self.logs.push(format!("✕ 错误: {}", err));
                     }
@@ -709,8 +1305,10 @@ impl App for BovaGuiApp {
             let subtitle_rx = pb.subtitle_rx.clone();
             let eos_rx = pb.eos_rx.clone();
             let track_info_rx = pb.track_info_rx.clone();
+            let event_rx = pb.event_rx.clone();
             let target_w = pb.target_render_w.clone();
             let target_h = pb.target_render_h.clone();
+            let stats = pb.stats.clone();
 
             // Update target render size based on video display area
             {
@@ -721,6 +1319,63 @@ impl App for BovaGuiApp {
                 target_h.store(th, Ordering::Relaxed);
             }
 
+            // Engine status events
+            if let Some(event_rx) = &event_rx {
+                while let Ok(event) = event_rx.try_recv() {
+                    match event {
+                        PlaybackEvent::Buffering(pct) => {
+                            if pct < 100 {
+                                if self.buffering_pct.is_none() {
+                                    self.logs.push("⏳ 缓冲中...".to_string());
+                                }
+                                self.buffering_pct = Some(pct);
+                            } else {
+                                self.buffering_pct = None;
+                            }
+                        }
+                        PlaybackEvent::VariantsAvailable(variants) => {
+                            self.logs.push(format!("🎚 发现 {} 个画质档位", variants.len()));
+                            self.variants = variants;
+                        }
+                        PlaybackEvent::VariantChanged(idx) => {
+                            if let Some(v) = self.variants.get(idx) {
+                                self.logs.push(format!("🎚 当前画质: {}", v));
+                            }
+                            self.current_variant = Some(idx);
+                        }
+                        PlaybackEvent::TrackChanged(url) => self.continue_with_queued(url),
+                        PlaybackEvent::Chapters(chapters) => {
+                            if !self.engine_chapters {
+                                self.logs.push(format!("📑 {} 个章节", chapters.len()));
+                            }
+                            self.chapters = chapters;
+                            self.engine_chapters = true;
+                        }
+                        PlaybackEvent::Metadata { has_video, tags } => {
+                            self.audio_only = !has_video;
+                            if let Some(title) = tags.display_title() {
+                                match &tags.album {
+                                    Some(album) => self.logs.push(format!("🎵 {} ({})", title, album)),
+                                    None => self.logs.push(format!("🎵 {}", title)),
+                                }
+                                // 播放列表中没有标题的条目用标签补上
+                                if let Some(index) = self.playlist.current {
+                                    let entry = &mut self.playlist.entries[index];
+                                    if entry.url == self.url && entry.title.is_none() {
+                                        entry.title = Some(title);
+                                    }
+                                }
+                            }
+                        }
+                        PlaybackEvent::Stats(stats) => self.playback_stats = Some(stats),
+                        PlaybackEvent::Error(err) => {
+                            self.logs.push(format!("✕ 错误: {}", err));
+                        }
+                        _ => {}
+                    }
+                }
+            }
+
             // Receive subtitle track info from MPV
             if let Some(track_rx) = &track_info_rx {
                 if let Ok(tracks) = track_rx.try_recv() {
@@ -729,12 +1384,14 @@ impl App for BovaGuiApp {
                         self.logs.push(format!("  {t}"));
                     }
                     self.subtitle_tracks = tracks;
+                    self.restore_subtitle_choice();
                 }
             }
 
             // Drain video channel to latest frame (skip intermediate frames)
             // MPV handles A/V sync internally, so we just display the newest frame
             let mut latest_frame: Option<bova_playback::VideoFrame> = self.pending_video.take();
+            let mut skipped = 0;
             while let Ok(frame) = video_rx.try_recv() {
                 // Update position and duration from every frame we see
                 if let Some(pts) = frame.pts_ms {
@@ -743,9 +1400,21 @@ impl App for BovaGuiApp {
                 if let Some(dur) = frame.duration_ms {
                     self.duration_ms = dur;
                 }
-                latest_frame = Some(frame);
+                if latest_frame.replace(frame).is_some() {
+                    skipped += 1;
+                }
             }
+            stats.record_dropped(skipped);
             if let Some(frame) = latest_frame {
+                // FFmpeg 引擎由界面按音频时钟同步（mpv 自己统计音画偏差）
+                let drift = match (self.playback_engine, frame.pts_ms, self.current_audio_time_ms()) {
+                    (PlaybackEngine::FFmpeg, Some(pts), Some(audio_ms)) => Some(audio_ms - pts),
+                    _ => None,
+                };
+                if self.playback_engine == PlaybackEngine::FFmpeg {
+                    stats.set_av_drift(drift.map(|d| d as f64));
+                }
+                stats.record_presented(drift.is_some_and(|d| d > LATE_FRAME_MS));
                 self.show_video_frame(ctx, frame);
             }
 
@@ -756,6 +1425,9 @@ impl App for BovaGuiApp {
                         Ok(af) => {
                             let buf = SamplesBuffer::new(af.channels as u16, af.sample_rate, af.samples);
                             sink.append(buf);
+                            if let Some(dur) = af.duration_ms {
+                                self.duration_ms = dur;
+                            }
                             if let Some(pts) = af.pts_ms {
                                 if self.audio_anchor_pts.is_none() {
                                     self.audio_anchor_pts = Some(pts);
@@ -769,6 +1441,13 @@ impl App for BovaGuiApp {
                 }
             }
             
+            // 纯音频没有带时间戳的视频帧，按音频时钟更新进度
+            if self.audio_only {
+                if let Some(ms) = self.current_audio_time_ms() {
+                    self.position_ms = ms;
+                }
+            }
+
             if self.subtitle_enabled {
                 let mut n = 0;
                 while n < 5 {
@@ -783,20 +1462,42 @@ impl App for BovaGuiApp {
 
             if eos_rx.try_recv().is_ok() {
                 self.logs.push("◼ 播放结束".to_string());
-                if self.loop_play {
-                    self.start_playback();
-                } else {
-                    self.playing = false;
+                self.handle_end_of_media();
+            } else if self.playing {
+                self.queue_next_item();
+                self.check_intro();
+            }
+        }
+
+        // 外挂字幕需在文件加载（出现首帧）后再交给引擎
+        if !self.pending_external_subs.is_empty() && self.video_tex.is_some() {
+            let cmd_tx = self.playback.as_ref().and_then(|pb| pb.cmd_tx.clone());
+            for path in self.pending_external_subs.drain(..) {
+                match &cmd_tx {
+                    Some(cmd_tx) => { let _ = cmd_tx.try_send(MpvCommand::LoadExternalSub(path)); }
+                    None => self.logs.push("✕ 外挂字幕仅支持 MPV 引擎".to_string()),
                 }
             }
         }
 
+        // Emby 进度上报（暂停/继续立即上报，播放中每 10 秒一次）
+        if self.playback.is_some() {
+            if let Some(reporter) = &mut self.emby_reporter {
+                reporter.tick(self.position_ms, !self.playing);
+            }
+        }
+
         // ━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━
         //  UI Layout
         // ━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━
         //  UI Layout - 只在播放器模式下显示顶部栏和底部栏
         // ━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━
 
+        // I 键切换播放诊断浮层（输入框有焦点时不响应）
+        if self.app_mode == AppMode::Player && !ctx.wants_keyboard_input() && ctx.input(|i| i.key_pressed(egui::Key::I)) {
+            self.show_stats = !self.show_stats;
+        }
+
         // 只在播放器模式下显示顶部栏和底部栏
         if self.app_mode == AppMode::Player {
             // ── Top bar ──
@@ -841,7 +1542,10 @@ impl App for BovaGuiApp {
 
                         ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
                             // Status indicator
-                            let (status_text, status_color) = if self.playing {
+                            let buffering_text = self.buffering_pct.map(|pct| format!("缓冲中 {}%", pct));
+                            let (status_text, status_color) = if let Some(text) = buffering_text.as_deref() {
+                                (text, theme::WARNING)
+                            } else if self.playing {
                                 ("播放中", theme::SUCCESS)
                             } else if self.opened {
                                 ("已暂停", theme::WARNING)
@@ -865,6 +1569,16 @@ impl App for BovaGuiApp {
                                     .size(11.0)
                                     .strong()
                             );
+
+                            // Adaptive stream quality badge
+                            if let Some(v) = self.current_variant.and_then(|i| self.variants.get(i)) {
+                                let prefix = if self.quality_mode == QualityMode::Auto { "自动 · " } else { "" };
+                                ui.label(
+                                    egui::RichText::new(format!("{}{}", prefix, v))
+                                        .color(theme::TEXT_DIM)
+                                        .size(11.0)
+                                );
+                            }
                         });
                     });
                 });
@@ -958,6 +1672,32 @@ impl App for BovaGuiApp {
                         }
                     }
 
+                    // 章节分隔与片头区间
+                    if self.duration_ms > 0 {
+                        let x_at = |ms: i64| rect.min.x + rect.width() * (ms as f32 / self.duration_ms as f32).clamp(0.0, 1.0);
+                        if let Some((start, end)) = self.intro_range {
+                            let intro_rect = egui::Rect::from_x_y_ranges(x_at(start)..=x_at(end), rect.center().y + 4.0..=rect.center().y + 6.0);
+                            painter.rect_filled(intro_rect, 1.0, theme::WARNING);
+                        }
+                        for ch in self.chapters.iter().filter(|c| c.start_ms > 0 && c.start_ms < self.duration_ms) {
+                            let x = x_at(ch.start_ms);
+                            painter.line_segment(
+                                [egui::pos2(x, rect.center().y - 5.0), egui::pos2(x, rect.center().y + 5.0)],
+                                egui::Stroke::new(2.0, theme::BG_DARK),
+                            );
+                        }
+                        if !self.chapters.is_empty() {
+                            if let Some(pos) = response.hover_pos() {
+                                let ms = (self.duration_ms as f32 * ((pos.x - rect.min.x) / rect.width()).clamp(0.0, 1.0)) as i64;
+                                if let Some(i) = Chapter::index_at(&self.chapters, ms) {
+                                    let ch = &self.chapters[i];
+                                    let title = ch.title.clone().unwrap_or_else(|| format!("第 {} 章", i + 1));
+                                    response.clone().on_hover_text_at_pointer(format!("{}  {}", Self::format_time(ms), title));
+                                }
+                            }
+                        }
+                    }
+
                     // Duration text
                     ui.label(
                         egui::RichText::new(Self::format_time(self.duration_ms))
@@ -969,6 +1709,35 @@ impl App for BovaGuiApp {
                 
                 ui.add_space(4.0);
 
+                // 续播提示
+                if let Some(resume_ms) = self.resume_offer {
+                    ui.horizontal(|ui| {
+                        ui.label(egui::RichText::new(format!("上次播放到 {}", Self::format_time(resume_ms)))
+                            .color(theme::TEXT_SECONDARY).size(12.0));
+                        if accent_button(ui, "⏯ 继续播放").clicked() {
+                            self.resume_offer = None;
+                            self.seek_to(resume_ms);
+                        }
+                        if subtle_button(ui, "从头开始").clicked() {
+                            self.resume_offer = None;
+                            self.player.clear_resume();
+                        }
+                    });
+                    ui.add_space(4.0);
+                }
+
+                // 片头期间提供跳过
+                if let Some((start, end)) = self.intro_range {
+                    if !self.intro_handled && self.position_ms >= start && self.position_ms < end {
+                        ui.horizontal(|ui| {
+                            if accent_button(ui, "⏭ 跳过片头").clicked() {
+                                self.skip_intro_now();
+                            }
+                        });
+                        ui.add_space(4.0);
+                    }
+                }
+
                 // Transport controls
                 ui.horizontal(|ui| {
                     // Left: playlist controls
@@ -1003,8 +1772,12 @@ impl App for BovaGuiApp {
                         }
                     }
                     
-                    if icon_button(ui, "⏹", "停止").clicked() { self.stop_playback(); }
+                    if icon_button(ui, "⏹", "停止").clicked() { self.finish_emby_session(); self.stop_playback(); }
                     if icon_button(ui, "⏭", "下一首").clicked() { self.playlist_next(); }
+                    if !self.chapters.is_empty() {
+                        if icon_button(ui, "⇤", "上一章节").clicked() { self.step_chapter(false); }
+                        if icon_button(ui, "⇥", "下一章节").clicked() { self.step_chapter(true); }
+                    }
 
                     ui.add_space(12.0);
                     ui.separator();
@@ -1015,12 +1788,12 @@ impl App for BovaGuiApp {
                     let vol_slider = egui::Slider::new(&mut self.volume, 0.0..=1.0)
                         .show_value(false)
                         .custom_formatter(|v, _| format!("{:.0}%", v * 100.0));
-                    if ui.add_sized(egui::vec2(80.0, 20.0), vol_slider).changed() {
-                         if let Some(pb) = &self.playback {
-                            if let Some(cmd_tx) = &pb.cmd_tx {
-                                let _ = cmd_tx.try_send(MpvCommand::SetVolume((self.volume * 100.0) as f64));
-                            }
-                        }
+                    let vol_resp = ui.add_sized(egui::vec2(80.0, 20.0), vol_slider);
+                    if vol_resp.changed() {
+                        self.apply_volume();
+                    }
+                    if vol_resp.drag_stopped() || vol_resp.clicked() {
+                        self.remember_volume();
                     }
 
                     ui.add_space(8.0);
@@ -1028,13 +1801,19 @@ impl App for BovaGuiApp {
                     ui.add_space(8.0);
 
                     // Toggles
-                    let loop_text = if self.loop_play {
-                        egui::RichText::new("🔁").color(theme::ACCENT)
-                    } else {
-                        egui::RichText::new("🔁").color(theme::TEXT_DIM)
+                    let (loop_icon, loop_tip) = match self.playlist.repeat {
+                        RepeatMode::Off => ("🔁", "循环: 关"),
+                        RepeatMode::All => ("🔁", "循环: 列表"),
+                        RepeatMode::One => ("🔂", "循环: 单曲"),
                     };
-                    if ui.add(egui::Button::new(loop_text).frame(false)).on_hover_text("循环播放").clicked() {
-                        self.loop_play = !self.loop_play;
+                    let loop_color = if self.playlist.repeat == RepeatMode::Off { theme::TEXT_DIM } else { theme::ACCENT };
+                    if ui.add(egui::Button::new(egui::RichText::new(loop_icon).color(loop_color)).frame(false)).on_hover_text(loop_tip).clicked() {
+                        self.playlist.repeat = self.playlist.repeat.cycle();
+                    }
+                    let shuffle = self.playlist.shuffle();
+                    let shuffle_color = if shuffle { theme::ACCENT } else { theme::TEXT_DIM };
+                    if ui.add(egui::Button::new(egui::RichText::new("🔀").color(shuffle_color)).frame(false)).on_hover_text("随机播放").clicked() {
+                        self.playlist.set_shuffle(!shuffle);
                     }
 
                     ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
@@ -1045,6 +1824,9 @@ impl App for BovaGuiApp {
                         if subtle_button(ui, if self.show_probe { "🔍 信息 ▾" } else { "🔍 信息" }).clicked() {
                             self.show_probe = !self.show_probe;
                         }
+                        if subtle_button(ui, if self.show_stats { "📊 统计 ▾" } else { "📊 统计" }).on_hover_text("播放诊断（I 键）").clicked() {
+                            self.show_stats = !self.show_stats;
+                        }
                     });
                 });
             });
@@ -1091,9 +1873,9 @@ impl App for BovaGuiApp {
                         if subtle_button(ui, "📂 选择文件").clicked() {
                             self.pick_and_play_file();
                         }
-                        if subtle_button(ui, "🔍 Probe").clicked() {
-                            let info = bova_probe::probe(&self.url);
-                            self.last_probe_json = Some(serde_json::to_string_pretty(&info).unwrap());
+                        let probing = self.probe_rx.is_some();
+                        if subtle_button(ui, if probing { "⏳ Probe…" } else { "🔍 Probe" }).clicked() && !probing {
+                            self.start_probe();
                         }
                     });
                     
@@ -1113,6 +1895,47 @@ impl App for BovaGuiApp {
                         ui.radio_value(&mut self.playback_engine, PlaybackEngine::FFmpeg, 
                             egui::RichText::new("FFmpeg").size(12.0));
                     });
+                    ui.horizontal(|ui| {
+                        ui.label(egui::RichText::new("淡入淡出:").color(theme::TEXT_DIM).size(12.0));
+                        ui.add(egui::Slider::new(&mut self.crossfade_ms, 0..=10_000).step_by(500.0).suffix(" ms"))
+                            .on_hover_text("播放列表项之间的音频交叉淡化，0 为无缝衔接（仅 FFmpeg 引擎）");
+                    });
+                    ui.horizontal(|ui| {
+                        ui.checkbox(&mut self.skip_intro,
+                            egui::RichText::new("自动跳过片头").color(theme::TEXT_PRIMARY).size(13.0))
+                            .on_hover_text("Emby 服务器标记了片头时自动跳过");
+                    });
+
+                    // ── Quality Section (HLS/DASH) ──
+                    if !self.variants.is_empty() {
+                        ui.add_space(4.0);
+                        ui.separator();
+                        section_header(ui, "画质");
+                        let mut chosen: Option<QualityMode> = None;
+                        let is_auto = self.quality_mode == QualityMode::Auto;
+                        let auto_label = match self.current_variant.and_then(|i| self.variants.get(i)) {
+                            Some(v) if is_auto => format!("自动 ({})", v),
+                            _ => "自动".to_string(),
+                        };
+                        if ui.selectable_label(is_auto,
+                            egui::RichText::new(auto_label).color(if is_auto { theme::ACCENT } else { theme::TEXT_SECONDARY }).size(12.0)
+                        ).clicked() {
+                            chosen = Some(QualityMode::Auto);
+                        }
+                        for v in &self.variants {
+                            let is_selected = self.quality_mode == QualityMode::Fixed(v.index);
+                            if ui.selectable_label(is_selected,
+                                egui::RichText::new(v.to_string())
+                                    .color(if is_selected { theme::ACCENT } else { theme::TEXT_SECONDARY })
+                                    .size(12.0)
+                            ).clicked() {
+                                chosen = Some(QualityMode::Fixed(v.index));
+                            }
+                        }
+                        if let Some(mode) = chosen {
+                            self.set_quality(mode);
+                        }
+                    }
 
                     // ── Subtitle Section ──
                     ui.add_space(4.0);
@@ -1148,6 +1971,7 @@ impl App for BovaGuiApp {
                                     let _ = cmd_tx.try_send(MpvCommand::DisableSubtitle);
                                 }
                             }
+                            self.update_file_state(|s| { s.subtitle_track = None; s.subtitle_off = true; });
                         }
 
                         // Track options
@@ -1166,12 +1990,30 @@ impl App for BovaGuiApp {
                                         let _ = cmd_tx.try_send(MpvCommand::SelectSubtitle(track.id));
                                     }
                                 }
+                                self.update_file_state(|s| { s.subtitle_track = Some(track.id); s.subtitle_off = false; });
                             }
                         }
                     } else if self.playing {
                         ui.label(egui::RichText::new("无字幕轨道").color(theme::TEXT_DIM).size(11.0));
                     }
 
+                    // Emby 服务器提供的字幕（下载后作为外挂字幕加载）
+                    if !self.emby_subtitle_tracks.is_empty() {
+                        ui.add_space(4.0);
+                        ui.label(egui::RichText::new("服务器字幕:").color(theme::TEXT_DIM).size(12.0));
+                        let tracks_snapshot = self.emby_subtitle_tracks.clone();
+                        for track in &tracks_snapshot {
+                            let is_selected = self.selected_emby_subtitle == Some(track.id);
+                            if ui.selectable_label(is_selected,
+                                egui::RichText::new(format!("{}", track))
+                                    .color(if is_selected { theme::ACCENT } else { theme::TEXT_SECONDARY })
+                                    .size(12.0)
+                            ).clicked() && !is_selected {
+                                self.select_emby_subtitle(track.id);
+                            }
+                        }
+                    }
+
                     // Load external subtitle
                     ui.add_space(4.0);
                     if subtle_button(ui, "📄 加载外部字幕").clicked() {
@@ -1204,32 +2046,48 @@ impl App for BovaGuiApp {
                     }
                     
                     // Playlist
+                    ui.add_space(4.0);
+                    ui.separator();
+                    section_header(ui, "播放列表");
+                    ui.horizontal(|ui| {
+                        if ui.small_button("📂 导入").clicked() {
+                            if let Some(path) = FileDialog::new()
+                                .add_filter("播放列表", &["m3u", "m3u8", "pls", "xspf"])
+                                .pick_file()
+                            {
+                                self.import_playlist(&path);
+                            }
+                        }
+                        if !self.playlist.is_empty() {
+                            if ui.small_button("💾 导出").clicked() { self.export_playlist(); }
+                            if ui.small_button("🗑 清空").clicked() { self.playlist.clear(); }
+                        }
+                    });
                     if !self.playlist.is_empty() {
-                        ui.add_space(4.0);
-                        ui.separator();
-                        section_header(ui, "播放列表");
-                        
                         egui::ScrollArea::vertical().max_height(200.0).show(ui, |ui| {
-                            let playlist_snapshot = self.playlist.clone();
-                            for (i, it) in playlist_snapshot.iter().enumerate() {
-                                let basename = std::path::Path::new(it)
-                                    .file_name()
-                                    .map(|n| n.to_string_lossy().to_string())
-                                    .unwrap_or_else(|| it.clone());
-                                let is_current = i == self.playlist_index;
-                                let text = if is_current {
-                                    egui::RichText::new(format!("▶ {}", basename))
-                                        .color(theme::ACCENT)
-                                        .size(12.0)
-                                } else {
-                                    egui::RichText::new(format!("  {}", basename))
-                                        .color(theme::TEXT_SECONDARY)
-                                        .size(12.0)
-                                };
-                                if ui.add(egui::Label::new(text).sense(egui::Sense::click())).clicked() {
-                                    let path = it.clone();
-                                    self.open_and_play(path);
+                            let entries = self.playlist.entries.clone();
+                            let last = entries.len() - 1;
+                            for (i, entry) in entries.iter().enumerate() {
+                                let is_current = self.playlist.current == Some(i);
+                                let mut label = format!("{} {}", if is_current { "▶" } else { " " }, entry.display_title());
+                                if let Some(ms) = entry.duration_ms {
+                                    label.push_str(&format!("  {}", Self::format_time(ms)));
                                 }
+                                let color = if is_current { theme::ACCENT } else { theme::TEXT_SECONDARY };
+                                ui.horizontal(|ui| {
+                                    if ui.add(egui::Label::new(egui::RichText::new(label).color(color).size(12.0)).sense(egui::Sense::click()))
+                                        .on_hover_text(&entry.url)
+                                        .clicked()
+                                    {
+                                        self.playlist.select(i);
+                                        self.open_and_play(entry.url.clone());
+                                    }
+                                    ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                                        if ui.small_button("✕").on_hover_text("移除").clicked() { self.playlist.remove(i); }
+                                        if i < last && ui.small_button("↓").clicked() { self.playlist.move_entry(i, i + 1); }
+                                        if i > 0 && ui.small_button("↑").clicked() { self.playlist.move_entry(i, i - 1); }
+                                    });
+                                });
                             }
                         });
                     }
@@ -1280,20 +2138,30 @@ impl App for BovaGuiApp {
                             ).frame(false)).clicked() {
                                 self.logs.clear();
                             }
+                            egui::ComboBox::from_id_source("log_level")
+                                .selected_text(logs::level_label(self.logs.min_level))
+                                .width(70.0)
+                                .show_ui(ui, |ui| {
+                                    for level in logs::LEVELS {
+                                        ui.selectable_value(&mut self.logs.min_level, level, logs::level_label(level));
+                                    }
+                                })
+                                .response
+                                .on_hover_text("显示该级别及以上的日志；收集的级别由 BOVA_LOG 环境变量控制");
                         });
                     });
                     egui::ScrollArea::vertical()
                         .stick_to_bottom(true)
                         .show(ui, |ui| {
-                            for line in &self.logs {
-                                let color = if line.starts_with('✕') {
-                                    theme::ERROR
-                                } else if line.starts_with('▶') {
-                                    theme::SUCCESS
-                                } else if line.starts_with('◼') {
-                                    theme::WARNING
-                                } else {
-                                    theme::TEXT_DIM
+                            for entry in self.logs.visible() {
+                                let line = &entry.text;
+                                let color = match entry.level {
+                                    log::Level::Error => theme::ERROR,
+                                    log::Level::Warn => theme::WARNING,
+                                    _ if line.starts_with('▶') => theme::SUCCESS,
+                                    _ if line.starts_with('◼') => theme::WARNING,
+                                    log::Level::Info => theme::TEXT_SECONDARY,
+                                    _ => theme::TEXT_DIM,
                                 };
                                 ui.label(
                                     egui::RichText::new(line)
@@ -1374,6 +2242,12 @@ impl App for BovaGuiApp {
                             }
                         }
                         
+                        if self.show_stats {
+                            if let Some(stats) = &self.playback_stats {
+                                render_stats_overlay(ui, &painter, rect, stats);
+                            }
+                        }
+
                         // 浮动控制按钮 - 鼠标悬停在视频中央时显示
                         let mouse_pos = response.hover_pos();
                         let show_floating_controls = if let Some(pos) = mouse_pos {
@@ -1518,6 +2392,15 @@ impl App for BovaGuiApp {
 
         // ── Process Emby Events ──
         self.process_emby_events();
+        self.process_remote_events();
+        self.process_library_events();
+        self.process_probe_result();
+        if self.playing && self.duration_ms > 0 {
+            self.player.update_position(self.position_ms, self.duration_ms);
+        }
+        if self.settings_synced_at.elapsed() >= Duration::from_secs(2) {
+            self.sync_settings();
+        }
         
         // ── Process pending images ──
         if !self.pending_images.is_empty() {
@@ -1552,6 +2435,18 @@ impl App for BovaGuiApp {
                         }
                         self.show_emby_ui(ctx, ui);
                     }
+                    AppMode::Remote => {
+                        if self.current_remote.is_none() {
+                            egui::TopBottomPanel::top("remote_top_nav").frame(egui::Frame::none().fill(theme::BG_PANEL).inner_margin(8.0)).show_inside(ui, |ui| {
+                                ui.horizontal(|ui| {
+                                    if ui.button("⬅ 返回首页").clicked() {
+                                        self.app_mode = AppMode::Welcome;
+                                    }
+                                });
+                            });
+                        }
+                        self.show_remote_ui(ctx, ui);
+                    }
                 }
             });
 
@@ -1562,6 +2457,11 @@ impl App for BovaGuiApp {
             ctx.request_repaint_after(std::time::Duration::from_millis(100));
         }
     }
+
+    fn on_exit(&mut self, _gl: Option<&eframe::glow::Context>) {
+        let _ = self.player.stop();
+        self.sync_settings();
+    }
 }
 
 impl BovaGuiApp {
@@ -1592,7 +2492,7 @@ impl BovaGuiApp {
             
             // 两个大按钮
             ui.horizontal(|ui| {
-                ui.add_space((available.x - 600.0) / 2.0);
+                ui.add_space((available.x - 920.0) / 2.0);
                 
                 // 本地播放按钮
                 let local_btn = egui::Button::new(
@@ -1627,6 +2527,24 @@ impl BovaGuiApp {
                     // 总是先进入服务器列表页
                     self.emby_view_mode = EmbyViewMode::ServerList;
                 }
+
+                ui.add_space(40.0);
+
+                // 网络共享按钮
+                let remote_btn = egui::Button::new(
+                    egui::RichText::new("📁\n\n网络共享")
+                        .size(24.0)
+                        .color(egui::Color32::WHITE)
+                )
+                .fill(theme::BG_SURFACE)
+                .stroke(egui::Stroke::new(2.0, theme::BORDER_BRIGHT))
+                .rounding(egui::Rounding::same(12.0))
+                .min_size(egui::vec2(280.0, 200.0));
+
+                if ui.add(remote_btn).clicked() {
+                    self.app_mode = AppMode::Remote;
+                    self.current_remote = None;
+                }
             });
             
             ui.add_space(40.0);
@@ -1642,30 +2560,16 @@ impl BovaGuiApp {
                     .color(theme::TEXT_DIM)
                     .size(13.0)
             );
+            ui.label(
+                egui::RichText::new("网络共享：浏览 WebDAV / FTP 上的文件")
+                    .color(theme::TEXT_DIM)
+                    .size(13.0)
+            );
         });
     }
     
-    fn load_servers() -> Vec<EmbyServer> {
-        if let Ok(path) = std::env::current_dir() {
-             let path = path.join("bova_emby_config.json");
-             if path.exists() {
-                 if let Ok(file) = std::fs::File::open(path) {
-                     if let Ok(servers) = serde_json::from_reader(file) {
-                         return servers;
-                     }
-                 }
-             }
-        }
-        Vec::new()
-    }
-
     fn save_servers(&self) {
-        if let Ok(path) = std::env::current_dir() {
-             let path = path.join("bova_emby_config.json");
-             if let Ok(file) = std::fs::File::create(path) {
-                 let _ = serde_json::to_writer_pretty(file, &self.emby_servers);
-             }
-        }
+        self.server_store.save(&self.emby_servers, &self.remote_connections);
     }
     
     // Extract existing player UI into helper
@@ -1790,18 +2694,18 @@ impl BovaGuiApp {
                                               }
                                          }
                                      }
-                                     if icon_button(ui, "⏹", "停止").clicked() { self.stop_playback(); }
+                                     if icon_button(ui, "⏹", "停止").clicked() { self.finish_emby_session(); self.stop_playback(); }
                                      if icon_button(ui, "⏭", "下一首").clicked() { self.playlist_next(); }
                                      
                                      ui.add_space(20.0);
                                      ui.label("🔊");
                                      let vol_slider = egui::Slider::new(&mut self.volume, 0.0..=1.0).show_value(false);
-                                     if ui.add_sized(egui::vec2(80.0, 20.0), vol_slider).changed() {
-                                          if let Some(pb) = &self.playback {
-                                              if let Some(cmd_tx) = &pb.cmd_tx {
-                                                  let _ = cmd_tx.try_send(MpvCommand::SetVolume((self.volume * 100.0) as f64));
-                                              }
-                                          }
+                                     let vol_resp = ui.add_sized(egui::vec2(80.0, 20.0), vol_slider);
+                                     if vol_resp.changed() {
+                                          self.apply_volume();
+                                     }
+                                     if vol_resp.drag_stopped() || vol_resp.clicked() {
+                                          self.remember_volume();
                                      }
                                      
                                      ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
@@ -1873,7 +2777,7 @@ impl BovaGuiApp {
         while let Ok(event) = self.emby_event_rx.try_recv() {
             match event {
                 EmbyEvent::AuthSuccess(server) => {
-                    self.emby_status_msg = Some(format!("已连接到 {}", server.name));
+                    self.emby_status_msg = Some(format!("已连接到 {} ({})", server.name, server.kind));
                     self.emby_servers.push(server.clone());
                     self.save_servers();
                     self.current_emby_server = Some(server);
@@ -1899,35 +2803,7 @@ impl BovaGuiApp {
                                 // 为每个 Season 加载 Episodes
                                 if let (Some(client), Some(srv)) = (&self.emby_client, &self.current_emby_server) {
                                     for season in &items {
-                                        let season_id = season.id.clone();
-                                        let tx = self.emby_event_tx.clone();
-                                        let srv_clone = srv.clone();
-                                        
-                                        std::thread::spawn(move || {
-                                            if let (Some(token), Some(user_id)) = (&srv_clone.access_token, &srv_clone.user_id) {
-                                                let client = reqwest::blocking::Client::new();
-                                                let fields = "Fields=PrimaryImageAspectRatio,Overview";
-                                                let url = format!(
-                                                    "{}/Users/{}/Items?ParentId={}&{}", 
-                                                    srv_clone.url.trim_end_matches('/'), 
-                                                    user_id, 
-                                                    season_id, 
-                                                    fields
-                                                );
-                                                
-                                                if let Ok(resp) = client.get(&url).header("X-Emby-Token", token).send() {
-                                                    if resp.status().is_success() {
-                                                        #[derive(serde::Deserialize)]
-                                                        struct ItemsResp {
-                                                            Items: Vec<EmbyItem>,
-                                                        }
-                                                        if let Ok(data) = resp.json::<ItemsResp>() {
-                                                            let _ = tx.send(EmbyEvent::SeasonEpisodesLoaded(season_id, data.Items));
-                                                        }
-                                                    }
-                                                }
-                                            }
-                                        });
+                                        client.get_season_episodes(srv, &season.id);
                                     }
                                 }
                                 return;
@@ -1936,11 +2812,23 @@ impl BovaGuiApp {
                     }
                     
                     // 否则是正常的浏览器模式
+                    self.emby_total_items = items.len();
                     self.emby_items = items;
-                    self.emby_current_page = 0; // 重置到第一页
                     self.emby_view_mode = EmbyViewMode::Browser; // Switch to browser when items loaded
                     self.emby_status_msg = None;
                 }
+                EmbyEvent::ItemPageLoaded(page) => {
+                    // 丢弃过期查询的结果（用户已翻页或修改了条件）
+                    if page.query == self.emby_query {
+                        self.emby_items = page.items;
+                        self.emby_total_items = page.total;
+                        self.emby_view_mode = EmbyViewMode::Browser;
+                        self.emby_status_msg = None;
+                    }
+                }
+                EmbyEvent::GenresLoaded(genres) => {
+                    self.emby_genres = genres;
+                }
                 EmbyEvent::DashboardLoaded(dash) => {
                     // 保存 dashboard
                     self.emby_dashboard = Some(dash.clone());
@@ -1949,40 +2837,7 @@ impl BovaGuiApp {
                     // 自动加载每个 View 的内容（递归查找影片）
                     if let (Some(client), Some(srv)) = (&self.emby_client, &self.current_emby_server) {
                         for view in &dash.views {
-                            let view_id = view.id.clone();
-                            let tx = self.emby_event_tx.clone();
-                            let srv_clone = srv.clone();
-                            
-                            // 在后台线程递归加载内容
-                            std::thread::spawn(move || {
-                                if let (Some(token), Some(user_id)) = (&srv_clone.access_token, &srv_clone.user_id) {
-                                    let client = reqwest::blocking::Client::new();
-                                    
-                                    // 使用 Recursive=true 参数递归获取
-                                    // IncludeItemTypes: Movie,Series,Audio,Photo,MusicAlbum
-                                    // 显示 Movie 和 Series（不显示单个 Episode），同时支持音乐和照片
-                                    let fields = "Fields=PrimaryImageAspectRatio,Overview,ProductionYear,CommunityRating,OfficialRating,ChildCount,RecursiveItemCount";
-                                    let url = format!(
-                                        "{}/Users/{}/Items?ParentId={}&Recursive=true&IncludeItemTypes=Movie,Series,Audio,Photo,MusicAlbum&Limit=12&SortBy=DateCreated,SortName&SortOrder=Descending&{}", 
-                                        srv_clone.url.trim_end_matches('/'), 
-                                        user_id, 
-                                        view_id, 
-                                        fields
-                                    );
-                                    
-                                    if let Ok(resp) = client.get(&url).header("X-Emby-Token", token).send() {
-                                        if resp.status().is_success() {
-                                            #[derive(serde::Deserialize)]
-                                            struct ItemsResp {
-                                                Items: Vec<EmbyItem>,
-                                            }
-                                            if let Ok(data) = resp.json::<ItemsResp>() {
-                                                let _ = tx.send(EmbyEvent::ViewItemsLoaded(view_id, data.Items));
-                                            }
-                                        }
-                                    }
-                                }
-                            });
+                            client.get_view_items(srv, &view.id);
                         }
                     }
                 }
@@ -2014,6 +2869,42 @@ impl BovaGuiApp {
                     self.series_episode_count.insert(series_id.clone(), count);
                     self.series_count_loading.remove(&series_id);
                 }
+                EmbyEvent::PlaybackResolved(item, selection, start_ms) => {
+                    let selection = *selection;
+                    self.emby_status_msg = None;
+                    let src = &selection.source;
+                    self.logs.push(format!(
+                        "🌐 媒体源: {} · {} · {}",
+                        src.name.as_deref().unwrap_or(&src.id),
+                        src.container.as_deref().unwrap_or("?"),
+                        src.bitrate.map(|b| format_bitrate(Some(b))).unwrap_or_else(|| "未知码率".to_string()),
+                    ));
+                    self.start_emby_stream(&item, selection.url, selection.session, Some(selection.source), start_ms);
+                }
+                EmbyEvent::SubtitleDownloaded(index, path) => {
+                    // 下载期间已切换到其他字幕或停止播放则丢弃
+                    if self.selected_emby_subtitle == Some(index) {
+                        self.logs.push(format!("📄 已下载服务器字幕 #{}", index));
+                        self.pending_external_subs.push(path);
+                    }
+                }
+                EmbyEvent::SubtitleError(e) => {
+                    self.selected_emby_subtitle = None;
+                    self.logs.push(format!("✕ {}", e));
+                }
+                EmbyEvent::PlaybackError(e) => {
+                    self.emby_status_msg = Some(format!("播放失败: {}", e));
+                    self.logs.push(format!("✕ Emby播放失败: {}", e));
+                }
+                EmbyEvent::RequestFailed(request, e) => match request {
+                    // 图片失败保留在 loading 集合里，避免每帧重复请求
+                    EmbyRequest::Image(key) => log::warn!("image {} failed: {}", key, e),
+                    EmbyRequest::EpisodeCount(series_id) => {
+                        self.series_count_loading.remove(&series_id);
+                    }
+                    EmbyRequest::Report => {} // 已在上报任务中输出
+                    other => self.logs.push(format!("✕ Emby请求失败 {:?}: {}", other, e)),
+                },
             }
         }
     }
@@ -2108,11 +2999,14 @@ impl BovaGuiApp {
                                 self.emby_status_msg = Some("正在连接...".to_string());
                                 if let Some(client) = &self.emby_client {
                                     let server = EmbyServer {
-                                        name: self.new_server_url.clone(), // Temp name
+                                        name: self.new_server_url.clone(), // 识别成功后替换为 ServerName
                                         url: self.new_server_url.clone(),
                                         username: self.new_server_user.clone(),
                                         user_id: None,
                                         access_token: None,
+                                        kind: Default::default(),
+                                        version: None,
+                                        max_bitrate: None,
                                     };
                                     client.authenticate(server, self.new_server_pass.clone());
                                 }
@@ -2140,6 +3034,9 @@ impl BovaGuiApp {
         
         ui.add_space(10.0);
         ui.separator();
+
+        self.show_library_entry(ui);
+        ui.add_space(10.0);
         
         let mut delete_idx = None;
         egui::ScrollArea::vertical().show(ui, |ui| {
@@ -2149,7 +3046,7 @@ impl BovaGuiApp {
                         ui.label(egui::RichText::new("🌐").size(20.0));
                         ui.vertical(|ui| {
                             ui.heading(&server.name);
-                            ui.label(&server.url);
+                            ui.label(format!("{} · {}", server.kind, server.url));
                         });
                         ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
                             if ui.button("🗑").on_hover_text("删除").clicked() {
@@ -2169,7 +3066,8 @@ impl BovaGuiApp {
         });
         
         if let Some(i) = delete_idx {
-            self.emby_servers.remove(i);
+            let server = self.emby_servers.remove(i);
+            self.server_store.forget_emby(&server);
             self.save_servers();
         }
     }
@@ -2178,8 +3076,10 @@ impl BovaGuiApp {
          // 顶部导航栏 - 返回服务器列表
          ui.horizontal(|ui| {
              if ui.add(egui::Button::new("⬅ 服务器列表").min_size(egui::vec2(100.0, 28.0))).clicked() {
+                 self.cancel_emby_navigation();
                  self.emby_view_mode = EmbyViewMode::ServerList;
                  self.current_emby_server = None;
+                 self.library_active = false;
                  self.emby_dashboard = None;
                  self.emby_items.clear();
                  self.emby_navigation_stack.clear();
@@ -2187,6 +3087,49 @@ impl BovaGuiApp {
              ui.separator();
              if let Some(server) = &self.current_emby_server {
                  ui.label(egui::RichText::new(&server.name).color(theme::TEXT_PRIMARY).size(14.0));
+             } else if self.library_active {
+                 ui.label(egui::RichText::new("📚 本地媒体库").color(theme::TEXT_PRIMARY).size(14.0));
+                 if self.library.scanning() {
+                     ui.label(egui::RichText::new("正在扫描...").color(theme::TEXT_DIM).size(12.0));
+                 }
+             }
+             // 每个服务器单独保存的最大码率，超过则由服务器转码
+             let mut new_bitrate = None;
+             let mut start_search = false;
+             if self.current_emby_server.is_some() || self.library_active {
+                 ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                     if let Some(server) = &self.current_emby_server {
+                         egui::ComboBox::from_id_source("emby_max_bitrate")
+                             .selected_text(format_bitrate(server.max_bitrate))
+                             .show_ui(ui, |ui| {
+                                 for &preset in BITRATE_PRESETS {
+                                     if ui.selectable_label(server.max_bitrate == preset, format_bitrate(preset)).clicked() {
+                                         new_bitrate = Some(preset);
+                                     }
+                                 }
+                             });
+                         ui.label(egui::RichText::new("最大码率").color(theme::TEXT_DIM).size(12.0));
+                         ui.add_space(16.0);
+                     }
+                     let search = ui.add(egui::TextEdit::singleline(&mut self.emby_search_input)
+                         .hint_text("🔍 搜索影片、剧集")
+                         .desired_width(220.0));
+                     if search.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) {
+                         start_search = true;
+                     }
+                 });
+             }
+             if let (Some(bitrate), Some(server)) = (new_bitrate, self.current_emby_server.as_mut()) {
+                 server.max_bitrate = bitrate;
+                 let (url, user) = (server.url.clone(), server.username.clone());
+                 for s in self.emby_servers.iter_mut().filter(|s| s.url == url && s.username == user) {
+                     s.max_bitrate = bitrate;
+                 }
+                 self.logs.push(format!("🌐 最大码率: {}", format_bitrate(bitrate)));
+                 self.save_servers();
+             }
+             if start_search && !self.emby_search_input.trim().is_empty() {
+                 self.search_emby(self.emby_search_input.trim().to_string());
              }
          });
          ui.separator();
@@ -2198,7 +3141,29 @@ impl BovaGuiApp {
              egui::ScrollArea::vertical().show(ui, |ui| {
                  ui.add_space(10.0);
                  
-                 // 只显示媒体库，去掉"继续观看"
+                 // 继续观看：从服务器记录的位置续播
+                 if !dash.resume_items.is_empty() {
+                     ui.heading(egui::RichText::new("继续观看").size(20.0).strong());
+                     ui.add_space(10.0);
+                     let mut clicked_item = None;
+                     egui::ScrollArea::horizontal()
+                         .id_source("row_resume")
+                         .show(ui, |ui| {
+                             ui.horizontal(|ui| {
+                                 for item in &dash.resume_items {
+                                     if self.render_wide_item_card(ui, item) {
+                                         clicked_item = Some(item.clone());
+                                     }
+                                 }
+                             });
+                         });
+                     if let Some(item) = clicked_item {
+                         self.play_emby_item(&item, true);
+                     }
+                     ui.add_space(5.0);
+                     ui.separator();
+                 }
+
                  if !dash.views.is_empty() {
                      // 克隆 views 以避免借用问题
                      let views = dash.views.clone();
@@ -2231,12 +3196,7 @@ impl BovaGuiApp {
                          if clicked_more {
                              self.emby_navigation_stack.clear();
                              self.emby_navigation_stack.push((view_id.clone(), view_name.clone()));
-                             self.emby_current_page = 0;
-                             if let Some(client) = &self.emby_client {
-                                 if let Some(srv) = &self.current_emby_server {
-                                     client.get_items(srv, Some(view_id.clone()), true);  // 浏览器模式，使用递归
-                                 }
-                             }
+                             self.browse_emby(ItemQuery::browse(&view_id, true));  // 浏览器模式，使用递归
                          }
                          
                          ui.add_space(10.0);
@@ -2513,7 +3473,7 @@ impl BovaGuiApp {
             
             // 额外信息
             let mut info_parts = Vec::new();
-            if let Some(series) = &item.original_title {
+            if let Some(series) = &item.series_name {
                 info_parts.push(series.clone());
             }
             if let (Some(season), Some(episode)) = (item.parent_index_number, item.index_number) {
@@ -2530,6 +3490,15 @@ impl BovaGuiApp {
                 );
             }
             
+            // 观看进度条
+            if let Some(pct) = item.user_data.as_ref().and_then(|d| d.played_percentage) {
+                let bar = egui::Rect::from_min_size(
+                    egui::pos2(rect.min.x, rect.max.y - 4.0),
+                    egui::vec2(width * (pct as f32 / 100.0).clamp(0.0, 1.0), 4.0)
+                );
+                ui.painter().rect_filled(bar, 0.0, theme::ACCENT);
+            }
+            
             // Hover 效果
             if response.hovered() {
                 ui.painter().rect_stroke(rect, 8.0, egui::Stroke::new(3.0, theme::ACCENT));
@@ -2611,6 +3580,13 @@ impl BovaGuiApp {
     
     // 加载 Emby 图片
     fn load_emby_image(&mut self, item: &EmbyItem, is_backdrop: bool) {
+        if library::is_local_id(&item.id) {
+            // 本地条目读取旁边的海报文件；没有图片也记入 loading，避免每帧重复查找
+            let image_key = format!("{}_{}", item.id, if is_backdrop { "Backdrop" } else { "Primary" });
+            self.library.load_image(&item.id, &image_key, is_backdrop);
+            self.emby_image_loading.insert(image_key);
+            return;
+        }
         if let Some(srv) = &self.current_emby_server {
             let image_key = format!("{}_{}", item.id, if is_backdrop { "Backdrop" } else { "Primary" });
             
@@ -2624,18 +3600,10 @@ impl BovaGuiApp {
             if let Some(tag) = tag {
                 self.emby_image_loading.insert(image_key.clone());
                 
-                let url = EmbyClient::get_image_url(srv, &item.id, &tag, is_backdrop);
-                let tx = self.emby_event_tx.clone();
-                
-                // 在后台线程加载图片
-                std::thread::spawn(move || {
-                    if let Ok(response) = reqwest::blocking::get(&url) {
-                        if let Ok(bytes) = response.bytes() {
-                            // 发送图片数据回主线程
-                            let _ = tx.send(EmbyEvent::ImageLoaded(image_key, bytes.to_vec()));
-                        }
-                    }
-                });
+                let url = srv.image_url(&item.id, &tag, is_backdrop);
+                if let Some(client) = &self.emby_client {
+                    client.load_image(&image_key, &url);
+                }
             }
         }
     }
@@ -2644,8 +3612,10 @@ impl BovaGuiApp {
         // 面包屑导航 - 紧凑单行
         ui.horizontal(|ui| {
             if ui.add(egui::Button::new("⬅ 服务器").min_size(egui::vec2(80.0, 28.0))).clicked() {
+                self.cancel_emby_navigation();
                 self.emby_view_mode = EmbyViewMode::ServerList;
                 self.current_emby_server = None;
+                self.library_active = false;
                 self.emby_dashboard = None;
                 self.emby_items.clear();
                 self.emby_navigation_stack.clear();
@@ -2655,9 +3625,11 @@ impl BovaGuiApp {
             ui.separator();
             
             if ui.add(egui::Button::new("🏠 首页").min_size(egui::vec2(70.0, 28.0))).clicked() {
+                self.cancel_emby_navigation();
                 self.emby_view_mode = EmbyViewMode::Dashboard;
-                self.emby_current_page = 0;
-                if let (Some(client), Some(srv)) = (&self.emby_client, &self.current_emby_server) {
+                if self.library_active {
+                    self.refresh_library_dashboard();
+                } else if let (Some(client), Some(srv)) = (&self.emby_client, &self.current_emby_server) {
                     client.get_dashboard(srv);
                 }
                 return;
@@ -2674,13 +3646,12 @@ impl BovaGuiApp {
             if let Some(idx) = target_idx {
                 // truncate stack to idx+1 (keep the clicked one)
                 self.emby_navigation_stack.truncate(idx + 1);
-                self.emby_current_page = 0;
-                let (pid, _) = &self.emby_navigation_stack.last().unwrap();
-                if let Some(client) = &self.emby_client {
-                    if let Some(srv) = &self.current_emby_server {
-                       client.get_items(srv, Some(pid.clone()), true);  // 浏览器模式，使用递归
-                    }
-                }
+                let (pid, _) = self.emby_navigation_stack.last().unwrap().clone();
+                self.browse_emby(ItemQuery::browse(&pid, true));  // 浏览器模式，使用递归
+            }
+
+            if let Some(term) = self.emby_query.search_term.as_deref().filter(|_| self.emby_navigation_stack.is_empty()) {
+                ui.label(egui::RichText::new(format!("/ 搜索: {}", term)).color(theme::TEXT_SECONDARY));
             }
         });
         ui.separator();
@@ -2689,11 +3660,15 @@ impl BovaGuiApp {
             ui.label(msg);
         }
 
-        // 计算分页
-        let total_items = self.emby_items.len();
-        let total_pages = (total_items + self.emby_items_per_page - 1) / self.emby_items_per_page;
-        let start_idx = self.emby_current_page * self.emby_items_per_page;
-        let end_idx = (start_idx + self.emby_items_per_page).min(total_items);
+        self.show_emby_filter_bar(ui);
+        ui.add_space(6.0);
+
+        // 服务器端分页：emby_items 即当前页
+        let total_items = self.emby_total_items;
+        let per_page = self.emby_query.limit.max(1);
+        let total_pages = total_items.div_ceil(per_page);
+        let current_page = self.emby_query.start_index / per_page;
+        let mut goto_page = None;
         
         // 单行分页控制栏
         ui.horizontal(|ui| {
@@ -2703,34 +3678,40 @@ impl BovaGuiApp {
                 ui.add_space(20.0);
                 
                 // 紧凑的分页按钮
-                if ui.add_enabled(self.emby_current_page > 0, 
+                if ui.add_enabled(current_page > 0, 
                     egui::Button::new("⏮").min_size(egui::vec2(32.0, 24.0))
                 ).on_hover_text("首页").clicked() {
-                    self.emby_current_page = 0;
+                    goto_page = Some(0);
                 }
                 
-                if ui.add_enabled(self.emby_current_page > 0,
+                if ui.add_enabled(current_page > 0,
                     egui::Button::new("◀").min_size(egui::vec2(32.0, 24.0))
                 ).on_hover_text("上一页").clicked() {
-                    self.emby_current_page -= 1;
+                    goto_page = Some(current_page - 1);
                 }
                 
-                ui.label(egui::RichText::new(format!("{} / {}", self.emby_current_page + 1, total_pages))
+                ui.label(egui::RichText::new(format!("{} / {}", current_page + 1, total_pages))
                     .color(theme::TEXT_PRIMARY).size(13.0));
                 
-                if ui.add_enabled(self.emby_current_page < total_pages - 1,
+                if ui.add_enabled(current_page + 1 < total_pages,
                     egui::Button::new("▶").min_size(egui::vec2(32.0, 24.0))
                 ).on_hover_text("下一页").clicked() {
-                    self.emby_current_page += 1;
+                    goto_page = Some(current_page + 1);
                 }
                 
-                if ui.add_enabled(self.emby_current_page < total_pages - 1,
+                if ui.add_enabled(current_page + 1 < total_pages,
                     egui::Button::new("⏭").min_size(egui::vec2(32.0, 24.0))
                 ).on_hover_text("末页").clicked() {
-                    self.emby_current_page = total_pages - 1;
+                    goto_page = Some(total_pages - 1);
                 }
             }
         });
+
+        if let Some(page) = goto_page {
+            let mut query = self.emby_query.clone();
+            query.start_index = page * per_page;
+            self.run_emby_query(query);
+        }
         
         ui.add_space(10.0);
         ui.separator();
@@ -2747,11 +3728,7 @@ impl BovaGuiApp {
             let cols = if cols == 0 { 1 } else { cols };
 
             // 克隆当前页的项目以避免借用冲突
-            let page_items: Vec<EmbyItem> = if start_idx < total_items {
-                self.emby_items[start_idx..end_idx].to_vec()
-            } else {
-                Vec::new()
-            };
+            let page_items: Vec<EmbyItem> = self.emby_items.clone();
 
             egui::Grid::new("emby_grid")
                 .spacing(egui::vec2(spacing, spacing))
@@ -2919,8 +3896,10 @@ impl BovaGuiApp {
              // 顶部导航栏 - 紧凑单行
              ui.horizontal(|ui| {
                  if ui.add(egui::Button::new("⬅ 服务器").min_size(egui::vec2(80.0, 28.0))).clicked() {
+                     self.cancel_emby_navigation();
                      self.emby_view_mode = EmbyViewMode::ServerList;
                      self.current_emby_server = None;
+                     self.library_active = false;
                      self.emby_dashboard = None;
                      self.emby_items.clear();
                      self.emby_navigation_stack.clear();
@@ -3084,14 +4063,25 @@ impl BovaGuiApp {
                                          .fill(theme::ACCENT)
                                          .rounding(egui::Rounding::same(8.0))
                                  ).clicked() {
+                                     self.play_emby_item(&item, false);
+                                 }
+                                 if let Some(resume_ms) = item.resume_position_ms() {
+                                     ui.add_space(8.0);
+                                     if subtle_button(ui, &format!("⏯ 从 {} 继续", Self::format_time(resume_ms))).clicked() {
+                                         self.play_emby_item(&item, true);
+                                     }
+                                 }
+                                 ui.add_space(8.0);
+                                 if self.library_active {
+                                     let watched = item.user_data.as_ref().and_then(|d| d.played).unwrap_or(false);
+                                     if subtle_button(ui, if watched { "↺ 标记为未看" } else { "✓ 标记为已看" }).clicked() {
+                                         self.set_library_watched(&item.id, !watched);
+                                     }
+                                 } else if subtle_button(ui, "📶 自适应播放 (HLS)").clicked() {
                                      if let Some(srv) = &self.current_emby_server {
-                                         let url = EmbyClient::get_stream_url(srv, &item.id);
-                                         if !url.is_empty() {
-                                             self.app_mode = AppMode::Player;
-                                             self.url = url.clone();
-                                             self.logs.push(format!("🌐 Emby播放: {}", item.name));
-                                             self.open_and_play(url);
-                                         }
+                                         let url = srv.hls_url(&item.id);
+                                         let session = PlaySession::new(&item.id, PlayMethod::Transcode);
+                                         self.start_emby_stream(&item, url, session, None, item.resume_position_ms());
                                      }
                                  }
                                  
@@ -3231,15 +4221,7 @@ impl BovaGuiApp {
                                                          
                                                          // 点击播放
                                                          if response.clicked() {
-                                                             if let Some(srv) = &self.current_emby_server {
-                                                                 let url = EmbyClient::get_stream_url(srv, &episode.id);
-                                                                 if !url.is_empty() {
-                                                                     self.app_mode = AppMode::Player;
-                                                                     self.url = url.clone();
-                                                                     self.logs.push(format!("🌐 Emby播放: {}", episode.name));
-                                                                     self.open_and_play(url);
-                                                                 }
-                                                             }
+                                                             self.play_emby_item(episode, true);
                                                          }
                                                      }
                                                      
@@ -3295,6 +4277,179 @@ impl BovaGuiApp {
          }
     }
 
+    /// 离开当前页面时取消尚未完成的列表/详情请求，避免旧结果覆盖新页面
+    fn cancel_emby_navigation(&self) {
+        if let Some(client) = &self.emby_client {
+            client.cancel(RequestScope::Navigation);
+        }
+    }
+
+    /// 进入目录：新的查询沿用当前的排序和筛选条件
+    fn browse_emby(&mut self, mut query: ItemQuery) {
+        if query.recursive {
+            query.sort_by = self.emby_query.sort_by;
+            query.descending = self.emby_query.descending;
+        }
+        query.genres = self.emby_query.genres.clone();
+        query.years = self.emby_query.years.clone();
+        query.min_community_rating = self.emby_query.min_community_rating;
+        query.played = self.emby_query.played;
+        query.favorites_only = self.emby_query.favorites_only;
+        query.limit = self.emby_query.limit;
+        self.emby_search_input.clear();
+        self.emby_view_mode = EmbyViewMode::Browser;
+        self.emby_items.clear();
+        self.emby_total_items = 0;
+        if let (Some(client), Some(srv)) = (&self.emby_client, &self.current_emby_server) {
+            client.get_genres(srv, query.parent_id.clone());
+        }
+        self.run_emby_query(query);
+    }
+
+    /// 全库搜索
+    fn search_emby(&mut self, term: String) {
+        self.emby_navigation_stack.clear();
+        let query = ItemQuery {
+            search_term: Some(term),
+            include_item_types: Some("Movie,Series,Episode".to_string()),
+            sort_by: SortField::SortName,
+            descending: false,
+            limit: self.emby_query.limit,
+            ..Default::default()
+        };
+        self.emby_view_mode = EmbyViewMode::Browser;
+        self.emby_items.clear();
+        self.emby_total_items = 0;
+        if let (Some(client), Some(srv)) = (&self.emby_client, &self.current_emby_server) {
+            client.get_genres(srv, None);
+        }
+        self.run_emby_query(query);
+    }
+
+    /// 发起查询；无筛选的全库搜索走 /Search/Hints，其余走 Items 查询
+    fn run_emby_query(&mut self, query: ItemQuery) {
+        self.emby_query = query.clone();
+        self.cancel_emby_navigation();
+        if self.library_active {
+            self.media_state.reload();
+            let page = self.library.query(&query, self.media_state.get());
+            self.emby_items = page.items;
+            self.emby_total_items = page.total;
+            self.emby_status_msg = None;
+            return;
+        }
+        if let (Some(client), Some(srv)) = (&self.emby_client, &self.current_emby_server) {
+            self.emby_status_msg = Some("加载中...".to_string());
+            let hints = query.search_term.is_some() && query.parent_id.is_none() && !query.has_filters()
+                && query.sort_by == SortField::SortName && !query.descending;
+            if hints {
+                client.search_hints(srv, query);
+            } else {
+                client.query_items(srv, query);
+            }
+        }
+    }
+
+    /// 浏览器顶部的搜索 / 排序 / 筛选栏
+    fn show_emby_filter_bar(&mut self, ui: &mut egui::Ui) {
+        let mut query = self.emby_query.clone();
+        let mut search = false;
+
+        ui.horizontal_wrapped(|ui| {
+            let resp = ui.add(egui::TextEdit::singleline(&mut self.emby_search_input)
+                .hint_text(if query.parent_id.is_some() { "🔍 在此目录中搜索" } else { "🔍 搜索" })
+                .desired_width(180.0));
+            if resp.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) {
+                search = true;
+            }
+
+            ui.separator();
+            ui.label(egui::RichText::new("排序").color(theme::TEXT_DIM).size(12.0));
+            egui::ComboBox::from_id_source("emby_sort")
+                .selected_text(query.sort_by.label())
+                .show_ui(ui, |ui| {
+                    for field in SortField::ALL {
+                        ui.selectable_value(&mut query.sort_by, field, field.label());
+                    }
+                });
+            if ui.button(if query.descending { "↓ 降序" } else { "↑ 升序" }).clicked() {
+                query.descending = !query.descending;
+            }
+
+            ui.separator();
+            let genre_text = if query.genres.is_empty() { "全部类型".to_string() } else { query.genres.join(", ") };
+            egui::ComboBox::from_id_source("emby_genre")
+                .selected_text(genre_text)
+                .show_ui(ui, |ui| {
+                    for genre in &self.emby_genres {
+                        let mut on = query.genres.contains(genre);
+                        if ui.checkbox(&mut on, genre).changed() {
+                            if on {
+                                query.genres.push(genre.clone());
+                            } else {
+                                query.genres.retain(|g| g != genre);
+                            }
+                        }
+                    }
+                });
+
+            let year = ui.add(egui::TextEdit::singleline(&mut self.emby_year_input)
+                .hint_text("年份 2020 / 2010-2019")
+                .desired_width(130.0));
+            if year.lost_focus() {
+                query.years = emby::parse_years(&self.emby_year_input);
+            }
+
+            let rating_text = match query.min_community_rating {
+                Some(r) => format!("★ {}+", r),
+                None => "评分不限".to_string(),
+            };
+            egui::ComboBox::from_id_source("emby_rating")
+                .selected_text(rating_text)
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(&mut query.min_community_rating, None, "评分不限");
+                    for r in [6.0, 7.0, 8.0, 9.0] {
+                        ui.selectable_value(&mut query.min_community_rating, Some(r), format!("★ {}+", r));
+                    }
+                });
+
+            let played_text = match query.played {
+                None => "全部",
+                Some(false) => "未播放",
+                Some(true) => "已播放",
+            };
+            egui::ComboBox::from_id_source("emby_played")
+                .selected_text(played_text)
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(&mut query.played, None, "全部");
+                    ui.selectable_value(&mut query.played, Some(false), "未播放");
+                    ui.selectable_value(&mut query.played, Some(true), "已播放");
+                });
+
+            ui.checkbox(&mut query.favorites_only, "❤ 收藏");
+
+            if query.has_filters() && ui.button("清除筛选").clicked() {
+                query.clear_filters();
+                self.emby_year_input.clear();
+            }
+        });
+
+        if search {
+            let term = self.emby_search_input.trim().to_string();
+            query.search_term = if term.is_empty() { None } else { Some(term) };
+            if query.parent_id.is_none() && query.search_term.is_none() {
+                return;
+            }
+        }
+
+        // 条件变化后从第一页重新查询
+        query.start_index = self.emby_query.start_index;
+        if query != self.emby_query {
+            query.start_index = 0;
+            self.run_emby_query(query);
+        }
+    }
+
     fn handle_emby_item_click(&mut self, item: &EmbyItem) {
         let item_type = item.field_type.as_deref();
         
@@ -3307,6 +4462,10 @@ impl BovaGuiApp {
                 self.season_episodes.clear();
                 self.selected_season_index = 0;  // 重置为第一季
                 
+                if self.library_active {
+                    self.load_library_seasons(&item.id);
+                    return;
+                }
                 // 加载该 Series 的所有 Seasons（不递归）
                 if let Some(client) = &self.emby_client {
                     if let Some(srv) = &self.current_emby_server {
@@ -3317,24 +4476,16 @@ impl BovaGuiApp {
             // Folder, Season 等进入浏览器模式
             Some("Folder") | Some("CollectionFolder") | Some("UserView") | Some("BoxSet") => {
                 self.emby_navigation_stack.push((item.id.clone(), item.name.clone()));
-                self.emby_current_page = 0;
-                self.emby_view_mode = EmbyViewMode::Browser;
-                if let Some(client) = &self.emby_client {
-                    if let Some(srv) = &self.current_emby_server {
-                        client.get_items(srv, Some(item.id.clone()), true);  // 浏览器模式，递归查找影片
-                    }
-                }
+                self.browse_emby(ItemQuery::browse(&item.id, true));  // 浏览器模式，递归查找影片
             }
             // Season 进入浏览器模式，显示 Episodes
             Some("Season") => {
                 self.emby_navigation_stack.push((item.id.clone(), item.name.clone()));
-                self.emby_current_page = 0;
-                self.emby_view_mode = EmbyViewMode::Browser;
-                if let Some(client) = &self.emby_client {
-                    if let Some(srv) = &self.current_emby_server {
-                        client.get_items(srv, Some(item.id.clone()), false);  // Season -> Episode，不递归
-                    }
-                }
+                // Season -> Episode，不递归，按集数排序
+                let mut query = ItemQuery::browse(&item.id, false);
+                query.sort_by = SortField::SortName;
+                query.descending = false;
+                self.browse_emby(query);
             }
             // 可播放的项目（Movie 或 Episode），显示详情
             _ => {
@@ -3345,6 +4496,440 @@ impl BovaGuiApp {
     }
 }
 
+// ━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━
+//  Local Library
+// ━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━
+impl BovaGuiApp {
+    fn process_library_events(&mut self) {
+        while let Ok(event) = self.library_event_rx.try_recv() {
+            match event {
+                LibraryEvent::Scanned(files, stats) => {
+                    self.library.apply_scan(files);
+                    self.logs.push(format!(
+                        "📚 媒体库扫描完成: {} 个文件（新增 {}，移除 {}）",
+                        stats.total, stats.added, stats.removed
+                    ));
+                    if self.library_active && self.emby_view_mode == EmbyViewMode::Dashboard {
+                        self.refresh_library_dashboard();
+                    }
+                }
+                LibraryEvent::ImageLoaded(key, data) => {
+                    if let Ok(img) = image::load_from_memory(&data) {
+                        let rgba = img.to_rgba8();
+                        let size = [img.width() as usize, img.height() as usize];
+                        self.pending_images.push((key, egui::ColorImage::from_rgba_unmultiplied(size, &rgba)));
+                    }
+                }
+            }
+        }
+    }
+
+    /// 服务器列表顶部的本地媒体库入口与文件夹管理
+    fn show_library_entry(&mut self, ui: &mut egui::Ui) {
+        let mut remove_folder = None;
+        ui.group(|ui| {
+            ui.horizontal(|ui| {
+                ui.label(egui::RichText::new("📚").size(20.0));
+                ui.vertical(|ui| {
+                    ui.heading("本地媒体库");
+                    let index = self.library.index();
+                    let status = if self.library.scanning() {
+                        "正在扫描...".to_string()
+                    } else {
+                        format!("{} 个文件夹 · {} 个文件", index.folders.len(), index.files.len())
+                    };
+                    ui.label(status);
+                });
+                ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                    if ui.add_enabled(!self.library.index().folders.is_empty(), egui::Button::new("进入")).clicked() {
+                        self.enter_library();
+                    }
+                    if ui.add_enabled(!self.library.scanning(), egui::Button::new("🔄 重新扫描")).clicked() {
+                        self.library.rescan();
+                    }
+                    if ui.button("➕ 添加文件夹").clicked() {
+                        if let Some(dir) = FileDialog::new().pick_folder() {
+                            let dir = dir.display().to_string();
+                            if self.library.add_folder(&dir) {
+                                self.logs.push(format!("📚 已添加文件夹: {}", dir));
+                            }
+                        }
+                    }
+                });
+            });
+            for folder in &self.library.index().folders {
+                ui.horizontal(|ui| {
+                    ui.add_space(32.0);
+                    ui.label(egui::RichText::new(folder).color(theme::TEXT_SECONDARY).size(12.0));
+                    if ui.small_button("🗑").on_hover_text("从媒体库移除").clicked() {
+                        remove_folder = Some(folder.clone());
+                    }
+                });
+            }
+        });
+        if let Some(folder) = remove_folder {
+            self.library.remove_folder(&folder);
+            self.logs.push(format!("📚 已移除文件夹: {}", folder));
+        }
+    }
+
+    fn enter_library(&mut self) {
+        self.cancel_emby_navigation();
+        self.current_emby_server = None;
+        self.library_active = true;
+        self.emby_items.clear();
+        self.emby_navigation_stack.clear();
+        self.emby_genres.clear();
+        self.emby_view_mode = EmbyViewMode::Dashboard;
+        self.refresh_library_dashboard();
+    }
+
+    /// 用本地索引生成首页；观看状态读自 state.json 的最新内容
+    fn refresh_library_dashboard(&mut self) {
+        self.media_state.reload();
+        let state = self.media_state.get();
+        let dash = self.library.dashboard(state);
+        for view in &dash.views {
+            self.emby_view_items.insert(view.id.clone(), self.library.view_items(&view.id, state));
+        }
+        self.series_episode_count.extend(self.library.episode_counts());
+        self.emby_dashboard = Some(dash);
+        self.emby_status_msg = None;
+    }
+
+    fn load_library_seasons(&mut self, series_id: &str) {
+        self.media_state.reload();
+        let state = self.media_state.get();
+        self.series_seasons = self.library.seasons(series_id, state);
+        self.season_episodes = self.series_seasons.iter()
+            .map(|season| (season.id.clone(), self.library.season_episodes(&season.id, state)))
+            .collect();
+    }
+
+    /// 标记条目（剧集为全部集）已看 / 未看，并刷新详情页
+    fn set_library_watched(&mut self, item_id: &str, watched: bool) {
+        let files: Vec<_> = self.library.files_of(item_id).into_iter().cloned().collect();
+        let result = self.media_state.update(|state| {
+            for file in &files {
+                bova_core::library::set_watched(state, file, watched);
+            }
+        });
+        if let Err(e) = result {
+            log::warn!("cannot save watched state: {}", e);
+            return;
+        }
+        self.logs.push(format!("📚 已标记为{}: {} 个文件", if watched { "已看" } else { "未看" }, files.len()));
+        self.selected_emby_item = self.library.item(item_id, self.media_state.get());
+        if self.selected_emby_item.as_ref().and_then(|i| i.field_type.as_deref()) == Some("Series") {
+            self.load_library_seasons(item_id);
+        }
+    }
+}
+
+// ━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━
+//  Probe
+// ━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━
+impl BovaGuiApp {
+    /// 在后台线程探测当前 URL，结果由 `process_probe_result` 取回
+    fn start_probe(&mut self) {
+        let mut opts = bova_probe::ProbeOptions {
+            timeout_ms: Some(self.media_options.network_timeout_ms.unwrap_or(10_000)),
+            user_agent: self.media_options.user_agent.clone(),
+            headers: self.media_options.http_headers.clone(),
+            ..Default::default()
+        };
+        if is_session_url(&self.url) {
+            if let Some(srv) = &self.current_emby_server {
+                opts.headers.extend(srv.stream_headers());
+            }
+        }
+        let (tx, rx) = channel();
+        let url = self.url.clone();
+        std::thread::spawn(move || {
+            let result = bova_probe::probe_with(&url, &opts);
+            let _ = tx.send((url, result));
+        });
+        self.probe_rx = Some(rx);
+    }
+
+    fn process_probe_result(&mut self) {
+        let Some(rx) = &self.probe_rx else { return };
+        let (url, result) = match rx.try_recv() {
+            Ok(done) => done,
+            Err(mpsc::TryRecvError::Empty) => return,
+            Err(mpsc::TryRecvError::Disconnected) => {
+                self.probe_rx = None;
+                return;
+            }
+        };
+        self.probe_rx = None;
+        // 探测期间已切换到其他媒体
+        if url != self.url { return; }
+        match result {
+            Ok(info) => self.last_probe_json = Some(serde_json::to_string_pretty(&info).unwrap()),
+            Err(e) => {
+                self.last_probe_json = None;
+                self.logs.push(format!("✕ Probe 失败: {e}"));
+            }
+        }
+    }
+}
+
+// ━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━
+//  Remote Shares (WebDAV / FTP)
+// ━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━
+impl BovaGuiApp {
+    fn process_remote_events(&mut self) {
+        while let Ok(event) = self.remote_event_rx.try_recv() {
+            match event {
+                RemoteEvent::Listed(path, entries) => {
+                    // 忽略过期的列表结果（用户已切换目录）
+                    if path == self.remote_path {
+                        self.remote_entries = entries;
+                        self.remote_loading = false;
+                        self.remote_status_msg = None;
+                    }
+                }
+                RemoteEvent::Error(err) => {
+                    self.remote_loading = false;
+                    self.remote_status_msg = Some(err.clone());
+                    self.logs.push(format!("✕ 远程共享: {}", err));
+                }
+            }
+        }
+    }
+
+    fn browse_remote(&mut self, path: String) {
+        if let Some(conn) = &self.current_remote {
+            self.remote_path = path.clone();
+            self.remote_entries.clear();
+            self.remote_loading = true;
+            self.remote_status_msg = None;
+            self.remote_browser.list(conn.clone(), path);
+        }
+    }
+
+    fn play_remote(&mut self, entry: RemoteEntry) {
+        let Some(conn) = self.current_remote.clone() else { return };
+        // 仅用于显示，实际通过 MediaSource 读取
+        self.url = match reqwest::Url::parse(&conn.url) {
+            Ok(mut u) => {
+                u.set_path(&entry.path);
+                u.to_string()
+            }
+            Err(_) => entry.path.clone(),
+        };
+        self.quality_mode = QualityMode::Auto;
+        self.logs.push(format!("📁 {} 播放: {}", conn.kind, entry.name));
+        self.remote_playing = Some((conn, entry));
+        self.app_mode = AppMode::Player;
+        self.start_playback();
+    }
+
+    fn show_remote_ui(&mut self, ctx: &egui::Context, ui: &mut egui::Ui) {
+        if self.current_remote.is_some() {
+            self.show_remote_browser(ui);
+        } else {
+            self.show_remote_list(ui);
+        }
+
+        if self.show_add_remote_window {
+            egui::Window::new("添加网络共享")
+                .anchor(egui::Align2::CENTER_CENTER, egui::vec2(0.0, 0.0))
+                .collapsible(false)
+                .resizable(false)
+                .show(ctx, |ui| {
+                    ui.set_min_width(500.0);
+                    ui.add_space(20.0);
+
+                    egui::Grid::new("add_remote_grid")
+                        .spacing(egui::vec2(15.0, 15.0))
+                        .show(ui, |ui| {
+                            ui.label(egui::RichText::new("名称:").size(16.0));
+                            ui.add_sized(egui::vec2(380.0, 24.0), egui::TextEdit::singleline(&mut self.new_remote_name));
+                            ui.end_row();
+
+                            ui.label(egui::RichText::new("地址:").size(16.0));
+                            ui.add_sized(egui::vec2(380.0, 24.0), egui::TextEdit::singleline(&mut self.new_remote_url))
+                                .on_hover_text("例如: https://nas.local/dav/ 或 ftp://192.168.1.10/media");
+                            ui.end_row();
+
+                            ui.label(egui::RichText::new("用户名:").size(16.0));
+                            ui.add_sized(egui::vec2(380.0, 24.0), egui::TextEdit::singleline(&mut self.new_remote_user));
+                            ui.end_row();
+
+                            ui.label(egui::RichText::new("密码:").size(16.0));
+                            ui.add_sized(egui::vec2(380.0, 24.0), egui::TextEdit::singleline(&mut self.new_remote_pass).password(true));
+                            ui.end_row();
+                        });
+
+                    ui.add_space(20.0);
+
+                    ui.horizontal(|ui| {
+                        ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                            if ui.add_sized(egui::vec2(80.0, 30.0), egui::Button::new("取消")).clicked() {
+                                self.show_add_remote_window = false;
+                            }
+                            ui.add_space(10.0);
+                            if ui.add_sized(egui::vec2(80.0, 30.0), egui::Button::new("保存").fill(theme::ACCENT)).clicked() {
+                                let url = self.new_remote_url.trim().to_string();
+                                if reqwest::Url::parse(&url).is_err() {
+                                    self.remote_status_msg = Some("地址无效".to_string());
+                                } else {
+                                    let name = if self.new_remote_name.trim().is_empty() { url.clone() } else { self.new_remote_name.trim().to_string() };
+                                    let non_empty = |s: &str| if s.is_empty() { None } else { Some(s.to_string()) };
+                                    let conn = RemoteConnection::new(&name, &url, non_empty(&self.new_remote_user), non_empty(&self.new_remote_pass));
+                                    self.remote_connections.push(conn);
+                                    self.save_servers();
+                                    self.new_remote_name.clear();
+                                    self.new_remote_url.clear();
+                                    self.new_remote_user.clear();
+                                    self.new_remote_pass.clear();
+                                    self.remote_status_msg = None;
+                                    self.show_add_remote_window = false;
+                                }
+                            }
+                        });
+                    });
+
+                    if let Some(msg) = &self.remote_status_msg {
+                        ui.add_space(10.0);
+                        ui.label(egui::RichText::new(msg).color(theme::ERROR));
+                    }
+                    ui.add_space(10.0);
+                });
+        }
+    }
+
+    fn show_remote_list(&mut self, ui: &mut egui::Ui) {
+        ui.heading("网络共享 (WebDAV / FTP)");
+        ui.add_space(10.0);
+
+        if ui.button("➕ 添加连接").clicked() {
+            self.show_add_remote_window = true;
+            self.remote_status_msg = None;
+        }
+
+        ui.add_space(10.0);
+        ui.separator();
+
+        let mut delete_idx = None;
+        let mut enter = None;
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            for (i, conn) in self.remote_connections.iter().enumerate() {
+                ui.group(|ui| {
+                    ui.horizontal(|ui| {
+                        ui.label(egui::RichText::new("📁").size(20.0));
+                        ui.vertical(|ui| {
+                            ui.heading(&conn.name);
+                            ui.label(format!("{} · {}", conn.kind, conn.url));
+                        });
+                        ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                            if ui.button("🗑").on_hover_text("删除").clicked() {
+                                delete_idx = Some(i);
+                            }
+                            if ui.button("浏览").clicked() {
+                                enter = Some(conn.clone());
+                            }
+                        });
+                    });
+                });
+            }
+        });
+
+        if let Some(i) = delete_idx {
+            let conn = self.remote_connections.remove(i);
+            self.server_store.forget_remote(&conn);
+            self.save_servers();
+        }
+        if let Some(conn) = enter {
+            let root = conn.root_path();
+            self.current_remote = Some(conn);
+            self.browse_remote(root);
+        }
+    }
+
+    fn show_remote_browser(&mut self, ui: &mut egui::Ui) {
+        let Some(conn) = self.current_remote.clone() else { return };
+        let root = conn.root_path();
+
+        ui.horizontal(|ui| {
+            if ui.add(egui::Button::new("⬅ 连接列表").min_size(egui::vec2(100.0, 28.0))).clicked() {
+                self.current_remote = None;
+                self.remote_entries.clear();
+            }
+            let at_root = self.remote_path.trim_end_matches('/') == root.trim_end_matches('/');
+            if ui.add_enabled(!at_root, egui::Button::new("⬆ 上级")).clicked() {
+                let parent = match self.remote_path.trim_end_matches('/').rsplit_once('/') {
+                    Some((p, _)) if !p.is_empty() => p.to_string(),
+                    _ => "/".to_string(),
+                };
+                self.browse_remote(parent);
+            }
+            if ui.button("🔄").on_hover_text("刷新").clicked() {
+                self.browse_remote(self.remote_path.clone());
+            }
+            ui.separator();
+            ui.label(egui::RichText::new(&conn.name).color(theme::TEXT_PRIMARY).size(14.0));
+            ui.label(egui::RichText::new(&self.remote_path).color(theme::TEXT_DIM).size(12.0));
+        });
+        ui.separator();
+
+        if self.remote_loading {
+            ui.horizontal(|ui| {
+                ui.spinner();
+                ui.label(egui::RichText::new("加载中...").color(theme::TEXT_DIM));
+            });
+            return;
+        }
+        if let Some(msg) = &self.remote_status_msg {
+            ui.label(egui::RichText::new(format!("✕ {}", msg)).color(theme::ERROR));
+        }
+        if self.remote_entries.is_empty() {
+            ui.label(egui::RichText::new("空目录").color(theme::TEXT_DIM));
+            return;
+        }
+
+        let mut open_dir = None;
+        let mut play = None;
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            for entry in &self.remote_entries {
+                let playable = !entry.is_dir && bova_remote::is_media_file(&entry.name);
+                let (icon, color) = if entry.is_dir {
+                    ("📁", theme::TEXT_PRIMARY)
+                } else if playable {
+                    ("🎬", theme::TEXT_PRIMARY)
+                } else {
+                    ("📄", theme::TEXT_DIM)
+                };
+                ui.horizontal(|ui| {
+                    let label = egui::RichText::new(format!("{} {}", icon, entry.name)).color(color).size(14.0);
+                    let resp = ui.add_enabled(entry.is_dir || playable, egui::SelectableLabel::new(false, label));
+                    if resp.clicked() {
+                        if entry.is_dir {
+                            open_dir = Some(entry.path.clone());
+                        } else {
+                            play = Some(entry.clone());
+                        }
+                    }
+                    ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                        if let Some(size) = entry.size.filter(|_| !entry.is_dir) {
+                            ui.label(egui::RichText::new(remote::format_size(size)).color(theme::TEXT_DIM).size(12.0));
+                        }
+                    });
+                });
+            }
+        });
+
+        if let Some(path) = open_dir {
+            self.browse_remote(path);
+        }
+        if let Some(entry) = play {
+            self.play_remote(entry);
+        }
+    }
+}
+
 // ━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━
 //  Original Update Loop (Renamed/Moved)
 // ━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━
@@ -3374,9 +4959,14 @@ impl BovaGuiApp {
         if let Some(file) = dlg
             .add_filter("视频文件", &["mp4","mkv","mov","avi","wmv","webm","flv","ts","m2ts","mpg","mpeg","rmvb","rm","3gp","vob"])
             .add_filter("音频文件", &["mp3","flac","m4a","aac","wav","ogg","wma","opus","ape","alac"])
+            .add_filter("播放列表", &["m3u","m3u8","pls","xspf"])
             .add_filter("所有文件", &["*"])
             .pick_file()
         {
+            if PlaylistFormat::from_path(&file).is_some() {
+                self.import_playlist(&file);
+                return;
+            }
             if let Some(path) = file.to_str() {
                 self.url = path.to_string();
                 self.logs.push(format!("📂 选择文件: {}", self.file_basename()));
@@ -3391,6 +4981,7 @@ impl BovaGuiApp {
 // ━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━
 
 fn main() -> eframe::Result<()> {
+    bova_core::logging::init();
     let native_options = eframe::NativeOptions {
         viewport: egui::ViewportBuilder::default()
             .with_inner_size([1100.0, 720.0])
@@ -3405,6 +4996,17 @@ fn main() -> eframe::Result<()> {
     )
 }
 
+/// 距结尾多久时预先打开播放列表的下一项
+const PRELOAD_LEAD_MS: i64 = 15_000;
+
+/// 显示时落后音频时钟超过该值的帧计为迟到帧
+const LATE_FRAME_MS: i64 = 40;
+
+/// Emby 串流地址带有访问令牌且只对本次会话有效，不记入最近文件 / 播放列表 / 逐文件记忆
+fn is_session_url(url: &str) -> bool {
+    url.contains("api_key=")
+}
+
 // CJK font loader
 fn load_cjk_font() -> Option<Vec<u8>> {
     use std::fs;
@@ -0,0 +1,44 @@
+use bova_remote::{RemoteConnection, RemoteEntry};
+use std::sync::mpsc::Sender;
+use std::thread;
+
+#[derive(Debug, Clone)]
+pub enum RemoteEvent {
+    /// (path, entries) of a finished directory listing
+    Listed(String, Vec<RemoteEntry>),
+    Error(String),
+}
+
+/// Runs blocking WebDAV/FTP listings in a background thread
+pub struct RemoteBrowser {
+    tx: Sender<RemoteEvent>,
+}
+
+impl RemoteBrowser {
+    pub fn new(tx: Sender<RemoteEvent>) -> Self {
+        Self { tx }
+    }
+
+    pub fn list(&self, conn: RemoteConnection, path: String) {
+        let tx = self.tx.clone();
+        thread::spawn(move || {
+            let result = bova_remote::connect(&conn).and_then(|mut fs| fs.list(&path));
+            let _ = match result {
+                Ok(entries) => tx.send(RemoteEvent::Listed(path, entries)),
+                Err(e) => tx.send(RemoteEvent::Error(format!("{}: {}", conn.name, e))),
+            };
+        });
+    }
+}
+
+/// Human readable file size
+pub fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KB", "MB", "GB", "TB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 { format!("{} B", bytes) } else { format!("{:.1} {}", size, UNITS[unit]) }
+}
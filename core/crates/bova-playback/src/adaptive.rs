@@ -0,0 +1,380 @@
+//! Adaptive streaming (HLS / DASH): manifest parsing, variant selection and
+//! throughput-driven switching.
+//!
+//! This module decides *which* variant should be active. mpv opens the
+//! manifest itself and switches variants in-stream by changing the video
+//! track; the FFmpeg engine reads HLS through `hls::HlsSource`, which fetches
+//! the segments and may change variant at every segment boundary.
+
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
+
+use crate::NetworkOptions;
+
+/// One selectable quality level of an adaptive stream.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Variant {
+    /// Position in the manifest order
+    pub index: usize,
+    /// Peak bandwidth in bits per second (`BANDWIDTH` / `bandwidth`)
+    pub bandwidth: u64,
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    pub codecs: Option<String>,
+    pub frame_rate: Option<f32>,
+    pub name: Option<String>,
+    /// Absolute URI of the media playlist (HLS) or representation id (DASH)
+    pub uri: String,
+}
+
+impl std::fmt::Display for Variant {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mbps = self.bandwidth as f64 / 1_000_000.0;
+        match (self.height, &self.name) {
+            (Some(h), _) => write!(f, "{}p · {:.1} Mbps", h, mbps),
+            (None, Some(name)) => write!(f, "{} · {:.1} Mbps", name, mbps),
+            (None, None) => write!(f, "{:.1} Mbps", mbps),
+        }
+    }
+}
+
+/// Quality selection requested by the user.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum QualityMode {
+    /// Follow measured throughput
+    #[default]
+    Auto,
+    /// Pin to `Variant::index`
+    Fixed(usize),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ManifestKind {
+    Hls,
+    Dash,
+}
+
+/// Detect an adaptive manifest from the URL path (query string ignored).
+pub fn manifest_kind(url: &str) -> Option<ManifestKind> {
+    let path = url.split(['?', '#']).next().unwrap_or(url).to_ascii_lowercase();
+    if path.ends_with(".m3u8") || path.ends_with(".m3u") {
+        Some(ManifestKind::Hls)
+    } else if path.ends_with(".mpd") {
+        Some(ManifestKind::Dash)
+    } else {
+        None
+    }
+}
+
+/// Parse an HLS master playlist. Returns an empty list for media playlists.
+pub fn parse_hls_master(text: &str, base_url: &str) -> Vec<Variant> {
+    let mut variants = Vec::new();
+    let mut pending: Option<Variant> = None;
+
+    for line in text.lines().map(str::trim) {
+        if let Some(attrs) = line.strip_prefix("#EXT-X-STREAM-INF:") {
+            let mut v = Variant {
+                index: variants.len(),
+                bandwidth: 0,
+                width: None,
+                height: None,
+                codecs: None,
+                frame_rate: None,
+                name: None,
+                uri: String::new(),
+            };
+            for (key, value) in parse_attribute_list(attrs) {
+                match key.as_str() {
+                    "BANDWIDTH" => v.bandwidth = value.parse().unwrap_or(0),
+                    "AVERAGE-BANDWIDTH" if v.bandwidth == 0 => v.bandwidth = value.parse().unwrap_or(0),
+                    "RESOLUTION" => {
+                        if let Some((w, h)) = value.split_once(['x', 'X']) {
+                            v.width = w.parse().ok();
+                            v.height = h.parse().ok();
+                        }
+                    }
+                    "CODECS" => v.codecs = Some(value),
+                    "FRAME-RATE" => v.frame_rate = value.parse().ok(),
+                    "NAME" | "VIDEO" if v.name.is_none() => v.name = Some(value),
+                    _ => {}
+                }
+            }
+            pending = Some(v);
+        } else if !line.is_empty() && !line.starts_with('#') {
+            if let Some(mut v) = pending.take() {
+                v.uri = resolve_url(base_url, line);
+                variants.push(v);
+            }
+        }
+    }
+    variants
+}
+
+/// Parse the video representations of a DASH MPD.
+pub fn parse_dash_mpd(text: &str, _base_url: &str) -> Vec<Variant> {
+    let mut variants = Vec::new();
+    // Representations inherit mimeType/contentType from their AdaptationSet.
+    let mut set_is_video = false;
+    for tag in text.split('<').skip(1) {
+        let tag = tag.trim_start();
+        if let Some(rest) = tag.strip_prefix("AdaptationSet") {
+            let attrs = parse_xml_attributes(rest);
+            set_is_video = attrs.iter().any(|(k, v)| {
+                (k == "mimeType" && v.starts_with("video/")) || (k == "contentType" && v == "video")
+            });
+        } else if let Some(rest) = tag.strip_prefix("Representation") {
+            let attrs = parse_xml_attributes(rest);
+            let get = |name: &str| attrs.iter().find(|(k, _)| k == name).map(|(_, v)| v.clone());
+            let is_video = set_is_video
+                || get("mimeType").is_some_and(|m| m.starts_with("video/"))
+                || get("width").is_some();
+            if !is_video {
+                continue;
+            }
+            variants.push(Variant {
+                index: variants.len(),
+                bandwidth: get("bandwidth").and_then(|b| b.parse().ok()).unwrap_or(0),
+                width: get("width").and_then(|w| w.parse().ok()),
+                height: get("height").and_then(|h| h.parse().ok()),
+                codecs: get("codecs"),
+                frame_rate: get("frameRate").and_then(|r| parse_frame_rate(&r)),
+                name: None,
+                uri: get("id").unwrap_or_default(),
+            });
+        }
+    }
+    variants
+}
+
+/// HTTP client for manifests and segments, with the stream's timeout.
+pub(crate) fn http_client(net: &NetworkOptions) -> reqwest::Result<reqwest::blocking::Client> {
+    reqwest::blocking::Client::builder()
+        .timeout(Duration::from_millis(net.timeout_ms as u64))
+        .build()
+}
+
+/// GET `url` with the stream's user agent and headers.
+pub(crate) fn http_get(
+    client: &reqwest::blocking::Client,
+    url: &str,
+    net: &NetworkOptions,
+) -> reqwest::Result<reqwest::blocking::Response> {
+    let mut req = client.get(url);
+    if let Some(ua) = &net.user_agent {
+        req = req.header(reqwest::header::USER_AGENT, ua);
+    }
+    for (k, v) in &net.headers {
+        req = req.header(k.as_str(), v.as_str());
+    }
+    req.send()?.error_for_status()
+}
+
+/// Download and parse the manifest at `url`.
+pub fn fetch_variants(url: &str, net: &NetworkOptions) -> anyhow::Result<Vec<Variant>> {
+    let kind = manifest_kind(url).ok_or_else(|| anyhow::anyhow!("not an HLS/DASH manifest: {url}"))?;
+    let resp = http_get(&http_client(net)?, url, net)?;
+    // Relative variant URIs resolve against the final URL after redirects
+    let base = resp.url().to_string();
+    let text = resp.text()?;
+    Ok(match kind {
+        ManifestKind::Hls => parse_hls_master(&text, &base),
+        ManifestKind::Dash => parse_dash_mpd(&text, &base),
+    })
+}
+
+/// Fraction of the measured throughput a variant may use
+pub const SAFETY: f64 = 0.8;
+
+/// Lowest-bandwidth variant, the usual starting point before any throughput is known.
+pub fn lowest_variant(variants: &[Variant]) -> Option<usize> {
+    variants.iter().min_by_key(|v| v.bandwidth).map(|v| v.index)
+}
+
+/// Highest variant whose bandwidth fits in `safety` × `throughput_bps`,
+/// or the lowest one if none fits.
+pub fn pick_variant(variants: &[Variant], throughput_bps: u64, safety: f64) -> Option<usize> {
+    let budget = (throughput_bps as f64 * safety) as u64;
+    let lowest = variants.iter().min_by_key(|v| v.bandwidth)?;
+    let best = variants
+        .iter()
+        .filter(|v| v.bandwidth <= budget)
+        .max_by_key(|v| v.bandwidth)
+        .unwrap_or(lowest);
+    Some(best.index)
+}
+
+/// Dual exponentially-weighted moving average of throughput samples.
+/// The estimate is the smaller of a fast and a slow average, so drops are
+/// reacted to quickly while spikes need to persist before they count.
+#[derive(Debug, Clone)]
+pub struct ThroughputEstimator {
+    fast: Option<f64>,
+    slow: Option<f64>,
+}
+
+impl ThroughputEstimator {
+    const FAST_ALPHA: f64 = 0.5;
+    const SLOW_ALPHA: f64 = 0.1;
+
+    pub fn new() -> Self {
+        Self { fast: None, slow: None }
+    }
+
+    /// Record a throughput sample in bits per second.
+    pub fn sample(&mut self, bps: u64) {
+        let x = bps as f64;
+        self.fast = Some(self.fast.map_or(x, |f| f + Self::FAST_ALPHA * (x - f)));
+        self.slow = Some(self.slow.map_or(x, |s| s + Self::SLOW_ALPHA * (x - s)));
+    }
+
+    pub fn estimate_bps(&self) -> Option<u64> {
+        match (self.fast, self.slow) {
+            (Some(f), Some(s)) => Some(f.min(s) as u64),
+            _ => None,
+        }
+    }
+}
+
+impl Default for ThroughputEstimator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Decides when to switch variants in `QualityMode::Auto`.
+#[derive(Debug, Clone)]
+pub struct AdaptiveController {
+    variants: Vec<Variant>,
+    mode: QualityMode,
+    current: Option<usize>,
+    last_switch: Option<Instant>,
+    estimator: ThroughputEstimator,
+}
+
+impl AdaptiveController {
+    /// Minimum time between up-switches, to avoid oscillating
+    const UPSWITCH_HOLD: Duration = Duration::from_secs(10);
+
+    pub fn new(variants: Vec<Variant>, mode: QualityMode) -> Self {
+        Self { variants, mode, current: None, last_switch: None, estimator: ThroughputEstimator::new() }
+    }
+
+    pub fn variants(&self) -> &[Variant] {
+        &self.variants
+    }
+
+    pub fn current(&self) -> Option<usize> {
+        self.current
+    }
+
+    pub fn mode(&self) -> QualityMode {
+        self.mode
+    }
+
+    pub fn sample(&mut self, bps: u64) {
+        self.estimator.sample(bps);
+    }
+
+    pub fn set_mode(&mut self, mode: QualityMode) {
+        self.mode = mode;
+        self.last_switch = None;
+    }
+
+    /// Record that `index` is now active (e.g. the engine's initial pick).
+    pub fn set_current(&mut self, index: usize) {
+        self.current = Some(index);
+        self.last_switch = Some(Instant::now());
+    }
+
+    /// Like `set_current`, but the first up-switch isn't held back: used when
+    /// starting on the lowest variant before any throughput was measured.
+    pub fn set_initial(&mut self, index: usize) {
+        self.current = Some(index);
+        self.last_switch = None;
+    }
+
+    /// Variant that should be active now, if it differs from the current one.
+    pub fn next_switch(&mut self) -> Option<usize> {
+        let target = match self.mode {
+            QualityMode::Fixed(i) => self.variants.iter().find(|v| v.index == i)?.index,
+            QualityMode::Auto => {
+                let bps = self.estimator.estimate_bps()?;
+                let target = pick_variant(&self.variants, bps, SAFETY)?;
+                if let Some(cur) = self.current.and_then(|c| self.variants.iter().find(|v| v.index == c)) {
+                    let upswitch = self.variants[target].bandwidth > cur.bandwidth;
+                    let held = self.last_switch.is_none_or(|t| t.elapsed() >= Self::UPSWITCH_HOLD);
+                    if upswitch && !held {
+                        return None;
+                    }
+                }
+                target
+            }
+        };
+        if self.current == Some(target) {
+            return None;
+        }
+        self.set_current(target);
+        Some(target)
+    }
+}
+
+/// Resolve a (possibly relative) URI against the manifest URL.
+pub(crate) fn resolve_url(base: &str, uri: &str) -> String {
+    match reqwest::Url::parse(base).and_then(|b| b.join(uri)) {
+        Ok(u) => u.to_string(),
+        Err(_) => uri.to_string(),
+    }
+}
+
+/// Parse an HLS attribute list: `KEY=value,KEY="quoted, value"`.
+fn parse_attribute_list(s: &str) -> Vec<(String, String)> {
+    let mut out = Vec::new();
+    let mut rest = s;
+    while !rest.is_empty() {
+        let Some(eq) = rest.find('=') else { break };
+        let key = rest[..eq].trim().trim_start_matches(',').trim().to_string();
+        rest = &rest[eq + 1..];
+        let value;
+        if let Some(quoted) = rest.strip_prefix('"') {
+            let end = quoted.find('"').unwrap_or(quoted.len());
+            value = quoted[..end].to_string();
+            rest = quoted.get(end + 1..).unwrap_or("");
+        } else {
+            let end = rest.find(',').unwrap_or(rest.len());
+            value = rest[..end].trim().to_string();
+            rest = &rest[end..];
+        }
+        rest = rest.trim_start_matches(',');
+        out.push((key, value));
+    }
+    out
+}
+
+/// Parse `name="value"` pairs of one XML start tag (up to its closing `>`).
+fn parse_xml_attributes(tag: &str) -> Vec<(String, String)> {
+    let tag = tag.split('>').next().unwrap_or(tag);
+    let mut out = Vec::new();
+    let mut rest = tag;
+    while let Some(eq) = rest.find('=') {
+        let key = rest[..eq].split_whitespace().last().unwrap_or("").to_string();
+        let after = rest[eq + 1..].trim_start();
+        let Some(quote) = after.chars().next().filter(|c| *c == '"' || *c == '\'') else { break };
+        let body = &after[1..];
+        let end = body.find(quote).unwrap_or(body.len());
+        out.push((key, body[..end].to_string()));
+        rest = body.get(end + 1..).unwrap_or("");
+    }
+    out
+}
+
+/// DASH frame rates are either `25` or a fraction like `30000/1001`.
+fn parse_frame_rate(s: &str) -> Option<f32> {
+    match s.split_once('/') {
+        Some((n, d)) => {
+            let n: f32 = n.parse().ok()?;
+            let d: f32 = d.parse().ok()?;
+            if d > 0.0 { Some(n / d) } else { None }
+        }
+        None => s.parse().ok(),
+    }
+}
@@ -0,0 +1,366 @@
+//! Streaming-aware demuxing for the FFmpeg engine.
+//!
+//! - `open_input` passes reconnect / timeout / user-agent / header options to
+//!   FFmpeg's network protocols, so a dropped HTTP connection is resumed with a
+//!   range request instead of ending playback.
+//! - `spawn_demuxer` reads packets on its own thread into a `PacketQueue`
+//!   bounded by `NetworkOptions::cache_ms` of media, which the decode loop
+//!   drains; the queue fill level drives `PlaybackEvent::Buffering`.
+//! - `bova-source://` URLs are read from a registered `MediaSource` through a
+//!   custom `AVIOContext` instead of FFmpeg's own protocols.
+
+use ffmpeg_next as ffmpeg;
+use std::collections::VecDeque;
+use std::ffi::CString;
+use std::io::SeekFrom;
+use std::ops::{Deref, DerefMut};
+use std::os::raw::{c_int, c_void};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use crate::source::{self, MediaSource};
+use crate::{is_network_url, ts_to_ms, NetworkOptions};
+
+/// Hard cap on queued packets, for streams whose timestamps don't advance
+/// the reference clock (audio-only, broken pts).
+const MAX_QUEUED_PACKETS: usize = 4096;
+/// Consecutive read errors tolerated before the input is given up.
+const MAX_READ_RETRIES: u32 = 5;
+
+/// Read buffer handed to `avio_alloc_context`
+const AVIO_BUFFER_SIZE: usize = 64 * 1024;
+
+/// Demuxer input; owns the custom I/O context when reading a `MediaSource`.
+pub(crate) struct DemuxInput {
+    // Declared first: the format context must be closed before its I/O is freed
+    input: ffmpeg::format::context::Input,
+    _io: Option<CustomIo>,
+}
+
+impl Deref for DemuxInput {
+    type Target = ffmpeg::format::context::Input;
+    fn deref(&self) -> &Self::Target {
+        &self.input
+    }
+}
+
+impl DerefMut for DemuxInput {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.input
+    }
+}
+
+/// `AVIOContext` reading from a boxed `MediaSource` (the `opaque` pointer).
+struct CustomIo {
+    avio: *mut ffmpeg::ffi::AVIOContext,
+    opaque: *mut Box<dyn MediaSource>,
+}
+
+// The context and source are only touched by the thread that owns the input.
+unsafe impl Send for CustomIo {}
+
+impl Drop for CustomIo {
+    fn drop(&mut self) {
+        unsafe {
+            if !self.avio.is_null() {
+                // FFmpeg may have replaced the buffer; free whatever it holds now
+                ffmpeg::ffi::av_freep(&mut (*self.avio).buffer as *mut *mut u8 as *mut c_void);
+                ffmpeg::ffi::avio_context_free(&mut self.avio);
+            }
+            drop(Box::from_raw(self.opaque));
+        }
+    }
+}
+
+unsafe extern "C" fn avio_read(opaque: *mut c_void, buf: *mut u8, size: c_int) -> c_int {
+    let src = &mut *(opaque as *mut Box<dyn MediaSource>);
+    let slice = std::slice::from_raw_parts_mut(buf, size.max(0) as usize);
+    match src.read(slice) {
+        Ok(0) => c_int::from(ffmpeg::Error::Eof),
+        Ok(n) => n as c_int,
+        Err(e) => {
+            log::warn!("source read error: {e}");
+            c_int::from(ffmpeg::Error::Unknown)
+        }
+    }
+}
+
+unsafe extern "C" fn avio_seek(opaque: *mut c_void, offset: i64, whence: c_int) -> i64 {
+    let src = &mut *(opaque as *mut Box<dyn MediaSource>);
+    let whence = whence as u32;
+    if whence & ffmpeg::ffi::AVSEEK_SIZE != 0 {
+        return src.size().map(|s| s as i64).unwrap_or(-1);
+    }
+    let pos = match whence & !ffmpeg::ffi::AVSEEK_FORCE {
+        0 => SeekFrom::Start(offset.max(0) as u64), // SEEK_SET
+        1 => SeekFrom::Current(offset),             // SEEK_CUR
+        2 => SeekFrom::End(offset),                 // SEEK_END
+        _ => return -1,
+    };
+    src.seek(pos).map(|p| p as i64).unwrap_or(-1)
+}
+
+/// Open a registered `MediaSource` through a custom `AVIOContext`.
+fn open_source(url: &str, src: Box<dyn MediaSource>) -> Result<DemuxInput, ffmpeg::Error> {
+    use ffmpeg::ffi;
+    let seekable = src.is_seekable();
+    unsafe {
+        let buffer = ffi::av_malloc(AVIO_BUFFER_SIZE) as *mut u8;
+        if buffer.is_null() {
+            return Err(ffmpeg::Error::Other { errno: ffmpeg::error::ENOMEM });
+        }
+        let opaque = Box::into_raw(Box::new(src));
+        let avio = ffi::avio_alloc_context(
+            buffer,
+            AVIO_BUFFER_SIZE as c_int,
+            0,
+            opaque as *mut c_void,
+            Some(avio_read),
+            None,
+            if seekable { Some(avio_seek) } else { None },
+        );
+        if avio.is_null() {
+            ffi::av_free(buffer as *mut c_void);
+            drop(Box::from_raw(opaque));
+            return Err(ffmpeg::Error::Other { errno: ffmpeg::error::ENOMEM });
+        }
+        // From here on `io` frees the context, buffer and source on every path
+        let io = CustomIo { avio, opaque };
+
+        let mut ctx = ffi::avformat_alloc_context();
+        if ctx.is_null() {
+            return Err(ffmpeg::Error::Other { errno: ffmpeg::error::ENOMEM });
+        }
+        (*ctx).pb = avio;
+        (*ctx).flags |= ffi::AVFMT_FLAG_CUSTOM_IO as c_int;
+
+        // The URL only serves as a format hint (file extension)
+        let c_url = CString::new(url).unwrap_or_default();
+        let r = ffi::avformat_open_input(&mut ctx, c_url.as_ptr(), std::ptr::null(), std::ptr::null_mut());
+        if r < 0 {
+            // avformat_open_input frees ctx on failure
+            return Err(ffmpeg::Error::from(r));
+        }
+        let r = ffi::avformat_find_stream_info(ctx, std::ptr::null_mut());
+        if r < 0 {
+            ffi::avformat_close_input(&mut ctx);
+            return Err(ffmpeg::Error::from(r));
+        }
+        Ok(DemuxInput { input: ffmpeg::format::context::Input::wrap(ctx), _io: Some(io) })
+    }
+}
+
+/// Open `url` with network options applied when it is a remote input.
+pub(crate) fn open_input(url: &str, net: &NetworkOptions) -> Result<DemuxInput, ffmpeg::Error> {
+    if source::is_source_url(url) {
+        let src = source::take_source(url).ok_or(ffmpeg::Error::StreamNotFound)?;
+        return open_source(url, src);
+    }
+    open_url(url, net).map(|input| DemuxInput { input, _io: None })
+}
+
+fn open_url(url: &str, net: &NetworkOptions) -> Result<ffmpeg::format::context::Input, ffmpeg::Error> {
+    if !is_network_url(url) {
+        return ffmpeg::format::input(&url);
+    }
+
+    let mut opts = ffmpeg::Dictionary::new();
+    if net.reconnect {
+        opts.set("reconnect", "1");
+        opts.set("reconnect_streamed", "1");
+        opts.set("reconnect_on_network_error", "1");
+        opts.set("reconnect_delay_max", &net.reconnect_delay_max_s.to_string());
+    }
+    // rw_timeout is in microseconds
+    opts.set("rw_timeout", &(net.timeout_ms as u64 * 1000).to_string());
+    if let Some(ua) = &net.user_agent {
+        opts.set("user_agent", ua);
+    }
+    if !net.headers.is_empty() {
+        let headers: String = net.headers.iter()
+            .map(|(k, v)| format!("{k}: {v}\r\n"))
+            .collect();
+        opts.set("headers", &headers);
+    }
+    ffmpeg::format::input_with_dictionary(&url, opts)
+}
+
+/// Result of `PacketQueue::pop`.
+pub(crate) enum Pop {
+    Packet(ffmpeg::Packet),
+    /// Nothing queued yet but the input is still being read (underrun)
+    Empty,
+    /// Input fully consumed; carries the read error if it ended abnormally
+    End(Option<String>),
+}
+
+struct QueueState {
+    packets: VecDeque<(ffmpeg::Packet, Option<i64>)>,
+    /// Timestamp (ms) of the last reference-stream packet handed to the decoder
+    head_ms: Option<i64>,
+    /// Timestamp (ms) of the last reference-stream packet read from the input
+    tail_ms: Option<i64>,
+    finished: bool,
+    closed: bool,
+    error: Option<String>,
+}
+
+impl QueueState {
+    fn buffered_ms(&self) -> i64 {
+        match (self.head_ms, self.tail_ms) {
+            (Some(head), Some(tail)) => (tail - head).max(0),
+            _ => 0,
+        }
+    }
+}
+
+/// Packet queue bounded by media duration rather than packet count.
+pub(crate) struct PacketQueue {
+    state: Mutex<QueueState>,
+    cond: Condvar,
+    capacity_ms: i64,
+}
+
+impl PacketQueue {
+    pub(crate) fn new(cache_ms: u32) -> Self {
+        Self {
+            state: Mutex::new(QueueState {
+                packets: VecDeque::new(),
+                head_ms: None,
+                tail_ms: None,
+                finished: false,
+                closed: false,
+                error: None,
+            }),
+            cond: Condvar::new(),
+            capacity_ms: cache_ms.max(100) as i64,
+        }
+    }
+
+    fn is_full(&self, st: &QueueState) -> bool {
+        st.buffered_ms() >= self.capacity_ms || st.packets.len() >= MAX_QUEUED_PACKETS
+    }
+
+    /// Block until there is room, then enqueue. `ts_ms` is set for packets of
+    /// the reference stream only. Returns false once the consumer has closed.
+    fn push(&self, packet: ffmpeg::Packet, ts_ms: Option<i64>) -> bool {
+        let mut st = self.state.lock().unwrap();
+        while !st.closed && self.is_full(&st) {
+            st = self.cond.wait(st).unwrap();
+        }
+        if st.closed {
+            return false;
+        }
+        if let Some(ts) = ts_ms {
+            if st.head_ms.is_none() {
+                st.head_ms = Some(ts);
+            }
+            st.tail_ms = Some(ts);
+        }
+        st.packets.push_back((packet, ts_ms));
+        self.cond.notify_all();
+        true
+    }
+
+    /// Take the next packet, waiting up to `timeout` for the demuxer.
+    pub(crate) fn pop(&self, timeout: Duration) -> Pop {
+        let mut st = self.state.lock().unwrap();
+        if st.packets.is_empty() && !st.finished {
+            st = self.cond.wait_timeout(st, timeout).unwrap().0;
+        }
+        match st.packets.pop_front() {
+            Some((packet, ts_ms)) => {
+                if ts_ms.is_some() {
+                    st.head_ms = ts_ms;
+                }
+                self.cond.notify_all();
+                Pop::Packet(packet)
+            }
+            None if st.finished => Pop::End(st.error.take()),
+            None => Pop::Empty,
+        }
+    }
+
+    /// Wait up to `timeout` for the demuxer to add data (or finish).
+    pub(crate) fn wait_for_data(&self, timeout: Duration) {
+        let st = self.state.lock().unwrap();
+        if !st.finished && !self.is_full(&st) {
+            let _ = self.cond.wait_timeout(st, timeout).unwrap();
+        }
+    }
+
+    /// Read-ahead fill level in percent; 100 when full or the input has ended.
+    pub(crate) fn fill_percent(&self) -> u8 {
+        let st = self.state.lock().unwrap();
+        if st.finished || self.is_full(&st) {
+            return 100;
+        }
+        (st.buffered_ms() * 100 / self.capacity_ms).clamp(0, 99) as u8
+    }
+
+    fn finish(&self, error: Option<String>) {
+        let mut st = self.state.lock().unwrap();
+        st.finished = true;
+        st.error = error;
+        self.cond.notify_all();
+    }
+
+    /// Consumer is done: drop queued packets and release a blocked demuxer.
+    pub(crate) fn close(&self) {
+        let mut st = self.state.lock().unwrap();
+        st.closed = true;
+        st.packets.clear();
+        self.cond.notify_all();
+    }
+
+    fn is_closed(&self) -> bool {
+        self.state.lock().unwrap().closed
+    }
+}
+
+/// Read packets from `ictx` until EOF, an unrecoverable error, or `queue.close()`.
+/// `ref_stream` is the stream whose timestamps measure the buffered duration.
+pub(crate) fn spawn_demuxer(
+    mut ictx: DemuxInput,
+    queue: Arc<PacketQueue>,
+    ref_stream: usize,
+) -> JoinHandle<()> {
+    let ref_tb = ictx.stream(ref_stream).map(|s| s.time_base());
+    thread::spawn(move || {
+        let mut errors: u32 = 0;
+        loop {
+            let mut packet = ffmpeg::Packet::empty();
+            match packet.read(&mut ictx) {
+                Ok(()) => {
+                    errors = 0;
+                    let ts_ms = if packet.stream() == ref_stream {
+                        packet.dts().or(packet.pts())
+                            .and_then(|ts| ref_tb.map(|tb| ts_to_ms(ts, tb)))
+                    } else {
+                        None
+                    };
+                    if !queue.push(packet, ts_ms) {
+                        return;
+                    }
+                }
+                Err(ffmpeg::Error::Eof) => break,
+                Err(e) => {
+                    // FFmpeg already retried the connection per `reconnect*`;
+                    // give the server a few more chances before giving up.
+                    errors += 1;
+                    log::warn!("demux read error ({errors}/{MAX_READ_RETRIES}): {e}");
+                    if errors >= MAX_READ_RETRIES {
+                        queue.finish(Some(format!("read failed: {e}")));
+                        return;
+                    }
+                    thread::sleep(Duration::from_millis(250 * errors as u64));
+                    if queue.is_closed() {
+                        return;
+                    }
+                }
+            }
+        }
+        queue.finish(None);
+    })
+}
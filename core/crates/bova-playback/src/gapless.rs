@@ -0,0 +1,205 @@
+//! Gapless continuation and crossfade for the FFmpeg engine.
+//!
+//! 每一项由独立的 `playback_thread` 解码，`run_sequence` 把当前项的输出转发到
+//! `PlaybackHandles` 的通道。经 `next_tx` 排入的下一项立即开始打开和解码：它的
+//! 输出通道有界，无人读取时解码线程阻塞，相当于预先缓冲了 demux 队列和若干解码帧。
+//! 当前项结束后直接改为转发下一项，前端的音频输出不重建，因此没有间隙；
+//! `crossfade_ms` 大于 0 时，当前项最后一段音频与下一项开头按线性增益混合。
+
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::thread;
+use std::time::Instant;
+
+use crossbeam_channel::{bounded, never, select, tick, Receiver, Sender};
+
+use crate::stats::STATS_INTERVAL;
+use crate::{playback_thread, AudioFrame, PlaybackConfig, PlaybackEvent, StatsCollector, SubtitleFrame, VideoFrame};
+
+/// `PlaybackHandles` 一侧的发送端
+pub(crate) struct Outputs {
+    pub video_tx: Sender<VideoFrame>,
+    pub audio_tx: Sender<AudioFrame>,
+    pub subtitle_tx: Sender<SubtitleFrame>,
+    pub event_tx: Sender<PlaybackEvent>,
+    pub stats: Arc<StatsCollector>,
+}
+
+/// 一项的解码线程及其输出
+struct Item {
+    url: String,
+    video_rx: Receiver<VideoFrame>,
+    audio_rx: Receiver<AudioFrame>,
+    subtitle_rx: Receiver<SubtitleFrame>,
+    event_rx: Receiver<PlaybackEvent>,
+    stop_tx: Sender<()>,
+    done_rx: Receiver<()>,
+    /// 解码侧统计；预先打开的下一项不影响当前项的数据
+    stats: Arc<StatsCollector>,
+}
+
+impl Item {
+    fn spawn(url: String, cfg: PlaybackConfig) -> Self {
+        let (video_tx, video_rx) = bounded::<VideoFrame>(32);
+        let (audio_tx, audio_rx) = bounded::<AudioFrame>(64);
+        let (subtitle_tx, subtitle_rx) = bounded::<SubtitleFrame>(32);
+        let (event_tx, event_rx) = bounded::<PlaybackEvent>(64);
+        let (stop_tx, stop_rx) = bounded::<()>(1);
+        let (done_tx, done_rx) = bounded::<()>(1);
+        let stats = Arc::new(StatsCollector::new("ffmpeg"));
+        let thread_url = url.clone();
+        let thread_stats = stats.clone();
+        thread::spawn(move || {
+            if let Err(e) = playback_thread(&thread_url, &video_tx, &audio_tx, &subtitle_tx, &stop_rx, &event_tx, &cfg, &thread_stats) {
+                log::error!("playback_thread error: {e:?}");
+                let _ = event_tx.try_send(PlaybackEvent::Error(format!("{e:#}")));
+            }
+            let _ = done_tx.send(());
+        });
+        Self { url, video_rx, audio_rx, subtitle_rx, event_rx, stop_tx, done_rx, stats }
+    }
+
+    /// 通知解码线程退出；接收端随之释放，阻塞在发送上的线程也会返回
+    fn stop(self) {
+        let _ = self.stop_tx.try_send(());
+    }
+}
+
+/// 当前项末尾与下一项开头的音频混合
+struct Crossfade {
+    window_ms: i64,
+    /// 已从下一项取出、尚未混入的样本（交错）
+    pending: VecDeque<i16>,
+    channels: u16,
+    sample_rate: u32,
+}
+
+impl Crossfade {
+    fn new(window_ms: u32) -> Self {
+        Self { window_ms: window_ms as i64, pending: VecDeque::new(), channels: 2, sample_rate: 48_000 }
+    }
+
+    /// 当前项的帧落在结尾的混合窗口内时，叠加下一项开头的样本：
+    /// 当前项增益从 1 线性降到 0，下一项从 0 升到 1
+    fn mix(&mut self, mut frame: AudioFrame, next: &Receiver<AudioFrame>) -> AudioFrame {
+        self.channels = frame.channels;
+        self.sample_rate = frame.sample_rate;
+        let (Some(pts), Some(duration)) = (frame.pts_ms, frame.duration_ms) else { return frame };
+        let remaining = duration - pts;
+        if self.window_ms <= 0 || remaining > self.window_ms || frame.sample_rate == 0 {
+            return frame;
+        }
+        // 下一项已预先缓冲，取不到时按静音处理，不阻塞当前项
+        while self.pending.len() < frame.samples.len() {
+            match next.try_recv() {
+                Ok(f) => self.pending.extend(f.samples),
+                Err(_) => break,
+            }
+        }
+        let channels = frame.channels.max(1) as usize;
+        let rate = frame.sample_rate as i64;
+        for (i, sample) in frame.samples.iter_mut().enumerate() {
+            let offset_ms = (i / channels) as i64 * 1000 / rate;
+            let gain = ((remaining - offset_ms) as f32 / self.window_ms as f32).clamp(0.0, 1.0);
+            let incoming = self.pending.pop_front().unwrap_or(0) as f32;
+            *sample = (*sample as f32 * gain + incoming * (1.0 - gain)).clamp(i16::MIN as f32, i16::MAX as f32) as i16;
+        }
+        frame
+    }
+
+    /// 切换到下一项时，先输出已取出但未混入的部分
+    fn take_pending(&mut self) -> Option<AudioFrame> {
+        if self.pending.is_empty() { return None; }
+        Some(AudioFrame {
+            channels: self.channels,
+            sample_rate: self.sample_rate,
+            samples: self.pending.drain(..).collect(),
+            pts_ms: None,
+            duration_ms: None,
+        })
+    }
+}
+
+/// 播放 `url`，结束后依次衔接经 `next_rx` 排入的项；全部结束或收到停止信号时返回
+pub(crate) fn run_sequence(url: String, cfg: PlaybackConfig, out: &Outputs, stop_rx: &Receiver<()>, next_rx: Receiver<String>) {
+    let mut next_rx = next_rx;
+    let mut current = Item::spawn(url, cfg.clone());
+    let mut next: Option<Item> = None;
+    let mut fade = Crossfade::new(cfg.crossfade_ms);
+    let stats_tick = tick(STATS_INTERVAL);
+    let mut last_stats = Instant::now();
+
+    loop {
+        select! {
+            recv(stop_rx) -> _ => break,
+            recv(stats_tick) -> _ => {
+                current.stats.finish_interval(last_stats.elapsed());
+                last_stats = Instant::now();
+                out.stats.copy_decoder_from(&current.stats);
+                out.stats.set_queues(
+                    (out.video_tx.len(), out.video_tx.capacity().unwrap_or(0)),
+                    (out.audio_tx.len(), out.audio_tx.capacity().unwrap_or(0)),
+                );
+                let _ = out.event_tx.try_send(PlaybackEvent::Stats(out.stats.snapshot()));
+            },
+            recv(next_rx) -> msg => match msg {
+                Ok(url) => {
+                    if let Some(old) = next.take() { old.stop(); }
+                    fade.pending.clear();
+                    log::debug!("pre-opening next item: {url}");
+                    // 续播位置只对第一项有效
+                    next = Some(Item::spawn(url, PlaybackConfig { start_ms: None, ..cfg.clone() }));
+                }
+                // 句柄已释放，不再有排队项
+                Err(_) => next_rx = never(),
+            },
+            recv(current.video_rx) -> msg => if let Ok(frame) = msg {
+                let _ = out.video_tx.send(frame);
+            },
+            recv(current.audio_rx) -> msg => if let Ok(frame) = msg {
+                let frame = match &next {
+                    Some(item) => fade.mix(frame, &item.audio_rx),
+                    None => frame,
+                };
+                let _ = out.audio_tx.send(frame);
+            },
+            recv(current.subtitle_rx) -> msg => if let Ok(frame) = msg {
+                let _ = out.subtitle_tx.send(frame);
+            },
+            recv(current.event_rx) -> msg => if let Ok(event) = msg {
+                let _ = out.event_tx.try_send(event);
+            },
+            recv(current.done_rx) -> _ => {
+                // 解码线程已退出，先转发通道里剩余的帧
+                for frame in current.video_rx.try_iter() {
+                    let _ = out.video_tx.send(frame);
+                }
+                for frame in current.audio_rx.try_iter() {
+                    let frame = match &next {
+                        Some(item) => fade.mix(frame, &item.audio_rx),
+                        None => frame,
+                    };
+                    let _ = out.audio_tx.send(frame);
+                }
+                for frame in current.subtitle_rx.try_iter() {
+                    let _ = out.subtitle_tx.send(frame);
+                }
+                for event in current.event_rx.try_iter() {
+                    let _ = out.event_tx.try_send(event);
+                }
+                let Some(item) = next.take() else { break };
+                log::info!("continuing with {}", item.url);
+                if let Some(frame) = fade.take_pending() {
+                    let _ = out.audio_tx.send(frame);
+                }
+                let _ = out.event_tx.try_send(PlaybackEvent::TrackChanged(item.url.clone()));
+                current = item;
+            },
+        }
+    }
+
+    current.stop();
+    if let Some(item) = next {
+        item.stop();
+    }
+}
@@ -0,0 +1,285 @@
+//! HLS segment reader for the FFmpeg engine.
+//!
+//! FFmpeg's own HLS demuxer stays on the variant it was opened with. `HlsSource`
+//! instead downloads the MPEG-TS segments itself and exposes them as one
+//! continuous `MediaSource` (read through the custom `AVIOContext` like any
+//! other source). Every segment download is a throughput sample for the
+//! `AdaptiveController`, which may pick another variant before the next
+//! segment; the TS demuxer and decoders follow the switch in-stream.
+//!
+//! Only plain TS playlists can be concatenated like this: fMP4 (`EXT-X-MAP`),
+//! encrypted (`EXT-X-KEY`) or byte-range playlists are rejected by `open`, and
+//! the engine then opens the chosen media playlist with FFmpeg directly.
+
+use std::collections::HashMap;
+use std::io::{self, SeekFrom};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crossbeam_channel::Sender;
+
+use crate::adaptive::{self, AdaptiveController, QualityMode, Variant};
+use crate::source::MediaSource;
+use crate::{NetworkOptions, PlaybackEvent};
+
+/// Segments behind the live edge to start a live stream at
+const LIVE_START_SEGMENTS: usize = 3;
+/// Playlist reloads without a new segment before a live stream is considered ended
+const LIVE_RELOAD_ATTEMPTS: u32 = 6;
+/// Download attempts per segment before the read fails
+const SEGMENT_ATTEMPTS: u32 = 3;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Segment {
+    pub duration_ms: i64,
+    /// Absolute URI
+    pub uri: String,
+}
+
+/// An HLS media playlist (the per-variant segment list).
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MediaPlaylist {
+    pub target_duration_ms: i64,
+    /// `EXT-X-MEDIA-SEQUENCE` of the first segment
+    pub media_sequence: u64,
+    pub segments: Vec<Segment>,
+    /// `EXT-X-ENDLIST` present: VOD, or a live stream that has finished
+    pub ended: bool,
+    /// Segments are self-contained TS that can be appended to each other
+    pub concatenable: bool,
+}
+
+impl MediaPlaylist {
+    fn segment(&self, seq: u64) -> Option<&Segment> {
+        let i = seq.checked_sub(self.media_sequence)?;
+        self.segments.get(usize::try_from(i).ok()?)
+    }
+
+    fn last_sequence(&self) -> u64 {
+        self.media_sequence + self.segments.len() as u64
+    }
+
+    /// Start time of segment `seq`, relative to the first listed segment.
+    fn start_ms(&self, seq: u64) -> i64 {
+        let n = seq.saturating_sub(self.media_sequence) as usize;
+        self.segments.iter().take(n).map(|s| s.duration_ms).sum()
+    }
+
+    /// Sequence number of the segment playing at `ms` (clamped to the list).
+    fn sequence_at(&self, ms: i64) -> u64 {
+        let mut end = 0;
+        for (i, seg) in self.segments.iter().enumerate() {
+            end += seg.duration_ms;
+            if ms < end {
+                return self.media_sequence + i as u64;
+            }
+        }
+        self.last_sequence().saturating_sub(1).max(self.media_sequence)
+    }
+}
+
+/// Parse an HLS media playlist; segment URIs are resolved against `base_url`.
+pub fn parse_hls_media(text: &str, base_url: &str) -> MediaPlaylist {
+    let mut playlist = MediaPlaylist { concatenable: true, ..Default::default() };
+    let mut duration_ms: Option<i64> = None;
+    for line in text.lines().map(str::trim) {
+        if let Some(v) = line.strip_prefix("#EXT-X-TARGETDURATION:") {
+            playlist.target_duration_ms = v.trim().parse::<f64>().map_or(0, |s| (s * 1000.0) as i64);
+        } else if let Some(v) = line.strip_prefix("#EXT-X-MEDIA-SEQUENCE:") {
+            playlist.media_sequence = v.trim().parse().unwrap_or(0);
+        } else if let Some(v) = line.strip_prefix("#EXTINF:") {
+            let secs = v.split(',').next().unwrap_or("").trim();
+            duration_ms = Some(secs.parse::<f64>().map_or(0, |s| (s * 1000.0).round() as i64));
+        } else if line == "#EXT-X-ENDLIST" {
+            playlist.ended = true;
+        } else if line.starts_with("#EXT-X-MAP") || line.starts_with("#EXT-X-BYTERANGE") {
+            playlist.concatenable = false;
+        } else if let Some(attrs) = line.strip_prefix("#EXT-X-KEY:") {
+            if !attrs.contains("METHOD=NONE") {
+                playlist.concatenable = false;
+            }
+        } else if !line.is_empty() && !line.starts_with('#') {
+            playlist.segments.push(Segment {
+                duration_ms: duration_ms.take().unwrap_or(playlist.target_duration_ms),
+                uri: adaptive::resolve_url(base_url, line),
+            });
+        }
+    }
+    playlist
+}
+
+/// Continuous TS stream over the segments of an HLS master playlist.
+pub struct HlsSource {
+    client: reqwest::blocking::Client,
+    net: NetworkOptions,
+    controller: AdaptiveController,
+    playlists: HashMap<usize, MediaPlaylist>,
+    active: usize,
+    /// Sequence number of the next segment to download
+    next_seq: u64,
+    segment: Vec<u8>,
+    segment_pos: usize,
+    events: Option<Sender<PlaybackEvent>>,
+}
+
+impl HlsSource {
+    /// Start on `QualityMode::Fixed` or, in `Auto`, the lowest variant, at the
+    /// segment containing `start_ms`. Fails when the variant's playlist can't
+    /// be fetched or isn't plain TS. Variant switches are reported on `events`.
+    pub fn open(
+        variants: Vec<Variant>,
+        mode: QualityMode,
+        start_ms: Option<i64>,
+        net: &NetworkOptions,
+        events: Option<Sender<PlaybackEvent>>,
+    ) -> anyhow::Result<Self> {
+        let active = match mode {
+            QualityMode::Fixed(i) if variants.iter().any(|v| v.index == i) => i,
+            _ => adaptive::lowest_variant(&variants).ok_or_else(|| anyhow::anyhow!("no variants"))?,
+        };
+        let mut controller = AdaptiveController::new(variants, mode);
+        controller.set_initial(active);
+        let mut source = Self {
+            client: adaptive::http_client(net)?,
+            net: net.clone(),
+            controller,
+            playlists: HashMap::new(),
+            active,
+            next_seq: 0,
+            segment: Vec::new(),
+            segment_pos: 0,
+            events,
+        };
+        let playlist = source.load_playlist(active)?;
+        if !playlist.concatenable {
+            anyhow::bail!("variant {active} is not a plain TS playlist");
+        }
+        source.next_seq = if playlist.ended {
+            playlist.sequence_at(start_ms.unwrap_or(0))
+        } else {
+            playlist.last_sequence().saturating_sub(LIVE_START_SEGMENTS as u64).max(playlist.media_sequence)
+        };
+        Ok(source)
+    }
+
+    /// Variant the next segment is read from.
+    pub fn current(&self) -> usize {
+        self.active
+    }
+
+    fn load_playlist(&mut self, index: usize) -> anyhow::Result<&MediaPlaylist> {
+        let uri = self
+            .controller
+            .variants()
+            .iter()
+            .find(|v| v.index == index)
+            .map(|v| v.uri.clone())
+            .ok_or_else(|| anyhow::anyhow!("unknown variant {index}"))?;
+        let resp = adaptive::http_get(&self.client, &uri, &self.net)?;
+        let base = resp.url().to_string();
+        let playlist = parse_hls_media(&resp.text()?, &base);
+        Ok(self.playlists.entry(index).insert_entry(playlist).into_mut())
+    }
+
+    /// Switch to the controller's choice before the next segment. Falls back to
+    /// the current variant when the new playlist can't be used.
+    fn maybe_switch(&mut self) {
+        let Some(target) = self.controller.next_switch() else { return };
+        if target == self.active {
+            return;
+        }
+        let vod_position = self.playlists.get(&self.active).filter(|p| p.ended).map(|p| p.start_ms(self.next_seq));
+        let next_seq = match self.load_playlist(target) {
+            Ok(p) if p.concatenable => match vod_position {
+                // VOD: segment boundaries may differ between variants, continue by time
+                Some(ms) => p.sequence_at(ms),
+                // live: media sequence numbers are aligned across variants
+                None => self.next_seq,
+            },
+            Ok(_) => {
+                log::warn!("HLS variant {target} is not plain TS, staying on {}", self.active);
+                self.controller.set_current(self.active);
+                return;
+            }
+            Err(e) => {
+                log::warn!("HLS variant {target} playlist failed: {e:#}");
+                self.controller.set_current(self.active);
+                return;
+            }
+        };
+        log::info!("HLS switching variant {} -> {} at segment {}", self.active, target, next_seq);
+        self.active = target;
+        self.next_seq = next_seq;
+        if let Some(tx) = &self.events {
+            let _ = tx.try_send(PlaybackEvent::VariantChanged(target));
+        }
+    }
+
+    /// Download the next segment into `self.segment`; `Ok(false)` at the end of the stream.
+    fn next_segment(&mut self) -> io::Result<bool> {
+        self.maybe_switch();
+        let mut reloads = 0;
+        let uri = loop {
+            let playlist = &self.playlists[&self.active];
+            if let Some(seg) = playlist.segment(self.next_seq) {
+                break seg.uri.clone();
+            }
+            if playlist.ended || reloads >= LIVE_RELOAD_ATTEMPTS {
+                return Ok(false);
+            }
+            // live: wait for the playlist to grow; jump ahead if we fell behind its window
+            let wait = Duration::from_millis((playlist.target_duration_ms / 2).max(500) as u64);
+            thread::sleep(wait);
+            reloads += 1;
+            let first = self.load_playlist(self.active).map_err(io::Error::other)?.media_sequence;
+            self.next_seq = self.next_seq.max(first);
+        };
+
+        let mut attempt = 1;
+        let (data, elapsed) = loop {
+            let started = Instant::now();
+            match adaptive::http_get(&self.client, &uri, &self.net).and_then(|resp| resp.bytes()) {
+                Ok(data) => break (data, started.elapsed()),
+                Err(e) if attempt < SEGMENT_ATTEMPTS => {
+                    log::warn!("HLS segment {} failed ({attempt}/{SEGMENT_ATTEMPTS}): {e}", self.next_seq);
+                    thread::sleep(Duration::from_millis(250 * attempt as u64));
+                    attempt += 1;
+                }
+                Err(e) => return Err(io::Error::other(e)),
+            }
+        };
+        let secs = elapsed.as_secs_f64().max(0.001);
+        self.controller.sample((data.len() as f64 * 8.0 / secs) as u64);
+
+        self.segment = data.to_vec();
+        self.segment_pos = 0;
+        self.next_seq += 1;
+        Ok(true)
+    }
+}
+
+impl MediaSource for HlsSource {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.segment_pos >= self.segment.len() {
+            if !self.next_segment()? {
+                return Ok(0);
+            }
+        }
+        let n = buf.len().min(self.segment.len() - self.segment_pos);
+        buf[..n].copy_from_slice(&self.segment[self.segment_pos..self.segment_pos + n]);
+        self.segment_pos += n;
+        Ok(n)
+    }
+
+    fn seek(&mut self, _pos: SeekFrom) -> io::Result<u64> {
+        Err(io::Error::new(io::ErrorKind::Unsupported, "HLS segment stream is not seekable"))
+    }
+
+    fn size(&mut self) -> Option<u64> {
+        None
+    }
+
+    fn is_seekable(&self) -> bool {
+        false
+    }
+}
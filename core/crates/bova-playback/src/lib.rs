@@ -11,6 +11,19 @@ mod mpv_player;
 pub use mpv_player::MpvPlayer;
 #[cfg(feature = "mpv")]
 pub use mpv_player::start_mpv_playback_handles;
+#[cfg(feature = "ffmpeg")]
+mod demux;
+#[cfg(feature = "ffmpeg")]
+mod gapless;
+pub mod adaptive;
+pub mod hls;
+pub mod source;
+pub mod stats;
+
+pub use adaptive::{QualityMode, Variant};
+pub use source::{FileSource, MediaSource, MemorySource};
+pub use stats::{PlaybackStats, StatsCollector};
+pub use bova_probe::{Chapter, MediaTags};
 
 // 播放引擎类型
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -40,6 +53,21 @@ pub enum PlaybackEvent {
     Stopped,
     Finished,
     PositionChanged(f64),
+    /// Demux read-ahead fill level while (re)buffering, 0-100
+    Buffering(u8),
+    /// Quality levels of an HLS/DASH stream, in manifest order
+    VariantsAvailable(Vec<Variant>),
+    /// Active variant changed (`Variant::index`)
+    VariantChanged(usize),
+    /// Tags of the opened media; `has_video` is false for audio-only files
+    /// (a cover picture, if any, arrives as a single `VideoFrame`)
+    Metadata { has_video: bool, tags: MediaTags },
+    /// Chapters of the opened media, sorted by start; only sent when there are any
+    Chapters(Vec<Chapter>),
+    /// Playback continued into the item queued through `PlaybackHandles::next_tx` (its URL)
+    TrackChanged(String),
+    /// Diagnostics snapshot, sent every `stats::STATS_INTERVAL` (same data as `PlaybackHandles::stats`)
+    Stats(PlaybackStats),
     Error(String),
 }
 
@@ -122,6 +150,11 @@ pub enum MpvCommand {
     Pause,                    // set pause=yes
     Resume,                   // set pause=no
     SetVolume(f64),           // set volume=N (0-100)
+    SetQuality(QualityMode),  // HLS/DASH variant: auto or fixed
+    NextChapter,              // add chapter 1
+    PreviousChapter,          // add chapter -1
+    SelectAudio(i64),         // set aid=N
+    SetSpeed(f64),            // set speed=N
 }
 
 #[cfg(feature = "ffmpeg")]
@@ -129,12 +162,8 @@ pub fn start_playback_with(url: &str, cfg: PlaybackConfig) -> anyhow::Result<Pla
     use ffmpeg_next as ffmpeg;
     use std::thread;
     let _ = ffmpeg::init();
-    // 降低 FFmpeg 日志等级，抑制 AAC 时间戳告警等噪声
-    #[allow(deprecated)]
-    {
-        use ffmpeg_next::util::log::Level;
-        ffmpeg_next::util::log::set_level(Level::Error);
-    }
+    // FFmpeg 日志转发到 `log`（target `ffmpeg`，默认过滤规则只保留错误，抑制 AAC 时间戳告警等噪声）
+    forward_ffmpeg_log();
 
     // channels
     let (video_tx, video_rx) = bounded::<VideoFrame>(32); // 增加视频帧缓冲区大小
@@ -142,19 +171,20 @@ pub fn start_playback_with(url: &str, cfg: PlaybackConfig) -> anyhow::Result<Pla
     let (subtitle_tx, subtitle_rx) = bounded::<SubtitleFrame>(32); // 字幕帧缓冲区
     let (stop_tx, stop_rx) = bounded::<()>(1);
     let (eos_tx, eos_rx) = bounded::<()>(1);
+    let (event_tx, event_rx) = bounded::<PlaybackEvent>(64);
+    let (next_tx, next_rx) = bounded::<String>(1);
+    let stats = Arc::new(StatsCollector::new("ffmpeg"));
 
+    // 每一项由各自的 playback_thread 解码，gapless 负责转发与衔接
     let url = url.to_string();
-    let hw = cfg.hwaccel;
-    let subtitle_enabled = cfg.subtitle_enabled;
-    let subtitle_index = cfg.subtitle_index;
+    let out_stats = stats.clone();
     thread::spawn(move || {
-        if let Err(e) = playback_thread(&url, &video_tx, &audio_tx, &subtitle_tx, &stop_rx, hw, subtitle_enabled, subtitle_index) {
-            eprintln!("playback_thread error: {e:?}");
-        }
+        let out = gapless::Outputs { video_tx, audio_tx, subtitle_tx, event_tx, stats: out_stats };
+        gapless::run_sequence(url, cfg, &out, &stop_rx, next_rx);
         let _ = eos_tx.send(());
     });
 
-    Ok(PlaybackHandles { video_rx, audio_rx, subtitle_rx, stop_tx, eos_rx, cmd_tx: None, track_info_rx: None, target_render_w: Arc::new(AtomicU32::new(640)), target_render_h: Arc::new(AtomicU32::new(360)) })
+    Ok(PlaybackHandles { video_rx, audio_rx, subtitle_rx, stop_tx, eos_rx, cmd_tx: None, track_info_rx: None, target_render_w: Arc::new(AtomicU32::new(640)), target_render_h: Arc::new(AtomicU32::new(360)), event_rx: Some(event_rx), next_tx: Some(next_tx), stats, source: None })
 }
 
 #[cfg(not(feature = "ffmpeg"))]
@@ -170,12 +200,30 @@ pub fn start_playback_with(url: &str, cfg: PlaybackConfig) -> anyhow::Result<Pla
     }
 }
 
+/// Play from a custom `MediaSource` instead of a URL. `name` is used as a
+/// container format hint (file extension). Honours `cfg.engine`.
+pub fn start_playback_source(source: Box<dyn MediaSource>, name: &str, cfg: PlaybackConfig) -> anyhow::Result<PlaybackHandles> {
+    let guard = source::register_source(source, name);
+    #[cfg(feature = "mpv")]
+    let result = if cfg.engine == Some(PlaybackEngine::MPV) {
+        start_mpv_playback_handles(guard.url(), &cfg)
+    } else {
+        start_playback_with(guard.url(), cfg)
+    };
+    #[cfg(not(feature = "mpv"))]
+    let result = start_playback_with(guard.url(), cfg);
+    // on error the guard is dropped here, unregistering the source
+    result.map(|handles| PlaybackHandles { source: Some(Arc::new(guard)), ..handles })
+}
+
 #[derive(Debug, Clone)]
 pub struct AudioFrame {
     pub channels: u16,
     pub sample_rate: u32,
     pub samples: Vec<i16>, // interleaved
     pub pts_ms: Option<i64>,
+    /// Duration of the item this frame belongs to, when the container reports one
+    pub duration_ms: Option<i64>,
 }
 
 #[derive(Debug, Clone)]
@@ -192,6 +240,17 @@ pub struct PlaybackHandles {
     /// Dynamic render size — GUI writes, render thread reads
     pub target_render_w: Arc<AtomicU32>,
     pub target_render_h: Arc<AtomicU32>,
+    /// Engine status events (buffering, errors, ...)
+    pub event_rx: Option<Receiver<PlaybackEvent>>,
+    /// Queue the item to play after the current one. It is opened and buffered
+    /// right away and continues without a gap (`PlaybackEvent::TrackChanged`);
+    /// queuing again replaces the previously queued item.
+    pub next_tx: Option<Sender<String>>,
+    /// Decode/render diagnostics; the front end records presented, dropped and late frames here
+    pub stats: Arc<StatsCollector>,
+    /// Registration of the custom source being played; when the last clone of
+    /// the handles is dropped it is unregistered, if the engine never opened it
+    pub source: Option<Arc<source::SourceGuard>>,
 }
 
 #[derive(Debug, Clone, Default)]
@@ -200,6 +259,73 @@ pub struct PlaybackConfig {
     pub subtitle_enabled: bool,
     pub subtitle_index: Option<u32>,
     pub engine: Option<PlaybackEngine>,
+    pub network: NetworkOptions,
+    /// Initial HLS/DASH variant selection
+    pub quality: QualityMode,
+    /// Start position in milliseconds (resume), `None` = from the beginning
+    pub start_ms: Option<i64>,
+    /// Overlap between the end of an item and the start of the queued next one,
+    /// 0 = plain gapless. Only the FFmpeg engine mixes audio; mpv stays gapless.
+    pub crossfade_ms: u32,
+    pub replay_gain: ReplayGainMode,
+    /// Audio track, 1-based in file order (mpv `aid` numbering); `None` = default track
+    pub audio_track: Option<u32>,
+}
+
+/// Loudness normalisation from ReplayGain / R128 tags
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ReplayGainMode {
+    Off,
+    #[default]
+    Track,
+    Album,
+}
+
+/// Options applied when the input is a network URL (http/https/...).
+#[derive(Debug, Clone)]
+pub struct NetworkOptions {
+    /// Read-ahead budget of the demux queue, in milliseconds of media
+    pub cache_ms: u32,
+    /// Socket read/write timeout before a request is considered stalled
+    pub timeout_ms: u32,
+    /// Reconnect when the server drops the connection mid-stream
+    pub reconnect: bool,
+    /// Upper bound for the reconnect backoff, in seconds
+    pub reconnect_delay_max_s: u32,
+    pub user_agent: Option<String>,
+    /// Extra HTTP request headers, e.g. `("X-Emby-Token", token)`
+    pub headers: Vec<(String, String)>,
+}
+
+impl Default for NetworkOptions {
+    fn default() -> Self {
+        Self {
+            cache_ms: 1000,
+            timeout_ms: 10_000,
+            reconnect: true,
+            reconnect_delay_max_s: 5,
+            user_agent: Some(format!("BovaPlayer/{}", env!("CARGO_PKG_VERSION"))),
+            headers: Vec::new(),
+        }
+    }
+}
+
+impl From<&bova_core::MediaOptions> for NetworkOptions {
+    fn from(opts: &bova_core::MediaOptions) -> Self {
+        let mut net = Self { cache_ms: opts.network_cache_ms, ..Self::default() };
+        if let Some(ms) = opts.network_timeout_ms { net.timeout_ms = ms; }
+        if let Some(ua) = &opts.user_agent { net.user_agent = Some(ua.clone()); }
+        net.headers = opts.http_headers.clone();
+        net
+    }
+}
+
+/// True for inputs that go through FFmpeg/mpv network protocols.
+pub fn is_network_url(url: &str) -> bool {
+    let lower = url.to_ascii_lowercase();
+    ["http://", "https://", "rtmp://", "rtsp://", "ftp://", "tcp://", "udp://"]
+        .iter()
+        .any(|p| lower.starts_with(p))
 }
 
 // MPV播放器启动函数 (command-based API) — legacy, not actively used
@@ -209,7 +335,7 @@ pub fn start_mpv_playback(url: &str) -> anyhow::Result<(Sender<PlaybackCommand>,
     let (_event_tx, event_rx) = bounded::<PlaybackEvent>(32);
     
     // The command-based API is deprecated in favor of start_mpv_playback_handles
-    eprintln!("[bova-playback] Warning: start_mpv_playback is deprecated, use start_mpv_playback_handles");
+    log::warn!("start_mpv_playback is deprecated, use start_mpv_playback_handles");
     
     Ok((cmd_tx, event_rx))
 }
@@ -257,63 +383,103 @@ pub fn start_playback(_url: &str) -> anyhow::Result<PlaybackHandles> {
     let (_eos_tx, eos_rx) = bounded::<()>(1);
     // no-op producer
     let _ = video_tx;
-    Ok(PlaybackHandles { video_rx, audio_rx, subtitle_rx, stop_tx, eos_rx, cmd_tx: None, track_info_rx: None, target_render_w: Arc::new(AtomicU32::new(640)), target_render_h: Arc::new(AtomicU32::new(360)) })
+    Ok(PlaybackHandles { video_rx, audio_rx, subtitle_rx, stop_tx, eos_rx, cmd_tx: None, track_info_rx: None, target_render_w: Arc::new(AtomicU32::new(640)), target_render_h: Arc::new(AtomicU32::new(360)), event_rx: None, next_tx: None, stats: Arc::new(StatsCollector::default()), source: None })
 }
 
 #[cfg(feature = "ffmpeg")]
 pub fn start_playback(url: &str) -> anyhow::Result<PlaybackHandles> {
-    // use anyhow::Context; // 暂时未使用
-    // use crossbeam_channel::select; // 暂时未使用
-    use ffmpeg_next as ffmpeg;
-    use std::thread;
-
-    // init ffmpeg
-    let _ = ffmpeg::init();
-    #[allow(deprecated)]
-    {
-        use ffmpeg_next::util::log::Level;
-        ffmpeg_next::util::log::set_level(Level::Error);
-    }
-
-    // channels
-    let (video_tx, video_rx) = bounded::<VideoFrame>(32); // 增加视频帧缓冲区大小
-    let (audio_tx, audio_rx) = bounded::<AudioFrame>(64); // 增加音频帧缓冲区大小
-    let (subtitle_tx, subtitle_rx) = bounded::<SubtitleFrame>(32); // 字幕帧缓冲区
-    let (stop_tx, stop_rx) = bounded::<()>(1);
-    let (eos_tx, eos_rx) = bounded::<()>(1);
-
-    let url = url.to_string();
-    thread::spawn(move || {
-        if let Err(e) = playback_thread(&url, &video_tx, &audio_tx, &subtitle_tx, &stop_rx, false, false, None) {
-            eprintln!("playback_thread error: {e:?}");
-        }
-        let _ = eos_tx.send(());
-    });
-
-    Ok(PlaybackHandles { video_rx, audio_rx, subtitle_rx, stop_tx, eos_rx, cmd_tx: None, track_info_rx: None, target_render_w: Arc::new(AtomicU32::new(640)), target_render_h: Arc::new(AtomicU32::new(360)) })
+    start_playback_with(url, PlaybackConfig::default())
 }
 
 #[cfg(feature = "ffmpeg")]
-fn playback_thread(url: &str, video_tx: &Sender<VideoFrame>, audio_tx: &Sender<AudioFrame>, subtitle_tx: &Sender<SubtitleFrame>, stop_rx: &Receiver<()>, hwaccel: bool, subtitle_enabled: bool, subtitle_index: Option<u32>) -> anyhow::Result<()> {
+fn playback_thread(url: &str, video_tx: &Sender<VideoFrame>, audio_tx: &Sender<AudioFrame>, subtitle_tx: &Sender<SubtitleFrame>, stop_rx: &Receiver<()>, event_tx: &Sender<PlaybackEvent>, cfg: &PlaybackConfig, stats: &StatsCollector) -> anyhow::Result<()> {
     use anyhow::Context;
     use crossbeam_channel::select;
     use ffmpeg_next as ffmpeg;
+    use std::time::{Duration, Instant};
 
-    // open input
-    let mut ictx = ffmpeg::format::input(&url).with_context(|| format!("open input failed: {url}"))?;
+    let hwaccel = cfg.hwaccel;
+    let subtitle_enabled = cfg.subtitle_enabled;
+    let subtitle_index = cfg.subtitle_index;
 
-    // find best streams
-    let vs = ictx
+    // HLS master playlist: segments are fetched by `hls::HlsSource`, which measures
+    // throughput per segment and switches variant at segment boundaries (Auto).
+    // Playlists it can't concatenate are opened on a single variant instead.
+    let mut open_url = url.to_string();
+    let mut _hls_source = None;
+    let mut start_ms = cfg.start_ms.filter(|&ms| ms > 0);
+    if let Some(kind) = adaptive::manifest_kind(url).filter(|_| !source::is_source_url(url)) {
+        match adaptive::fetch_variants(url, &cfg.network) {
+            Ok(variants) if !variants.is_empty() => {
+                let _ = event_tx.try_send(PlaybackEvent::VariantsAvailable(variants.clone()));
+                if kind == adaptive::ManifestKind::Hls {
+                    match hls::HlsSource::open(variants.clone(), cfg.quality, start_ms, &cfg.network, Some(event_tx.clone())) {
+                        Ok(src) => {
+                            let i = src.current();
+                            log::info!("HLS variant {}: {}", i, variants[i]);
+                            let _ = event_tx.try_send(PlaybackEvent::VariantChanged(i));
+                            let guard = source::register_source(Box::new(src), "hls.ts");
+                            open_url = guard.url().to_string();
+                            _hls_source = Some(guard);
+                            // the source already starts at the segment containing start_ms
+                            start_ms = None;
+                        }
+                        Err(e) => {
+                            let i = match cfg.quality {
+                                QualityMode::Fixed(i) if i < variants.len() => i,
+                                _ => adaptive::lowest_variant(&variants).unwrap_or(0),
+                            };
+                            log::info!("HLS variant {} without in-stream switching ({e:#}): {}", i, variants[i]);
+                            open_url = variants[i].uri.clone();
+                            let _ = event_tx.try_send(PlaybackEvent::VariantChanged(i));
+                        }
+                    }
+                }
+            }
+            Ok(_) => {}
+            Err(e) => log::warn!("manifest fetch failed, opening as-is: {e:#}"),
+        }
+    }
+
+    // open input (reconnect / timeout / headers for network URLs)
+    let mut ictx = demux::open_input(&open_url, &cfg.network).with_context(|| format!("open input failed: {open_url}"))?;
+    // container duration (AV_TIME_BASE units), used for the crossfade window
+    let duration_ms = Some(ictx.duration()).filter(|&d| d > 0).map(|d| d / 1000);
+
+    // resume: seek to the keyframe at or before the start position (AV_TIME_BASE units)
+    if let Some(start_ms) = start_ms {
+        let ts = start_ms * 1000;
+        if let Err(e) = ictx.seek(ts, ..ts) {
+            log::warn!("start seek to {start_ms}ms failed: {e}");
+        }
+    }
+
+    // find best streams; cover art is not a video track (audio-only files)
+    let vs_opt = ictx
         .streams()
         .best(ffmpeg::media::Type::Video)
-        .context("no video stream")?;
-    let stream_index = vs.index();
-    let v_time_base = vs.time_base();
+        .filter(|s| !is_cover_art(s))
+        .or_else(|| ictx.streams().find(|s| s.parameters().medium() == ffmpeg::media::Type::Video && !is_cover_art(s)));
+    let video_index_opt = vs_opt.as_ref().map(|s| s.index());
+    let v_time_base = vs_opt.as_ref().map_or(ffmpeg::Rational(1, 1000), |s| s.time_base());
 
-    let audio_index_opt: Option<usize> = ictx
-        .streams()
-        .best(ffmpeg::media::Type::Audio)
-        .map(|s| s.index());
+    // 指定的音轨（第 N 条音频流），不存在时退回最佳音频流
+    let requested_audio = cfg.audio_track.filter(|&n| n > 0).and_then(|n| {
+        let found = ictx
+            .streams()
+            .filter(|s| s.parameters().medium() == ffmpeg::media::Type::Audio)
+            .nth(n as usize - 1)
+            .map(|s| s.index());
+        if found.is_none() {
+            log::warn!("音轨 {} 不存在，使用默认音轨", n);
+        }
+        found
+    });
+    let audio_index_opt: Option<usize> = requested_audio.or_else(|| {
+        ictx.streams()
+            .best(ffmpeg::media::Type::Audio)
+            .map(|s| s.index())
+    });
         
     // 查找字幕流
     let subtitle_index_opt: Option<usize> = if subtitle_enabled {
@@ -325,11 +491,11 @@ fn playback_thread(url: &str, video_tx: &Sender<VideoFrame>, audio_tx: &Sender<A
                 if stream.parameters().medium() == ffmpeg::media::Type::Subtitle {
                     Some(idx_usize)
                 } else {
-                    eprintln!("[bova-playback] 指定的字幕流索引 {} 不是字幕类型", idx);
+                    log::warn!("指定的字幕流索引 {} 不是字幕类型", idx);
                     None
                 }
             } else {
-                eprintln!("[bova-playback] 指定的字幕流索引 {} 超出范围", idx);
+                log::warn!("指定的字幕流索引 {} 超出范围", idx);
                 None
             }
         } else {
@@ -341,135 +507,136 @@ fn playback_thread(url: &str, video_tx: &Sender<VideoFrame>, audio_tx: &Sender<A
     };
     
     if let Some(idx) = subtitle_index_opt {
-        eprintln!("[bova-playback] 已选择字幕流: {}", idx);
+        log::debug!("已选择字幕流: {}", idx);
     }
 
-    // open video decoder (v7 style)
-    let codec_params = vs.parameters();
-    let mut context = ffmpeg::codec::context::Context::from_parameters(codec_params).context("from_parameters")?;
-
-    // 尝试附加 VideoToolbox 硬件设备（macOS），失败则忽略回退
-    // 先缓存 AVCodecContext 指针，避免 decoder().video() 移动 context 后无法访问
-    let mut ctx_ptr_saved: *mut ffmpeg_next::ffi::AVCodecContext = std::ptr::null_mut();
-    if hwaccel {
-        // Try attach VideoToolbox device; if not present, fallback silently
-        unsafe {
-            use ffmpeg_next::ffi;
-            let name = std::ffi::CString::new("videotoolbox").unwrap();
-            let dev_type = ffi::av_hwdevice_find_type_by_name(name.as_ptr());
-            // Attempt device creation regardless; av_hwdevice_ctx_create will fail if type is invalid
-            let mut hw_dev: *mut ffi::AVBufferRef = std::ptr::null_mut();
-            let r = ffi::av_hwdevice_ctx_create(
-                &mut hw_dev,
-                dev_type,
-                std::ptr::null(),
-                std::ptr::null_mut(),
-                0,
-            );
-            if r >= 0 && !hw_dev.is_null() {
-                let ctx_ptr = context.as_mut_ptr();
-                ctx_ptr_saved = ctx_ptr;
-                if !ctx_ptr.is_null() {
-                    // 安装 get_format：优先选择名为 "videotoolbox_vld" 的像素格式
-                    extern "C" fn vt_get_format(_ctx: *mut ffmpeg_next::ffi::AVCodecContext, fmts: *const ffmpeg_next::ffi::AVPixelFormat) -> ffmpeg_next::ffi::AVPixelFormat {
-                        unsafe {
-                            let mut i = 0isize;
-                            let mut first: ffmpeg_next::ffi::AVPixelFormat = *fmts; // assume list not empty
-                            loop {
-                                let fmt = *fmts.offset(i);
-                                if fmt as i32 == -1 { break; } // AV_PIX_FMT_NONE
-                                let name_ptr = ffmpeg_next::ffi::av_get_pix_fmt_name(fmt);
-                                if !name_ptr.is_null() {
-                                    let c = std::ffi::CStr::from_ptr(name_ptr);
-                                    if let Ok(s) = c.to_str() {
-                                        if s == "videotoolbox_vld" { return fmt; }
-                                    }
-                                }
-                                if i == 0 { first = fmt; }
-                                i += 1;
-                            }
-                            first
-                        }
-                    }
-                    (*ctx_ptr).get_format = Some(vt_get_format);
-                    // 绑定设备
-                    (*ctx_ptr).hw_device_ctx = hw_dev;
-                    eprintln!("[bova-playback] VideoToolbox device attached");
-                } else {
-                    ffi::av_buffer_unref(&mut hw_dev);
-                }
-            } else {
-                eprintln!("[bova-playback] create VideoToolbox device failed (code={r}) -> fallback software");
-            }
-        }
+    if video_index_opt.is_none() && audio_index_opt.is_none() {
+        anyhow::bail!("no audio or video stream");
     }
-    let mut dec = context.decoder().video().context("open video decoder")?;
 
-    // 创建 hw_frames_ctx（在 decoder 打开后，通过 context 获取设备引用），失败回退
-    if hwaccel {
-        unsafe {
-            use ffmpeg_next::ffi;
-            let ctx_ptr = ctx_ptr_saved;
-            if !ctx_ptr.is_null() {
-                let dev = (*ctx_ptr).hw_device_ctx;
-                if !dev.is_null() {
-                    let frames_ref = ffi::av_hwframe_ctx_alloc(dev);
-                    if !frames_ref.is_null() {
-                        let frames_ctx = (*frames_ref).data as *mut ffi::AVHWFramesContext;
-                        if !frames_ctx.is_null() {
-                            // format: videotoolbox_vld；sw_format: NV12；尺寸：解码器宽高
-                            let vt_name = std::ffi::CString::new("videotoolbox_vld").unwrap();
-                            let sw_name = std::ffi::CString::new("nv12").unwrap();
-                            let vt_fmt = ffi::av_get_pix_fmt(vt_name.as_ptr());
-                            let sw_fmt = ffi::av_get_pix_fmt(sw_name.as_ptr());
-                            (*frames_ctx).format = vt_fmt;
-                            (*frames_ctx).sw_format = sw_fmt;
-                            (*frames_ctx).width = dec.width() as i32;
-                            (*frames_ctx).height = dec.height() as i32;
-                            if ffi::av_hwframe_ctx_init(frames_ref) >= 0 {
-                                (*ctx_ptr).hw_frames_ctx = frames_ref;
-                                eprintln!("[bova-playback] hw_frames_ctx initialized");
-                            } else {
-                                ffi::av_buffer_unref(&mut (frames_ref as *mut _));
-                                eprintln!("[bova-playback] hw_frames_ctx init failed -> fallback possible");
-                            }
-                        }
-                    }
-                }
-            }
+    // 标签与 ReplayGain；纯音频时显示内嵌封面
+    let tags = bova_probe::read_tags(&ictx);
+    let _ = event_tx.try_send(PlaybackEvent::Metadata { has_video: video_index_opt.is_some(), tags: tags.clone() });
+    let chapters = bova_probe::read_chapters(&ictx);
+    if !chapters.is_empty() {
+        log::info!("{} chapters", chapters.len());
+        let _ = event_tx.try_send(PlaybackEvent::Chapters(chapters));
+    }
+    let replay_gain = match cfg.replay_gain {
+        ReplayGainMode::Off => None,
+        ReplayGainMode::Track => tags.replay_gain.linear_gain(false),
+        ReplayGainMode::Album => tags.replay_gain.linear_gain(true),
+    }.filter(|g| (g - 1.0).abs() > 0.001);
+    if let Some(g) = replay_gain {
+        log::info!("ReplayGain ({:?}): x{:.3}", cfg.replay_gain, g);
+    }
+    if video_index_opt.is_none() {
+        log::info!("audio-only media");
+        if let Some(cover) = ictx.streams().find(is_cover_art).and_then(|s| decode_cover_art(&s, duration_ms)) {
+            let _ = video_tx.send(cover);
         }
     }
 
+    let mut dec_opt = match &vs_opt {
+        Some(vs) => Some(open_video_decoder(vs, hwaccel)?),
+        None => None,
+    };
+    // 硬件解码方式在首次成功下载硬件帧后才确定
+    stats.set_stream(dec_opt.as_ref().and_then(|d| d.codec()).map(|c| c.name().to_string()), None);
+
     // swscale: convert to RGBA
     let mut scaler: Option<ffmpeg::software::scaling::Context> = None;
 
-    // audio decoder/resampler (lazy init on first audio packet)
+    // audio decoder/resampler — opened up front, the demux thread owns the input afterwards
     let mut adec_opt: Option<ffmpeg::decoder::Audio> = None;
     let mut ares_opt: Option<ffmpeg::software::resampling::Context> = None;
-    let mut out_ch_layout = ffmpeg::channel_layout::ChannelLayout::STEREO;
+    let out_ch_layout = ffmpeg::channel_layout::ChannelLayout::STEREO;
     let out_rate = 48_000;
     let mut a_time_base_opt: Option<ffmpeg::Rational> = None;
+    if let Some(stream) = audio_index_opt.and_then(|ai| ictx.stream(ai)) {
+        if let Ok(acontext) = ffmpeg::codec::context::Context::from_parameters(stream.parameters()) {
+            if let Ok(adec) = acontext.decoder().audio() {
+                let in_fmt = adec.format();
+                let in_rate = adec.rate();
+                let in_ch_layout = adec.channel_layout();
+                a_time_base_opt = Some(stream.time_base());
+                if let Ok(ares) = ffmpeg::software::resampling::Context::get(
+                    in_fmt,
+                    in_ch_layout,
+                    in_rate,
+                    ffmpeg::format::Sample::I16(ffmpeg::format::sample::Type::Packed),
+                    out_ch_layout,
+                    out_rate,
+                ) {
+                    adec_opt = Some(adec);
+                    ares_opt = Some(ares);
+                }
+            }
+        }
+    }
 
-    // read packets
-    let mut hw_dl_ok: u64 = 0;
-    let mut hw_dl_fail: u64 = 0;
-    let mut v_frames: u64 = 0;
-
-    // 字幕解码器（懒加载）
+    // 字幕解码器
     let mut sdec_opt: Option<ffmpeg::decoder::Subtitle> = None;
     let mut subtitle_time_base_opt: Option<ffmpeg::Rational> = None;
-    
-    for (stream, packet) in ictx.packets() {
+    if let Some(stream) = subtitle_index_opt.and_then(|si| ictx.stream(si)) {
+        if let Ok(scontext) = ffmpeg::codec::context::Context::from_parameters(stream.parameters()) {
+            if let Ok(sdec) = scontext.decoder().subtitle() {
+                sdec_opt = Some(sdec);
+                subtitle_time_base_opt = Some(stream.time_base());
+                log::debug!("字幕解码器初始化成功");
+            }
+        }
+    }
+
+    // Demux on a separate thread into a bounded queue holding `cache_ms` of media,
+    // so short network stalls are absorbed instead of starving the decoders.
+    let queue = Arc::new(demux::PacketQueue::new(cfg.network.cache_ms));
+    // buffered duration is measured on the video stream, or the audio stream for audio-only media
+    let ref_stream = video_index_opt.or(audio_index_opt).unwrap_or(0);
+    let _demuxer = demux::spawn_demuxer(ictx, queue.clone(), ref_stream);
+
+    let mut buffering = true;
+    let mut last_buffering_pct: Option<u8> = None;
+    loop {
         // stop request
         select! {
             recv(stop_rx) -> _ => { break; }
             default => {}
         }
 
-        if stream.index() == stream_index {
+        // (re)buffering: hold decoding until the queue is full again or input ended
+        if buffering {
+            let pct = queue.fill_percent();
+            if last_buffering_pct != Some(pct) {
+                let _ = event_tx.try_send(PlaybackEvent::Buffering(pct));
+                last_buffering_pct = Some(pct);
+            }
+            if pct < 100 {
+                queue.wait_for_data(Duration::from_millis(50));
+                continue;
+            }
+            buffering = false;
+            last_buffering_pct = None;
+        }
+
+        let packet = match queue.pop(Duration::from_millis(50)) {
+            demux::Pop::Packet(p) => p,
+            demux::Pop::Empty => { buffering = true; continue; }
+            demux::Pop::End(None) => break,
+            demux::Pop::End(Some(err)) => {
+                let _ = event_tx.try_send(PlaybackEvent::Error(err));
+                break;
+            }
+        };
+        let packet_stream = packet.stream();
+        stats.set_demux_fill(queue.fill_percent());
+
+        if video_index_opt == Some(packet_stream) {
             // video packet
+            let Some(dec) = dec_opt.as_mut() else { continue };
+            let decode_started = Instant::now();
             if let Err(e) = dec.send_packet(&packet) {
-                eprintln!("send_packet video err: {e:?}");
+                log::warn!("send_packet video err: {e:?}");
                 continue;
             }
             let mut frame = ffmpeg::frame::Video::empty();
@@ -490,22 +657,24 @@ fn playback_thread(url: &str, video_tx: &Sender<VideoFrame>, audio_tx: &Sender<A
                             let tr = ffi::av_hwframe_transfer_data(dst_ptr, src_ptr, 0);
                             if tr >= 0 {
                                 use_frame_ref = false; // 使用 sw_download
-                                hw_dl_ok = hw_dl_ok.saturating_add(1);
                             } else {
-                                eprintln!("[bova-playback] hwframe transfer failed: {tr}, fallback to original frame");
-                                hw_dl_fail = hw_dl_fail.saturating_add(1);
+                                log::warn!("hwframe transfer failed: {tr}, fallback to original frame");
                             }
+                            stats.record_hw_transfer("videotoolbox", tr >= 0);
                         }
                     }
                 }
 
+                let decode_time = decode_started.elapsed();
                 let (src_fw, src_fh) = if use_frame_ref { (frame.width(), frame.height()) } else { (sw_download.width(), sw_download.height()) };
                 let src_w = src_fw;
                 let src_h = src_fh;
                 // init scaler if needed
-                if scaler.is_none() || scaler.as_ref().unwrap().input().format != frame.format() {
+                // 格式或尺寸变化时重建（HLS 切换档位后分辨率会变）
+                let src_format = if use_frame_ref { frame.format() } else { sw_download.format() };
+                if scaler.as_ref().is_none_or(|sc| sc.input().format != src_format || sc.input().width != src_w || sc.input().height != src_h) {
                     scaler = Some(ffmpeg::software::scaling::Context::get(
-                        if use_frame_ref { frame.format() } else { sw_download.format() },
+                        src_format,
                         src_w, src_h,
                         ffmpeg::format::Pixel::RGBA,
                         src_w, src_h,
@@ -516,6 +685,7 @@ fn playback_thread(url: &str, video_tx: &Sender<VideoFrame>, audio_tx: &Sender<A
                 rgba.set_format(ffmpeg::format::Pixel::RGBA);
                 rgba.set_width(src_w);
                 rgba.set_height(src_h);
+                let scale_started = Instant::now();
                 if let Some(sc) = &mut scaler {
                     if use_frame_ref {
                         sc.run(&frame, &mut rgba).context("swscale run")?;
@@ -533,30 +703,14 @@ fn playback_thread(url: &str, video_tx: &Sender<VideoFrame>, audio_tx: &Sender<A
                     let end = start + w * 4;
                     buf.extend_from_slice(&data[start..end]);
                 }
+                stats.record_video_frame(w as u32, h as u32, Some(decode_time), scale_started.elapsed());
                 // compute pts in ms
                 let pts_ms = if use_frame_ref { frame.timestamp().map(|ts| ts_to_ms(ts, v_time_base)) } else { sw_download.timestamp().map(|ts| ts_to_ms(ts, v_time_base)) };
-                let _ = video_tx.send(VideoFrame { width: w as u32, height: h as u32, rgba: buf, pts_ms, duration_ms: None });
-
-                v_frames = v_frames.saturating_add(1);
-                if v_frames % 120 == 0 && hwaccel {
-                    eprintln!("[bova-playback] HW transfer stats: ok={}, fail={}", hw_dl_ok, hw_dl_fail);
-                }
+                let _ = video_tx.send(VideoFrame { width: w as u32, height: h as u32, rgba: buf, pts_ms, duration_ms });
             }
         } else if let Some(si) = subtitle_index_opt {
-            if stream.index() == si {
+            if packet_stream == si {
                 // 字幕包处理
-                if sdec_opt.is_none() {
-                    // 懒加载字幕解码器
-                    let scodec_params = stream.parameters();
-                    if let Ok(scontext) = ffmpeg::codec::context::Context::from_parameters(scodec_params) {
-                        if let Ok(sdec) = scontext.decoder().subtitle() {
-                            sdec_opt = Some(sdec);
-                            subtitle_time_base_opt = Some(stream.time_base());
-                            eprintln!("[bova-playback] 字幕解码器初始化成功");
-                        }
-                    }
-                }
-                
                 if let Some(sdec) = &mut sdec_opt {
                     let mut sub = ffmpeg::Subtitle::new();
                     if sdec.decode(&packet, &mut sub).is_ok() {
@@ -614,34 +768,10 @@ fn playback_thread(url: &str, video_tx: &Sender<VideoFrame>, audio_tx: &Sender<A
         }
         
         if let Some(ai) = audio_index_opt {
-            if stream.index() == ai {
-                // lazy init audio decoder/resampler with current stream params
-                if adec_opt.is_none() || ares_opt.is_none() {
-                    let acodec_params = stream.parameters();
-                    if let Ok(acontext) = ffmpeg::codec::context::Context::from_parameters(acodec_params) {
-                        if let Ok(adec) = acontext.decoder().audio() {
-                            out_ch_layout = ffmpeg::channel_layout::ChannelLayout::STEREO;
-                            let in_fmt = adec.format();
-                            let in_rate = adec.rate();
-                            let in_ch_layout = adec.channel_layout();
-                            a_time_base_opt = Some(stream.time_base());
-                            if let Ok(ares) = ffmpeg::software::resampling::Context::get(
-                                in_fmt,
-                                in_ch_layout,
-                                in_rate,
-                                ffmpeg::format::Sample::I16(ffmpeg::format::sample::Type::Packed),
-                                out_ch_layout,
-                                out_rate,
-                            ) {
-                                adec_opt = Some(adec);
-                                ares_opt = Some(ares);
-                            }
-                        }
-                    }
-                }
+            if packet_stream == ai {
                 if let (Some(adec), Some(ares)) = (&mut adec_opt, &mut ares_opt) {
                     if let Err(e) = adec.send_packet(&packet) {
-                        eprintln!("send_packet audio err: {e:?}");
+                        log::warn!("send_packet audio err: {e:?}");
                         continue;
                     }
                     let mut afr = ffmpeg::frame::Audio::empty();
@@ -666,8 +796,14 @@ fn playback_thread(url: &str, video_tx: &Sender<VideoFrame>, audio_tx: &Sender<A
                                 let ptr = data.as_ptr() as *const i16;
                                 vec.extend_from_slice(std::slice::from_raw_parts(ptr, copy_samples));
                             }
+                            if let Some(g) = replay_gain {
+                                for sample in &mut vec {
+                                    *sample = (*sample as f32 * g).clamp(i16::MIN as f32, i16::MAX as f32) as i16;
+                                }
+                            }
                             let pts_ms = a_time_base_opt.and_then(|tb| afr.timestamp().map(|ts| ts_to_ms(ts, tb)));
-                            let _ = audio_tx.send(AudioFrame { channels: 2, sample_rate: out_rate as u32, samples: vec, pts_ms });
+                            stats.record_audio_frame();
+                            let _ = audio_tx.send(AudioFrame { channels: 2, sample_rate: out_rate as u32, samples: vec, pts_ms, duration_ms });
                         }
                     }
                 }
@@ -675,13 +811,223 @@ fn playback_thread(url: &str, video_tx: &Sender<VideoFrame>, audio_tx: &Sender<A
         }
     }
 
+    // Unblock the demux thread if it is waiting for queue space
+    queue.close();
+
     // Best-effort flush (optional)
-    let mut frame = ffmpeg::frame::Video::empty();
-    while dec.receive_frame(&mut frame).is_ok() {}
+    if let Some(dec) = dec_opt.as_mut() {
+        let mut frame = ffmpeg::frame::Video::empty();
+        while dec.receive_frame(&mut frame).is_ok() {}
+    }
 
     Ok(())
 }
 
+/// Open the video decoder, attaching a VideoToolbox device when `hwaccel` is set
+/// (falls back to software decoding silently).
+#[cfg(feature = "ffmpeg")]
+fn open_video_decoder(vs: &ffmpeg_next::Stream, hwaccel: bool) -> anyhow::Result<ffmpeg_next::decoder::Video> {
+    use anyhow::Context;
+    use ffmpeg_next as ffmpeg;
+
+    // open video decoder (v7 style)
+    let codec_params = vs.parameters();
+    let mut context = ffmpeg::codec::context::Context::from_parameters(codec_params).context("from_parameters")?;
+
+    // 尝试附加 VideoToolbox 硬件设备（macOS），失败则忽略回退
+    // 先缓存 AVCodecContext 指针，避免 decoder().video() 移动 context 后无法访问
+    let mut ctx_ptr_saved: *mut ffmpeg_next::ffi::AVCodecContext = std::ptr::null_mut();
+    if hwaccel {
+        // Try attach VideoToolbox device; if not present, fallback silently
+        unsafe {
+            use ffmpeg_next::ffi;
+            let name = std::ffi::CString::new("videotoolbox").unwrap();
+            let dev_type = ffi::av_hwdevice_find_type_by_name(name.as_ptr());
+            // Attempt device creation regardless; av_hwdevice_ctx_create will fail if type is invalid
+            let mut hw_dev: *mut ffi::AVBufferRef = std::ptr::null_mut();
+            let r = ffi::av_hwdevice_ctx_create(
+                &mut hw_dev,
+                dev_type,
+                std::ptr::null(),
+                std::ptr::null_mut(),
+                0,
+            );
+            if r >= 0 && !hw_dev.is_null() {
+                let ctx_ptr = context.as_mut_ptr();
+                ctx_ptr_saved = ctx_ptr;
+                if !ctx_ptr.is_null() {
+                    // 安装 get_format：优先选择名为 "videotoolbox_vld" 的像素格式
+                    extern "C" fn vt_get_format(_ctx: *mut ffmpeg_next::ffi::AVCodecContext, fmts: *const ffmpeg_next::ffi::AVPixelFormat) -> ffmpeg_next::ffi::AVPixelFormat {
+                        unsafe {
+                            let mut i = 0isize;
+                            let mut first: ffmpeg_next::ffi::AVPixelFormat = *fmts; // assume list not empty
+                            loop {
+                                let fmt = *fmts.offset(i);
+                                if fmt as i32 == -1 { break; } // AV_PIX_FMT_NONE
+                                let name_ptr = ffmpeg_next::ffi::av_get_pix_fmt_name(fmt);
+                                if !name_ptr.is_null() {
+                                    let c = std::ffi::CStr::from_ptr(name_ptr);
+                                    if let Ok(s) = c.to_str() {
+                                        if s == "videotoolbox_vld" { return fmt; }
+                                    }
+                                }
+                                if i == 0 { first = fmt; }
+                                i += 1;
+                            }
+                            first
+                        }
+                    }
+                    (*ctx_ptr).get_format = Some(vt_get_format);
+                    // 绑定设备
+                    (*ctx_ptr).hw_device_ctx = hw_dev;
+                    log::debug!("VideoToolbox device attached");
+                } else {
+                    ffi::av_buffer_unref(&mut hw_dev);
+                }
+            } else {
+                log::warn!("create VideoToolbox device failed (code={r}) -> fallback software");
+            }
+        }
+    }
+    let dec = context.decoder().video().context("open video decoder")?;
+
+    // 创建 hw_frames_ctx（在 decoder 打开后，通过 context 获取设备引用），失败回退
+    if hwaccel {
+        unsafe {
+            use ffmpeg_next::ffi;
+            let ctx_ptr = ctx_ptr_saved;
+            if !ctx_ptr.is_null() {
+                let dev = (*ctx_ptr).hw_device_ctx;
+                if !dev.is_null() {
+                    let frames_ref = ffi::av_hwframe_ctx_alloc(dev);
+                    if !frames_ref.is_null() {
+                        let frames_ctx = (*frames_ref).data as *mut ffi::AVHWFramesContext;
+                        if !frames_ctx.is_null() {
+                            // format: videotoolbox_vld；sw_format: NV12；尺寸：解码器宽高
+                            let vt_name = std::ffi::CString::new("videotoolbox_vld").unwrap();
+                            let sw_name = std::ffi::CString::new("nv12").unwrap();
+                            let vt_fmt = ffi::av_get_pix_fmt(vt_name.as_ptr());
+                            let sw_fmt = ffi::av_get_pix_fmt(sw_name.as_ptr());
+                            (*frames_ctx).format = vt_fmt;
+                            (*frames_ctx).sw_format = sw_fmt;
+                            (*frames_ctx).width = dec.width() as i32;
+                            (*frames_ctx).height = dec.height() as i32;
+                            if ffi::av_hwframe_ctx_init(frames_ref) >= 0 {
+                                (*ctx_ptr).hw_frames_ctx = frames_ref;
+                                log::debug!("hw_frames_ctx initialized");
+                            } else {
+                                ffi::av_buffer_unref(&mut (frames_ref as *mut _));
+                                log::warn!("hw_frames_ctx init failed -> fallback possible");
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    Ok(dec)
+}
+
+/// Cover art is stored as a video stream holding a single attached picture.
+#[cfg(feature = "ffmpeg")]
+fn is_cover_art(stream: &ffmpeg_next::Stream) -> bool {
+    stream.disposition().contains(ffmpeg_next::format::stream::Disposition::ATTACHED_PIC)
+}
+
+/// Decode the attached picture of a cover-art stream into a still RGBA frame.
+#[cfg(feature = "ffmpeg")]
+fn decode_cover_art(stream: &ffmpeg_next::Stream, duration_ms: Option<i64>) -> Option<VideoFrame> {
+    use ffmpeg_next as ffmpeg;
+
+    let data = unsafe {
+        let pkt = &(*stream.as_ptr()).attached_pic;
+        if pkt.data.is_null() || pkt.size <= 0 { return None; }
+        std::slice::from_raw_parts(pkt.data, pkt.size as usize)
+    };
+    let packet = ffmpeg::Packet::copy(data);
+    let context = ffmpeg::codec::context::Context::from_parameters(stream.parameters()).ok()?;
+    let mut dec = context.decoder().video().ok()?;
+    dec.send_packet(&packet).ok()?;
+    let _ = dec.send_eof();
+    let mut frame = ffmpeg::frame::Video::empty();
+    dec.receive_frame(&mut frame).ok()?;
+
+    let (w, h) = (frame.width(), frame.height());
+    let mut scaler = ffmpeg::software::scaling::Context::get(
+        frame.format(), w, h,
+        ffmpeg::format::Pixel::RGBA, w, h,
+        ffmpeg::software::scaling::flag::Flags::BILINEAR,
+    ).ok()?;
+    let mut rgba = ffmpeg::frame::Video::empty();
+    scaler.run(&frame, &mut rgba).ok()?;
+    let linesize = rgba.stride(0);
+    let row = w as usize * 4;
+    let mut buf = Vec::with_capacity(row * h as usize);
+    for y in 0..h as usize {
+        let start = y * linesize;
+        buf.extend_from_slice(&rgba.data(0)[start..start + row]);
+    }
+    Some(VideoFrame { width: w, height: h, rgba: buf, pts_ms: None, duration_ms })
+}
+
+/// Route av_log output through `log` (target `ffmpeg`); installed once per process.
+#[cfg(feature = "ffmpeg")]
+fn forward_ffmpeg_log() {
+    use ffmpeg_next::ffi;
+    use std::ffi::c_int;
+    static INSTALL: std::sync::Once = std::sync::Once::new();
+    INSTALL.call_once(|| {
+        // 自定义回调收到所有级别；av_log_set_level 只用来让 FFmpeg 跳过不需要的调试输出
+        let level = match log::max_level() {
+            log::LevelFilter::Off => ffi::AV_LOG_QUIET as c_int,
+            log::LevelFilter::Error => ffi::AV_LOG_ERROR as c_int,
+            log::LevelFilter::Warn => ffi::AV_LOG_WARNING as c_int,
+            log::LevelFilter::Info => ffi::AV_LOG_INFO as c_int,
+            log::LevelFilter::Debug => ffi::AV_LOG_DEBUG as c_int,
+            log::LevelFilter::Trace => ffi::AV_LOG_TRACE as c_int,
+        };
+        unsafe {
+            ffi::av_log_set_level(level);
+            ffi::av_log_set_callback(Some(ffmpeg_log_callback));
+        }
+    });
+}
+
+/// av_log callback. The generated bindings give the `va_list` parameter a different
+/// type per platform, so it is taken generically and passed on to av_log_format_line2.
+#[cfg(feature = "ffmpeg")]
+unsafe extern "C" fn ffmpeg_log_callback<V>(avcl: *mut std::ffi::c_void, level: std::ffi::c_int, fmt: *const std::ffi::c_char, vl: V) {
+    use ffmpeg_next::ffi;
+    use std::cell::Cell;
+    use std::ffi::{c_char, c_int, CStr};
+
+    thread_local! {
+        // 上一行是否以换行结束（FFmpeg 会分几次输出同一行）
+        static PRINT_PREFIX: Cell<c_int> = const { Cell::new(1) };
+    }
+    let log_level = match level {
+        l if l <= ffi::AV_LOG_ERROR as c_int => log::Level::Error,
+        l if l <= ffi::AV_LOG_WARNING as c_int => log::Level::Warn,
+        l if l <= ffi::AV_LOG_INFO as c_int => log::Level::Info,
+        l if l <= ffi::AV_LOG_DEBUG as c_int => log::Level::Debug,
+        _ => log::Level::Trace,
+    };
+    if !log::log_enabled!(target: "ffmpeg", log_level) {
+        return;
+    }
+    let mut line = [0 as c_char; 1024];
+    let mut print_prefix = PRINT_PREFIX.with(Cell::get);
+    // SAFETY: 回调参数与 av_log_format_line2 的 vl 由同一个 C 类型生成，V 就是该类型
+    ffi::av_log_format_line2(avcl, level, fmt, std::mem::transmute_copy(&vl), line.as_mut_ptr(), line.len() as c_int, &mut print_prefix);
+    PRINT_PREFIX.with(|p| p.set(print_prefix));
+    let text = CStr::from_ptr(line.as_ptr()).to_string_lossy();
+    let text = text.trim_end();
+    if !text.is_empty() {
+        log::log!(target: "ffmpeg", log_level, "{text}");
+    }
+}
+
 #[cfg(feature = "ffmpeg")]
 fn ts_to_ms(ts: i64, tb: ffmpeg_next::Rational) -> i64 {
     // ts * num / den -> seconds, then *1000
@@ -6,6 +6,10 @@
 //! - Dynamic render resolution (reads target size from Arc<AtomicU32>)
 //! - Subtitle track querying and selection via MpvCommand
 //! - External subtitle file loading
+//! - HLS/DASH variant selection (auto by throughput, or fixed) via video track switching
+//! - Custom `MediaSource` input through a `stream_cb` protocol handler
+//! - Gapless continuation: queued items are appended to mpv's playlist and
+//!   prefetched (`prefetch-playlist`, `gapless-audio`)
 
 use anyhow::Result;
 use crossbeam_channel::{bounded, Receiver, Sender, TryRecvError};
@@ -13,12 +17,16 @@ use std::ffi::CString;
 use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
 use std::sync::Arc;
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
+use crate::adaptive::{fetch_variants, manifest_kind, AdaptiveController};
+use crate::source::{self, MediaSource, SOURCE_SCHEME};
 use crate::{
-    AudioFrame, MpvCommand, PlaybackConfig, PlaybackHandles,
-    SubtitleFrame, SubtitleTrackInfo, VideoFrame,
+    is_network_url, AudioFrame, Chapter, MpvCommand, PlaybackConfig, PlaybackEvent,
+    PlaybackHandles, QualityMode, ReplayGainMode, StatsCollector, SubtitleFrame, SubtitleTrackInfo, Variant,
+    VideoFrame,
 };
+use crate::stats::STATS_INTERVAL;
 
 /// Start MPV playback and return `PlaybackHandles` (same interface as FFmpeg path).
 #[cfg(feature = "mpv")]
@@ -30,22 +38,34 @@ pub fn start_mpv_playback_handles(url: &str, cfg: &PlaybackConfig) -> Result<Pla
     let (eos_tx, eos_rx) = bounded::<()>(1);
     let (cmd_tx, cmd_rx) = bounded::<MpvCommand>(16);
     let (track_info_tx, track_info_rx) = bounded::<Vec<SubtitleTrackInfo>>(4);
+    let (event_tx, event_rx) = bounded::<PlaybackEvent>(64);
+    let (next_tx, next_rx) = bounded::<String>(1);
 
     // Shared atomics for dynamic render size
     let target_w = Arc::new(AtomicU32::new(640));
     let target_h = Arc::new(AtomicU32::new(360));
-    let tw_clone = target_w.clone();
-    let th_clone = target_h.clone();
-
-    let url = url.to_string();
-    let hwaccel = cfg.hwaccel;
+    let stats = Arc::new(StatsCollector::new("mpv"));
+    if cfg.crossfade_ms > 0 {
+        log::warn!("crossfade is not supported by the mpv engine, transitions are gapless only");
+    }
 
+    let params = MpvThreadParams {
+        url: url.to_string(),
+        cfg: cfg.clone(),
+        video_tx,
+        stop_rx,
+        cmd_rx,
+        next_rx,
+        track_info_tx,
+        event_tx,
+        target_w: target_w.clone(),
+        target_h: target_h.clone(),
+        stats: stats.clone(),
+    };
     thread::spawn(move || {
-        if let Err(e) = mpv_playback_thread(
-            &url, &video_tx, &stop_rx, &cmd_rx, &track_info_tx,
-            &tw_clone, &th_clone, hwaccel,
-        ) {
-            eprintln!("[bova-mpv] playback thread error: {e:?}");
+        if let Err(e) = mpv_playback_thread(&params) {
+            log::error!("playback thread error: {e:?}");
+            let _ = params.event_tx.try_send(PlaybackEvent::Error(format!("{e:#}")));
         }
         let _ = eos_tx.send(());
     });
@@ -60,6 +80,10 @@ pub fn start_mpv_playback_handles(url: &str, cfg: &PlaybackConfig) -> Result<Pla
         track_info_rx: Some(track_info_rx),
         target_render_w: target_w,
         target_render_h: target_h,
+        event_rx: Some(event_rx),
+        next_tx: Some(next_tx),
+        stats,
+        source: None,
     })
 }
 
@@ -68,22 +92,39 @@ pub fn start_mpv_playback_handles(_url: &str, _cfg: &PlaybackConfig) -> Result<P
     Err(anyhow::anyhow!("MPV feature not enabled"))
 }
 
+/// Channels, shared state and settings of one `mpv_playback_thread`.
+#[cfg(feature = "mpv")]
+struct MpvThreadParams {
+    url: String,
+    cfg: PlaybackConfig,
+    video_tx: Sender<VideoFrame>,
+    stop_rx: Receiver<()>,
+    cmd_rx: Receiver<MpvCommand>,
+    next_rx: Receiver<String>,
+    track_info_tx: Sender<Vec<SubtitleTrackInfo>>,
+    event_tx: Sender<PlaybackEvent>,
+    /// Render size requested by the front end
+    target_w: Arc<AtomicU32>,
+    target_h: Arc<AtomicU32>,
+    stats: Arc<StatsCollector>,
+}
+
 /// The core MPV playback thread. Uses libmpv2-sys raw FFI for SW render context.
 #[cfg(feature = "mpv")]
-fn mpv_playback_thread(
-    url: &str,
-    video_tx: &Sender<VideoFrame>,
-    stop_rx: &Receiver<()>,
-    cmd_rx: &Receiver<MpvCommand>,
-    track_info_tx: &Sender<Vec<SubtitleTrackInfo>>,
-    target_w: &Arc<AtomicU32>,
-    target_h: &Arc<AtomicU32>,
-    hwaccel: bool,
-) -> Result<()> {
+fn mpv_playback_thread(params: &MpvThreadParams) -> Result<()> {
     use libmpv2_sys::*;
     use std::os::raw::{c_char, c_int, c_void};
     use std::ptr;
 
+    let MpvThreadParams { url, cfg, video_tx, stop_rx, cmd_rx, next_rx, track_info_tx, event_tx, target_w, target_h, stats } = params;
+    let url = url.as_str();
+    let hwaccel = cfg.hwaccel;
+    let network = &cfg.network;
+    let quality = cfg.quality;
+    let start_ms = cfg.start_ms;
+    let replay_gain = cfg.replay_gain;
+    let audio_track = cfg.audio_track;
+
     // ── 1. Create and configure mpv handle ──
     let mpv = unsafe { mpv_create() };
     if mpv.is_null() {
@@ -108,8 +149,63 @@ fn mpv_playback_thread(
     mpv_set_opt!("force-window", "no");
     mpv_set_opt!("keep-open", "yes");
     mpv_set_opt!("pause", "yes");
+    // Queued items: open the next playlist entry ahead of time and keep the audio output across files
+    mpv_set_opt!("prefetch-playlist", "yes");
+    mpv_set_opt!("gapless-audio", "yes");
+    mpv_set_opt!("replaygain", match replay_gain {
+        ReplayGainMode::Off => "no",
+        ReplayGainMode::Track => "track",
+        ReplayGainMode::Album => "album",
+    });
     // Enable subtitle rendering in SW output
     mpv_set_opt!("sub-visibility", "yes");
+    if let Some(ms) = start_ms.filter(|&ms| ms > 0) {
+        mpv_set_opt!("start", format!("{:.3}", ms as f64 / 1000.0));
+    }
+    if let Some(aid) = audio_track.filter(|&n| n > 0) {
+        mpv_set_opt!("aid", aid.to_string());
+    }
+
+    // Network: read-ahead cache, timeouts, reconnect and request headers
+    if is_network_url(url) {
+        mpv_set_opt!("cache", "yes");
+        mpv_set_opt!("cache-secs", format!("{:.3}", network.cache_ms as f64 / 1000.0));
+        mpv_set_opt!("network-timeout", format!("{:.3}", network.timeout_ms as f64 / 1000.0));
+        if network.reconnect {
+            mpv_set_opt!(
+                "stream-lavf-o",
+                format!(
+                    "reconnect=1,reconnect_streamed=1,reconnect_on_network_error=1,reconnect_delay_max={}",
+                    network.reconnect_delay_max_s
+                )
+            );
+        }
+        if let Some(ua) = &network.user_agent {
+            mpv_set_opt!("user-agent", ua.as_str());
+        }
+        for (k, v) in &network.headers {
+            mpv_set_opt!("http-header-fields-append", format!("{k}: {v}"));
+        }
+    }
+
+    // Adaptive streams: start on the lowest variant in auto mode so the first
+    // frame arrives quickly; the controller moves up once throughput is known.
+    let mut variants_rx: Option<Receiver<Vec<Variant>>> = None;
+    if manifest_kind(url).is_some() && !source::is_source_url(url) {
+        if quality == QualityMode::Auto {
+            mpv_set_opt!("hls-bitrate", "min");
+        }
+        let (tx, rx) = bounded::<Vec<Variant>>(1);
+        let manifest_url = url.to_string();
+        let net = network.clone();
+        thread::spawn(move || match fetch_variants(&manifest_url, &net) {
+            Ok(variants) => {
+                let _ = tx.send(variants);
+            }
+            Err(e) => log::warn!("manifest fetch failed: {e:#}"),
+        });
+        variants_rx = Some(rx);
+    }
 
     let init_err = unsafe { mpv_initialize(mpv) };
     if init_err < 0 {
@@ -117,7 +213,18 @@ fn mpv_playback_thread(
         anyhow::bail!("mpv_initialize failed: {}", init_err);
     }
 
-    eprintln!("[bova-mpv] mpv initialized");
+    log::debug!("mpv initialized");
+
+    // mpv 自己的日志经事件队列转发到 `log`（target `mpv::<模块>`）
+    let log_level = CString::new(mpv_log_level_for_filter()).unwrap();
+    unsafe { mpv_request_log_messages(mpv, log_level.as_ptr()) };
+
+    // bova-source:// URLs are served from registered MediaSources
+    let protocol = CString::new(SOURCE_SCHEME).unwrap();
+    let r = unsafe { mpv_stream_cb_add_ro(mpv, protocol.as_ptr(), ptr::null_mut(), Some(source_open)) };
+    if r < 0 {
+        log::warn!("stream_cb registration failed: {r}");
+    }
 
     // ── 2. Create SW render context ──
     let frame_ready = Arc::new(AtomicBool::new(false));
@@ -157,7 +264,7 @@ fn mpv_playback_thread(
         );
     }
 
-    eprintln!("[bova-mpv] SW render context created");
+    log::debug!("SW render context created");
 
     // ── 3. Load file ──
     let cmd_loadfile = CString::new("loadfile").unwrap();
@@ -165,7 +272,7 @@ fn mpv_playback_thread(
     let cmd_args: [*const c_char; 3] = [cmd_loadfile.as_ptr(), cmd_url.as_ptr(), ptr::null()];
     let load_err = unsafe { mpv_command(mpv, cmd_args.as_ptr() as *mut *const c_char) };
     if load_err < 0 {
-        eprintln!("[bova-mpv] loadfile failed: {load_err}");
+        log::warn!("loadfile failed: {load_err}");
     }
 
     thread::sleep(Duration::from_millis(200));
@@ -175,7 +282,7 @@ fn mpv_playback_thread(
     let pause_val = CString::new("no").unwrap();
     unsafe { mpv_set_property_string(mpv, pause_name.as_ptr(), pause_val.as_ptr()) };
 
-    eprintln!("[bova-mpv] playing: {url}");
+    log::info!("playing: {url}");
 
     // ── 4. Main render loop ──
     let sw_format = CString::new("rgba").unwrap();
@@ -189,19 +296,43 @@ fn mpv_playback_thread(
     let mut video_size_queried = false;
     let mut cached_duration_ms: Option<i64> = None;
     let mut tracks_queried = false;
+    let mut chapters_queried = false;
     let mut buf: Vec<u8> = Vec::new();
+    let mut last_buffering_pct: Option<u8> = None;
+    let mut loop_count: u64 = 0;
+    // Adaptive state: controller plus variant index → mpv video track id
+    let mut adaptive: Option<AdaptiveController> = None;
+    let mut variant_tracks: Vec<Option<i64>> = Vec::new();
+    let mut pending_quality = quality;
+    let mut last_speed_sample = Instant::now();
+    let mut last_position_report = Instant::now();
+    let mut eof_reported = false;
+    let mut last_stats = Instant::now();
+    // URL appended to mpv's playlist after the current entry
+    let mut queued_url: Option<String> = None;
 
     loop {
         // Check stop signal
         match stop_rx.try_recv() {
             Ok(_) => {
-                eprintln!("[bova-mpv] stop signal received");
+                log::debug!("stop signal received");
                 break;
             }
             Err(TryRecvError::Disconnected) => break,
             Err(TryRecvError::Empty) => {}
         }
 
+        // ── Drain mpv events (only log messages are used; state is polled below) ──
+        loop {
+            let event = unsafe { &*mpv_wait_event(mpv, 0.0) };
+            if event.event_id == mpv_event_id_MPV_EVENT_NONE {
+                break;
+            }
+            if event.event_id == mpv_event_id_MPV_EVENT_LOG_MESSAGE && !event.data.is_null() {
+                forward_mpv_log(unsafe { &*(event.data as *const mpv_event_log_message) });
+            }
+        }
+
         // ── Process commands ──
         while let Ok(cmd) = cmd_rx.try_recv() {
             match cmd {
@@ -216,13 +347,13 @@ fn mpv_playback_thread(
                             &val as *const i64 as *mut c_void,
                         );
                     }
-                    eprintln!("[bova-mpv] subtitle track set to {id}");
+                    log::debug!("subtitle track set to {id}");
                 }
                 MpvCommand::DisableSubtitle => {
                     let prop = CString::new("sid").unwrap();
                     let val = CString::new("no").unwrap();
                     unsafe { mpv_set_property_string(mpv, prop.as_ptr(), val.as_ptr()) };
-                    eprintln!("[bova-mpv] subtitles disabled");
+                    log::debug!("subtitles disabled");
                 }
                 MpvCommand::LoadExternalSub(path) => {
                     let cmd_name = CString::new("sub-add").unwrap();
@@ -233,18 +364,18 @@ fn mpv_playback_thread(
                         mpv_command(mpv, args.as_ptr() as *mut *const c_char)
                     };
                     if r >= 0 {
-                        eprintln!("[bova-mpv] loaded external subtitle: {path}");
+                        log::info!("loaded external subtitle: {path}");
                         // Re-query tracks after loading
                         tracks_queried = false;
                     } else {
-                        eprintln!("[bova-mpv] sub-add failed: {r}");
+                        log::warn!("sub-add failed: {r}");
                     }
                 }
                 MpvCommand::SetSubVisibility(visible) => {
                     let prop = CString::new("sub-visibility").unwrap();
                     let val = CString::new(if visible { "yes" } else { "no" }).unwrap();
                     unsafe { mpv_set_property_string(mpv, prop.as_ptr(), val.as_ptr()) };
-                    eprintln!("[bova-mpv] subtitle visibility: {visible}");
+                    log::debug!("subtitle visibility: {visible}");
                 }
                 MpvCommand::SeekAbsolute(secs) => {
                     let cmd = CString::new("seek").unwrap();
@@ -254,28 +385,86 @@ fn mpv_playback_thread(
                         cmd.as_ptr(), pos_str.as_ptr(), mode.as_ptr(), ptr::null(),
                     ];
                     unsafe { mpv_command(mpv, args.as_ptr() as *mut *const c_char) };
-                    eprintln!("[bova-mpv] seek to {secs:.1}s");
+                    log::debug!("seek to {secs:.1}s");
                 }
                 MpvCommand::Pause => {
                     let prop = CString::new("pause").unwrap();
                     let val = CString::new("yes").unwrap();
                     unsafe { mpv_set_property_string(mpv, prop.as_ptr(), val.as_ptr()) };
-                    eprintln!("[bova-mpv] paused");
+                    log::debug!("paused");
                 }
                 MpvCommand::Resume => {
                     let prop = CString::new("pause").unwrap();
                     let val = CString::new("no").unwrap();
                     unsafe { mpv_set_property_string(mpv, prop.as_ptr(), val.as_ptr()) };
-                    eprintln!("[bova-mpv] resumed");
+                    log::debug!("resumed");
                 }
                 MpvCommand::SetVolume(vol) => {
                     let prop = CString::new("volume").unwrap();
                     let val = CString::new(format!("{:.1}", vol)).unwrap();
                     unsafe { mpv_set_property_string(mpv, prop.as_ptr(), val.as_ptr()) };
                 }
+                MpvCommand::NextChapter => {
+                    let r = run_mpv_command(mpv, &["add", "chapter", "1"]);
+                    log::debug!("next chapter ({r})");
+                }
+                MpvCommand::PreviousChapter => {
+                    let r = run_mpv_command(mpv, &["add", "chapter", "-1"]);
+                    log::debug!("previous chapter ({r})");
+                }
+                MpvCommand::SelectAudio(id) => {
+                    let r = run_mpv_command(mpv, &["set", "aid", &id.to_string()]);
+                    log::debug!("audio track set to {id} ({r})");
+                }
+                MpvCommand::SetSpeed(speed) => {
+                    let r = run_mpv_command(mpv, &["set", "speed", &format!("{:.2}", speed)]);
+                    log::debug!("speed {speed:.2}x ({r})");
+                }
+                MpvCommand::SetQuality(mode) => {
+                    pending_quality = mode;
+                    if let Some(ctl) = adaptive.as_mut() {
+                        ctl.set_mode(mode);
+                        if let Some(idx) = ctl.next_switch() {
+                            switch_variant(mpv, &variant_tracks, idx, event_tx);
+                        }
+                    }
+                    log::debug!("quality mode: {mode:?}");
+                }
+            }
+        }
+
+        // ── Queue the next item (replaces a previously queued one) ──
+        while let Ok(next_url) = next_rx.try_recv() {
+            // The resume position only applies to the first file
+            let start_name = CString::new("start").unwrap();
+            let start_val = CString::new("none").unwrap();
+            unsafe { mpv_set_property_string(mpv, start_name.as_ptr(), start_val.as_ptr()) };
+            run_mpv_command(mpv, &["playlist-clear"]);
+            let r = run_mpv_command(mpv, &["loadfile", next_url.as_str(), "append"]);
+            if r >= 0 {
+                log::debug!("queued next: {next_url}");
+                queued_url = Some(next_url);
+            } else {
+                log::warn!("queueing {next_url} failed: {r}");
+                queued_url = None;
             }
         }
 
+        // ── mpv moved on to the queued entry: drop the finished one, re-query per-file state ──
+        if queued_url.is_some() && get_mpv_int_property(mpv, "playlist-pos").unwrap_or(0) > 0 {
+            run_mpv_command(mpv, &["playlist-remove", "0"]);
+            let next_url = queued_url.take().unwrap_or_default();
+            log::info!("continuing with {next_url}");
+            video_size_queried = false;
+            tracks_queried = false;
+            chapters_queried = false;
+            cached_duration_ms = None;
+            adaptive = None;
+            variant_tracks.clear();
+            variants_rx = None;
+            let _ = event_tx.try_send(PlaybackEvent::TrackChanged(next_url));
+        }
+
         // EOS check — but not when paused
         let is_paused = unsafe {
             let pause_name = CString::new("pause").unwrap();
@@ -299,12 +488,58 @@ fn mpv_playback_thread(
                     &mut idle_val as *mut i32 as *mut c_void,
                 );
                 if r >= 0 && idle_val != 0 {
-                    eprintln!("[bova-mpv] end of stream");
+                    log::info!("end of stream");
                     break;
                 }
             }
         }
 
+        // ── Report cache buffering (polled, ~every 100 ms when idle) ──
+        loop_count = loop_count.wrapping_add(1);
+        if loop_count.is_multiple_of(100) {
+            let paused_for_cache = get_mpv_flag_property(mpv, "paused-for-cache");
+            if paused_for_cache {
+                let pct = get_mpv_int_property(mpv, "cache-buffering-state").unwrap_or(0).clamp(0, 100) as u8;
+                if last_buffering_pct != Some(pct) {
+                    let _ = event_tx.try_send(PlaybackEvent::Buffering(pct));
+                    last_buffering_pct = Some(pct);
+                }
+            } else if last_buffering_pct.take().is_some() {
+                let _ = event_tx.try_send(PlaybackEvent::Buffering(100));
+            }
+        }
+
+        // ── Position, and end of file (keep-open holds the last frame instead of going idle) ──
+        if last_position_report.elapsed() >= Duration::from_millis(250) {
+            last_position_report = Instant::now();
+            if let Some(pos) = get_mpv_double_property(mpv, "time-pos") {
+                let _ = event_tx.try_send(PlaybackEvent::PositionChanged(pos));
+            }
+            let eof = get_mpv_flag_property(mpv, "eof-reached");
+            if eof && !eof_reported && queued_url.is_none() {
+                log::info!("reached end of file");
+                let _ = event_tx.try_send(PlaybackEvent::Finished);
+            }
+            eof_reported = eof;
+        }
+
+        // ── Diagnostics: decoding happens inside mpv, read its counters ──
+        if last_stats.elapsed() >= STATS_INTERVAL {
+            stats.finish_interval(last_stats.elapsed());
+            last_stats = Instant::now();
+            let codec = get_mpv_string_property(mpv, &CString::new("video-codec").unwrap());
+            let hwdec = get_mpv_string_property(mpv, &CString::new("hwdec-current").unwrap())
+                .filter(|h| !h.is_empty() && h != "no");
+            stats.set_stream(codec, hwdec);
+            let dropped = get_mpv_int_property(mpv, "frame-drop-count").unwrap_or(0)
+                + get_mpv_int_property(mpv, "decoder-frame-drop-count").unwrap_or(0);
+            stats.set_engine_dropped(dropped.max(0) as u64);
+            stats.set_av_drift(get_mpv_double_property(mpv, "avsync").map(|s| s * 1000.0));
+            // 音频由 mpv 直接输出，不经过 audio_rx
+            stats.set_queues((video_tx.len(), video_tx.capacity().unwrap_or(0)), (0, 0));
+            let _ = event_tx.try_send(PlaybackEvent::Stats(stats.snapshot()));
+        }
+
         // ── Query video native size once ──
         if !video_size_queried {
             unsafe {
@@ -326,7 +561,7 @@ fn mpv_playback_thread(
                     native_w = w;
                     native_h = h;
                     video_size_queried = true;
-                    eprintln!("[bova-mpv] native video size: {native_w}x{native_h}");
+                    log::debug!("native video size: {native_w}x{native_h}");
                 }
             }
         }
@@ -348,7 +583,7 @@ fn mpv_playback_thread(
             if new_w != render_w || new_h != render_h {
                 render_w = new_w;
                 render_h = new_h;
-                eprintln!("[bova-mpv] render size adjusted: {render_w}x{render_h}");
+                log::debug!("render size adjusted: {render_w}x{render_h}");
             }
         }
 
@@ -356,15 +591,64 @@ fn mpv_playback_thread(
         if video_size_queried && !tracks_queried {
             let tracks = query_subtitle_tracks(mpv);
             if !tracks.is_empty() {
-                eprintln!("[bova-mpv] found {} subtitle tracks", tracks.len());
+                log::info!("found {} subtitle tracks", tracks.len());
                 for t in &tracks {
-                    eprintln!("[bova-mpv]   {t}");
+                    log::debug!("  {t}");
                 }
                 let _ = track_info_tx.try_send(tracks);
             }
             tracks_queried = true;
         }
 
+        // ── Chapters, once the file is loaded ──
+        if video_size_queried && !chapters_queried {
+            let chapters = query_chapters(mpv);
+            if !chapters.is_empty() {
+                log::info!("found {} chapters", chapters.len());
+                let _ = event_tx.try_send(PlaybackEvent::Chapters(chapters));
+            }
+            chapters_queried = true;
+        }
+
+        // ── Adaptive streaming: map variants to video tracks, then follow throughput ──
+        if video_size_queried {
+            if let Some(rx) = &variants_rx {
+                match rx.try_recv() {
+                    Ok(variants) => {
+                        variants_rx = None;
+                        if !variants.is_empty() {
+                            variant_tracks = map_variant_tracks(mpv, &variants);
+                            let current_vid = get_mpv_int_property(mpv, "vid");
+                            let mut ctl = AdaptiveController::new(variants.clone(), pending_quality);
+                            let _ = event_tx.try_send(PlaybackEvent::VariantsAvailable(variants));
+                            if let Some(idx) = variant_tracks.iter().position(|t| t.is_some() && *t == current_vid) {
+                                ctl.set_current(idx);
+                                let _ = event_tx.try_send(PlaybackEvent::VariantChanged(idx));
+                            }
+                            if let Some(idx) = ctl.next_switch() {
+                                switch_variant(mpv, &variant_tracks, idx, event_tx);
+                            }
+                            adaptive = Some(ctl);
+                        }
+                    }
+                    Err(TryRecvError::Disconnected) => variants_rx = None,
+                    Err(TryRecvError::Empty) => {}
+                }
+            }
+        }
+        if let Some(ctl) = adaptive.as_mut() {
+            if last_speed_sample.elapsed() >= Duration::from_secs(1) {
+                last_speed_sample = Instant::now();
+                // cache-speed: bytes/s currently read into the demuxer cache
+                if let Some(bytes_per_sec) = get_mpv_int_property(mpv, "cache-speed").filter(|b| *b > 0) {
+                    ctl.sample(bytes_per_sec as u64 * 8);
+                }
+                if let Some(idx) = ctl.next_switch() {
+                    switch_variant(mpv, &variant_tracks, idx, event_tx);
+                }
+            }
+        }
+
         // ── Render frame ──
         if frame_ready.swap(false, Ordering::AcqRel) || frame_count == 0 {
             let stride = render_w as usize * 4;
@@ -397,9 +681,11 @@ fn mpv_playback_thread(
                 },
             ];
 
+            let render_started = Instant::now();
             let render_err = unsafe {
                 mpv_render_context_render(render_ctx, render_params.as_ptr() as *mut mpv_render_param)
             };
+            let render_time = render_started.elapsed();
 
             if render_err >= 0 {
                 // Get current position
@@ -424,7 +710,7 @@ fn mpv_playback_thread(
                         );
                         if r >= 0 && dur > 0.0 {
                             cached_duration_ms = Some((dur * 1000.0) as i64);
-                            eprintln!("[bova-mpv] duration: {:.1}s", dur);
+                            log::debug!("duration: {:.1}s", dur);
                         }
                     }
                 }
@@ -438,13 +724,14 @@ fn mpv_playback_thread(
                 };
 
                 let _ = video_tx.try_send(vf);
+                stats.record_video_frame(render_w as u32, render_h as u32, None, render_time);
 
                 frame_count += 1;
                 if frame_count % 300 == 0 {
-                    eprintln!("[bova-mpv] rendered {} frames ({}x{})", frame_count, render_w, render_h);
+                    log::debug!("rendered {} frames ({}x{})", frame_count, render_w, render_h);
                 }
             } else if render_err != -6 {
-                eprintln!("[bova-mpv] render error: {}", render_err);
+                log::warn!("render error: {}", render_err);
             }
         } else {
             thread::sleep(Duration::from_millis(1));
@@ -452,7 +739,7 @@ fn mpv_playback_thread(
     }
 
     // ── 5. Cleanup ──
-    eprintln!("[bova-mpv] cleaning up, rendered {} frames total", frame_count);
+    log::debug!("cleaning up, rendered {} frames total", frame_count);
 
     let cmd_stop = CString::new("stop").unwrap();
     let stop_args: [*const c_char; 2] = [cmd_stop.as_ptr(), ptr::null()];
@@ -463,7 +750,7 @@ fn mpv_playback_thread(
         mpv_destroy(mpv);
     }
 
-    eprintln!("[bova-mpv] shutdown complete");
+    log::debug!("shutdown complete");
     Ok(())
 }
 
@@ -549,6 +836,59 @@ fn query_subtitle_tracks(mpv: *mut libmpv2_sys::mpv_handle) -> Vec<SubtitleTrack
     tracks
 }
 
+/// Most verbose mpv log level the `mpv` target lets through, for mpv_request_log_messages.
+#[cfg(feature = "mpv")]
+fn mpv_log_level_for_filter() -> &'static str {
+    use log::Level;
+    [(Level::Trace, "trace"), (Level::Debug, "debug"), (Level::Info, "info"), (Level::Warn, "warn"), (Level::Error, "error")]
+        .into_iter()
+        .find(|&(level, _)| log::log_enabled!(target: "mpv", level))
+        .map_or("no", |(_, name)| name)
+}
+
+#[cfg(feature = "mpv")]
+fn forward_mpv_log(msg: &libmpv2_sys::mpv_event_log_message) {
+    use libmpv2_sys::*;
+    use std::ffi::CStr;
+
+    let level = match msg.log_level {
+        l if l <= mpv_log_level_MPV_LOG_LEVEL_ERROR => log::Level::Error,
+        l if l <= mpv_log_level_MPV_LOG_LEVEL_WARN => log::Level::Warn,
+        l if l <= mpv_log_level_MPV_LOG_LEVEL_INFO => log::Level::Info,
+        l if l <= mpv_log_level_MPV_LOG_LEVEL_DEBUG => log::Level::Debug,
+        _ => log::Level::Trace,
+    };
+    let to_str = |p: *const std::os::raw::c_char| {
+        if p.is_null() { Default::default() } else { unsafe { CStr::from_ptr(p) }.to_string_lossy() }
+    };
+    let target = format!("mpv::{}", to_str(msg.prefix));
+    let text = to_str(msg.text);
+    log::log!(target: &target, level, "{}", text.trim_end());
+}
+
+/// Read chapters from mpv's chapter-list; a chapter ends where the next one starts
+#[cfg(feature = "mpv")]
+fn query_chapters(mpv: *mut libmpv2_sys::mpv_handle) -> Vec<Chapter> {
+    let count = get_mpv_int_property(mpv, "chapter-list/count").unwrap_or(0);
+    let mut chapters: Vec<Chapter> = (0..count)
+        .filter_map(|i| {
+            let start = get_mpv_double_property(mpv, &format!("chapter-list/{i}/time"))?;
+            let title_name = CString::new(format!("chapter-list/{i}/title")).unwrap();
+            Some(Chapter {
+                id: i,
+                start_ms: (start * 1000.0) as i64,
+                end_ms: 0,
+                title: get_mpv_string_property(mpv, &title_name).filter(|t| !t.is_empty()),
+            })
+        })
+        .collect();
+    let duration_ms = get_mpv_double_property(mpv, "duration").map(|d| (d * 1000.0) as i64).unwrap_or(0);
+    for i in 0..chapters.len() {
+        chapters[i].end_ms = chapters.get(i + 1).map_or(duration_ms, |next| next.start_ms);
+    }
+    chapters
+}
+
 #[cfg(feature = "mpv")]
 fn get_mpv_string_property(mpv: *mut libmpv2_sys::mpv_handle, name: &CString) -> Option<String> {
     use libmpv2_sys::*;
@@ -569,5 +909,175 @@ fn get_mpv_string_property(mpv: *mut libmpv2_sys::mpv_handle, name: &CString) ->
     }
 }
 
+#[cfg(feature = "mpv")]
+fn get_mpv_flag_property(mpv: *mut libmpv2_sys::mpv_handle, name: &str) -> bool {
+    use libmpv2_sys::*;
+    use std::os::raw::c_void;
+    let name = CString::new(name).unwrap();
+    let mut val: i32 = 0;
+    let r = unsafe {
+        mpv_get_property(
+            mpv, name.as_ptr(), mpv_format_MPV_FORMAT_FLAG,
+            &mut val as *mut i32 as *mut c_void,
+        )
+    };
+    r >= 0 && val != 0
+}
+
+/// Run an mpv command given as string arguments; returns mpv's status code
+#[cfg(feature = "mpv")]
+fn run_mpv_command(mpv: *mut libmpv2_sys::mpv_handle, args: &[&str]) -> i32 {
+    use std::os::raw::c_char;
+    let args: Vec<CString> = args.iter().map(|a| CString::new(*a).unwrap()).collect();
+    let mut ptrs: Vec<*const c_char> = args.iter().map(|a| a.as_ptr()).collect();
+    ptrs.push(std::ptr::null());
+    unsafe { libmpv2_sys::mpv_command(mpv, ptrs.as_mut_ptr()) }
+}
+
+#[cfg(feature = "mpv")]
+fn get_mpv_double_property(mpv: *mut libmpv2_sys::mpv_handle, name: &str) -> Option<f64> {
+    use libmpv2_sys::*;
+    use std::os::raw::c_void;
+    let name = CString::new(name).unwrap();
+    let mut val: f64 = 0.0;
+    let r = unsafe {
+        mpv_get_property(
+            mpv, name.as_ptr(), mpv_format_MPV_FORMAT_DOUBLE,
+            &mut val as *mut f64 as *mut c_void,
+        )
+    };
+    if r >= 0 { Some(val) } else { None }
+}
+
+#[cfg(feature = "mpv")]
+fn get_mpv_int_property(mpv: *mut libmpv2_sys::mpv_handle, name: &str) -> Option<i64> {
+    use libmpv2_sys::*;
+    use std::os::raw::c_void;
+    let name = CString::new(name).unwrap();
+    let mut val: i64 = 0;
+    let r = unsafe {
+        mpv_get_property(
+            mpv, name.as_ptr(), mpv_format_MPV_FORMAT_INT64,
+            &mut val as *mut i64 as *mut c_void,
+        )
+    };
+    if r >= 0 { Some(val) } else { None }
+}
+
+// ── stream_cb callbacks: the cookie is a leaked `Box<Box<dyn MediaSource>>` ──
+
+#[cfg(feature = "mpv")]
+unsafe extern "C" fn source_open(
+    _user_data: *mut std::os::raw::c_void,
+    uri: *mut std::os::raw::c_char,
+    info: *mut libmpv2_sys::mpv_stream_cb_info,
+) -> std::os::raw::c_int {
+    use libmpv2_sys::*;
+    let uri = std::ffi::CStr::from_ptr(uri).to_string_lossy();
+    let Some(src) = source::take_source(&uri) else {
+        log::warn!("unknown or already opened source: {uri}");
+        return mpv_error_MPV_ERROR_LOADING_FAILED;
+    };
+    let seekable = src.is_seekable();
+    let info = &mut *info;
+    info.cookie = Box::into_raw(Box::new(src)) as *mut std::os::raw::c_void;
+    info.read_fn = Some(source_read);
+    info.seek_fn = if seekable { Some(source_seek) } else { None };
+    info.size_fn = Some(source_size);
+    info.close_fn = Some(source_close);
+    info.cancel_fn = None;
+    0
+}
+
+#[cfg(feature = "mpv")]
+unsafe extern "C" fn source_read(cookie: *mut std::os::raw::c_void, buf: *mut std::os::raw::c_char, nbytes: u64) -> i64 {
+    let src = &mut *(cookie as *mut Box<dyn MediaSource>);
+    let slice = std::slice::from_raw_parts_mut(buf as *mut u8, nbytes as usize);
+    match src.read(slice) {
+        Ok(n) => n as i64,
+        Err(e) => {
+            log::warn!("source read error: {e}");
+            -1
+        }
+    }
+}
+
+#[cfg(feature = "mpv")]
+unsafe extern "C" fn source_seek(cookie: *mut std::os::raw::c_void, offset: i64) -> i64 {
+    let src = &mut *(cookie as *mut Box<dyn MediaSource>);
+    match src.seek(std::io::SeekFrom::Start(offset.max(0) as u64)) {
+        Ok(pos) => pos as i64,
+        Err(_) => libmpv2_sys::mpv_error_MPV_ERROR_GENERIC as i64,
+    }
+}
+
+#[cfg(feature = "mpv")]
+unsafe extern "C" fn source_size(cookie: *mut std::os::raw::c_void) -> i64 {
+    let src = &mut *(cookie as *mut Box<dyn MediaSource>);
+    src.size().map(|s| s as i64).unwrap_or(libmpv2_sys::mpv_error_MPV_ERROR_UNSUPPORTED as i64)
+}
+
+#[cfg(feature = "mpv")]
+unsafe extern "C" fn source_close(cookie: *mut std::os::raw::c_void) {
+    drop(Box::from_raw(cookie as *mut Box<dyn MediaSource>));
+}
+
+/// Match manifest variants to mpv video tracks: by `hls-bitrate` first, then
+/// by height, then by position when the counts line up.
+#[cfg(feature = "mpv")]
+fn map_variant_tracks(mpv: *mut libmpv2_sys::mpv_handle, variants: &[Variant]) -> Vec<Option<i64>> {
+    let count = get_mpv_int_property(mpv, "track-list/count").unwrap_or(0);
+    let mut tracks: Vec<(i64, Option<i64>, Option<i64>)> = Vec::new(); // (id, hls-bitrate, height)
+    for i in 0..count {
+        let type_name = CString::new(format!("track-list/{i}/type")).unwrap();
+        if get_mpv_string_property(mpv, &type_name).as_deref() != Some("video") {
+            continue;
+        }
+        let Some(id) = get_mpv_int_property(mpv, &format!("track-list/{i}/id")) else { continue };
+        let bitrate = get_mpv_int_property(mpv, &format!("track-list/{i}/hls-bitrate"));
+        let height = get_mpv_int_property(mpv, &format!("track-list/{i}/demux-h"));
+        tracks.push((id, bitrate, height));
+    }
+
+    let mut used: Vec<i64> = Vec::new();
+    let mut mapping = Vec::with_capacity(variants.len());
+    for v in variants {
+        let unused = |t: &&(i64, Option<i64>, Option<i64>)| !used.contains(&t.0);
+        let found = tracks.iter().filter(unused).find(|t| t.1 == Some(v.bandwidth as i64))
+            .or_else(|| tracks.iter().filter(unused).find(|t| v.height.is_some() && t.2 == v.height.map(i64::from)))
+            .or_else(|| if tracks.len() == variants.len() { tracks.get(v.index) } else { None })
+            .map(|t| t.0);
+        if let Some(id) = found {
+            used.push(id);
+        }
+        mapping.push(found);
+    }
+    log::debug!("{} variants mapped to video tracks {:?}", variants.len(), mapping);
+    mapping
+}
+
+#[cfg(feature = "mpv")]
+fn switch_variant(mpv: *mut libmpv2_sys::mpv_handle, variant_tracks: &[Option<i64>], index: usize, event_tx: &Sender<PlaybackEvent>) {
+    use libmpv2_sys::*;
+    use std::os::raw::c_void;
+    let Some(Some(vid)) = variant_tracks.get(index).copied() else {
+        log::warn!("variant {index} has no matching video track");
+        return;
+    };
+    let prop = CString::new("vid").unwrap();
+    let r = unsafe {
+        mpv_set_property(
+            mpv, prop.as_ptr(), mpv_format_MPV_FORMAT_INT64,
+            &vid as *const i64 as *mut c_void,
+        )
+    };
+    if r >= 0 {
+        log::info!("switched to variant {index} (vid={vid})");
+        let _ = event_tx.try_send(PlaybackEvent::VariantChanged(index));
+    } else {
+        log::warn!("variant switch failed: {r}");
+    }
+}
+
 /// MpvPlayer marker struct (kept for module-level exports)
 pub struct MpvPlayer;
\ No newline at end of file
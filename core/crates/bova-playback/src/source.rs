@@ -0,0 +1,150 @@
+//! Custom I/O sources: media that doesn't come from a path or URL the engines
+//! can open themselves (SMB/FTP/WebDAV clients, encrypted containers,
+//! in-memory buffers).
+//!
+//! A source is registered under a `bova-source://<id>/<name>` URL which both
+//! engines recognise: FFmpeg reads it through a custom `AVIOContext`, mpv
+//! through a `stream_cb` protocol handler. Each registered source can be
+//! opened once; the engine owns it from then on. Until then the registry
+//! entry lives as long as the `SourceGuard` returned by `register_source`.
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, Cursor, Read, Seek, SeekFrom};
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, OnceLock};
+
+/// URL scheme used for registered sources
+pub const SOURCE_SCHEME: &str = "bova-source";
+
+/// Random-access byte stream the playback engines can read from.
+pub trait MediaSource: Send {
+    /// Read up to `buf.len()` bytes; `Ok(0)` means end of stream.
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize>;
+    /// Move the read position, returning the new absolute offset.
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64>;
+    /// Total size in bytes, if known.
+    fn size(&mut self) -> Option<u64>;
+    /// False for forward-only streams (engines then avoid seeking).
+    fn is_seekable(&self) -> bool {
+        true
+    }
+}
+
+/// In-memory buffer, mainly for tests and small embedded clips.
+pub struct MemorySource {
+    inner: Cursor<Vec<u8>>,
+}
+
+impl MemorySource {
+    pub fn new(data: Vec<u8>) -> Self {
+        Self { inner: Cursor::new(data) }
+    }
+}
+
+impl MediaSource for MemorySource {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.inner.read(buf)
+    }
+
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        self.inner.seek(pos)
+    }
+
+    fn size(&mut self) -> Option<u64> {
+        Some(self.inner.get_ref().len() as u64)
+    }
+}
+
+/// Local file read through the custom I/O path (reference implementation).
+pub struct FileSource {
+    file: File,
+    len: Option<u64>,
+}
+
+impl FileSource {
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        let file = File::open(path)?;
+        let len = file.metadata().ok().map(|m| m.len());
+        Ok(Self { file, len })
+    }
+}
+
+impl MediaSource for FileSource {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.file.read(buf)
+    }
+
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        self.file.seek(pos)
+    }
+
+    fn size(&mut self) -> Option<u64> {
+        self.len
+    }
+}
+
+fn registry() -> &'static Mutex<HashMap<u64, Box<dyn MediaSource>>> {
+    static SOURCES: OnceLock<Mutex<HashMap<u64, Box<dyn MediaSource>>>> = OnceLock::new();
+    SOURCES.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// Registration of a source; dropping it removes the source again if no
+/// engine has opened it. Keep it alive for as long as the playback runs
+/// (`PlaybackHandles::source` does that for `start_playback_source`).
+#[derive(Debug)]
+#[must_use = "the source is unregistered when the guard is dropped"]
+pub struct SourceGuard {
+    url: String,
+}
+
+impl SourceGuard {
+    /// URL to hand to an engine.
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+}
+
+impl Drop for SourceGuard {
+    fn drop(&mut self) {
+        unregister_source(&self.url);
+    }
+}
+
+/// Register `source` under a new URL (see `SourceGuard::url`).
+/// `name` (e.g. the remote file name) is kept in the URL as a format hint.
+pub fn register_source(source: Box<dyn MediaSource>, name: &str) -> SourceGuard {
+    static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+    let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+    registry().lock().unwrap().insert(id, source);
+    let name = name.rsplit(['/', '\\']).next().unwrap_or("");
+    SourceGuard { url: format!("{SOURCE_SCHEME}://{id}/{name}") }
+}
+
+/// Remove a source that was registered but never opened.
+pub fn unregister_source(url: &str) {
+    if let Some(id) = source_id(url) {
+        registry().lock().unwrap().remove(&id);
+    }
+}
+
+/// Take ownership of the source behind `url`, if it is a registered one.
+pub fn take_source(url: &str) -> Option<Box<dyn MediaSource>> {
+    let id = source_id(url)?;
+    registry().lock().unwrap().remove(&id)
+}
+
+/// True while the source behind `url` is registered and not yet opened.
+pub fn is_registered(url: &str) -> bool {
+    source_id(url).is_some_and(|id| registry().lock().unwrap().contains_key(&id))
+}
+
+pub fn is_source_url(url: &str) -> bool {
+    source_id(url).is_some()
+}
+
+fn source_id(url: &str) -> Option<u64> {
+    let rest = url.strip_prefix(SOURCE_SCHEME)?.strip_prefix("://")?;
+    rest.split('/').next()?.parse().ok()
+}
@@ -0,0 +1,221 @@
+//! Playback diagnostics, similar to mpv's stats overlay.
+//!
+//! 引擎记录解码侧：每帧解码耗时、转换为 RGBA 的耗时（FFmpeg 为 swscale，mpv 为软件渲染）、
+//! 硬件帧下载结果与队列深度；前端（GUI、FFI、CLI）记录呈现侧：显示、跳过和迟到的帧以及
+//! 音画偏差。两侧写入同一个 `StatsCollector`（`PlaybackHandles::stats`），引擎每隔
+//! `STATS_INTERVAL` 发送一次 `PlaybackEvent::Stats` 快照，也可以随时调用 `snapshot()`。
+
+use serde::Serialize;
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// `PlaybackEvent::Stats` 的发送间隔，也是耗时平均值/最大值的统计周期
+pub const STATS_INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct PlaybackStats {
+    /// "ffmpeg" or "mpv"
+    pub engine: String,
+    pub video_codec: Option<String>,
+    /// Hardware decoding API in use (e.g. "videotoolbox"), `None` = software decoding
+    pub hwaccel: Option<String>,
+    /// Size of the frames sent to `video_rx`
+    pub width: u32,
+    pub height: u32,
+    pub frames_decoded: u64,
+    pub audio_frames_decoded: u64,
+    /// Video frames produced per second during the last interval
+    pub decode_fps: f64,
+    /// Per-frame decode time over the last interval (FFmpeg engine only)
+    pub decode_ms_avg: Option<f64>,
+    pub decode_ms_max: Option<f64>,
+    /// Per-frame conversion to RGBA over the last interval: swscale (FFmpeg) or software render (mpv)
+    pub scale_ms_avg: Option<f64>,
+    pub scale_ms_max: Option<f64>,
+    /// Hardware frames downloaded to system memory, and failed downloads
+    pub hw_transfers_ok: u64,
+    pub hw_transfers_failed: u64,
+    /// Frames waiting in `PlaybackHandles::video_rx` / `audio_rx`
+    pub video_queue: usize,
+    pub video_queue_capacity: usize,
+    pub audio_queue: usize,
+    pub audio_queue_capacity: usize,
+    /// Fill level of the demux read-ahead queue, 0-100 (FFmpeg engine only)
+    pub demux_fill_pct: Option<u8>,
+    /// Frames shown by the front end
+    pub frames_presented: u64,
+    /// Frames skipped by the front end, plus frames mpv dropped itself
+    pub frames_dropped: u64,
+    /// Frames shown after their due time
+    pub frames_late: u64,
+    /// Audio clock minus video position in milliseconds (mpv's A-V);
+    /// positive = video is behind. `None` without audio or video
+    pub av_drift_ms: Option<f64>,
+}
+
+/// 一个统计周期内的耗时
+#[derive(Debug, Default, Clone, Copy)]
+struct Window {
+    sum_ms: f64,
+    max_ms: f64,
+    count: u64,
+}
+
+impl Window {
+    fn add(&mut self, d: Duration) {
+        let ms = d.as_secs_f64() * 1000.0;
+        self.sum_ms += ms;
+        self.max_ms = self.max_ms.max(ms);
+        self.count += 1;
+    }
+
+    /// 周期结束：返回 (平均, 最大) 并清零；没有样本时保留上一周期的值
+    fn take(&mut self) -> Option<(f64, f64)> {
+        let window = std::mem::take(self);
+        (window.count > 0).then(|| (window.sum_ms / window.count as f64, window.max_ms))
+    }
+}
+
+#[derive(Debug, Default)]
+struct Inner {
+    stats: PlaybackStats,
+    decode: Window,
+    scale: Window,
+    interval_frames: u64,
+    /// 前端跳过的帧 / 引擎自己丢掉的帧，快照时相加
+    skipped: u64,
+    engine_dropped: u64,
+}
+
+/// Shared by the engine thread and the front end; see the module docs.
+#[derive(Debug, Default)]
+pub struct StatsCollector {
+    inner: Mutex<Inner>,
+}
+
+impl StatsCollector {
+    pub fn new(engine: &str) -> Self {
+        let collector = Self::default();
+        collector.lock().stats.engine = engine.to_string();
+        collector
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Inner> {
+        self.inner.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Current values; timings cover the last completed interval.
+    pub fn snapshot(&self) -> PlaybackStats {
+        let inner = self.lock();
+        let mut stats = inner.stats.clone();
+        stats.frames_dropped = inner.skipped + inner.engine_dropped;
+        stats
+    }
+
+    /// A frame was shown; `late` when it was shown after its due time.
+    pub fn record_presented(&self, late: bool) {
+        let mut inner = self.lock();
+        inner.stats.frames_presented += 1;
+        if late {
+            inner.stats.frames_late += 1;
+        }
+    }
+
+    /// Frames taken from `video_rx` but never shown.
+    pub fn record_dropped(&self, frames: u64) {
+        self.lock().skipped += frames;
+    }
+
+    /// Audio clock minus the position of the shown frame, see `PlaybackStats::av_drift_ms`.
+    pub fn set_av_drift(&self, drift_ms: Option<f64>) {
+        self.lock().stats.av_drift_ms = drift_ms;
+    }
+}
+
+// 引擎一侧；部分方法只有 FFmpeg 引擎使用
+#[cfg_attr(not(all(feature = "ffmpeg", feature = "mpv")), allow(dead_code))]
+impl StatsCollector {
+    pub(crate) fn set_stream(&self, video_codec: Option<String>, hwaccel: Option<String>) {
+        let mut inner = self.lock();
+        inner.stats.video_codec = video_codec;
+        inner.stats.hwaccel = hwaccel;
+    }
+
+    /// A video frame was sent; `decode` is `None` when the engine can't measure it (mpv).
+    pub(crate) fn record_video_frame(&self, width: u32, height: u32, decode: Option<Duration>, scale: Duration) {
+        let mut inner = self.lock();
+        inner.stats.width = width;
+        inner.stats.height = height;
+        inner.stats.frames_decoded += 1;
+        inner.interval_frames += 1;
+        if let Some(d) = decode {
+            inner.decode.add(d);
+        }
+        inner.scale.add(scale);
+    }
+
+    pub(crate) fn record_audio_frame(&self) {
+        self.lock().stats.audio_frames_decoded += 1;
+    }
+
+    pub(crate) fn record_hw_transfer(&self, api: &str, ok: bool) {
+        let mut inner = self.lock();
+        if ok {
+            inner.stats.hw_transfers_ok += 1;
+            if inner.stats.hwaccel.as_deref() != Some(api) {
+                inner.stats.hwaccel = Some(api.to_string());
+            }
+        } else {
+            inner.stats.hw_transfers_failed += 1;
+        }
+    }
+
+    pub(crate) fn set_demux_fill(&self, pct: u8) {
+        self.lock().stats.demux_fill_pct = Some(pct);
+    }
+
+    pub(crate) fn set_queues(&self, video: (usize, usize), audio: (usize, usize)) {
+        let mut inner = self.lock();
+        (inner.stats.video_queue, inner.stats.video_queue_capacity) = video;
+        (inner.stats.audio_queue, inner.stats.audio_queue_capacity) = audio;
+    }
+
+    /// Frames mpv dropped on its own (decoder and video output drops), as a running total.
+    pub(crate) fn set_engine_dropped(&self, frames: u64) {
+        self.lock().engine_dropped = frames;
+    }
+
+    /// 结束一个统计周期：计算帧率与耗时的平均值/最大值
+    pub(crate) fn finish_interval(&self, elapsed: Duration) {
+        let mut inner = self.lock();
+        let frames = std::mem::take(&mut inner.interval_frames);
+        inner.stats.decode_fps = frames as f64 / elapsed.as_secs_f64().max(0.001);
+        if let Some((avg, max)) = inner.decode.take() {
+            (inner.stats.decode_ms_avg, inner.stats.decode_ms_max) = (Some(avg), Some(max));
+        }
+        if let Some((avg, max)) = inner.scale.take() {
+            (inner.stats.scale_ms_avg, inner.stats.scale_ms_max) = (Some(avg), Some(max));
+        }
+    }
+
+    /// 复制另一个收集器的解码侧数据（gapless：每一项有自己的解码线程与收集器）
+    pub(crate) fn copy_decoder_from(&self, item: &StatsCollector) {
+        let src = item.snapshot();
+        let mut inner = self.lock();
+        let stats = &mut inner.stats;
+        stats.video_codec = src.video_codec;
+        stats.hwaccel = src.hwaccel;
+        stats.width = src.width;
+        stats.height = src.height;
+        stats.frames_decoded = src.frames_decoded;
+        stats.audio_frames_decoded = src.audio_frames_decoded;
+        stats.decode_fps = src.decode_fps;
+        stats.decode_ms_avg = src.decode_ms_avg;
+        stats.decode_ms_max = src.decode_ms_max;
+        stats.scale_ms_avg = src.scale_ms_avg;
+        stats.scale_ms_max = src.scale_ms_max;
+        stats.hw_transfers_ok = src.hw_transfers_ok;
+        stats.hw_transfers_failed = src.hw_transfers_failed;
+        stats.demux_fill_pct = src.demux_fill_pct;
+    }
+}
@@ -0,0 +1,171 @@
+//! Minimal HTTP/1.1 file server for the playback integration tests.
+//!
+//! Serves in-memory files with `Range` support. A server can drop the first
+//! response for a path partway through the body (to exercise reconnects) and
+//! throttle paths to a fixed rate (to steer throughput measurements). Every
+//! request is logged as `(path, range_start)`. `wav` generates test media.
+
+#![allow(dead_code)]
+
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{Shutdown, TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+#[derive(Default)]
+struct Config {
+    files: HashMap<String, Vec<u8>>,
+    /// path -> bytes sent before the first response is cut off
+    drop_after: HashMap<String, usize>,
+    /// path prefix -> bytes per second
+    throttle: Vec<(String, usize)>,
+}
+
+pub struct TestServer {
+    addr: String,
+    config: Arc<Mutex<Config>>,
+    requests: Arc<Mutex<Vec<(String, u64)>>>,
+}
+
+impl TestServer {
+    pub fn start() -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        let config = Arc::new(Mutex::new(Config::default()));
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let (cfg, log) = (config.clone(), requests.clone());
+        thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let (cfg, log) = (cfg.clone(), log.clone());
+                thread::spawn(move || serve(stream, &cfg, &log));
+            }
+        });
+        Self { addr, config, requests }
+    }
+
+    pub fn add(&self, path: &str, data: impl Into<Vec<u8>>) {
+        self.config.lock().unwrap().files.insert(path.to_string(), data.into());
+    }
+
+    /// Cut the first response for `path` off after `bytes` of body.
+    pub fn drop_first_response_after(&self, path: &str, bytes: usize) {
+        self.config.lock().unwrap().drop_after.insert(path.to_string(), bytes);
+    }
+
+    /// Send bodies of paths starting with `prefix` at `bytes_per_sec`.
+    pub fn throttle(&self, prefix: &str, bytes_per_sec: usize) {
+        self.config.lock().unwrap().throttle.push((prefix.to_string(), bytes_per_sec));
+    }
+
+    pub fn url(&self, path: &str) -> String {
+        format!("http://{}{}", self.addr, path)
+    }
+
+    /// `(path, range start)` of every request so far.
+    pub fn requests(&self) -> Vec<(String, u64)> {
+        self.requests.lock().unwrap().clone()
+    }
+}
+
+fn serve(mut stream: TcpStream, config: &Mutex<Config>, log: &Mutex<Vec<(String, u64)>>) {
+    let mut reader = BufReader::new(stream.try_clone().unwrap());
+    loop {
+        let mut request_line = String::new();
+        if reader.read_line(&mut request_line).unwrap_or(0) == 0 {
+            return;
+        }
+        let path = request_line.split_whitespace().nth(1).unwrap_or("/").to_string();
+        let mut range_start = 0u64;
+        loop {
+            let mut line = String::new();
+            if reader.read_line(&mut line).unwrap_or(0) == 0 {
+                return;
+            }
+            let line = line.trim();
+            if line.is_empty() {
+                break;
+            }
+            if let Some((name, value)) = line.split_once(':') {
+                if name.eq_ignore_ascii_case("range") {
+                    let value = value.trim().trim_start_matches("bytes=");
+                    range_start = value.split('-').next().unwrap_or("0").parse().unwrap_or(0);
+                }
+            }
+        }
+        log.lock().unwrap().push((path.clone(), range_start));
+
+        let (data, drop_after, rate) = {
+            let mut cfg = config.lock().unwrap();
+            let data = cfg.files.get(&path).cloned();
+            let drop_after = cfg.drop_after.remove(&path);
+            let rate = cfg.throttle.iter().find(|(p, _)| path.starts_with(p.as_str())).map(|&(_, r)| r);
+            (data, drop_after, rate)
+        };
+        let Some(data) = data else {
+            let _ = stream.write_all(b"HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\n\r\n");
+            continue;
+        };
+        let start = (range_start as usize).min(data.len());
+        let head = if range_start > 0 {
+            format!(
+                "HTTP/1.1 206 Partial Content\r\nContent-Length: {}\r\nContent-Range: bytes {}-{}/{}\r\nAccept-Ranges: bytes\r\n\r\n",
+                data.len() - start,
+                start,
+                data.len().saturating_sub(1),
+                data.len()
+            )
+        } else {
+            format!("HTTP/1.1 200 OK\r\nContent-Length: {}\r\nAccept-Ranges: bytes\r\n\r\n", data.len())
+        };
+        if stream.write_all(head.as_bytes()).is_err() {
+            return;
+        }
+        let body = &data[start..];
+        let limit = drop_after.map_or(body.len(), |n| n.min(body.len()));
+        for chunk in body[..limit].chunks(4096) {
+            if stream.write_all(chunk).is_err() {
+                return;
+            }
+            if let Some(rate) = rate {
+                thread::sleep(Duration::from_secs_f64(chunk.len() as f64 / rate as f64));
+            }
+        }
+        if drop_after.is_some() {
+            // connection lost mid-file
+            let _ = stream.shutdown(Shutdown::Both);
+            return;
+        }
+    }
+}
+
+pub const SAMPLE_RATE: u32 = 48_000;
+pub const CHANNELS: u16 = 2;
+
+/// 16-bit PCM WAV with a 440 Hz tone.
+pub fn wav(duration_ms: u32) -> Vec<u8> {
+    let frames = SAMPLE_RATE * duration_ms / 1000;
+    let data_len = frames * CHANNELS as u32 * 2;
+    let mut out = Vec::with_capacity(44 + data_len as usize);
+    out.extend_from_slice(b"RIFF");
+    out.extend_from_slice(&(36 + data_len).to_le_bytes());
+    out.extend_from_slice(b"WAVEfmt ");
+    out.extend_from_slice(&16u32.to_le_bytes());
+    out.extend_from_slice(&1u16.to_le_bytes()); // PCM
+    out.extend_from_slice(&CHANNELS.to_le_bytes());
+    out.extend_from_slice(&SAMPLE_RATE.to_le_bytes());
+    out.extend_from_slice(&(SAMPLE_RATE * CHANNELS as u32 * 2).to_le_bytes());
+    out.extend_from_slice(&(CHANNELS * 2).to_le_bytes());
+    out.extend_from_slice(&16u16.to_le_bytes());
+    out.extend_from_slice(b"data");
+    out.extend_from_slice(&data_len.to_le_bytes());
+    for i in 0..frames {
+        let t = i as f32 / SAMPLE_RATE as f32;
+        let s = ((t * 440.0 * std::f32::consts::TAU).sin() * 8000.0) as i16;
+        for _ in 0..CHANNELS {
+            out.extend_from_slice(&s.to_le_bytes());
+        }
+    }
+    out
+}
@@ -0,0 +1,159 @@
+//! HLS variant handling against a local static server with generated playlists.
+
+mod common;
+
+use bova_playback::adaptive::{fetch_variants, QualityMode};
+use bova_playback::hls::{parse_hls_media, HlsSource};
+use bova_playback::{MediaSource, NetworkOptions, PlaybackEvent};
+use common::TestServer;
+use crossbeam_channel::unbounded;
+
+const SEGMENTS: usize = 6;
+
+/// Segment payload: every byte is `variant * 16 + seq`, so the stream shows
+/// which variant each segment came from.
+fn segment(variant: usize, seq: usize, len: usize) -> Vec<u8> {
+    vec![(variant * 16 + seq) as u8; len]
+}
+
+/// Master playlist with a 100 kbps and a 4 Mbps variant, 2 s segments each.
+/// `segment_len[v]` is the payload size of variant `v`'s segments.
+fn serve_stream(server: &TestServer, segment_len: [usize; 2]) {
+    server.add(
+        "/master.m3u8",
+        "#EXTM3U\n\
+         #EXT-X-STREAM-INF:BANDWIDTH=100000,RESOLUTION=320x180\n\
+         low/index.m3u8\n\
+         #EXT-X-STREAM-INF:BANDWIDTH=4000000,RESOLUTION=1280x720\n\
+         high/index.m3u8\n",
+    );
+    for (v, name) in ["low", "high"].into_iter().enumerate() {
+        let mut playlist = String::from("#EXTM3U\n#EXT-X-TARGETDURATION:2\n#EXT-X-MEDIA-SEQUENCE:0\n");
+        for seq in 0..SEGMENTS {
+            playlist.push_str(&format!("#EXTINF:2.0,\nseg{seq}.ts\n"));
+            server.add(&format!("/{name}/seg{seq}.ts"), segment(v, seq, segment_len[v]));
+        }
+        playlist.push_str("#EXT-X-ENDLIST\n");
+        server.add(&format!("/{name}/index.m3u8"), playlist);
+    }
+}
+
+/// Read the whole source and return the (variant, seq) of each segment in order.
+fn read_segments(src: &mut HlsSource) -> Vec<(usize, usize)> {
+    let mut out: Vec<(usize, usize)> = Vec::new();
+    let mut buf = [0u8; 1024];
+    loop {
+        let n = src.read(&mut buf).expect("read");
+        if n == 0 {
+            break;
+        }
+        for &b in &buf[..n] {
+            let id = ((b / 16) as usize, (b % 16) as usize);
+            if out.last() != Some(&id) {
+                out.push(id);
+            }
+        }
+    }
+    out
+}
+
+#[test]
+fn master_playlist_variants_resolve_against_server() {
+    let server = TestServer::start();
+    serve_stream(&server, [16, 16]);
+    let variants = fetch_variants(&server.url("/master.m3u8"), &NetworkOptions::default()).unwrap();
+    assert_eq!(variants.len(), 2);
+    assert_eq!(variants[0].bandwidth, 100_000);
+    assert_eq!(variants[1].height, Some(720));
+    assert_eq!(variants[1].uri, server.url("/high/index.m3u8"));
+}
+
+#[test]
+fn fixed_quality_reads_every_segment_of_one_variant() {
+    let server = TestServer::start();
+    serve_stream(&server, [2048, 2048]);
+    let net = NetworkOptions::default();
+    let variants = fetch_variants(&server.url("/master.m3u8"), &net).unwrap();
+    let (tx, rx) = unbounded();
+
+    let mut src = HlsSource::open(variants, QualityMode::Fixed(1), None, &net, Some(tx)).unwrap();
+    assert_eq!(src.current(), 1);
+    let expected: Vec<_> = (0..SEGMENTS).map(|seq| (1, seq)).collect();
+    assert_eq!(read_segments(&mut src), expected);
+    assert!(rx.try_iter().next().is_none(), "fixed quality must not switch");
+}
+
+#[test]
+fn start_position_selects_the_segment() {
+    let server = TestServer::start();
+    serve_stream(&server, [64, 64]);
+    let net = NetworkOptions::default();
+    let variants = fetch_variants(&server.url("/master.m3u8"), &net).unwrap();
+
+    let mut src = HlsSource::open(variants, QualityMode::Fixed(0), Some(5_000), &net, None).unwrap();
+    let expected: Vec<_> = (2..SEGMENTS).map(|seq| (0, seq)).collect();
+    assert_eq!(read_segments(&mut src), expected);
+}
+
+#[test]
+fn auto_switches_on_segment_throughput() {
+    let server = TestServer::start();
+    // low segments arrive at ~16 Mbps, enough for the 4 Mbps variant; high
+    // segments at ~160 kbps, which drives the estimate back below it
+    serve_stream(&server, [64 * 1024, 8 * 1024]);
+    server.throttle("/low/", 2_000_000);
+    server.throttle("/high/", 20_000);
+    let net = NetworkOptions::default();
+    let variants = fetch_variants(&server.url("/master.m3u8"), &net).unwrap();
+    let (tx, rx) = unbounded();
+
+    let mut src = HlsSource::open(variants, QualityMode::Auto, None, &net, Some(tx)).unwrap();
+    assert_eq!(src.current(), 0, "auto starts on the lowest variant");
+    let segments = read_segments(&mut src);
+
+    // every segment exactly once, in order, whichever variant it came from
+    let seqs: Vec<_> = segments.iter().map(|&(_, seq)| seq).collect();
+    assert_eq!(seqs, (0..SEGMENTS).collect::<Vec<_>>(), "segments: {segments:?}");
+    assert_eq!(segments[0], (0, 0));
+    assert_eq!(segments[1], (1, 1), "no up-switch after a fast segment: {segments:?}");
+    assert_eq!(segments.last().unwrap().0, 0, "no down-switch after slow segments: {segments:?}");
+
+    let switches: Vec<_> = rx
+        .try_iter()
+        .filter_map(|e| match e {
+            PlaybackEvent::VariantChanged(i) => Some(i),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(switches, vec![1, 0]);
+}
+
+#[test]
+fn fragmented_mp4_playlists_are_not_concatenated() {
+    let server = TestServer::start();
+    server.add(
+        "/master.m3u8",
+        "#EXTM3U\n#EXT-X-STREAM-INF:BANDWIDTH=100000\nfmp4/index.m3u8\n",
+    );
+    server.add(
+        "/fmp4/index.m3u8",
+        "#EXTM3U\n#EXT-X-TARGETDURATION:2\n#EXT-X-MAP:URI=\"init.mp4\"\n#EXTINF:2.0,\nseg0.m4s\n#EXT-X-ENDLIST\n",
+    );
+    let net = NetworkOptions { timeout_ms: 2_000, ..NetworkOptions::default() };
+    let variants = fetch_variants(&server.url("/master.m3u8"), &net).unwrap();
+    assert!(HlsSource::open(variants, QualityMode::Auto, None, &net, None).is_err());
+}
+
+#[test]
+fn media_playlist_parsing() {
+    let text = "#EXTM3U\n#EXT-X-TARGETDURATION:6\n#EXT-X-MEDIA-SEQUENCE:41\n\
+                #EXTINF:5.005,\na.ts\n#EXTINF:6,title\nhttp://cdn.example/b.ts\n";
+    let playlist = parse_hls_media(text, "http://host/path/index.m3u8");
+    assert_eq!(playlist.target_duration_ms, 6_000);
+    assert_eq!(playlist.media_sequence, 41);
+    assert!(!playlist.ended, "no ENDLIST: live");
+    assert!(playlist.concatenable);
+    assert_eq!(playlist.segments[0].duration_ms, 5_005);
+    assert_eq!(playlist.segments[0].uri, "http://host/path/a.ts");
+    assert_eq!(playlist.segments[1].uri, "http://cdn.example/b.ts");
+}
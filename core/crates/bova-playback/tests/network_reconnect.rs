@@ -0,0 +1,78 @@
+//! FFmpeg engine over a server that drops the connection mid-file: playback
+//! must resume with a range request, reach the end of the stream and report
+//! buffering on the way.
+
+#![cfg(feature = "ffmpeg")]
+
+mod common;
+
+use std::time::{Duration, Instant};
+
+use bova_playback::{start_playback_with, NetworkOptions, PlaybackConfig, PlaybackEngine, PlaybackEvent};
+use common::{wav, TestServer};
+use crossbeam_channel::{after, select};
+
+const DURATION_MS: u32 = 3_000;
+
+#[test]
+fn reconnects_after_dropped_connection_and_reaches_eof() {
+    let file = wav(DURATION_MS);
+    let half = file.len() / 2;
+    let server = TestServer::start();
+    server.add("/tone.wav", file);
+    server.drop_first_response_after("/tone.wav", half);
+    // ~1.5 s to download: the read-ahead queue fills visibly slower than it is drained
+    server.throttle("/tone.wav", 400_000);
+
+    let cfg = PlaybackConfig {
+        engine: Some(PlaybackEngine::FFmpeg),
+        network: NetworkOptions { cache_ms: 500, timeout_ms: 5_000, reconnect_delay_max_s: 1, ..NetworkOptions::default() },
+        ..PlaybackConfig::default()
+    };
+    let handles = start_playback_with(&server.url("/tone.wav"), cfg).expect("start playback");
+    let events = handles.event_rx.clone().expect("event channel");
+
+    let mut audio_ms = 0.0f64;
+    let mut buffering = Vec::new();
+    let mut errors = Vec::new();
+    let deadline = after(Duration::from_secs(30));
+    let started = Instant::now();
+    loop {
+        select! {
+            recv(handles.audio_rx) -> frame => if let Ok(frame) = frame {
+                let frames = frame.samples.len() / frame.channels.max(1) as usize;
+                audio_ms += frames as f64 * 1000.0 / frame.sample_rate.max(1) as f64;
+            },
+            recv(events) -> event => match event {
+                Ok(PlaybackEvent::Buffering(pct)) => buffering.push(pct),
+                Ok(PlaybackEvent::Error(e)) => errors.push(e),
+                _ => {}
+            },
+            recv(handles.eos_rx) -> _ => break,
+            recv(deadline) -> _ => panic!("no end of stream after {:?}", started.elapsed()),
+        }
+    }
+    // frames still queued when the end of stream was signalled
+    for frame in handles.audio_rx.try_iter() {
+        let frames = frame.samples.len() / frame.channels.max(1) as usize;
+        audio_ms += frames as f64 * 1000.0 / frame.sample_rate.max(1) as f64;
+    }
+    for event in events.try_iter() {
+        match event {
+            PlaybackEvent::Buffering(pct) => buffering.push(pct),
+            PlaybackEvent::Error(e) => errors.push(e),
+            _ => {}
+        }
+    }
+
+    assert!(errors.is_empty(), "playback errors: {errors:?}");
+    let resumed = server.requests().iter().any(|(path, start)| path == "/tone.wav" && *start > 0);
+    assert!(resumed, "no range request after the drop: {:?}", server.requests());
+    assert!(
+        (audio_ms - DURATION_MS as f64).abs() < 100.0,
+        "decoded {audio_ms:.0} ms of audio, expected {DURATION_MS} ms"
+    );
+    // initial fill reported below 100 and then completed
+    assert_eq!(buffering.first().map(|&p| p < 100), Some(true), "buffering events: {buffering:?}");
+    assert!(buffering.contains(&100), "buffering never completed: {buffering:?}");
+}
@@ -0,0 +1,98 @@
+//! Custom sources: registry lifetime, and in-memory media played by the
+//! FFmpeg engine through the custom `AVIOContext` (read, seek, end of stream).
+
+mod common;
+
+use std::io::SeekFrom;
+
+use bova_playback::source::{self, MediaSource, MemorySource};
+
+#[test]
+fn memory_source_reads_seeks_and_ends() {
+    let mut src = MemorySource::new((0u8..100).collect());
+    assert_eq!(src.size(), Some(100));
+    let mut buf = [0u8; 10];
+    assert_eq!(src.read(&mut buf).unwrap(), 10);
+    assert_eq!(buf[9], 9);
+    assert_eq!(src.seek(SeekFrom::End(-5)).unwrap(), 95);
+    assert_eq!(src.read(&mut buf).unwrap(), 5);
+    assert_eq!(&buf[..5], &[95, 96, 97, 98, 99]);
+    assert_eq!(src.read(&mut buf).unwrap(), 0, "end of stream");
+}
+
+#[test]
+fn dropping_the_guard_unregisters_an_unopened_source() {
+    let guard = source::register_source(Box::new(MemorySource::new(vec![0; 16])), "/remote/dir/clip.wav");
+    let url = guard.url().to_string();
+    assert!(url.starts_with("bova-source://") && url.ends_with("/clip.wav"), "{url}");
+    assert!(source::is_registered(&url));
+    drop(guard);
+    assert!(!source::is_registered(&url));
+    assert!(source::take_source(&url).is_none());
+}
+
+#[test]
+fn opened_source_belongs_to_the_engine() {
+    let guard = source::register_source(Box::new(MemorySource::new(vec![7; 16])), "clip.wav");
+    let mut src = source::take_source(guard.url()).expect("registered");
+    assert!(source::take_source(guard.url()).is_none(), "a source opens once");
+    drop(guard);
+    let mut buf = [0u8; 4];
+    assert_eq!(src.read(&mut buf).unwrap(), 4, "dropping the guard leaves the opened source alone");
+}
+
+#[cfg(feature = "ffmpeg")]
+mod ffmpeg {
+    use std::time::Duration;
+
+    use bova_playback::source::{self, MemorySource};
+    use bova_playback::{start_playback_source, PlaybackConfig, PlaybackEngine, PlaybackEvent, PlaybackHandles};
+    use crossbeam_channel::{after, select};
+
+    use super::common::wav;
+
+    const DURATION_MS: u32 = 2_000;
+
+    /// Play until the end of stream; returns the decoded audio in ms.
+    fn play_to_end(handles: &PlaybackHandles) -> f64 {
+        let events = handles.event_rx.clone().expect("event channel");
+        let mut audio_ms = 0.0f64;
+        let deadline = after(Duration::from_secs(30));
+        let mut add = |frame: bova_playback::AudioFrame| {
+            let frames = frame.samples.len() / frame.channels.max(1) as usize;
+            audio_ms += frames as f64 * 1000.0 / frame.sample_rate.max(1) as f64;
+        };
+        loop {
+            select! {
+                recv(handles.audio_rx) -> frame => if let Ok(frame) = frame { add(frame) },
+                recv(events) -> event => if let Ok(PlaybackEvent::Error(e)) = event { panic!("playback error: {e}") },
+                recv(handles.eos_rx) -> _ => break,
+                recv(deadline) -> _ => panic!("no end of stream"),
+            }
+        }
+        handles.audio_rx.try_iter().for_each(&mut add);
+        audio_ms
+    }
+
+    fn config(start_ms: Option<i64>) -> PlaybackConfig {
+        PlaybackConfig { engine: Some(PlaybackEngine::FFmpeg), start_ms, ..PlaybackConfig::default() }
+    }
+
+    #[test]
+    fn plays_memory_source_to_the_end() {
+        let src = Box::new(MemorySource::new(wav(DURATION_MS)));
+        let handles = start_playback_source(src, "tone.wav", config(None)).expect("start playback");
+        let url = handles.source.as_ref().expect("source guard").url().to_string();
+        let audio_ms = play_to_end(&handles);
+        assert!((audio_ms - DURATION_MS as f64).abs() < 100.0, "decoded {audio_ms:.0} ms");
+        assert!(!source::is_registered(&url), "the engine took the source");
+    }
+
+    #[test]
+    fn start_position_seeks_through_the_source() {
+        let src = Box::new(MemorySource::new(wav(DURATION_MS)));
+        let handles = start_playback_source(src, "tone.wav", config(Some(1_000))).expect("start playback");
+        let audio_ms = play_to_end(&handles);
+        assert!((audio_ms - 1_000.0).abs() < 150.0, "decoded {audio_ms:.0} ms after seeking to 1 s");
+    }
+}
@@ -0,0 +1,298 @@
+//! FFmpeg-backed probing: container, streams, chapters, attachments and tags.
+//!
+//! Times are converted to milliseconds; codecpar fields FFmpeg leaves unset
+//! (0 / unknown) are reported as null.
+
+use std::ffi::{c_int, c_void, CStr, CString};
+use std::time::{Duration, Instant};
+
+use ffmpeg_next as ffmpeg;
+use ffmpeg::ffi;
+use ffmpeg::format::context::Input;
+use ffmpeg::media::Type;
+
+use crate::{Attachment, Chapter, ColorInfo, HdrFormat, HdrInfo, MediaInfo, MediaTags, ProbeError, ProbeOptions, StreamInfo};
+
+/// 打开并分析输入。超时由中断回调实现，覆盖连接、读取和 find_stream_info 全程
+pub(crate) fn open(url: &str, opts: &ProbeOptions) -> Result<Input, ProbeError> {
+    let mut dict = ffmpeg::Dictionary::new();
+    // analyzeduration / rw_timeout 均为微秒
+    if let Some(ms) = opts.analyze_duration_ms {
+        dict.set("analyzeduration", &(ms as u64 * 1000).to_string());
+    }
+    if let Some(size) = opts.probe_size {
+        dict.set("probesize", &size.to_string());
+    }
+    if let Some(ms) = opts.timeout_ms {
+        dict.set("rw_timeout", &(ms as u64 * 1000).to_string());
+    }
+    if let Some(ua) = &opts.user_agent {
+        dict.set("user_agent", ua);
+    }
+    if !opts.headers.is_empty() {
+        let headers: String = opts.headers.iter()
+            .map(|(k, v)| format!("{k}: {v}\r\n"))
+            .collect();
+        dict.set("headers", &headers);
+    }
+
+    let c_url = CString::new(url).map_err(|_| ProbeError::NotFound(url.to_string()))?;
+    // 回调读取的截止时间；仅在 open 期间有效，返回前解除回调
+    let deadline: Box<Option<Instant>> = Box::new(opts.timeout_ms.map(|ms| Instant::now() + Duration::from_millis(ms as u64)));
+    let to_error = |e: ffmpeg::Error| map_error(url, e, opts.timeout_ms);
+
+    unsafe {
+        let mut ctx = ffi::avformat_alloc_context();
+        if ctx.is_null() {
+            return Err(ProbeError::Other("out of memory".into()));
+        }
+        (*ctx).interrupt_callback = ffi::AVIOInterruptCB {
+            callback: Some(interrupt_cb),
+            opaque: &*deadline as *const Option<Instant> as *mut c_void,
+        };
+
+        let mut raw = dict.disown();
+        let r = ffi::avformat_open_input(&mut ctx, c_url.as_ptr(), std::ptr::null(), &mut raw);
+        // 未被识别的选项留在字典里，一并释放
+        drop(ffmpeg::Dictionary::own(raw));
+        if r < 0 {
+            // avformat_open_input frees ctx on failure
+            return Err(to_error(ffmpeg::Error::from(r)));
+        }
+        let r = ffi::avformat_find_stream_info(ctx, std::ptr::null_mut());
+        if r < 0 {
+            ffi::avformat_close_input(&mut ctx);
+            return Err(to_error(ffmpeg::Error::from(r)));
+        }
+        (*ctx).interrupt_callback = ffi::AVIOInterruptCB { callback: None, opaque: std::ptr::null_mut() };
+        Ok(Input::wrap(ctx))
+    }
+}
+
+unsafe extern "C" fn interrupt_cb(opaque: *mut c_void) -> c_int {
+    let deadline = &*(opaque as *const Option<Instant>);
+    matches!(deadline, Some(d) if Instant::now() >= *d) as c_int
+}
+
+fn map_error(url: &str, e: ffmpeg::Error, timeout_ms: Option<u32>) -> ProbeError {
+    use ffmpeg::error::{ENOENT, ETIMEDOUT};
+    use ffmpeg::Error;
+
+    let network = url.contains("://") && !url.starts_with("file://");
+    match e {
+        Error::Other { errno: ENOENT } | Error::HttpNotFound => ProbeError::NotFound(url.to_string()),
+        // 中断回调触发
+        Error::Exit => ProbeError::Timeout(timeout_ms.unwrap_or(0)),
+        Error::Other { errno: ETIMEDOUT } => ProbeError::Timeout(timeout_ms.unwrap_or(0)),
+        Error::InvalidData | Error::DemuxerNotFound | Error::ProtocolNotFound | Error::StreamNotFound => {
+            ProbeError::UnsupportedFormat(e.to_string())
+        }
+        Error::HttpBadRequest | Error::HttpUnauthorized | Error::HttpForbidden | Error::HttpOther4xx | Error::HttpServerError => {
+            ProbeError::Network(e.to_string())
+        }
+        Error::Other { .. } if network => ProbeError::Network(e.to_string()),
+        _ => ProbeError::Other(e.to_string()),
+    }
+}
+
+pub(crate) fn media_info(url: &str, ictx: &Input) -> MediaInfo {
+    // AV_TIME_BASE 为微秒
+    let d = ictx.duration();
+    let duration_ms = if d > 0 { Some(d as u64 / 1000) } else { None };
+    let b = ictx.bit_rate();
+    let bit_rate = if b > 0 { Some(b as u64) } else { None };
+    let format = ictx.format();
+
+    let mut streams = Vec::new();
+    let mut attachments = Vec::new();
+    for st in ictx.streams() {
+        if st.parameters().medium() == Type::Attachment {
+            attachments.push(attachment(&st));
+        }
+        streams.push(stream_info(&st));
+    }
+
+    MediaInfo {
+        url: url.to_string(),
+        format: Some(format.name().to_string()),
+        format_long_name: Some(format.description().to_string()).filter(|s| !s.is_empty()),
+        duration_ms,
+        bit_rate,
+        streams,
+        chapters: read_chapters(ictx),
+        attachments,
+        tags: read_tags(ictx),
+    }
+}
+
+/// 容器级元数据优先，其次是首选音频流的（Ogg / Opus 把标签写在流上）
+pub fn read_tags(ictx: &Input) -> MediaTags {
+    let container = ictx.metadata();
+    let audio = ictx.streams().best(Type::Audio);
+    let stream_meta = audio.as_ref().map(|s| s.metadata());
+    MediaTags::from_metadata(container.iter().chain(stream_meta.iter().flat_map(|m| m.iter())))
+}
+
+fn stream_info(st: &ffmpeg::format::stream::Stream) -> StreamInfo {
+    let params = st.parameters();
+    let medium = params.medium();
+    let codec_id = params.id();
+    let meta = st.metadata();
+    let text = |key: &str| meta.get(key).map(str::trim).filter(|v| !v.is_empty()).map(str::to_string);
+
+    let mut sinfo = StreamInfo {
+        index: st.index() as u32,
+        kind: match medium {
+            Type::Video => "video",
+            Type::Audio => "audio",
+            Type::Subtitle => "subtitle",
+            Type::Attachment => "attachment",
+            Type::Data => "data",
+            _ => "other",
+        }.to_string(),
+        codec: codec_id.name().to_string(),
+        // "und" 表示未指定
+        language: text("language").filter(|l| l != "und"),
+        title: text("title"),
+        disposition: st.disposition().iter_names().map(|(name, _)| name.to_ascii_lowercase()).collect(),
+        ..Default::default()
+    };
+
+    let par = unsafe { &*params.as_ptr() };
+    sinfo.profile = unsafe {
+        let name = ffi::avcodec_profile_name(par.codec_id, par.profile);
+        (!name.is_null()).then(|| CStr::from_ptr(name).to_string_lossy().into_owned())
+    };
+    if par.bit_rate > 0 {
+        sinfo.bit_rate = Some(par.bit_rate as u64);
+    }
+    if par.bits_per_raw_sample > 0 {
+        sinfo.bit_depth = Some(par.bits_per_raw_sample as u32);
+    }
+
+    // 通过 decoder context 读取参数（v7 风格）
+    if let Ok(ctx) = ffmpeg::codec::context::Context::from_parameters(st.parameters()) {
+        match medium {
+            Type::Video => {
+                if let Ok(vd) = ctx.decoder().video() {
+                    sinfo.width = Some(vd.width());
+                    sinfo.height = Some(vd.height());
+                    let pixel = vd.format();
+                    if pixel != ffmpeg::format::Pixel::None {
+                        if let Some(desc) = pixel.descriptor() {
+                            sinfo.pixel_format = Some(desc.name().to_string());
+                            if sinfo.bit_depth.is_none() {
+                                sinfo.bit_depth = Some(unsafe { (*desc.as_ptr()).comp[0].depth } as u32);
+                            }
+                        }
+                    }
+                    let color = ColorInfo {
+                        space: vd.color_space().name().map(str::to_string),
+                        primaries: vd.color_primaries().name().map(str::to_string),
+                        transfer: vd.color_transfer_characteristic().name().map(str::to_string),
+                        range: vd.color_range().name().map(str::to_string),
+                    };
+                    if color != ColorInfo::default() {
+                        sinfo.color = Some(color);
+                    }
+                }
+            }
+            Type::Audio => {
+                if let Ok(ad) = ctx.decoder().audio() {
+                    sinfo.channels = Some(ad.channels() as u32);
+                    sinfo.sample_rate = Some(ad.rate());
+                }
+            }
+            _ => {}
+        }
+    }
+
+    if medium == Type::Video {
+        sinfo.frame_rate = frame_rate(st);
+        sinfo.hdr = hdr_info(par, sinfo.color.as_ref());
+    }
+    sinfo
+}
+
+fn frame_rate(st: &ffmpeg::format::stream::Stream) -> Option<f64> {
+    [st.avg_frame_rate(), st.rate()]
+        .into_iter()
+        .find(|r| r.numerator() > 0 && r.denominator() > 0)
+        .map(f64::from)
+}
+
+/// 传输特性判断 HDR10 / HLG，DOVI 配置记录优先；亮度信息来自 coded_side_data
+fn hdr_info(par: &ffi::AVCodecParameters, color: Option<&ColorInfo>) -> Option<HdrInfo> {
+    use ffi::AVPacketSideDataType::*;
+
+    let side_data: &[ffi::AVPacketSideData] = if par.coded_side_data.is_null() || par.nb_coded_side_data <= 0 {
+        &[]
+    } else {
+        unsafe { std::slice::from_raw_parts(par.coded_side_data, par.nb_coded_side_data as usize) }
+    };
+
+    let format = if side_data.iter().any(|sd| sd.type_ == AV_PKT_DATA_DOVI_CONF) {
+        HdrFormat::DolbyVision
+    } else {
+        match color.and_then(|c| c.transfer.as_deref()) {
+            Some("smpte2084") => HdrFormat::Hdr10,
+            Some("arib-std-b67") => HdrFormat::Hlg,
+            _ => return None,
+        }
+    };
+
+    let mut hdr = HdrInfo { format, max_cll: None, max_fall: None, mastering_min_luminance: None, mastering_max_luminance: None };
+    for sd in side_data {
+        match sd.type_ {
+            AV_PKT_DATA_CONTENT_LIGHT_LEVEL if sd.size >= std::mem::size_of::<ffi::AVContentLightMetadata>() => {
+                let light = unsafe { &*(sd.data as *const ffi::AVContentLightMetadata) };
+                hdr.max_cll = Some(light.MaxCLL);
+                hdr.max_fall = Some(light.MaxFALL);
+            }
+            AV_PKT_DATA_MASTERING_DISPLAY_METADATA if sd.size >= std::mem::size_of::<ffi::AVMasteringDisplayMetadata>() => {
+                let mastering = unsafe { &*(sd.data as *const ffi::AVMasteringDisplayMetadata) };
+                if mastering.has_luminance != 0 {
+                    hdr.mastering_min_luminance = rational(mastering.min_luminance);
+                    hdr.mastering_max_luminance = rational(mastering.max_luminance);
+                }
+            }
+            _ => {}
+        }
+    }
+    Some(hdr)
+}
+
+fn rational(r: ffi::AVRational) -> Option<f64> {
+    (r.den != 0).then(|| r.num as f64 / r.den as f64)
+}
+
+/// 章节按开始时间排序返回（FFmpeg 已按 start 排序）
+pub fn read_chapters(ictx: &Input) -> Vec<Chapter> {
+    ictx.chapters()
+        .map(|ch| {
+            let tb = ch.time_base();
+            let to_ms = |t: i64| {
+                if tb.denominator() == 0 { return 0; }
+                (t as i128 * tb.numerator() as i128 * 1000 / tb.denominator() as i128) as i64
+            };
+            Chapter {
+                id: ch.id(),
+                start_ms: to_ms(ch.start()),
+                end_ms: to_ms(ch.end()),
+                title: ch.metadata().get("title").map(str::to_string).filter(|t| !t.is_empty()),
+            }
+        })
+        .collect()
+}
+
+/// Matroska 附件：文件名与 MIME 类型在流元数据中，内容在 extradata
+fn attachment(st: &ffmpeg::format::stream::Stream) -> Attachment {
+    let meta = st.metadata();
+    let size = unsafe { (*st.parameters().as_ptr()).extradata_size.max(0) as u64 };
+    Attachment {
+        index: st.index() as u32,
+        filename: meta.get("filename").map(str::to_string),
+        mime_type: meta.get("mimetype").map(str::to_string),
+        size,
+    }
+}
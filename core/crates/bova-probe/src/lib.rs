@@ -2,100 +2,279 @@
 //! If built without the `ffmpeg` feature, returns placeholder info (no I/O).
 
 use serde::{Deserialize, Serialize};
+use thiserror::Error;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg(feature = "ffmpeg")]
+mod inspect;
+mod tags;
+
+#[cfg(feature = "ffmpeg")]
+pub use inspect::{read_chapters, read_tags};
+pub use tags::{MediaTags, ReplayGain};
+
+/// One stream of the container. Fields that don't apply to the stream kind are null.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
 pub struct StreamInfo {
     pub index: u32,
-    pub kind: String,       // video/audio/subtitle
+    pub kind: String,       // video/audio/subtitle/attachment/data
+    /// FFmpeg codec name (`h264`, `aac`, `subrip`, ...)
     pub codec: String,
     pub profile: Option<String>,
     pub width: Option<u32>,
     pub height: Option<u32>,
     pub channels: Option<u32>,
     pub sample_rate: Option<u32>,
+    /// ISO 639 code from the stream metadata
+    pub language: Option<String>,
+    pub title: Option<String>,
+    /// Disposition flags in lowercase (`default`, `forced`, `hearing_impaired`, `attached_pic`, ...)
+    pub disposition: Vec<String>,
+    pub bit_rate: Option<u64>,
+    /// Bits per sample (audio) or per component (video)
+    pub bit_depth: Option<u32>,
+    /// Average frame rate in frames per second
+    pub frame_rate: Option<f64>,
+    pub pixel_format: Option<String>,
+    pub color: Option<ColorInfo>,
+    pub hdr: Option<HdrInfo>,
+}
+
+impl StreamInfo {
+    pub fn has_disposition(&self, flag: &str) -> bool {
+        self.disposition.iter().any(|d| d == flag)
+    }
+}
+
+/// FFmpeg names of the color properties (`bt709`, `smpte2084`, `tv`, ...)
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ColorInfo {
+    pub space: Option<String>,
+    pub primaries: Option<String>,
+    pub transfer: Option<String>,
+    pub range: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HdrFormat {
+    /// PQ (SMPTE ST 2084) transfer
+    Hdr10,
+    Hlg,
+    DolbyVision,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HdrInfo {
+    pub format: HdrFormat,
+    /// Content light level, cd/m²
+    #[serde(default)]
+    pub max_cll: Option<u32>,
+    #[serde(default)]
+    pub max_fall: Option<u32>,
+    /// Mastering display luminance range, cd/m²
+    #[serde(default)]
+    pub mastering_min_luminance: Option<f64>,
+    #[serde(default)]
+    pub mastering_max_luminance: Option<f64>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Chapter {
+    pub id: i64,
+    pub start_ms: i64,
+    pub end_ms: i64,
+    pub title: Option<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Within this distance of a chapter start, "previous" goes to the chapter before
+/// instead of back to the start of the current one
+const CHAPTER_RESTART_MS: i64 = 3000;
+
+impl Chapter {
+    /// Index of the chapter playing at `pos_ms`; `chapters` sorted by start
+    pub fn index_at(chapters: &[Chapter], pos_ms: i64) -> Option<usize> {
+        chapters.iter().rposition(|c| c.start_ms <= pos_ms)
+    }
+
+    /// Start of the first chapter after `pos_ms`
+    pub fn next_start(chapters: &[Chapter], pos_ms: i64) -> Option<i64> {
+        chapters.iter().map(|c| c.start_ms).find(|&start| start > pos_ms)
+    }
+
+    /// Start of the current chapter, or of the one before when `pos_ms` is
+    /// close to the current chapter's start (like a CD player's ⏮)
+    pub fn previous_start(chapters: &[Chapter], pos_ms: i64) -> Option<i64> {
+        let Some(i) = Self::index_at(chapters, pos_ms) else { return chapters.first().map(|c| c.start_ms) };
+        if i > 0 && pos_ms - chapters[i].start_ms < CHAPTER_RESTART_MS {
+            Some(chapters[i - 1].start_ms)
+        } else {
+            Some(chapters[i].start_ms)
+        }
+    }
+}
+
+/// Attached file (Matroska attachments: fonts for ASS subtitles, cover images)
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Attachment {
+    /// Stream index of the attachment
+    pub index: u32,
+    pub filename: Option<String>,
+    pub mime_type: Option<String>,
+    pub size: u64,
+}
+
+impl Attachment {
+    pub fn is_font(&self) -> bool {
+        let mime = self.mime_type.as_deref().unwrap_or("").to_ascii_lowercase();
+        let name = self.filename.as_deref().unwrap_or("").to_ascii_lowercase();
+        mime.contains("font") || mime.contains("truetype") || mime.contains("opentype")
+            || [".ttf", ".otf", ".ttc", ".woff", ".woff2"].iter().any(|ext| name.ends_with(ext))
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
 pub struct MediaInfo {
     pub url: String,
+    /// Container short name(s) as reported by FFmpeg (`matroska,webm`, `mov,mp4,m4a,3gp,3g2,mj2`, ...)
+    pub format: Option<String>,
+    pub format_long_name: Option<String>,
     pub duration_ms: Option<u64>,
     pub bit_rate: Option<u64>,
     pub streams: Vec<StreamInfo>,
+    pub chapters: Vec<Chapter>,
+    pub attachments: Vec<Attachment>,
+    /// Container tags: music fields (title / artist / album / track / ReplayGain) and the rest in `extra`
+    #[serde(skip_serializing_if = "MediaTags::is_empty")]
+    pub tags: MediaTags,
+}
+
+impl MediaInfo {
+    /// Fonts embedded in the container, for rendering ASS subtitles
+    pub fn fonts(&self) -> impl Iterator<Item = &Attachment> {
+        self.attachments.iter().filter(|a| a.is_font())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum ProbeError {
+    #[error("not found: {0}")]
+    NotFound(String),
+    #[error("unsupported format: {0}")]
+    UnsupportedFormat(String),
+    #[error("network error: {0}")]
+    Network(String),
+    #[error("probe timed out after {0} ms")]
+    Timeout(u32),
+    #[error("built without FFmpeg, probing is unavailable")]
+    FeatureDisabled,
+    #[error("probe failed: {0}")]
+    Other(String),
+}
+
+/// Options for opening the input. Unset fields use FFmpeg's defaults.
+#[derive(Debug, Clone, Default)]
+pub struct ProbeOptions {
+    /// Deadline for opening and analysing the input
+    pub timeout_ms: Option<u32>,
+    /// How much media FFmpeg reads to detect stream parameters (`analyzeduration`)
+    pub analyze_duration_ms: Option<u32>,
+    /// How many bytes FFmpeg reads to detect the format (`probesize`)
+    pub probe_size: Option<u64>,
+    pub user_agent: Option<String>,
+    /// Extra HTTP request headers, e.g. `("X-Emby-Token", token)`
+    pub headers: Vec<(String, String)>,
+}
+
+impl ProbeOptions {
+    /// Defaults for interactive use: give up on unresponsive inputs after 10 s
+    pub fn interactive() -> Self {
+        Self { timeout_ms: Some(10_000), ..Self::default() }
+    }
+}
+
+pub fn probe(url: &str) -> Result<MediaInfo, ProbeError> {
+    probe_with(url, &ProbeOptions::default())
+}
+
+#[cfg(not(feature = "ffmpeg"))]
+pub fn probe_with(_url: &str, _opts: &ProbeOptions) -> Result<MediaInfo, ProbeError> {
+    Err(ProbeError::FeatureDisabled)
+}
+
+#[cfg(feature = "ffmpeg")]
+pub fn probe_with(url: &str, opts: &ProbeOptions) -> Result<MediaInfo, ProbeError> {
+    // Initialize FFmpeg once; ignore repeated init errors
+    let _ = ffmpeg_next::init();
+
+    let ictx = inspect::open(url, opts)?;
+    Ok(inspect::media_info(url, &ictx))
+}
+
+/// Codecs the local build can decode, named like FFmpeg codec ids
+/// (`h264`, `hevc`, `aac`, ...). Used to build server device profiles.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DecoderCapabilities {
+    pub video: Vec<String>,
+    pub audio: Vec<String>,
+    pub subtitle: Vec<String>,
+}
+
+impl DecoderCapabilities {
+    pub fn can_decode_video(&self, codec: &str) -> bool {
+        self.video.iter().any(|c| c.eq_ignore_ascii_case(codec))
+    }
+
+    pub fn can_decode_audio(&self, codec: &str) -> bool {
+        self.audio.iter().any(|c| c.eq_ignore_ascii_case(codec))
+    }
 }
 
 #[cfg(not(feature = "ffmpeg"))]
-pub fn probe(url: &str) -> MediaInfo {
-    // Placeholder: echo back URL and return an empty stream list.
-    MediaInfo {
-        url: url.to_string(),
-        duration_ms: None,
-        bit_rate: None,
-        streams: vec![],
+pub fn decoder_capabilities() -> DecoderCapabilities {
+    // Without FFmpeg we can't enumerate; assume a stock FFmpeg build (as bundled with mpv).
+    let list = |names: &[&str]| names.iter().map(|s| s.to_string()).collect();
+    DecoderCapabilities {
+        video: list(&["h264", "hevc", "vp8", "vp9", "av1", "mpeg4", "mpeg2video", "mpeg1video", "vc1", "wmv3", "h263", "theora", "mjpeg"]),
+        audio: list(&["aac", "mp3", "mp2", "ac3", "eac3", "dts", "truehd", "flac", "alac", "opus", "vorbis", "wmav2", "pcm_s16le", "pcm_s24le"]),
+        subtitle: list(&["subrip", "ass", "ssa", "webvtt", "mov_text", "hdmv_pgs_subtitle", "dvd_subtitle", "dvb_subtitle"]),
     }
 }
 
 #[cfg(feature = "ffmpeg")]
-pub fn probe(url: &str) -> MediaInfo {
+pub fn decoder_capabilities() -> DecoderCapabilities {
     use ffmpeg_next as ffmpeg;
-    // Initialize FFmpeg once; ignore repeated init errors
-    let _ = ffmpeg::init();
+    use ffmpeg::ffi::{av_codec_is_decoder, av_codec_iterate, avcodec_get_name, AVMediaType};
+    use std::ffi::CStr;
 
-    let ictx = match ffmpeg::format::input(&url) {
-        Ok(ctx) => ctx,
-        Err(_) => {
-            return MediaInfo { url: url.to_string(), duration_ms: None, bit_rate: None, streams: vec![] };
+    let _ = ffmpeg::init();
+    let mut caps = DecoderCapabilities::default();
+    let mut opaque = std::ptr::null_mut();
+    loop {
+        let codec = unsafe { av_codec_iterate(&mut opaque) };
+        if codec.is_null() {
+            break;
         }
-    };
-
-    // ffmpeg-next v7: duration()/bit_rate() return i64; use >0 guard and assume milliseconds for duration
-    let d = ictx.duration();
-    let duration_ms = if d > 0 { Some(d as u64) } else { None };
-    let b = ictx.bit_rate();
-    let bit_rate = if b > 0 { Some(b as u64) } else { None };
-
-    let mut streams = Vec::new();
-    for (idx, st) in ictx.streams().enumerate() {
-        let codec_params = st.parameters();
-        let codec_id = codec_params.id();
-        let codec_name = format!("{:?}", codec_id);
-        let kind = match st.parameters().medium() {
-            ffmpeg::media::Type::Video => "video",
-            ffmpeg::media::Type::Audio => "audio",
-            ffmpeg::media::Type::Subtitle => "subtitle",
-            _ => "other",
-        }.to_string();
-        let mut sinfo = StreamInfo {
-            index: idx as u32,
-            kind,
-            codec: codec_name,
-            profile: None,
-            width: None,
-            height: None,
-            channels: None,
-            sample_rate: None,
-        };
-
-        // 通过 decoder context 读取参数（v7 风格）
-        if let Ok(ctx) = ffmpeg::codec::context::Context::from_parameters(codec_params) {
-            match st.parameters().medium() {
-                ffmpeg::media::Type::Video => {
-                    if let Ok(vd) = ctx.decoder().video() {
-                        sinfo.width = Some(vd.width());
-                        sinfo.height = Some(vd.height());
-                    }
-                }
-                ffmpeg::media::Type::Audio => {
-                    if let Ok(ad) = ctx.decoder().audio() {
-                        sinfo.channels = Some(ad.channels() as u32);
-                        sinfo.sample_rate = Some(ad.rate() as u32);
-                    }
-                }
-                _ => {}
+        unsafe {
+            if av_codec_is_decoder(codec) == 0 {
+                continue;
+            }
+            // Several decoders can share one id (h264 / h264_cuvid / ...); report the id name
+            let name = CStr::from_ptr(avcodec_get_name((*codec).id)).to_string_lossy().into_owned();
+            let list = match (*codec).type_ {
+                AVMediaType::AVMEDIA_TYPE_VIDEO => &mut caps.video,
+                AVMediaType::AVMEDIA_TYPE_AUDIO => &mut caps.audio,
+                AVMediaType::AVMEDIA_TYPE_SUBTITLE => &mut caps.subtitle,
+                _ => continue,
+            };
+            if !list.contains(&name) {
+                list.push(name);
             }
         }
-
-        streams.push(sinfo);
     }
-
-    MediaInfo { url: url.to_string(), duration_ms, bit_rate, streams }
+    caps
 }
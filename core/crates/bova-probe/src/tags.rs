@@ -0,0 +1,143 @@
+//! Music tags and ReplayGain read from container / stream metadata.
+//!
+//! 键名不区分大小写，兼容 ID3（经 FFmpeg 转换后的名称）、Vorbis comment、
+//! APE 与 MP4 常见写法。Opus 的 R128_*_GAIN 换算到 ReplayGain 的 -18 LUFS 参考电平。
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MediaTags {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub artist: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub album: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub album_artist: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub genre: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub date: Option<String>,
+    /// 音轨号（"3/12" 中的 3）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub track: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub track_total: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub disc: Option<u32>,
+    #[serde(skip_serializing_if = "ReplayGain::is_empty")]
+    pub replay_gain: ReplayGain,
+    /// 未映射到上面字段的其他标签（encoder、comment、copyright ...），键名小写
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub extra: BTreeMap<String, String>,
+}
+
+/// ReplayGain 增益（dB）与峰值（线性，1.0 = 满幅）
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ReplayGain {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub track_gain_db: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub track_peak: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub album_gain_db: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub album_peak: Option<f32>,
+}
+
+impl ReplayGain {
+    pub fn is_empty(&self) -> bool {
+        self.track_gain_db.is_none() && self.album_gain_db.is_none()
+    }
+
+    /// 播放时的线性增益；`prefer_album` 时优先专辑增益，缺失时退回另一种。
+    /// 有峰值信息时限制增益，避免削波
+    pub fn linear_gain(&self, prefer_album: bool) -> Option<f32> {
+        let track = self.track_gain_db.map(|g| (g, self.track_peak));
+        let album = self.album_gain_db.map(|g| (g, self.album_peak));
+        let (gain_db, peak) = if prefer_album { album.or(track) } else { track.or(album) }?;
+        let mut gain = 10f32.powf(gain_db / 20.0);
+        if let Some(peak) = peak.filter(|p| *p > 0.0) {
+            gain = gain.min(1.0 / peak);
+        }
+        Some(gain)
+    }
+}
+
+impl MediaTags {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// 从元数据键值对读取；同一字段出现多次时以先出现的为准
+    /// （调用方先传容器级元数据，再传音频流的）
+    pub fn from_metadata<'a>(entries: impl IntoIterator<Item = (&'a str, &'a str)>) -> Self {
+        let mut tags = Self::default();
+        for (key, value) in entries {
+            let value = value.trim();
+            if value.is_empty() { continue; }
+            let text = || Some(value.to_string());
+            let key = key.to_ascii_lowercase();
+            match key.as_str() {
+                "title" => set(&mut tags.title, text()),
+                "artist" | "author" => set(&mut tags.artist, text()),
+                "album" => set(&mut tags.album, text()),
+                "album_artist" | "albumartist" | "album artist" => set(&mut tags.album_artist, text()),
+                "genre" => set(&mut tags.genre, text()),
+                "date" | "year" => set(&mut tags.date, text()),
+                "track" | "tracknumber" => {
+                    let (n, total) = value.split_once('/').unwrap_or((value, ""));
+                    set(&mut tags.track, n.trim().parse().ok());
+                    set(&mut tags.track_total, total.trim().parse().ok());
+                }
+                "tracktotal" | "totaltracks" => set(&mut tags.track_total, value.parse().ok()),
+                "disc" | "discnumber" => {
+                    let n = value.split('/').next().unwrap_or(value);
+                    set(&mut tags.disc, n.trim().parse().ok());
+                }
+                "replaygain_track_gain" => set(&mut tags.replay_gain.track_gain_db, parse_db(value)),
+                "replaygain_track_peak" => set(&mut tags.replay_gain.track_peak, value.parse().ok()),
+                "replaygain_album_gain" => set(&mut tags.replay_gain.album_gain_db, parse_db(value)),
+                "replaygain_album_peak" => set(&mut tags.replay_gain.album_peak, value.parse().ok()),
+                "r128_track_gain" => set(&mut tags.replay_gain.track_gain_db, parse_r128(value)),
+                "r128_album_gain" => set(&mut tags.replay_gain.album_gain_db, parse_r128(value)),
+                _ => {
+                    tags.extra.entry(key).or_insert_with(|| value.to_string());
+                }
+            }
+        }
+        tags
+    }
+
+    /// "艺术家 - 标题"，缺少标题时返回 None
+    pub fn display_title(&self) -> Option<String> {
+        let title = self.title.as_deref()?;
+        Some(match self.artist.as_deref() {
+            Some(artist) => format!("{} - {}", artist, title),
+            None => title.to_string(),
+        })
+    }
+}
+
+fn set<T>(field: &mut Option<T>, value: Option<T>) {
+    if field.is_none() {
+        *field = value;
+    }
+}
+
+/// "-6.48 dB" / "+1.2dB"
+fn parse_db(value: &str) -> Option<f32> {
+    let number = value.trim_end_matches(|c: char| c.is_ascii_alphabetic() || c.is_whitespace());
+    number.trim_start_matches('+').parse().ok()
+}
+
+/// Opus R128 增益：Q7.8 定点数，参考 -23 LUFS；ReplayGain 参考约 -18 LUFS，相差 5 dB
+fn parse_r128(value: &str) -> Option<f32> {
+    let q78: i32 = value.parse().ok()?;
+    Some(q78 as f32 / 256.0 + 5.0)
+}
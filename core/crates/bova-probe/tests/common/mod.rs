@@ -0,0 +1,282 @@
+//! Tiny synthetic media files for the probe tests, built byte by byte so the
+//! tests don't need an encoder.
+//!
+//! Both files hold one 16×16 raw video frame tagged as BT.2020 / PQ (HDR10)
+//! with mastering display and content light level metadata, two chapters and
+//! container tags. The expected values are the constants below.
+
+#![allow(dead_code)]
+
+use std::path::PathBuf;
+
+pub const DURATION_MS: u64 = 2000;
+pub const CHAPTERS: [(i64, &str); 2] = [(0, "Intro"), (1000, "Main")];
+pub const TITLE: &str = "Test Pattern";
+pub const ARTIST: &str = "Bova";
+pub const COMMENT: &str = "synthetic";
+pub const MAX_CLL: u32 = 1000;
+pub const MAX_FALL: u32 = 400;
+/// Mastering display luminance, cd/m²
+pub const MASTERING_MIN: f64 = 0.005;
+pub const MASTERING_MAX: f64 = 1000.0;
+/// Matroska chapter UIDs, reported as the chapter ids
+pub const MKV_CHAPTER_UIDS: [u64; 2] = [11, 12];
+
+const WIDTH: u16 = 16;
+const HEIGHT: u16 = 16;
+/// BT.2020 primaries (R, G, B) and D65 white point as CIE xy
+const PRIMARIES: [(f64, f64); 3] = [(0.708, 0.292), (0.170, 0.797), (0.131, 0.046)];
+const WHITE_POINT: (f64, f64) = (0.3127, 0.3290);
+/// ISO/IEC 23091-2 code points: BT.2020 primaries, PQ transfer, BT.2020 non-constant matrix
+const COLOR_PRIMARIES: u16 = 9;
+const TRANSFER_PQ: u16 = 16;
+const MATRIX: u16 = 9;
+
+/// Write `data` to a fresh file in the temp directory and return its path.
+pub fn write(name: &str, data: &[u8]) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("bova-probe-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join(name);
+    std::fs::write(&path, data).unwrap();
+    path
+}
+
+// ---- Matroska ----
+
+/// EBML element with an 8-byte size field
+fn el(id: &[u8], payload: &[u8]) -> Vec<u8> {
+    let mut out = id.to_vec();
+    out.push(0x01);
+    out.extend_from_slice(&(payload.len() as u64).to_be_bytes()[1..]);
+    out.extend_from_slice(payload);
+    out
+}
+
+fn uint(id: &[u8], value: u64) -> Vec<u8> {
+    el(id, &value.to_be_bytes())
+}
+
+fn float(id: &[u8], value: f64) -> Vec<u8> {
+    el(id, &value.to_be_bytes())
+}
+
+fn string(id: &[u8], value: &str) -> Vec<u8> {
+    el(id, value.as_bytes())
+}
+
+fn simple_tag(name: &str, value: &str) -> Vec<u8> {
+    el(&[0x67, 0xC8], &[string(&[0x45, 0xA3], name), string(&[0x44, 0x87], value)].concat())
+}
+
+pub fn matroska() -> Vec<u8> {
+    let header = el(&[0x1A, 0x45, 0xDF, 0xA3], &[
+        uint(&[0x42, 0x86], 1),
+        uint(&[0x42, 0xF7], 1),
+        uint(&[0x42, 0xF2], 4),
+        uint(&[0x42, 0xF3], 8),
+        string(&[0x42, 0x82], "matroska"),
+        uint(&[0x42, 0x87], 4),
+        uint(&[0x42, 0x85], 2),
+    ].concat());
+
+    // TimestampScale 1 ms, so Duration is in milliseconds
+    let info = el(&[0x15, 0x49, 0xA9, 0x66], &[
+        uint(&[0x2A, 0xD7, 0xB1], 1_000_000),
+        string(&[0x4D, 0x80], "bova-probe tests"),
+        string(&[0x57, 0x41], "bova-probe tests"),
+        float(&[0x44, 0x89], DURATION_MS as f64),
+    ].concat());
+
+    let [r, g, b] = PRIMARIES;
+    let mastering = el(&[0x55, 0xD0], &[
+        float(&[0x55, 0xD1], r.0),
+        float(&[0x55, 0xD2], r.1),
+        float(&[0x55, 0xD3], g.0),
+        float(&[0x55, 0xD4], g.1),
+        float(&[0x55, 0xD5], b.0),
+        float(&[0x55, 0xD6], b.1),
+        float(&[0x55, 0xD7], WHITE_POINT.0),
+        float(&[0x55, 0xD8], WHITE_POINT.1),
+        float(&[0x55, 0xD9], MASTERING_MAX),
+        float(&[0x55, 0xDA], MASTERING_MIN),
+    ].concat());
+    let colour = el(&[0x55, 0xB0], &[
+        uint(&[0x55, 0xB1], MATRIX.into()),
+        // 1 = broadcast (limited) range
+        uint(&[0x55, 0xB9], 1),
+        uint(&[0x55, 0xBA], TRANSFER_PQ.into()),
+        uint(&[0x55, 0xBB], COLOR_PRIMARIES.into()),
+        uint(&[0x55, 0xBC], MAX_CLL.into()),
+        uint(&[0x55, 0xBD], MAX_FALL.into()),
+        mastering,
+    ].concat());
+    let video = el(&[0xE0], &[
+        uint(&[0xB0], WIDTH.into()),
+        uint(&[0xBA], HEIGHT.into()),
+        // FourCC of V_UNCOMPRESSED frames
+        el(&[0x2E, 0xB5, 0x24], b"I420"),
+        colour,
+    ].concat());
+    let tracks = el(&[0x16, 0x54, 0xAE, 0x6B], &el(&[0xAE], &[
+        uint(&[0xD7], 1),
+        uint(&[0x73, 0xC5], 1),
+        // video
+        uint(&[0x83], 1),
+        string(&[0x86], "V_UNCOMPRESSED"),
+        // 25 fps, in ns
+        uint(&[0x23, 0xE3, 0x83], 40_000_000),
+        video,
+    ].concat()));
+
+    let atoms: Vec<u8> = CHAPTERS.iter().enumerate().flat_map(|(i, &(start_ms, title))| {
+        let end_ms = CHAPTERS.get(i + 1).map_or(DURATION_MS as i64, |c| c.0);
+        el(&[0xB6], &[
+            uint(&[0x73, 0xC4], MKV_CHAPTER_UIDS[i]),
+            uint(&[0x91], start_ms as u64 * 1_000_000),
+            uint(&[0x92], end_ms as u64 * 1_000_000),
+            el(&[0x80], &[string(&[0x85], title), string(&[0x43, 0x7C], "eng")].concat()),
+        ].concat())
+    }).collect();
+    let chapters = el(&[0x10, 0x43, 0xA7, 0x70], &el(&[0x45, 0xB9], &atoms));
+
+    let tags = el(&[0x12, 0x54, 0xC3, 0x67], &el(&[0x73, 0x73], &[
+        // 50 = album / movie level: applies to the whole file
+        el(&[0x63, 0xC0], &uint(&[0x68, 0xCA], 50)),
+        simple_tag("TITLE", TITLE),
+        simple_tag("ARTIST", ARTIST),
+        simple_tag("COMMENT", COMMENT),
+    ].concat()));
+
+    // one keyframe at timestamp 0 on track 1
+    let mut block = vec![0x81, 0x00, 0x00, 0x80];
+    block.extend(frame(usize::from(WIDTH) * usize::from(HEIGHT) * 3 / 2));
+    let cluster = el(&[0x1F, 0x43, 0xB6, 0x75], &[uint(&[0xE7], 0), el(&[0xA3], &block)].concat());
+
+    let segment = el(&[0x18, 0x53, 0x80, 0x67], &[info, tracks, chapters, tags, cluster].concat());
+    [header, segment].concat()
+}
+
+// ---- MP4 ----
+
+fn atom(kind: &[u8; 4], payload: &[u8]) -> Vec<u8> {
+    let mut out = (8 + payload.len() as u32).to_be_bytes().to_vec();
+    out.extend_from_slice(kind);
+    out.extend_from_slice(payload);
+    out
+}
+
+/// Box with version 0 and the given flags
+fn full_atom(kind: &[u8; 4], flags: u32, payload: &[u8]) -> Vec<u8> {
+    atom(kind, &[&flags.to_be_bytes()[..], payload].concat())
+}
+
+const MATRIX_UNITY: [u32; 9] = [0x0001_0000, 0, 0, 0, 0x0001_0000, 0, 0, 0, 0x4000_0000];
+
+fn be32(values: &[u32]) -> Vec<u8> {
+    values.iter().flat_map(|v| v.to_be_bytes()).collect()
+}
+
+fn itunes_text(kind: &[u8; 4], value: &str) -> Vec<u8> {
+    // data type 1 = UTF-8, then the locale
+    atom(kind, &atom(b"data", &[&be32(&[1, 0])[..], value.as_bytes()].concat()))
+}
+
+pub fn mp4() -> Vec<u8> {
+    const TIMESCALE: u32 = 1000;
+    let duration = DURATION_MS as u32;
+    let sample = frame(usize::from(WIDTH) * usize::from(HEIGHT) * 3);
+
+    let ftyp = atom(b"ftyp", &[&b"isom"[..], &be32(&[0x200]), b"isommp41"].concat());
+    let mdat = atom(b"mdat", &sample);
+    // the sample follows the mdat header
+    let sample_offset = (ftyp.len() + 8) as u32;
+
+    let mvhd = full_atom(b"mvhd", 0, &[
+        be32(&[0, 0, TIMESCALE, duration, 0x0001_0000]),
+        vec![0x01, 0x00],
+        vec![0; 10],
+        be32(&MATRIX_UNITY),
+        vec![0; 24],
+        be32(&[2]),
+    ].concat());
+    let tkhd = full_atom(b"tkhd", 3, &[
+        be32(&[0, 0, 1, 0, duration, 0, 0]),
+        // layer, alternate group, volume, reserved
+        vec![0; 8],
+        be32(&MATRIX_UNITY),
+        be32(&[u32::from(WIDTH) << 16, u32::from(HEIGHT) << 16]),
+    ].concat());
+    // language "und", packed ISO 639-2
+    let mdhd = full_atom(b"mdhd", 0, &[be32(&[0, 0, TIMESCALE, duration]), vec![0x55, 0xC4, 0, 0]].concat());
+    let hdlr = full_atom(b"hdlr", 0, &[&be32(&[0])[..], b"vide", &be32(&[0, 0, 0]), b"Video\0"].concat());
+
+    let [r, g, b] = PRIMARIES;
+    // ST 2086 order (G, B, R, white point) in 0.00002 units, luminance in 0.0001 cd/m²
+    let chroma = |v: f64| ((v / 0.00002).round() as u16).to_be_bytes();
+    let mdcv = atom(b"mdcv", &[
+        [g, b, r, WHITE_POINT].iter().flat_map(|&(x, y)| [chroma(x), chroma(y)].concat()).collect(),
+        be32(&[(MASTERING_MAX * 10_000.0) as u32, (MASTERING_MIN * 10_000.0) as u32]),
+    ].concat());
+    let clli = atom(b"clli", &[(MAX_CLL as u16).to_be_bytes(), (MAX_FALL as u16).to_be_bytes()].concat());
+    // nclx without the full range flag: limited range
+    let colr = atom(b"colr", &[
+        &b"nclx"[..],
+        &COLOR_PRIMARIES.to_be_bytes(),
+        &TRANSFER_PQ.to_be_bytes(),
+        &MATRIX.to_be_bytes(),
+        &[0],
+    ].concat());
+    // uncompressed 24-bit RGB
+    let mut compressor = [0u8; 32];
+    compressor[0] = 3;
+    compressor[1..4].copy_from_slice(b"raw");
+    let sample_entry = atom(b"raw ", &[
+        vec![0; 6],
+        vec![0, 1],
+        vec![0; 16],
+        [WIDTH.to_be_bytes(), HEIGHT.to_be_bytes()].concat(),
+        be32(&[0x0048_0000, 0x0048_0000, 0]),
+        vec![0, 1],
+        compressor.to_vec(),
+        vec![0, 24, 0xFF, 0xFF],
+        colr,
+        mdcv,
+        clli,
+    ].concat());
+    let stbl = atom(b"stbl", &[
+        full_atom(b"stsd", 0, &[be32(&[1]), sample_entry].concat()),
+        full_atom(b"stts", 0, &be32(&[1, 1, duration])),
+        full_atom(b"stsc", 0, &be32(&[1, 1, 1, 1])),
+        full_atom(b"stsz", 0, &be32(&[sample.len() as u32, 1])),
+        full_atom(b"stco", 0, &be32(&[1, sample_offset])),
+    ].concat());
+    let dinf = atom(b"dinf", &full_atom(b"dref", 0, &[be32(&[1]), full_atom(b"url ", 1, &[])].concat()));
+    let minf = atom(b"minf", &[full_atom(b"vmhd", 1, &[0; 8]), dinf, stbl].concat());
+    let trak = atom(b"trak", &[tkhd, atom(b"mdia", &[mdhd, hdlr, minf].concat())].concat());
+
+    // Nero chapters, start times in 100 ns units
+    let mut chpl = vec![0, 0, 0, 0, CHAPTERS.len() as u8];
+    for (start_ms, title) in CHAPTERS {
+        chpl.extend((start_ms as u64 * 10_000).to_be_bytes());
+        chpl.push(title.len() as u8);
+        chpl.extend(title.as_bytes());
+    }
+    let ilst = atom(b"ilst", &[
+        itunes_text(b"\xA9nam", TITLE),
+        itunes_text(b"\xA9ART", ARTIST),
+        itunes_text(b"\xA9cmt", COMMENT),
+    ].concat());
+    let meta = full_atom(b"meta", 0, &[
+        full_atom(b"hdlr", 0, &[&be32(&[0])[..], b"mdir", b"appl", &be32(&[0, 0]), b"\0"].concat()),
+        ilst,
+    ].concat());
+    let udta = atom(b"udta", &[atom(b"chpl", &chpl), meta].concat());
+
+    let moov = atom(b"moov", &[mvhd, trak, udta].concat());
+    [ftyp, mdat, moov].concat()
+}
+
+/// Mid-grey frame of `len` bytes
+fn frame(len: usize) -> Vec<u8> {
+    vec![0x80; len]
+}
@@ -0,0 +1,90 @@
+//! Probe output for the synthetic Matroska and MP4 files from `common`:
+//! container, HDR10 video stream, chapters and tags.
+
+#![cfg(feature = "ffmpeg")]
+
+mod common;
+
+use bova_probe::{probe, Chapter, HdrFormat, MediaInfo};
+use common::{
+    matroska, mp4, write, ARTIST, CHAPTERS, COMMENT, DURATION_MS, MASTERING_MAX, MASTERING_MIN, MAX_CLL, MAX_FALL,
+    MKV_CHAPTER_UIDS, TITLE,
+};
+
+fn probe_file(name: &str, data: &[u8]) -> MediaInfo {
+    let path = write(name, data);
+    probe(path.to_str().unwrap()).unwrap()
+}
+
+fn assert_hdr10_video(info: &MediaInfo) {
+    assert_eq!(info.streams.len(), 1);
+    let video = &info.streams[0];
+    assert_eq!((video.kind.as_str(), video.codec.as_str()), ("video", "rawvideo"));
+    assert_eq!((video.width, video.height), (Some(16), Some(16)));
+
+    let color = video.color.as_ref().expect("color info");
+    assert_eq!(color.primaries.as_deref(), Some("bt2020"));
+    assert_eq!(color.transfer.as_deref(), Some("smpte2084"));
+    assert_eq!(color.space.as_deref(), Some("bt2020nc"));
+    assert_eq!(color.range.as_deref(), Some("tv"));
+
+    let hdr = video.hdr.as_ref().expect("hdr info");
+    assert_eq!(hdr.format, HdrFormat::Hdr10);
+    assert_eq!((hdr.max_cll, hdr.max_fall), (Some(MAX_CLL), Some(MAX_FALL)));
+    let min = hdr.mastering_min_luminance.unwrap();
+    let max = hdr.mastering_max_luminance.unwrap();
+    assert!((min - MASTERING_MIN).abs() < 1e-6, "{min}");
+    assert!((max - MASTERING_MAX).abs() < 1e-6, "{max}");
+}
+
+fn assert_chapters(chapters: &[Chapter], ids: [i64; 2]) {
+    let got: Vec<_> = chapters.iter().map(|c| (c.id, c.start_ms, c.end_ms, c.title.as_deref())).collect();
+    assert_eq!(got, [
+        (ids[0], CHAPTERS[0].0, CHAPTERS[1].0, Some(CHAPTERS[0].1)),
+        (ids[1], CHAPTERS[1].0, DURATION_MS as i64, Some(CHAPTERS[1].1)),
+    ]);
+}
+
+fn assert_tags(info: &MediaInfo) {
+    assert_eq!(info.tags.title.as_deref(), Some(TITLE));
+    assert_eq!(info.tags.artist.as_deref(), Some(ARTIST));
+    assert_eq!(info.tags.extra.get("comment").map(String::as_str), Some(COMMENT));
+}
+
+#[test]
+fn matroska_chapters_tags_and_hdr() {
+    let info = probe_file("hdr10.mkv", &matroska());
+
+    assert_eq!(info.format.as_deref(), Some("matroska,webm"));
+    assert_eq!(info.duration_ms, Some(DURATION_MS));
+    assert_hdr10_video(&info);
+    let video = &info.streams[0];
+    assert_eq!(video.pixel_format.as_deref(), Some("yuv420p"));
+    assert_eq!(video.frame_rate, Some(25.0));
+    assert_chapters(&info.chapters, MKV_CHAPTER_UIDS.map(|uid| uid as i64));
+    assert_tags(&info);
+    assert!(info.attachments.is_empty());
+}
+
+#[test]
+fn mp4_chapters_tags_and_hdr() {
+    let info = probe_file("hdr10.mp4", &mp4());
+
+    assert_eq!(info.format.as_deref(), Some("mov,mp4,m4a,3gp,3g2,mj2"));
+    assert_eq!(info.duration_ms, Some(DURATION_MS));
+    assert_hdr10_video(&info);
+    assert_eq!(info.streams[0].pixel_format.as_deref(), Some("rgb24"));
+    // Nero chapters are numbered from 0; the end comes from the next start / the duration
+    assert_chapters(&info.chapters, [0, 1]);
+    assert_tags(&info);
+}
+
+#[test]
+fn probe_output_round_trips_through_json() {
+    let info = probe_file("hdr10-json.mkv", &matroska());
+    let json = serde_json::to_value(&info).unwrap();
+    assert_eq!(json["streams"][0]["hdr"]["format"], "hdr10");
+    assert_eq!(json["tags"]["title"], TITLE);
+    let back: MediaInfo = serde_json::from_value(json).unwrap();
+    assert_eq!(back.chapters, info.chapters);
+}
@@ -0,0 +1,321 @@
+//! Minimal FTP client (passive mode, binary transfers): MLSD/LIST listing and
+//! REST+RETR streaming.
+
+use std::io::{self, BufRead, BufReader, Read, SeekFrom, Write};
+use std::net::{SocketAddr, TcpStream, ToSocketAddrs};
+use std::time::Duration;
+
+use bova_playback::MediaSource;
+
+use crate::{decode_path, join_path, sort_entries, RemoteConnection, RemoteEntry, RemoteError, RemoteFs};
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+const IO_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Host and port of an `ftp://` URL (default port 21).
+fn ftp_addr(url: &str) -> Result<(String, u16), RemoteError> {
+    let u = reqwest::Url::parse(url).map_err(|e| RemoteError::InvalidUrl(format!("{url}: {e}")))?;
+    let host = u.host_str().ok_or_else(|| RemoteError::InvalidUrl(url.to_string()))?.to_string();
+    Ok((host, u.port().unwrap_or(21)))
+}
+
+fn open_stream(host: &str, port: u16) -> Result<TcpStream, RemoteError> {
+    let addrs: Vec<SocketAddr> = (host, port).to_socket_addrs()?.collect();
+    let mut last_err = io::Error::new(io::ErrorKind::NotFound, format!("cannot resolve {host}"));
+    for addr in addrs {
+        match TcpStream::connect_timeout(&addr, CONNECT_TIMEOUT) {
+            Ok(s) => {
+                s.set_read_timeout(Some(IO_TIMEOUT))?;
+                s.set_write_timeout(Some(IO_TIMEOUT))?;
+                return Ok(s);
+            }
+            Err(e) => last_err = e,
+        }
+    }
+    Err(last_err.into())
+}
+
+pub struct FtpClient {
+    ctrl: BufReader<TcpStream>,
+}
+
+impl FtpClient {
+    /// Connect, log in (anonymous when no username) and switch to binary mode.
+    pub fn connect(conn: &RemoteConnection) -> Result<Self, RemoteError> {
+        let (host, port) = ftp_addr(&conn.url)?;
+        let mut client = Self { ctrl: BufReader::new(open_stream(&host, port)?) };
+        client.expect(&[220])?;
+
+        let user = conn.username.as_deref().filter(|u| !u.is_empty()).unwrap_or("anonymous");
+        match client.command(&format!("USER {user}"))? {
+            (230, _) => {}
+            (331, _) | (332, _) => {
+                let pass = conn.password.as_deref().unwrap_or("");
+                match client.command(&format!("PASS {pass}"))? {
+                    (230, _) | (202, _) => {}
+                    (530, _) => return Err(RemoteError::Auth),
+                    (code, msg) => return Err(RemoteError::Protocol(format!("PASS: {code} {msg}"))),
+                }
+            }
+            (530, _) => return Err(RemoteError::Auth),
+            (code, msg) => return Err(RemoteError::Protocol(format!("USER: {code} {msg}"))),
+        }
+        client.checked("TYPE I", &[200])?;
+        Ok(client)
+    }
+
+    /// Send one command and read its (possibly multi-line) reply.
+    fn command(&mut self, cmd: &str) -> Result<(u32, String), RemoteError> {
+        let stream = self.ctrl.get_mut();
+        stream.write_all(cmd.as_bytes())?;
+        stream.write_all(b"\r\n")?;
+        self.read_reply()
+    }
+
+    fn checked(&mut self, cmd: &str, ok: &[u32]) -> Result<String, RemoteError> {
+        let (code, msg) = self.command(cmd)?;
+        if ok.contains(&code) {
+            Ok(msg)
+        } else if code == 550 {
+            Err(RemoteError::NotFound(format!("{cmd}: {msg}")))
+        } else {
+            Err(RemoteError::Protocol(format!("{cmd}: {code} {msg}")))
+        }
+    }
+
+    fn expect(&mut self, ok: &[u32]) -> Result<String, RemoteError> {
+        let (code, msg) = self.read_reply()?;
+        if ok.contains(&code) {
+            Ok(msg)
+        } else {
+            Err(RemoteError::Protocol(format!("unexpected reply {code} {msg}")))
+        }
+    }
+
+    fn read_reply(&mut self) -> Result<(u32, String), RemoteError> {
+        let mut line = String::new();
+        if self.ctrl.read_line(&mut line)? == 0 {
+            return Err(RemoteError::Protocol("control connection closed".into()));
+        }
+        let code: u32 = line.get(..3).and_then(|c| c.parse().ok())
+            .ok_or_else(|| RemoteError::Protocol(format!("bad reply: {}", line.trim_end())))?;
+        let mut msg = line.get(4..).unwrap_or("").trim_end().to_string();
+        // Multi-line reply: "123-..." until a line starting with "123 "
+        if line.as_bytes().get(3) == Some(&b'-') {
+            let end = format!("{code} ");
+            loop {
+                line.clear();
+                if self.ctrl.read_line(&mut line)? == 0 {
+                    break;
+                }
+                msg.push('\n');
+                msg.push_str(line.trim_end());
+                if line.starts_with(&end) {
+                    break;
+                }
+            }
+        }
+        Ok((code, msg))
+    }
+
+    /// Enter passive mode and connect the data channel. The address in the
+    /// 227 reply is ignored in favour of the control peer (NAT-friendly).
+    fn passive(&mut self) -> Result<TcpStream, RemoteError> {
+        let msg = self.checked("PASV", &[227])?;
+        let nums: Vec<u16> = msg
+            .split(|c: char| !c.is_ascii_digit())
+            .filter(|s| !s.is_empty())
+            .filter_map(|s| s.parse().ok())
+            .collect();
+        // "227 Entering Passive Mode (h1,h2,h3,h4,p1,p2)": port is the last pair
+        if nums.len() < 6 {
+            return Err(RemoteError::Protocol(format!("bad PASV reply: {msg}")));
+        }
+        let port = nums[nums.len() - 2] * 256 + nums[nums.len() - 1];
+        let host = self.ctrl.get_ref().peer_addr()?.ip().to_string();
+        open_stream(&host, port)
+    }
+
+    /// Run a listing command and collect the data channel as text.
+    fn read_listing(&mut self, cmd: &str) -> Result<String, RemoteError> {
+        let mut data = self.passive()?;
+        self.checked(cmd, &[125, 150])?;
+        let mut raw = Vec::new();
+        data.read_to_end(&mut raw)?;
+        drop(data);
+        self.expect(&[226, 250])?;
+        Ok(String::from_utf8_lossy(&raw).into_owned())
+    }
+
+    pub fn size(&mut self, path: &str) -> Result<u64, RemoteError> {
+        let msg = self.checked(&format!("SIZE {path}"), &[213])?;
+        msg.trim().parse().map_err(|_| RemoteError::Protocol(format!("bad SIZE reply: {msg}")))
+    }
+
+    /// Start a binary download of `path` at byte `offset`.
+    fn retrieve(&mut self, path: &str, offset: u64) -> Result<TcpStream, RemoteError> {
+        let data = self.passive()?;
+        if offset > 0 {
+            self.checked(&format!("REST {offset}"), &[350])?;
+        }
+        self.checked(&format!("RETR {path}"), &[125, 150])?;
+        Ok(data)
+    }
+}
+
+impl RemoteFs for FtpClient {
+    fn list(&mut self, path: &str) -> Result<Vec<RemoteEntry>, RemoteError> {
+        let dir = if path.is_empty() { "/" } else { path };
+        let mut entries = match self.read_listing(&format!("MLSD {dir}")) {
+            Ok(text) => parse_mlsd(&text, dir),
+            // 500/502: MLSD not supported, fall back to Unix-style LIST
+            Err(RemoteError::Protocol(_)) => parse_list(&self.read_listing(&format!("LIST {dir}"))?, dir),
+            Err(e) => return Err(e),
+        };
+        sort_entries(&mut entries);
+        Ok(entries)
+    }
+}
+
+/// `type=file;size=123;modify=20240101120000; name.mkv`
+fn parse_mlsd(text: &str, dir: &str) -> Vec<RemoteEntry> {
+    let mut entries = Vec::new();
+    for line in text.lines() {
+        let Some((facts, name)) = line.split_once(' ') else { continue };
+        let mut is_dir = false;
+        let mut size = None;
+        let mut modified = None;
+        let mut skip = false;
+        for fact in facts.split(';').filter(|f| !f.is_empty()) {
+            let Some((k, v)) = fact.split_once('=') else { continue };
+            match k.to_ascii_lowercase().as_str() {
+                "type" => match v.to_ascii_lowercase().as_str() {
+                    "dir" => is_dir = true,
+                    "cdir" | "pdir" => skip = true,
+                    _ => {}
+                },
+                "size" => size = v.parse().ok(),
+                "modify" => modified = Some(v.to_string()),
+                _ => {}
+            }
+        }
+        if skip || name.is_empty() {
+            continue;
+        }
+        entries.push(RemoteEntry { name: name.to_string(), path: join_path(dir, name), is_dir, size, modified });
+    }
+    entries
+}
+
+/// `drwxr-xr-x 2 user group 4096 Jan 01 12:00 name with spaces`
+fn parse_list(text: &str, dir: &str) -> Vec<RemoteEntry> {
+    let mut entries = Vec::new();
+    for line in text.lines() {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        if fields.len() < 9 || line.starts_with("total") {
+            continue;
+        }
+        // Name is everything after the 8th field, spaces preserved
+        let mut rest = line;
+        for f in &fields[..8] {
+            rest = rest.trim_start()[f.len()..].trim_start();
+        }
+        let name = rest.split(" -> ").next().unwrap_or(rest);
+        if name == "." || name == ".." {
+            continue;
+        }
+        entries.push(RemoteEntry {
+            name: name.to_string(),
+            path: join_path(dir, name),
+            is_dir: fields[0].starts_with('d'),
+            size: fields[4].parse().ok(),
+            modified: Some(fields[5..8].join(" ")),
+        });
+    }
+    entries
+}
+
+/// FTP file read with `REST` + `RETR` on its own control connection.
+/// Connects on first read; a seek aborts the transfer and reconnects.
+pub struct FtpSource {
+    conn: RemoteConnection,
+    path: String,
+    pos: u64,
+    len: Option<u64>,
+    client: Option<FtpClient>,
+    data: Option<TcpStream>,
+}
+
+impl FtpSource {
+    pub fn new(conn: &RemoteConnection, path: &str, size: Option<u64>) -> Result<Self, RemoteError> {
+        ftp_addr(&conn.url)?;
+        Ok(Self { conn: conn.clone(), path: decode_path(path), pos: 0, len: size, client: None, data: None })
+    }
+
+    fn client(&mut self) -> io::Result<&mut FtpClient> {
+        if self.client.is_none() {
+            self.client = Some(FtpClient::connect(&self.conn).map_err(io::Error::other)?);
+        }
+        Ok(self.client.as_mut().unwrap())
+    }
+
+    fn reset(&mut self) {
+        // Closing both connections is the simplest reliable way to abort RETR
+        self.data = None;
+        self.client = None;
+    }
+}
+
+impl MediaSource for FtpSource {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.len.is_some_and(|len| self.pos >= len) {
+            return Ok(0);
+        }
+        if self.data.is_none() {
+            let (path, pos) = (self.path.clone(), self.pos);
+            let data = self.client()?.retrieve(&path, pos).map_err(io::Error::other)?;
+            self.data = Some(data);
+        }
+        let n = match self.data.as_mut().unwrap().read(buf) {
+            Ok(n) => n,
+            Err(e) => {
+                // Reconnect on the next read and resume with REST at `pos`
+                self.reset();
+                return Err(e);
+            }
+        };
+        if n == 0 {
+            // Transfer complete: consume the 226 so the connection stays usable
+            self.data = None;
+            if let Some(client) = self.client.as_mut() {
+                let _ = client.expect(&[226, 250]);
+            }
+        }
+        self.pos += n as u64;
+        Ok(n)
+    }
+
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let target = match pos {
+            SeekFrom::Start(p) => Some(p),
+            SeekFrom::Current(d) => self.pos.checked_add_signed(d),
+            SeekFrom::End(d) => self.size().and_then(|len| len.checked_add_signed(d)),
+        }
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "invalid seek"))?;
+        if target != self.pos {
+            if self.data.is_some() {
+                self.reset();
+            }
+            self.pos = target;
+        }
+        Ok(self.pos)
+    }
+
+    fn size(&mut self) -> Option<u64> {
+        if self.len.is_none() && self.data.is_none() {
+            let path = self.path.clone();
+            self.len = self.client().ok().and_then(|c| c.size(&path).ok());
+        }
+        self.len
+    }
+}
@@ -0,0 +1,137 @@
+//! bova-remote: browse and stream media from WebDAV and FTP servers.
+//!
+//! `connect` returns a `RemoteFs` for directory listing; `open_source` returns
+//! a lazily-connecting `MediaSource` that serves range reads to the playback
+//! engines (see `bova_playback::start_playback_source`).
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use bova_playback::MediaSource;
+
+mod ftp;
+mod webdav;
+
+pub use ftp::{FtpClient, FtpSource};
+pub use webdav::{HttpRangeSource, WebDavClient};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RemoteKind {
+    WebDav,
+    Ftp,
+}
+
+impl std::fmt::Display for RemoteKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RemoteKind::WebDav => write!(f, "WebDAV"),
+            RemoteKind::Ftp => write!(f, "FTP"),
+        }
+    }
+}
+
+/// A saved remote share, e.g. `https://nas.local/dav/` or `ftp://nas.local:21/media`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RemoteConnection {
+    pub name: String,
+    pub kind: RemoteKind,
+    pub url: String,
+    #[serde(default)]
+    pub username: Option<String>,
+    #[serde(default)]
+    pub password: Option<String>,
+}
+
+impl RemoteConnection {
+    /// Guess the kind from the URL scheme (`ftp://` → FTP, otherwise WebDAV).
+    pub fn new(name: &str, url: &str, username: Option<String>, password: Option<String>) -> Self {
+        let kind = if url.to_ascii_lowercase().starts_with("ftp://") { RemoteKind::Ftp } else { RemoteKind::WebDav };
+        Self { name: name.to_string(), kind, url: url.to_string(), username, password }
+    }
+
+    /// Path component of the connection URL, the root of the browser.
+    pub fn root_path(&self) -> String {
+        match reqwest::Url::parse(&self.url) {
+            Ok(u) => decode_path(u.path()),
+            Err(_) => "/".to_string(),
+        }
+    }
+}
+
+/// One directory entry. `path` is absolute on the server and not percent-encoded.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RemoteEntry {
+    pub name: String,
+    pub path: String,
+    pub is_dir: bool,
+    pub size: Option<u64>,
+    pub modified: Option<String>,
+}
+
+#[derive(Debug, Error)]
+pub enum RemoteError {
+    #[error("io error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("http error: {0}")]
+    Http(String),
+    #[error("authentication failed")]
+    Auth,
+    #[error("not found: {0}")]
+    NotFound(String),
+    #[error("protocol error: {0}")]
+    Protocol(String),
+    #[error("invalid url: {0}")]
+    InvalidUrl(String),
+}
+
+impl From<reqwest::Error> for RemoteError {
+    fn from(e: reqwest::Error) -> Self {
+        RemoteError::Http(e.to_string())
+    }
+}
+
+/// Directory browsing on a remote share.
+pub trait RemoteFs: Send {
+    /// List `path`; directories first, then files, each sorted by name.
+    fn list(&mut self, path: &str) -> Result<Vec<RemoteEntry>, RemoteError>;
+}
+
+pub fn connect(conn: &RemoteConnection) -> Result<Box<dyn RemoteFs>, RemoteError> {
+    Ok(match conn.kind {
+        RemoteKind::WebDav => Box::new(WebDavClient::new(conn)?),
+        RemoteKind::Ftp => Box::new(FtpClient::connect(conn)?),
+    })
+}
+
+/// Streaming source for `path`. Nothing is fetched until the engine reads;
+/// pass `size` from the listing when known to save a round trip.
+pub fn open_source(conn: &RemoteConnection, path: &str, size: Option<u64>) -> Result<Box<dyn MediaSource>, RemoteError> {
+    Ok(match conn.kind {
+        RemoteKind::WebDav => Box::new(HttpRangeSource::new(conn, path, size)?),
+        RemoteKind::Ftp => Box::new(FtpSource::new(conn, path, size)?),
+    })
+}
+
+/// Extensions shown as playable in the browser.
+pub fn is_media_file(name: &str) -> bool {
+    const EXTS: &[&str] = &[
+        "mkv", "mp4", "m4v", "avi", "mov", "wmv", "flv", "webm", "ts", "m2ts", "mts", "mpg", "mpeg",
+        "rmvb", "3gp", "ogv", "mp3", "flac", "m4a", "aac", "ogg", "opus", "wav", "ape", "wma",
+    ];
+    name.rsplit_once('.')
+        .map(|(_, ext)| EXTS.contains(&ext.to_ascii_lowercase().as_str()))
+        .unwrap_or(false)
+}
+
+fn sort_entries(entries: &mut [RemoteEntry]) {
+    entries.sort_by(|a, b| b.is_dir.cmp(&a.is_dir).then_with(|| a.name.to_lowercase().cmp(&b.name.to_lowercase())));
+}
+
+fn decode_path(p: &str) -> String {
+    percent_encoding::percent_decode_str(p).decode_utf8_lossy().into_owned()
+}
+
+/// Join a directory path and a child name with exactly one '/'.
+fn join_path(dir: &str, name: &str) -> String {
+    format!("{}/{}", dir.trim_end_matches('/'), name)
+}
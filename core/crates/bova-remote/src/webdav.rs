@@ -0,0 +1,215 @@
+//! WebDAV: PROPFIND listing and HTTP range reads.
+
+use reqwest::blocking::{Client, RequestBuilder, Response};
+use reqwest::header::{CONTENT_LENGTH, CONTENT_RANGE, RANGE};
+use reqwest::{Method, StatusCode, Url};
+use std::io::{self, Read, SeekFrom};
+use std::time::Duration;
+
+use bova_playback::MediaSource;
+
+use crate::{decode_path, sort_entries, RemoteConnection, RemoteEntry, RemoteError, RemoteFs};
+
+const PROPFIND_BODY: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<d:propfind xmlns:d="DAV:">
+  <d:prop><d:resourcetype/><d:getcontentlength/><d:getlastmodified/><d:displayname/></d:prop>
+</d:propfind>"#;
+
+fn http_client(read_timeout: Option<Duration>) -> Result<Client, RemoteError> {
+    Ok(Client::builder()
+        .connect_timeout(Duration::from_secs(10))
+        .timeout(read_timeout)
+        .user_agent(concat!("BovaPlayer/", env!("CARGO_PKG_VERSION")))
+        .build()?)
+}
+
+fn with_auth(req: RequestBuilder, conn: &RemoteConnection) -> RequestBuilder {
+    match &conn.username {
+        Some(user) if !user.is_empty() => req.basic_auth(user, conn.password.as_ref()),
+        _ => req,
+    }
+}
+
+/// URL for a server-absolute, unencoded `path`.
+fn url_for(base: &Url, path: &str) -> Url {
+    let mut url = base.clone();
+    url.set_path(path);
+    url
+}
+
+fn check_status(resp: &Response, path: &str) -> Result<(), RemoteError> {
+    match resp.status() {
+        s if s.is_success() => Ok(()),
+        StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => Err(RemoteError::Auth),
+        StatusCode::NOT_FOUND => Err(RemoteError::NotFound(path.to_string())),
+        s => Err(RemoteError::Http(format!("{} for {}", s, path))),
+    }
+}
+
+pub struct WebDavClient {
+    conn: RemoteConnection,
+    base: Url,
+    client: Client,
+}
+
+impl WebDavClient {
+    pub fn new(conn: &RemoteConnection) -> Result<Self, RemoteError> {
+        let base = Url::parse(&conn.url).map_err(|e| RemoteError::InvalidUrl(format!("{}: {}", conn.url, e)))?;
+        Ok(Self { conn: conn.clone(), base, client: http_client(Some(Duration::from_secs(30)))? })
+    }
+}
+
+impl RemoteFs for WebDavClient {
+    fn list(&mut self, path: &str) -> Result<Vec<RemoteEntry>, RemoteError> {
+        // Collections need the trailing slash, some servers redirect otherwise
+        let dir = format!("{}/", path.trim_end_matches('/'));
+        let req = self.client
+            .request(Method::from_bytes(b"PROPFIND").unwrap(), url_for(&self.base, &dir))
+            .header("Depth", "1")
+            .header("Content-Type", "application/xml; charset=utf-8")
+            .body(PROPFIND_BODY);
+        let resp = with_auth(req, &self.conn).send()?;
+        check_status(&resp, &dir)?;
+        let text = resp.text()?;
+        let mut entries = parse_multistatus(&text, &dir)?;
+        sort_entries(&mut entries);
+        Ok(entries)
+    }
+}
+
+/// Parse a PROPFIND `multistatus` response, skipping the listed directory itself.
+fn parse_multistatus(xml: &str, dir: &str) -> Result<Vec<RemoteEntry>, RemoteError> {
+    let doc = roxmltree::Document::parse(xml).map_err(|e| RemoteError::Protocol(format!("bad PROPFIND XML: {e}")))?;
+    let is_dav = |n: &roxmltree::Node, name: &str| n.is_element() && n.tag_name().name() == name;
+
+    let mut entries = Vec::new();
+    for resp in doc.descendants().filter(|n| is_dav(n, "response")) {
+        let Some(href) = resp.children().find(|n| is_dav(n, "href")).and_then(|n| n.text()) else { continue };
+        // href may be absolute (http://host/path) or just the path
+        let raw_path = Url::parse(href.trim()).map(|u| u.path().to_string()).unwrap_or_else(|_| href.trim().to_string());
+        let path = decode_path(&raw_path);
+        if path.trim_end_matches('/') == dir.trim_end_matches('/') {
+            continue;
+        }
+
+        let prop = |name: &str| resp.descendants().find(|n| is_dav(n, name));
+        let is_dir = prop("resourcetype").is_some_and(|rt| rt.children().any(|c| is_dav(&c, "collection")));
+        let size = prop("getcontentlength").and_then(|n| n.text()).and_then(|t| t.trim().parse().ok());
+        let modified = prop("getlastmodified").and_then(|n| n.text()).map(|t| t.trim().to_string());
+        let name = prop("displayname")
+            .and_then(|n| n.text())
+            .map(|t| t.trim().to_string())
+            .filter(|t| !t.is_empty())
+            .unwrap_or_else(|| path.trim_end_matches('/').rsplit('/').next().unwrap_or("").to_string());
+
+        entries.push(RemoteEntry {
+            name,
+            path: path.trim_end_matches('/').to_string(),
+            is_dir,
+            size,
+            modified,
+        });
+    }
+    Ok(entries)
+}
+
+/// Seekable HTTP(S) resource read with `Range` requests. A seek drops the
+/// current response; the next read issues a new ranged GET.
+pub struct HttpRangeSource {
+    conn: RemoteConnection,
+    client: Client,
+    url: Url,
+    pos: u64,
+    len: Option<u64>,
+    resp: Option<Response>,
+}
+
+impl HttpRangeSource {
+    pub fn new(conn: &RemoteConnection, path: &str, size: Option<u64>) -> Result<Self, RemoteError> {
+        let base = Url::parse(&conn.url).map_err(|e| RemoteError::InvalidUrl(format!("{}: {}", conn.url, e)))?;
+        Ok(Self {
+            conn: conn.clone(),
+            // No overall timeout: the body is streamed for the whole playback
+            client: http_client(None)?,
+            url: url_for(&base, path),
+            pos: 0,
+            len: size,
+            resp: None,
+        })
+    }
+
+    fn request(&mut self) -> io::Result<Response> {
+        let req = self.client.get(self.url.clone()).header(RANGE, format!("bytes={}-", self.pos));
+        let resp = with_auth(req, &self.conn).send().map_err(io::Error::other)?;
+        match resp.status() {
+            StatusCode::PARTIAL_CONTENT => {
+                // Content-Range: bytes 100-199/2000
+                if self.len.is_none() {
+                    self.len = resp.headers().get(CONTENT_RANGE)
+                        .and_then(|v| v.to_str().ok())
+                        .and_then(|v| v.rsplit('/').next())
+                        .and_then(|total| total.parse().ok());
+                }
+                Ok(resp)
+            }
+            StatusCode::OK if self.pos == 0 => {
+                if self.len.is_none() {
+                    self.len = resp.headers().get(CONTENT_LENGTH)
+                        .and_then(|v| v.to_str().ok())
+                        .and_then(|v| v.parse().ok());
+                }
+                Ok(resp)
+            }
+            StatusCode::OK => Err(io::Error::new(io::ErrorKind::Unsupported, "server ignores range requests")),
+            StatusCode::RANGE_NOT_SATISFIABLE => Err(io::Error::new(io::ErrorKind::UnexpectedEof, "range past end of file")),
+            s => Err(io::Error::other(format!("HTTP {s} for {}", self.url))),
+        }
+    }
+}
+
+impl MediaSource for HttpRangeSource {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.len.is_some_and(|len| self.pos >= len) {
+            return Ok(0);
+        }
+        if self.resp.is_none() {
+            self.resp = Some(self.request()?);
+        }
+        let n = match self.resp.as_mut().unwrap().read(buf) {
+            Ok(n) => n,
+            Err(e) => {
+                // Drop the broken response; the next read resumes with a range request at `pos`
+                self.resp = None;
+                return Err(e);
+            }
+        };
+        self.pos += n as u64;
+        Ok(n)
+    }
+
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let target = match pos {
+            SeekFrom::Start(p) => Some(p),
+            SeekFrom::Current(d) => self.pos.checked_add_signed(d),
+            SeekFrom::End(d) => self.size().and_then(|len| len.checked_add_signed(d)),
+        }
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "invalid seek"))?;
+        if target != self.pos {
+            self.resp = None;
+            self.pos = target;
+        }
+        Ok(self.pos)
+    }
+
+    fn size(&mut self) -> Option<u64> {
+        if self.len.is_none() {
+            let req = with_auth(self.client.head(self.url.clone()), &self.conn);
+            if let Ok(resp) = req.send() {
+                self.len = resp.headers().get(CONTENT_LENGTH)
+                    .and_then(|v| v.to_str().ok())
+                    .and_then(|v| v.parse().ok());
+            }
+        }
+        self.len
+    }
+}
@@ -0,0 +1,196 @@
+//! FTP listing and REST+RETR reads against a local passive-mode server.
+
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, SeekFrom, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use bova_remote::{connect, open_source, RemoteConnection, RemoteError};
+
+#[derive(Default)]
+struct Ftp {
+    files: HashMap<String, Vec<u8>>,
+    /// Data of `MLSD`/`LIST` per directory; `None` answers `MLSD` with 500
+    mlsd: Option<HashMap<String, String>>,
+    list: HashMap<String, String>,
+    password: String,
+    /// Every command received
+    log: Vec<String>,
+}
+
+fn start(ftp: Ftp) -> (String, Arc<Mutex<Ftp>>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("ftp://{}/", listener.local_addr().unwrap());
+    let ftp = Arc::new(Mutex::new(ftp));
+    let shared = ftp.clone();
+    thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            let ftp = shared.clone();
+            thread::spawn(move || serve(stream, &ftp));
+        }
+    });
+    (url, ftp)
+}
+
+fn serve(mut ctrl: TcpStream, ftp: &Mutex<Ftp>) {
+    let mut reader = BufReader::new(ctrl.try_clone().unwrap());
+    let reply = |ctrl: &mut TcpStream, msg: &str| ctrl.write_all(format!("{msg}\r\n").as_bytes()).is_ok();
+    reply(&mut ctrl, "220-test server\r\n220 ready");
+    let mut pasv: Option<TcpListener> = None;
+    let mut rest = 0usize;
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line).unwrap_or(0) == 0 {
+            return;
+        }
+        let line = line.trim_end().to_string();
+        ftp.lock().unwrap().log.push(line.clone());
+        let (cmd, arg) = line.split_once(' ').unwrap_or((&line, ""));
+        // data channel payload, sent after the 150
+        let mut transfer: Option<Vec<u8>> = None;
+        let ok = match cmd {
+            "USER" => reply(&mut ctrl, "331 password required"),
+            "PASS" if arg == ftp.lock().unwrap().password => reply(&mut ctrl, "230 logged in"),
+            "PASS" => reply(&mut ctrl, "530 login incorrect"),
+            "TYPE" => reply(&mut ctrl, "200 binary"),
+            "PASV" => {
+                let data = TcpListener::bind("127.0.0.1:0").unwrap();
+                let port = data.local_addr().unwrap().port();
+                pasv = Some(data);
+                reply(&mut ctrl, &format!("227 Entering Passive Mode (127,0,0,1,{},{})", port / 256, port % 256))
+            }
+            "SIZE" => match ftp.lock().unwrap().files.get(arg) {
+                Some(data) => reply(&mut ctrl, &format!("213 {}", data.len())),
+                None => reply(&mut ctrl, "550 no such file"),
+            },
+            "REST" => {
+                rest = arg.parse().unwrap();
+                reply(&mut ctrl, "350 restarting")
+            }
+            "RETR" => {
+                let data = ftp.lock().unwrap().files.get(arg).cloned();
+                match data {
+                    Some(data) => {
+                        transfer = Some(data[rest.min(data.len())..].to_vec());
+                        rest = 0;
+                        true
+                    }
+                    None => reply(&mut ctrl, "550 no such file"),
+                }
+            }
+            "MLSD" => {
+                let listing = ftp.lock().unwrap().mlsd.as_ref().map(|m| m.get(arg).cloned().unwrap_or_default());
+                match listing {
+                    Some(text) => {
+                        transfer = Some(text.into_bytes());
+                        true
+                    }
+                    None => reply(&mut ctrl, "500 unknown command"),
+                }
+            }
+            "LIST" => {
+                transfer = Some(ftp.lock().unwrap().list.get(arg).cloned().unwrap_or_default().into_bytes());
+                true
+            }
+            _ => reply(&mut ctrl, "502 not implemented"),
+        };
+        if !ok {
+            return;
+        }
+        if let Some(payload) = transfer {
+            let (mut data, _) = pasv.take().expect("PASV before transfer").accept().unwrap();
+            reply(&mut ctrl, "150 opening data connection");
+            // the client may abort by closing the data connection
+            let _ = data.write_all(&payload);
+            drop(data);
+            reply(&mut ctrl, "226 transfer complete");
+        }
+    }
+}
+
+fn file(len: usize) -> Vec<u8> {
+    (0..len).map(|i| (i % 251) as u8).collect()
+}
+
+fn conn(url: &str) -> RemoteConnection {
+    RemoteConnection::new("nas", url, Some("user".into()), Some("secret".into()))
+}
+
+#[test]
+fn lists_with_mlsd() {
+    let mlsd = "type=cdir;modify=20240101000000; .\r\n\
+                type=dir;modify=20240101000000; Shows\r\n\
+                type=file;size=1234;modify=20240102030405; b movie.mkv\r\n\
+                type=file;size=10;modify=20240101000000; a.mp4\r\n";
+    let ftp = Ftp {
+        mlsd: Some(HashMap::from([("/media".to_string(), mlsd.to_string())])),
+        password: "secret".into(),
+        ..Ftp::default()
+    };
+    let (url, _) = start(ftp);
+    let entries = connect(&conn(&url)).unwrap().list("/media").unwrap();
+
+    let names: Vec<_> = entries.iter().map(|e| e.name.as_str()).collect();
+    assert_eq!(names, ["Shows", "a.mp4", "b movie.mkv"]);
+    assert!(entries[0].is_dir);
+    assert_eq!(entries[2].path, "/media/b movie.mkv");
+    assert_eq!(entries[2].size, Some(1234));
+    assert_eq!(entries[2].modified.as_deref(), Some("20240102030405"));
+}
+
+#[test]
+fn falls_back_to_list() {
+    let list = "total 2\r\n\
+                drwxr-xr-x 2 ftp ftp 4096 Jan 01 12:00 Shows\r\n\
+                -rw-r--r-- 1 ftp ftp 1234 Jan 02 03:04 b  movie.mkv\r\n";
+    let ftp = Ftp {
+        list: HashMap::from([("/media".to_string(), list.to_string())]),
+        password: "secret".into(),
+        ..Ftp::default()
+    };
+    let (url, _) = start(ftp);
+    let entries = connect(&conn(&url)).unwrap().list("/media").unwrap();
+
+    assert_eq!(entries.len(), 2);
+    assert!(entries[0].is_dir && entries[0].name == "Shows");
+    assert_eq!(entries[1].name, "b  movie.mkv", "spaces in names are preserved");
+    assert_eq!(entries[1].size, Some(1234));
+}
+
+#[test]
+fn wrong_password_is_an_auth_error() {
+    let (url, _) = start(Ftp { password: "other".into(), ..Ftp::default() });
+    assert!(matches!(connect(&conn(&url)), Err(RemoteError::Auth)));
+}
+
+#[test]
+fn reads_and_seeks_with_rest() {
+    let data = file(100_000);
+    let ftp = Ftp {
+        files: HashMap::from([("/v.mkv".to_string(), data.clone())]),
+        password: "secret".into(),
+        ..Ftp::default()
+    };
+    let (url, ftp) = start(ftp);
+    let mut src = open_source(&conn(&url), "/v.mkv", None).unwrap();
+
+    assert_eq!(src.size(), Some(100_000));
+    let mut buf = vec![0u8; 8192];
+    assert!(src.read(&mut buf).unwrap() > 0);
+    assert_eq!(buf[0], data[0]);
+    assert_eq!(src.seek(SeekFrom::Start(90_000)).unwrap(), 90_000);
+    let mut tail = Vec::new();
+    loop {
+        let n = src.read(&mut buf).unwrap();
+        if n == 0 {
+            break;
+        }
+        tail.extend_from_slice(&buf[..n]);
+    }
+    assert_eq!(tail, &data[90_000..]);
+
+    let log = ftp.lock().unwrap().log.clone();
+    assert!(log.contains(&"REST 90000".to_string()), "commands: {log:?}");
+    assert_eq!(log.iter().filter(|c| c.starts_with("RETR")).count(), 2);
+}
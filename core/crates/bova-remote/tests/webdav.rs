@@ -0,0 +1,230 @@
+//! WebDAV listing and range reads against a local HTTP server.
+
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Read, SeekFrom, Write};
+use std::net::{Shutdown, TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use bova_remote::{connect, open_source, RemoteConnection, RemoteError};
+
+/// Serves PROPFIND from `listings` and GET/HEAD with `Range` from `files`.
+/// The first GET of a path in `drop_once` is cut off halfway through the body.
+#[derive(Default)]
+struct Dav {
+    listings: HashMap<String, String>,
+    files: HashMap<String, Vec<u8>>,
+    drop_once: Vec<String>,
+    /// `Authorization` header required on every request
+    auth: Option<String>,
+    /// (method, path, range start) of every request
+    log: Vec<(String, String, u64)>,
+}
+
+fn start(dav: Dav) -> (String, Arc<Mutex<Dav>>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    let dav = Arc::new(Mutex::new(dav));
+    let shared = dav.clone();
+    thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            let dav = shared.clone();
+            thread::spawn(move || serve(stream, &dav));
+        }
+    });
+    (url, dav)
+}
+
+fn serve(mut stream: TcpStream, dav: &Mutex<Dav>) {
+    let mut reader = BufReader::new(stream.try_clone().unwrap());
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line).unwrap_or(0) == 0 {
+            return;
+        }
+        let mut parts = line.split_whitespace();
+        let method = parts.next().unwrap_or("").to_string();
+        let path = parts.next().unwrap_or("/").to_string();
+        let (mut range, mut auth, mut body_len) = (0u64, None, 0usize);
+        loop {
+            let mut header = String::new();
+            if reader.read_line(&mut header).unwrap_or(0) == 0 {
+                return;
+            }
+            let Some((name, value)) = header.trim().split_once(':') else { break };
+            let value = value.trim();
+            match name.to_ascii_lowercase().as_str() {
+                "range" => range = value.trim_start_matches("bytes=").split('-').next().unwrap().parse().unwrap(),
+                "authorization" => auth = Some(value.to_string()),
+                "content-length" => body_len = value.parse().unwrap(),
+                _ => {}
+            }
+        }
+        let mut body = vec![0; body_len];
+        reader.read_exact(&mut body).unwrap();
+
+        let mut dav = dav.lock().unwrap();
+        dav.log.push((method.clone(), path.clone(), range));
+        if dav.auth.is_some() && dav.auth != auth {
+            let _ = stream.write_all(b"HTTP/1.1 401 Unauthorized\r\nContent-Length: 0\r\n\r\n");
+            continue;
+        }
+        if method == "PROPFIND" {
+            match dav.listings.get(&path) {
+                Some(xml) => {
+                    let head = format!("HTTP/1.1 207 Multi-Status\r\nContent-Length: {}\r\n\r\n", xml.len());
+                    let _ = stream.write_all(head.as_bytes());
+                    let _ = stream.write_all(xml.as_bytes());
+                }
+                None => {
+                    let _ = stream.write_all(b"HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\n\r\n");
+                }
+            }
+            continue;
+        }
+        let Some(data) = dav.files.get(&path).cloned() else {
+            let _ = stream.write_all(b"HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\n\r\n");
+            continue;
+        };
+        let cut = method == "GET" && dav.drop_once.iter().position(|p| *p == path).map(|i| dav.drop_once.remove(i)).is_some();
+        drop(dav);
+        let start = (range as usize).min(data.len());
+        let head = if range > 0 {
+            format!(
+                "HTTP/1.1 206 Partial Content\r\nContent-Length: {}\r\nContent-Range: bytes {}-{}/{}\r\n\r\n",
+                data.len() - start,
+                start,
+                data.len() - 1,
+                data.len()
+            )
+        } else {
+            format!("HTTP/1.1 200 OK\r\nContent-Length: {}\r\n\r\n", data.len())
+        };
+        let _ = stream.write_all(head.as_bytes());
+        if method == "HEAD" {
+            continue;
+        }
+        let body = &data[start..];
+        if cut {
+            let _ = stream.write_all(&body[..body.len() / 2]);
+            let _ = stream.shutdown(Shutdown::Both);
+            return;
+        }
+        let _ = stream.write_all(body);
+    }
+}
+
+fn multistatus(responses: &[(&str, bool, Option<u64>)]) -> String {
+    let mut xml = String::from(r#"<?xml version="1.0" encoding="utf-8"?><d:multistatus xmlns:d="DAV:">"#);
+    for (href, is_dir, size) in responses {
+        xml.push_str(&format!("<d:response><d:href>{href}</d:href><d:propstat><d:prop>"));
+        xml.push_str(if *is_dir { "<d:resourcetype><d:collection/></d:resourcetype>" } else { "<d:resourcetype/>" });
+        if let Some(size) = size {
+            xml.push_str(&format!("<d:getcontentlength>{size}</d:getcontentlength>"));
+        }
+        xml.push_str("</d:prop><d:status>HTTP/1.1 200 OK</d:status></d:propstat></d:response>");
+    }
+    xml.push_str("</d:multistatus>");
+    xml
+}
+
+fn file(len: usize) -> Vec<u8> {
+    (0..len).map(|i| (i % 251) as u8).collect()
+}
+
+#[test]
+fn lists_directory_entries() {
+    let mut dav = Dav::default();
+    dav.listings.insert(
+        "/media/".into(),
+        multistatus(&[
+            ("/media/", true, None),
+            ("/media/b%20movie.mkv", false, Some(1234)),
+            ("/media/Shows/", true, None),
+            ("/media/a.mp4", false, Some(10)),
+        ]),
+    );
+    let (url, _) = start(dav);
+    let conn = RemoteConnection::new("nas", &format!("{url}/media/"), None, None);
+    let entries = connect(&conn).unwrap().list("/media").unwrap();
+
+    let names: Vec<_> = entries.iter().map(|e| e.name.as_str()).collect();
+    assert_eq!(names, ["Shows", "a.mp4", "b movie.mkv"], "directories first, then by name");
+    assert!(entries[0].is_dir);
+    assert_eq!(entries[2].path, "/media/b movie.mkv");
+    assert_eq!(entries[2].size, Some(1234));
+}
+
+#[test]
+fn basic_auth_and_errors() {
+    let dav = Dav {
+        listings: HashMap::from([("/".to_string(), multistatus(&[("/", true, None)]))]),
+        // "user:secret"
+        auth: Some("Basic dXNlcjpzZWNyZXQ=".into()),
+        ..Dav::default()
+    };
+    let (url, _) = start(dav);
+    let good = RemoteConnection::new("nas", &url, Some("user".into()), Some("secret".into()));
+    assert!(connect(&good).unwrap().list("/").unwrap().is_empty());
+    assert!(matches!(connect(&good).unwrap().list("/missing"), Err(RemoteError::NotFound(_))));
+    let bad = RemoteConnection::new("nas", &url, Some("user".into()), Some("wrong".into()));
+    assert!(matches!(connect(&bad).unwrap().list("/"), Err(RemoteError::Auth)));
+}
+
+#[test]
+fn range_reads_and_seeks() {
+    let data = file(100_000);
+    let (url, dav) = start(Dav { files: HashMap::from([("/v.mkv".to_string(), data.clone())]), ..Dav::default() });
+    let conn = RemoteConnection::new("nas", &url, None, None);
+    let mut src = open_source(&conn, "/v.mkv", None).unwrap();
+
+    assert_eq!(src.size(), Some(100_000));
+    assert_eq!(src.seek(SeekFrom::Start(60_000)).unwrap(), 60_000);
+    let mut buf = vec![0u8; 1000];
+    assert_eq!(src.read(&mut buf[..1]).unwrap(), 1);
+    assert_eq!(buf[0], data[60_000]);
+    assert_eq!(src.seek(SeekFrom::End(-10)).unwrap(), 99_990);
+    let mut tail = Vec::new();
+    loop {
+        let n = src.read(&mut buf).unwrap();
+        if n == 0 {
+            break;
+        }
+        tail.extend_from_slice(&buf[..n]);
+    }
+    assert_eq!(tail, &data[99_990..]);
+
+    let gets: Vec<u64> = dav.lock().unwrap().log.iter().filter(|(m, ..)| m == "GET").map(|&(.., r)| r).collect();
+    assert_eq!(gets, [60_000, 99_990]);
+}
+
+#[test]
+fn read_resumes_after_dropped_connection() {
+    let data = file(200_000);
+    let dav = Dav {
+        files: HashMap::from([("/v.mkv".to_string(), data.clone())]),
+        drop_once: vec!["/v.mkv".into()],
+        ..Dav::default()
+    };
+    let (url, dav) = start(dav);
+    let conn = RemoteConnection::new("nas", &url, None, None);
+    let mut src = open_source(&conn, "/v.mkv", Some(data.len() as u64)).unwrap();
+
+    let mut out = Vec::new();
+    let mut buf = vec![0u8; 8192];
+    let mut errors = 0;
+    loop {
+        match src.read(&mut buf) {
+            Ok(0) => break,
+            Ok(n) => out.extend_from_slice(&buf[..n]),
+            Err(_) => {
+                errors += 1;
+                assert!(errors < 3, "read keeps failing");
+            }
+        }
+    }
+    assert_eq!(errors, 1);
+    assert!(out == data, "resumed data differs");
+    let gets: Vec<u64> = dav.lock().unwrap().log.iter().filter(|(m, ..)| m == "GET").map(|&(.., r)| r).collect();
+    assert_eq!(gets, [0, 100_000], "second request resumes at the failed offset");
+}
@@ -0,0 +1,192 @@
+//! Secret storage: OS secret service (Secret Service / Keychain / Credential
+//! Manager) when available, otherwise a ChaCha20-Poly1305 encrypted file.
+//!
+//! 加密文件的密钥保存在同目录下仅当前用户可读的 credentials.key 中，
+//! 用于避免配置被复制或分享时泄露令牌；它不能防御能以当前用户身份读文件的程序。
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+
+use crate::{config_dir, write_private, SettingsError};
+
+#[cfg(any(target_os = "linux", target_os = "macos", target_os = "windows"))]
+const SERVICE: &str = "bova-player";
+const KEY_FILE: &str = "credentials.key";
+const STORE_FILE: &str = "credentials.enc";
+const KEY_LEN: usize = 32;
+const NONCE_LEN: usize = 12;
+
+/// 实际使用的存储后端
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CredentialBackend {
+    SecretService,
+    EncryptedFile,
+}
+
+impl std::fmt::Display for CredentialBackend {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CredentialBackend::SecretService => write!(f, "系统密钥环"),
+            CredentialBackend::EncryptedFile => write!(f, "加密文件"),
+        }
+    }
+}
+
+/// 按账户名（如 `emby:https://host:user`）保存的密钥
+pub struct Credentials {
+    backend: Backend,
+}
+
+enum Backend {
+    #[cfg(any(target_os = "linux", target_os = "macos", target_os = "windows"))]
+    Keyring,
+    File(EncryptedFile),
+}
+
+impl Credentials {
+    /// 优先使用系统密钥环，不可用时退回配置目录下的加密文件
+    pub fn open() -> Result<Self, SettingsError> {
+        #[cfg(any(target_os = "linux", target_os = "macos", target_os = "windows"))]
+        if keyring_available() {
+            return Ok(Self { backend: Backend::Keyring });
+        }
+        Self::open_file(&config_dir()?)
+    }
+
+    /// 直接使用 `dir` 下的加密文件（无密钥环的平台或便携模式）
+    pub fn open_file(dir: &Path) -> Result<Self, SettingsError> {
+        Ok(Self { backend: Backend::File(EncryptedFile::open(dir)?) })
+    }
+
+    pub fn backend(&self) -> CredentialBackend {
+        match &self.backend {
+            #[cfg(any(target_os = "linux", target_os = "macos", target_os = "windows"))]
+            Backend::Keyring => CredentialBackend::SecretService,
+            Backend::File(_) => CredentialBackend::EncryptedFile,
+        }
+    }
+
+    pub fn get(&self, account: &str) -> Result<Option<String>, SettingsError> {
+        match &self.backend {
+            #[cfg(any(target_os = "linux", target_os = "macos", target_os = "windows"))]
+            Backend::Keyring => match entry(account)?.get_password() {
+                Ok(secret) => Ok(Some(secret)),
+                Err(keyring::Error::NoEntry) => Ok(None),
+                Err(e) => Err(SettingsError::Secret(e.to_string())),
+            },
+            Backend::File(file) => Ok(file.entries.lock().unwrap().get(account).cloned()),
+        }
+    }
+
+    pub fn set(&self, account: &str, secret: &str) -> Result<(), SettingsError> {
+        match &self.backend {
+            #[cfg(any(target_os = "linux", target_os = "macos", target_os = "windows"))]
+            Backend::Keyring => entry(account)?
+                .set_password(secret)
+                .map_err(|e| SettingsError::Secret(e.to_string())),
+            Backend::File(file) => {
+                let mut entries = file.entries.lock().unwrap();
+                if entries.get(account).map(String::as_str) == Some(secret) {
+                    return Ok(());
+                }
+                entries.insert(account.to_string(), secret.to_string());
+                file.save(&entries)
+            }
+        }
+    }
+
+    pub fn delete(&self, account: &str) -> Result<(), SettingsError> {
+        match &self.backend {
+            #[cfg(any(target_os = "linux", target_os = "macos", target_os = "windows"))]
+            Backend::Keyring => match entry(account)?.delete_credential() {
+                Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+                Err(e) => Err(SettingsError::Secret(e.to_string())),
+            },
+            Backend::File(file) => {
+                let mut entries = file.entries.lock().unwrap();
+                if entries.remove(account).is_some() {
+                    file.save(&entries)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+#[cfg(any(target_os = "linux", target_os = "macos", target_os = "windows"))]
+fn entry(account: &str) -> Result<keyring::Entry, SettingsError> {
+    keyring::Entry::new(SERVICE, account).map_err(|e| SettingsError::Secret(e.to_string()))
+}
+
+/// 读一个不存在的条目：NoEntry 说明服务可用，其余错误（无 D-Bus、被锁定等）视为不可用
+#[cfg(any(target_os = "linux", target_os = "macos", target_os = "windows"))]
+fn keyring_available() -> bool {
+    let probe = entry("__bova_probe__").and_then(|e| match e.get_password() {
+        Ok(_) | Err(keyring::Error::NoEntry) => Ok(()),
+        Err(e) => Err(SettingsError::Secret(e.to_string())),
+    });
+    if let Err(e) = &probe {
+        log::warn!("secret service unavailable ({}), using encrypted file", e);
+    }
+    probe.is_ok()
+}
+
+struct EncryptedFile {
+    path: PathBuf,
+    cipher: ChaCha20Poly1305,
+    entries: Mutex<BTreeMap<String, String>>,
+}
+
+impl EncryptedFile {
+    fn open(dir: &Path) -> Result<Self, SettingsError> {
+        std::fs::create_dir_all(dir)?;
+        let key_path = dir.join(KEY_FILE);
+        // 只在密钥文件不存在时生成新密钥；读取失败或长度不对时报错，
+        // 覆盖密钥会让已保存的所有条目无法解密
+        let key = match std::fs::read(&key_path) {
+            Ok(bytes) if bytes.len() == KEY_LEN => *Key::from_slice(&bytes),
+            Ok(bytes) => {
+                return Err(SettingsError::Secret(format!(
+                    "{} has {} bytes, expected {}", key_path.display(), bytes.len(), KEY_LEN
+                )));
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                let key = ChaCha20Poly1305::generate_key(&mut OsRng);
+                write_private(&key_path, key.as_slice())?;
+                key
+            }
+            Err(e) => return Err(e.into()),
+        };
+        let cipher = ChaCha20Poly1305::new(&key);
+        let path = dir.join(STORE_FILE);
+
+        // 密钥丢失或文件损坏时从空开始，用户重新登录即可
+        let entries = match std::fs::read(&path) {
+            Ok(data) if data.len() > NONCE_LEN => {
+                let (nonce, ciphertext) = data.split_at(NONCE_LEN);
+                cipher.decrypt(Nonce::from_slice(nonce), ciphertext).ok()
+                    .and_then(|plain| serde_json::from_slice(&plain).ok())
+                    .unwrap_or_else(|| {
+                        log::warn!("cannot decrypt {}, starting empty", path.display());
+                        BTreeMap::new()
+                    })
+            }
+            _ => BTreeMap::new(),
+        };
+        Ok(Self { path, cipher, entries: Mutex::new(entries) })
+    }
+
+    fn save(&self, entries: &BTreeMap<String, String>) -> Result<(), SettingsError> {
+        let plain = serde_json::to_vec(entries)?;
+        let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let ciphertext = self.cipher.encrypt(&nonce, plain.as_slice())
+            .map_err(|e| SettingsError::Secret(e.to_string()))?;
+        let mut data = nonce.to_vec();
+        data.extend_from_slice(&ciphertext);
+        write_private(&self.path, &data)
+    }
+}
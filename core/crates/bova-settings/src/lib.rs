@@ -0,0 +1,94 @@
+//! bova-settings: where BovaPlayer keeps its configuration.
+//!
+//! Config files live in the per-user config directory (`$XDG_CONFIG_HOME/bova-player`
+//! on Linux); secrets such as access tokens and passwords go to `Credentials`,
+//! which uses the OS secret service and falls back to an encrypted file.
+//! Preferences and per-file playback memory are versioned `Store`s shared by
+//! the GUI, CLI and FFI.
+
+use std::path::{Path, PathBuf};
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use thiserror::Error;
+
+mod credentials;
+mod prefs;
+mod state;
+mod store;
+
+pub use credentials::{CredentialBackend, Credentials};
+pub use prefs::{EnginePreference, Preferences, RepeatPreference, SettingsStore, MAX_RECENT};
+pub use state::{FileState, MediaState, StateStore, MAX_FILE_STATES};
+pub use store::{Store, Versioned};
+
+const APP_DIR: &str = "bova-player";
+
+#[derive(Debug, Error)]
+pub enum SettingsError {
+    #[error("no config directory available")]
+    NoConfigDir,
+    #[error("io error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("invalid json: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("secret store error: {0}")]
+    Secret(String),
+    #[error("unsupported format: {0}")]
+    Format(String),
+}
+
+/// 配置目录；可用 BOVA_CONFIG_DIR 覆盖（便携模式 / 测试）
+pub fn config_dir() -> Result<PathBuf, SettingsError> {
+    if let Some(dir) = std::env::var_os("BOVA_CONFIG_DIR").filter(|d| !d.is_empty()) {
+        return Ok(PathBuf::from(dir));
+    }
+    dirs::config_dir().map(|d| d.join(APP_DIR)).ok_or(SettingsError::NoConfigDir)
+}
+
+/// 配置目录下的文件路径，目录不存在时创建
+pub fn config_file(name: &str) -> Result<PathBuf, SettingsError> {
+    let dir = config_dir()?;
+    std::fs::create_dir_all(&dir)?;
+    Ok(dir.join(name))
+}
+
+/// 读取 JSON 文件；文件不存在时返回 Ok(None)
+pub fn load_json<T: DeserializeOwned>(path: &Path) -> Result<Option<T>, SettingsError> {
+    match std::fs::read(path) {
+        Ok(bytes) => Ok(Some(serde_json::from_slice(&bytes)?)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// 原子写入 JSON（先写临时文件再重命名），仅当前用户可读
+pub fn save_json<T: Serialize>(path: &Path, value: &T) -> Result<(), SettingsError> {
+    write_private(path, &serde_json::to_vec_pretty(value)?)
+}
+
+pub(crate) fn write_private(path: &Path, bytes: &[u8]) -> Result<(), SettingsError> {
+    let tmp = write_temp(path, bytes)?;
+    std::fs::rename(&tmp, path)?;
+    Ok(())
+}
+
+/// 把内容完整写入并同步到 `path` 旁的临时文件（仅当前用户可读），返回其路径；
+/// 之后 rename 到 `path` 即为原子替换
+pub(crate) fn write_temp(path: &Path, bytes: &[u8]) -> Result<PathBuf, SettingsError> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    let tmp = path.with_extension("tmp");
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut file = options.open(&tmp)?;
+    std::io::Write::write_all(&mut file, bytes)?;
+    file.sync_all()?;
+    Ok(tmp)
+}
@@ -0,0 +1,87 @@
+//! Global preferences (settings.json).
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::store::{Store, Versioned};
+
+/// 最近打开的文件数量上限
+pub const MAX_RECENT: usize = 10;
+
+/// 首选播放引擎（与 bova-playback 的 PlaybackEngine 对应）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EnginePreference {
+    #[default]
+    Mpv,
+    Ffmpeg,
+}
+
+/// 循环模式（与 bova-core 的 RepeatMode 对应）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RepeatPreference {
+    #[default]
+    Off,
+    One,
+    All,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Preferences {
+    /// 0.0 ..= 1.0
+    pub volume: f32,
+    pub repeat: RepeatPreference,
+    pub hwaccel: bool,
+    pub engine: EnginePreference,
+    pub subtitle_enabled: bool,
+    /// 播放列表项之间的淡入淡出时长（毫秒），0 为无缝衔接
+    pub crossfade_ms: u32,
+    /// Emby 标记了片头时自动跳过
+    pub skip_intro: bool,
+    /// 最近打开的文件，最新的在前
+    pub recent: Vec<String>,
+}
+
+impl Default for Preferences {
+    fn default() -> Self {
+        Self {
+            volume: 1.0,
+            repeat: RepeatPreference::default(),
+            hwaccel: true,
+            engine: EnginePreference::default(),
+            subtitle_enabled: true,
+            crossfade_ms: 0,
+            skip_intro: false,
+            recent: Vec::new(),
+        }
+    }
+}
+
+impl Preferences {
+    /// 记录最近打开的文件（移到最前，超出上限时丢弃最旧的）
+    pub fn push_recent(&mut self, path: &str) {
+        self.recent.retain(|p| p != path);
+        self.recent.insert(0, path.to_string());
+        self.recent.truncate(MAX_RECENT);
+    }
+}
+
+impl Versioned for Preferences {
+    const VERSION: u32 = 2;
+    const FILE: &'static str = "settings.json";
+
+    fn migrate(from: u32, value: &mut Value) {
+        // v1 → v2：loop_play（重播当前文件）改为循环模式
+        if from == 1 {
+            if let Some(map) = value.as_object_mut() {
+                if map.remove("loop_play").and_then(|v| v.as_bool()) == Some(true) {
+                    map.insert("repeat".to_string(), "one".into());
+                }
+            }
+        }
+    }
+}
+
+pub type SettingsStore = Store<Preferences>;
@@ -0,0 +1,74 @@
+//! Per-file playback memory (state.json).
+//!
+//! 以调用方给出的键（本地路径或 URL）记录每个文件的播放位置、所选音轨/字幕、
+//! 字幕延迟与音量；超过 `MAX_FILE_STATES` 条时淘汰最久未更新的记录。
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::store::{unix_now, Store, Versioned};
+
+pub const MAX_FILE_STATES: usize = 1000;
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FileState {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub position_ms: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub duration_ms: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub audio_track: Option<i64>,
+    /// 所选字幕轨道；`subtitle_off` 为 true 时表示用户关闭了字幕
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub subtitle_track: Option<i64>,
+    pub subtitle_off: bool,
+    pub subtitle_delay_ms: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub volume: Option<f32>,
+    /// 曾播放到接近结尾
+    pub watched: bool,
+    /// 最后更新时间（Unix 秒），用于淘汰
+    pub updated_at: u64,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MediaState {
+    pub files: HashMap<String, FileState>,
+}
+
+impl MediaState {
+    pub fn file(&self, key: &str) -> Option<&FileState> {
+        self.files.get(key)
+    }
+
+    /// 修改某个文件的记录（不存在时新建），更新时间戳并按上限淘汰
+    pub fn update_file(&mut self, key: &str, f: impl FnOnce(&mut FileState)) {
+        let state = self.files.entry(key.to_string()).or_default();
+        f(state);
+        state.updated_at = unix_now();
+        if self.files.len() > MAX_FILE_STATES {
+            let mut by_age: Vec<(u64, String)> = self.files.iter()
+                .filter(|(k, _)| k.as_str() != key)
+                .map(|(k, s)| (s.updated_at, k.clone()))
+                .collect();
+            by_age.sort();
+            for (_, key) in by_age.into_iter().take(self.files.len() - MAX_FILE_STATES) {
+                self.files.remove(&key);
+            }
+        }
+    }
+
+    pub fn forget_file(&mut self, key: &str) -> bool {
+        self.files.remove(key).is_some()
+    }
+}
+
+impl Versioned for MediaState {
+    const VERSION: u32 = 1;
+    const FILE: &'static str = "state.json";
+}
+
+pub type StateStore = Store<MediaState>;
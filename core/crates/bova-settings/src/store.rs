@@ -0,0 +1,179 @@
+//! Versioned JSON stores shared by the GUI, CLI and FFI.
+//!
+//! 文件内容为 `{"version": N, ...}`：读取时按版本逐步迁移到当前结构；
+//! 文件损坏时改名为 `<name>.corrupt-<时间戳>` 保留现场，并回退到上一次成功保存的
+//! `<name>.bak`（没有则使用默认值），保证程序总能启动。
+
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::{config_file, load_json, write_temp, SettingsError};
+
+/// 可持久化的版本化数据
+pub trait Versioned: Serialize + DeserializeOwned + Default {
+    /// 当前结构版本，结构变化时递增并在 `migrate` 中处理旧版本
+    const VERSION: u32;
+    /// 配置目录下的文件名
+    const FILE: &'static str;
+
+    /// 把 `from` 版本的 JSON 原地升级到 `from + 1`；未写版本号的文件视为版本 0
+    fn migrate(_from: u32, _value: &mut Value) {}
+}
+
+pub struct Store<T> {
+    path: Option<PathBuf>,
+    value: T,
+    dirty: bool,
+}
+
+impl<T: Versioned> Store<T> {
+    /// 打开配置目录下的 `T::FILE`；配置目录不可用时仅保存在内存中
+    pub fn open() -> Self {
+        match config_file(T::FILE) {
+            Ok(path) => Self::open_at(path),
+            Err(e) => {
+                log::warn!("{} kept in memory only: {}", T::FILE, e);
+                Self { path: None, value: T::default(), dirty: false }
+            }
+        }
+    }
+
+    pub fn open_at(path: PathBuf) -> Self {
+        let value = load(&path);
+        Self { path: Some(path), value, dirty: false }
+    }
+
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    pub fn get(&self) -> &T {
+        &self.value
+    }
+
+    /// 可变访问，标记为待保存
+    pub fn get_mut(&mut self) -> &mut T {
+        self.dirty = true;
+        &mut self.value
+    }
+
+    /// 替换全部内容；与当前相同时不标记
+    pub fn set(&mut self, value: T)
+    where
+        T: PartialEq,
+    {
+        if self.value != value {
+            self.value = value;
+            self.dirty = true;
+        }
+    }
+
+    /// 重新从磁盘读取（其他进程可能已修改），丢弃未保存的改动
+    pub fn reload(&mut self) {
+        if let Some(path) = &self.path {
+            self.value = load(path);
+            self.dirty = false;
+        }
+    }
+
+    /// 先读取磁盘上的最新内容再修改并立即保存；同一文件有多个实例或进程写入时使用，
+    /// 避免过期的内存副本覆盖别处的修改
+    pub fn update(&mut self, f: impl FnOnce(&mut T)) -> Result<(), SettingsError> {
+        self.reload();
+        f(&mut self.value);
+        self.save()
+    }
+
+    pub fn save(&mut self) -> Result<(), SettingsError> {
+        if let Some(path) = &self.path {
+            save(path, &self.value)?;
+        }
+        self.dirty = false;
+        Ok(())
+    }
+
+    /// 有未保存的改动时才写盘
+    pub fn save_if_dirty(&mut self) -> Result<(), SettingsError> {
+        if self.dirty {
+            self.save()?;
+        }
+        Ok(())
+    }
+}
+
+fn backup_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".bak");
+    path.with_file_name(name)
+}
+
+pub(crate) fn unix_now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)
+}
+
+fn load<T: Versioned>(path: &Path) -> T {
+    match read_versioned::<T>(path) {
+        Ok(Some(value)) => return value,
+        Ok(None) => {}
+        Err(e) => {
+            let mut name = path.file_name().unwrap_or_default().to_os_string();
+            name.push(format!(".corrupt-{}", unix_now()));
+            let quarantine = path.with_file_name(name);
+            log::warn!("{} is corrupt ({}), moved to {}", path.display(), e, quarantine.display());
+            let _ = std::fs::rename(path, &quarantine);
+        }
+    }
+    // 主文件缺失或损坏时尝试上次保存前的备份
+    let backup = backup_path(path);
+    match read_versioned::<T>(&backup) {
+        Ok(Some(value)) => {
+            log::info!("restored {} from {}", T::FILE, backup.display());
+            value
+        }
+        Ok(None) => T::default(),
+        Err(e) => {
+            log::warn!("backup {} unusable: {}", backup.display(), e);
+            T::default()
+        }
+    }
+}
+
+fn read_versioned<T: Versioned>(path: &Path) -> Result<Option<T>, SettingsError> {
+    let Some(mut value) = load_json::<Value>(path)? else { return Ok(None) };
+    if !value.is_object() {
+        return Err(SettingsError::Format(format!("expected an object in {}", T::FILE)));
+    }
+    let version = value.get("version").and_then(Value::as_u64).unwrap_or(0) as u32;
+    if version > T::VERSION {
+        // 较新版本写入的文件：尽量读取已知字段，未知字段在下次保存时丢弃
+        log::warn!("{} has version {} (supported {}), reading known fields", T::FILE, version, T::VERSION);
+    }
+    for from in version..T::VERSION {
+        T::migrate(from, &mut value);
+    }
+    Ok(Some(serde_json::from_value(value)?))
+}
+
+fn save<T: Versioned>(path: &Path, value: &T) -> Result<(), SettingsError> {
+    let mut json = serde_json::to_value(value)?;
+    if let Some(map) = json.as_object_mut() {
+        map.insert("version".to_string(), T::VERSION.into());
+    }
+    // 先完整写入临时文件，再把上一次的文件复制为备份，最后原子替换主文件：
+    // 任何一步中断，主文件要么是旧内容要么是新内容
+    let tmp = write_temp(path, &serde_json::to_vec_pretty(&json)?)?;
+    match std::fs::copy(path, backup_path(path)) {
+        Ok(_) => {}
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+        Err(e) => {
+            let _ = std::fs::remove_file(&tmp);
+            return Err(e.into());
+        }
+    }
+    std::fs::rename(&tmp, path)?;
+    Ok(())
+}
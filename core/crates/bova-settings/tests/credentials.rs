@@ -0,0 +1,57 @@
+//! Encrypted-file credentials: persistence across reopen, and a key file that
+//! can't be used is an error rather than a reason to start over.
+
+use std::path::PathBuf;
+
+use bova_settings::{CredentialBackend, Credentials, SettingsError};
+
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("bova-credentials-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+#[test]
+fn round_trip_and_reopen() {
+    let dir = temp_dir("reopen");
+    let creds = Credentials::open_file(&dir).unwrap();
+    assert_eq!(creds.backend(), CredentialBackend::EncryptedFile);
+    creds.set("emby:https://host:u1", "token-1").unwrap();
+    creds.set("ftp:nas", "pässwörd").unwrap();
+    assert_eq!(creds.get("emby:https://host:u1").unwrap().as_deref(), Some("token-1"));
+    let stored = std::fs::read(dir.join("credentials.enc")).unwrap();
+    assert!(!stored.windows(7).any(|w| w == b"token-1"), "secrets are not stored in clear");
+
+    let creds = Credentials::open_file(&dir).unwrap();
+    assert_eq!(creds.get("emby:https://host:u1").unwrap().as_deref(), Some("token-1"));
+    assert_eq!(creds.get("ftp:nas").unwrap().as_deref(), Some("pässwörd"));
+    creds.delete("ftp:nas").unwrap();
+    assert_eq!(Credentials::open_file(&dir).unwrap().get("ftp:nas").unwrap(), None);
+
+    let _ = std::fs::remove_dir_all(dir);
+}
+
+#[test]
+fn unreadable_key_is_an_error() {
+    let dir = temp_dir("unreadable");
+    Credentials::open_file(&dir).unwrap().set("ftp:nas", "secret").unwrap();
+    let key_path = dir.join("credentials.key");
+    let key = std::fs::read(&key_path).unwrap();
+
+    // a directory in place of the key file fails to read with something other than NotFound
+    std::fs::remove_file(&key_path).unwrap();
+    std::fs::create_dir(&key_path).unwrap();
+    assert!(matches!(Credentials::open_file(&dir), Err(SettingsError::Io(_))));
+    std::fs::remove_dir(&key_path).unwrap();
+
+    std::fs::write(&key_path, &key[..16]).unwrap();
+    assert!(matches!(Credentials::open_file(&dir), Err(SettingsError::Secret(_))));
+    assert_eq!(std::fs::read(&key_path).unwrap(), &key[..16], "short key left in place");
+
+    std::fs::write(&key_path, &key).unwrap();
+    let creds = Credentials::open_file(&dir).unwrap();
+    assert_eq!(creds.get("ftp:nas").unwrap().as_deref(), Some("secret"), "secrets survive the failed opens");
+
+    let _ = std::fs::remove_dir_all(dir);
+}
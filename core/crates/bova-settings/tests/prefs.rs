@@ -0,0 +1,29 @@
+//! `Preferences` migrations from older settings.json files.
+
+use std::path::PathBuf;
+
+use bova_settings::{Preferences, RepeatPreference, Store, Versioned};
+
+fn open(name: &str, json: &str) -> (PathBuf, Store<Preferences>) {
+    let dir = std::env::temp_dir().join(format!("bova-settings-prefs-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join(Preferences::FILE);
+    std::fs::write(&path, json).unwrap();
+    (dir, Store::open_at(path))
+}
+
+#[test]
+fn loop_play_becomes_repeat_one() {
+    let (dir, store) = open("loop", r#"{ "version": 1, "volume": 0.5, "loop_play": true }"#);
+    assert_eq!(store.get().repeat, RepeatPreference::One);
+    assert_eq!(store.get().volume, 0.5);
+    let _ = std::fs::remove_dir_all(dir);
+}
+
+#[test]
+fn loop_play_off_keeps_repeat_off() {
+    let (dir, store) = open("noloop", r#"{ "version": 1, "loop_play": false }"#);
+    assert_eq!(store.get().repeat, RepeatPreference::Off);
+    let _ = std::fs::remove_dir_all(dir);
+}
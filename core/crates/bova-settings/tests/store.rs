@@ -0,0 +1,68 @@
+//! `Store` persistence: atomic save with a backup of the previous file, and
+//! recovery from a corrupt main file.
+
+use std::path::PathBuf;
+
+use bova_settings::{Store, Versioned};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+struct Counter {
+    n: u32,
+}
+
+impl Versioned for Counter {
+    const VERSION: u32 = 1;
+    const FILE: &'static str = "counter.json";
+}
+
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("bova-settings-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn read(path: PathBuf) -> serde_json::Value {
+    serde_json::from_slice(&std::fs::read(path).unwrap()).unwrap()
+}
+
+#[test]
+fn save_keeps_previous_file_as_backup() {
+    let dir = temp_dir("backup");
+    let path = dir.join(Counter::FILE);
+    let mut store = Store::<Counter>::open_at(path.clone());
+    store.set(Counter { n: 1 });
+    store.save().unwrap();
+    assert!(!dir.join("counter.json.bak").exists(), "nothing to back up on the first save");
+
+    store.set(Counter { n: 2 });
+    store.save().unwrap();
+    assert_eq!(read(path.clone()), serde_json::json!({ "n": 2, "version": 1 }));
+    assert_eq!(read(dir.join("counter.json.bak")), serde_json::json!({ "n": 1, "version": 1 }));
+    assert!(!path.with_extension("tmp").exists(), "temp file renamed into place");
+
+    let _ = std::fs::remove_dir_all(dir);
+}
+
+#[test]
+fn corrupt_file_falls_back_to_backup() {
+    let dir = temp_dir("corrupt");
+    let path = dir.join(Counter::FILE);
+    let mut store = Store::<Counter>::open_at(path.clone());
+    store.set(Counter { n: 1 });
+    store.save().unwrap();
+    store.set(Counter { n: 2 });
+    store.save().unwrap();
+
+    std::fs::write(&path, b"{ truncated").unwrap();
+    let store = Store::<Counter>::open_at(path);
+    assert_eq!(store.get(), &Counter { n: 1 });
+    let quarantined = std::fs::read_dir(&dir)
+        .unwrap()
+        .filter_map(|e| e.ok())
+        .any(|e| e.file_name().to_string_lossy().starts_with("counter.json.corrupt-"));
+    assert!(quarantined, "corrupt file kept for inspection");
+
+    let _ = std::fs::remove_dir_all(dir);
+}
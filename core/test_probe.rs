@@ -1,6 +1,8 @@
 use bova_probe;
 
 fn main() {
-    let info = bova_probe::probe("sample.mp4");
-    println!("{}", serde_json::to_string_pretty(&info).unwrap());
+    match bova_probe::probe("sample.mp4") {
+        Ok(info) => println!("{}", serde_json::to_string_pretty(&info).unwrap()),
+        Err(e) => eprintln!("probe failed: {e}"),
+    }
 }
\ No newline at end of file